@@ -0,0 +1,178 @@
+use eyre::{Context, Result};
+use inquire::{Confirm, MultiSelect};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::tr;
+use crate::DriaEnv;
+
+/// API key commands.
+#[derive(clap::Subcommand)]
+pub enum ApiKeysCommands {
+    /// Import API keys from another `.env` or JSON file into the current environment.
+    Import {
+        /// Path to the `.env` or JSON file to import from.
+        file: PathBuf,
+    },
+}
+
+/// Handles the API key commands.
+pub fn handle_apikeys(command: &ApiKeysCommands, env_path: &Path) -> Result<()> {
+    match command {
+        ApiKeysCommands::Import { file } => import_api_keys(file, env_path),
+    }
+}
+
+/// Imports the recognized API keys from the given file into the environment file,
+/// letting the user choose which ones to import.
+///
+/// ### Arguments
+/// - `file`: path to the `.env` or JSON file to import from
+/// - `env_path`: path to the environment file to import into
+///
+/// ### Errors
+/// - If the file could not be read or parsed
+/// - If the environment file could not be saved
+fn import_api_keys(file: &Path, env_path: &Path) -> Result<()> {
+    let found = read_api_keys(file)?;
+    if found.is_empty() {
+        eprintln!(
+            "No recognized API keys found in {}, expected any of: {}",
+            file.display(),
+            DriaEnv::API_KEY_NAMES.join(", ")
+        );
+        return Ok(());
+    }
+
+    let mut dria_env = DriaEnv::new_from_env();
+    let options = found
+        .iter()
+        .map(|(key, value)| {
+            let status = match dria_env.get(key) {
+                Some(existing) if existing == value => "unchanged",
+                Some(existing) if !existing.is_empty() => "overwrites existing",
+                _ => "new",
+            };
+            format!("{key} ({status})")
+        })
+        .collect::<Vec<_>>();
+    let defaults = (0..options.len()).collect::<Vec<_>>();
+
+    let selected = MultiSelect::new(
+        &tr!(
+            "Found {count} API keys, choose the ones to import:",
+            count = found.len()
+        ),
+        options.clone(),
+    )
+    .with_default(&defaults)
+    .with_help_message(tr!(
+        "↑↓ to move, SPACE to select one, ←/→ to select all/none, ENTER to confirm"
+    ))
+    .prompt()?;
+    if selected.is_empty() {
+        log::info!("No API keys selected, nothing to import.");
+        return Ok(());
+    }
+
+    for (key, value) in &found {
+        let is_selected = selected.iter().any(|option| {
+            option
+                .split_once(' ')
+                .is_some_and(|(selected_key, _)| selected_key == *key)
+        });
+        if is_selected {
+            dria_env.set(key, value);
+        }
+    }
+
+    if !dria_env.is_changed() {
+        log::info!("No changes made.");
+        return Ok(());
+    }
+
+    if Confirm::new(&tr!(
+        "Import {count} API keys into {path}?",
+        count = selected.len(),
+        path = env_path.display()
+    ))
+    .with_default(true)
+    .prompt()?
+    {
+        dria_env.save_to_file(env_path)?;
+    } else {
+        log::info!("Aborting, nothing was imported.");
+    }
+
+    Ok(())
+}
+
+/// Reads the recognized API keys (see [`DriaEnv::API_KEY_NAMES`]) from the given file,
+/// ignoring empty values.
+///
+/// The file is parsed as JSON if it has a `.json` extension or starts with `{`,
+/// otherwise as a `.env` file.
+fn read_api_keys(file: &Path) -> Result<BTreeMap<&'static str, String>> {
+    let content = std::fs::read_to_string(file)
+        .wrap_err_with(|| format!("could not read {}", file.display()))?;
+
+    let is_json =
+        file.extension().is_some_and(|ext| ext == "json") || content.trim_start().starts_with('{');
+    let entries: Vec<(String, String)> = if is_json {
+        serde_json::from_str::<BTreeMap<String, serde_json::Value>>(&content)
+            .wrap_err("could not parse JSON file")?
+            .into_iter()
+            .filter_map(|(key, value)| value.as_str().map(|value| (key, value.to_string())))
+            .collect()
+    } else {
+        dotenvy::from_read_iter(content.as_bytes())
+            .collect::<Result<_, _>>()
+            .wrap_err("could not parse env file")?
+    };
+
+    Ok(entries
+        .into_iter()
+        .filter_map(|(key, value)| {
+            let key = DriaEnv::API_KEY_NAMES.into_iter().find(|k| *k == key)?;
+            let value = value.trim().to_string();
+            (!value.is_empty()).then_some((key, value))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_api_keys() {
+        let dir = std::env::temp_dir().join(format!(
+            "dkn-test-read-api-keys-{}-{}",
+            std::process::id(),
+            line!()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let env_file = dir.join("secrets.env");
+        std::fs::write(
+            &env_file,
+            "OPENAI_API_KEY=sk-123\nGEMINI_API_KEY=\nUNRELATED=foo\n# JINA_API_KEY=commented\n",
+        )
+        .unwrap();
+        let keys = read_api_keys(&env_file).unwrap();
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[DriaEnv::OPENAI_APIKEY_KEY], "sk-123");
+
+        let json_file = dir.join("secrets.json");
+        std::fs::write(
+            &json_file,
+            r#"{"OPENROUTER_API_KEY": "or-123", "SERPER_API_KEY": 5}"#,
+        )
+        .unwrap();
+        let keys = read_api_keys(&json_file).unwrap();
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[DriaEnv::OPENROUTER_APIKEY_KEY], "or-123");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
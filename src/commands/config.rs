@@ -0,0 +1,246 @@
+use eyre::{Context, Result};
+use inquire::Confirm;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::settings::{validate_api_keys, ApiKeyStatus};
+use crate::tr;
+use crate::utils::{
+    audit_network, get_current_key_name, other_profile_env_paths, DriaEnv, NodeLock,
+    DKN_MOVED_MARKER_FILE, OBSOLETE_KEY_NAMES,
+};
+
+/// Configuration commands.
+#[derive(clap::Subcommand)]
+pub enum ConfigCommands {
+    /// Move the binaries, env files and state to a new directory (e.g. a bigger disk).
+    MoveData {
+        /// The new data directory, created if it does not exist.
+        new_dir: PathBuf,
+    },
+    /// Validate the values within the environment file.
+    Validate,
+    /// List the URLs derived from `DKN_NETWORK` and check if they are reachable.
+    Network,
+}
+
+/// Handles the configuration commands.
+pub async fn handle_config(
+    command: &ConfigCommands,
+    exe_dir: &Path,
+    env_path: &Path,
+) -> Result<()> {
+    match command {
+        ConfigCommands::MoveData { new_dir } => move_data(exe_dir, env_path, new_dir),
+        ConfigCommands::Validate => validate_config(env_path).await,
+        ConfigCommands::Network => {
+            if !audit_network(None).await {
+                eyre::bail!("some network endpoints are unreachable");
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Validates the values within the environment file, w.r.t the kinds of values each key expects.
+///
+/// Unknown keys that look like launcher keys (i.e. starting with `DKN_` or `OLLAMA_`) are reported
+/// as warnings, as they may be typos.
+///
+/// The configured API keys are validated with their providers as well, and keys that could not
+/// be validated (e.g. the provider is unreachable) are reported as warnings.
+///
+/// ### Errors
+/// - If there are any invalid values, or required values are missing
+/// - If any API key is rejected by its provider
+async fn validate_config(env_path: &Path) -> Result<()> {
+    let (dria_env, _) = DriaEnv::new_from_file(env_path)?;
+    let mut issues = dria_env
+        .validate()
+        .into_iter()
+        .map(|(key, err)| format!("{key}: {err}"))
+        .collect::<Vec<_>>();
+
+    if dria_env.get(DriaEnv::DKN_WALLET_KEY).is_none() {
+        issues.push(format!("{}: not set", DriaEnv::DKN_WALLET_KEY));
+    }
+    if dria_env.get_models().is_empty() {
+        issues.push(format!("{}: no models selected", DriaEnv::DKN_MODELS_KEY));
+    }
+
+    for (kind, status) in validate_api_keys(&dria_env).await {
+        match status {
+            ApiKeyStatus::Valid => {}
+            ApiKeyStatus::Invalid(_) => issues.push(format!("{kind} {status}")),
+            ApiKeyStatus::Unknown(_) => log::warn!("{kind} {status}"),
+        }
+    }
+
+    // the process environment includes unrelated keys, so we read the file directly for unknown keys
+    if let Ok(entries) = dotenvy::from_path_iter(env_path) {
+        for (key, _) in entries.flatten() {
+            if let Some(current) = get_current_key_name(&key) {
+                log::warn!("Legacy key {key}, it should be renamed to {current}.");
+            } else if OBSOLETE_KEY_NAMES.contains(&key.as_str()) {
+                log::warn!("Obsolete key {key}, it is no longer used.");
+            } else if (key.starts_with("DKN_") || key.starts_with("OLLAMA_"))
+                && !DriaEnv::KEY_NAMES.contains(&key.as_str())
+            {
+                log::warn!("Unknown key {key}, is it a typo?");
+            }
+        }
+    }
+
+    if issues.is_empty() {
+        eprintln!("{} is valid.", env_path.display());
+        return Ok(());
+    }
+
+    for issue in &issues {
+        eprintln!("  - {issue}");
+    }
+    eyre::bail!("{} has {} issues", env_path.display(), issues.len());
+}
+
+/// Moves the contents of the data directory into a new directory, and leaves a redirect marker
+/// at the old location so that the launcher still finds the files with the old paths.
+///
+/// Files are first copied to the new directory, and removed from the old one only after all of
+/// them have been copied; so a failure halfway leaves the old directory intact.
+///
+/// ### Arguments
+/// - `exe_dir`: the current data directory
+/// - `env_path`: path to the environment file, used to make sure the nodes are not running
+/// - `new_dir`: the new data directory
+///
+/// ### Errors
+/// - If the node of any profile within the data directory is running
+/// - If the new directory is the same as, or within, the current one
+/// - If the new directory has conflicting files
+/// - If any file could not be copied or removed
+fn move_data(exe_dir: &Path, env_path: &Path, new_dir: &Path) -> Result<()> {
+    // make sure that no profile is running while we move the files, as they are all moved
+    let locks = std::iter::once(env_path.to_path_buf())
+        .chain(other_profile_env_paths(env_path))
+        .map(|env_path| NodeLock::acquire(exe_dir, &env_path, false))
+        .collect::<Result<Vec<_>>>()?;
+
+    let old_dir = exe_dir
+        .canonicalize()
+        .wrap_err("could not resolve data directory")?;
+    fs::create_dir_all(new_dir).wrap_err("could not create new directory")?;
+    let new_dir = new_dir
+        .canonicalize()
+        .wrap_err("could not resolve new directory")?;
+    if new_dir.starts_with(&old_dir) {
+        eyre::bail!(
+            "New directory {} must be outside of {}",
+            new_dir.display(),
+            old_dir.display()
+        );
+    }
+
+    // the launcher itself is left in-place, along with lock files & the redirect marker
+    let launcher_path = std::env::current_exe()
+        .and_then(|path| path.canonicalize())
+        .ok();
+    let entries = fs::read_dir(&old_dir)?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| Some(path) != launcher_path.as_ref())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name != DKN_MOVED_MARKER_FILE && !name.ends_with(".lock"))
+        })
+        .collect::<Vec<_>>();
+    if entries.is_empty() {
+        log::info!("Nothing to move within {}", old_dir.display());
+        return Ok(());
+    }
+
+    if let Some(conflict) = entries
+        .iter()
+        .filter_map(|path| path.file_name())
+        .map(|name| new_dir.join(name))
+        .find(|path| path.exists())
+    {
+        eyre::bail!("{} already exists", conflict.display());
+    }
+
+    let answer = Confirm::new(&tr!(
+        "Move {count} files from {from} to {to}?",
+        count = entries.len(),
+        from = old_dir.display(),
+        to = new_dir.display()
+    ))
+    .with_default(true)
+    .prompt()?;
+    if !answer {
+        log::info!("Aborting, nothing was moved.");
+        return Ok(());
+    }
+
+    // copy everything first, and clean up the copies if anything fails
+    let mut copied = Vec::new();
+    for path in &entries {
+        let target = new_dir.join(path.file_name().expect("must have a name"));
+        log::info!("Copying {} to {}", path.display(), target.display());
+        if let Err(err) = copy_recursive(path, &target) {
+            for target in copied.iter().chain(std::iter::once(&target)) {
+                let _ = remove_recursive(target);
+            }
+            return Err(err).wrap_err_with(|| format!("could not copy {}", path.display()));
+        }
+        copied.push(target);
+    }
+
+    // leave a redirect marker behind before removing the old files
+    fs::write(
+        old_dir.join(DKN_MOVED_MARKER_FILE),
+        new_dir.to_string_lossy().as_bytes(),
+    )
+    .wrap_err("could not write redirect marker")?;
+
+    for path in &entries {
+        remove_recursive(path).wrap_err_with(|| format!("could not remove {}", path.display()))?;
+    }
+
+    // locks are released at the old location, there is nothing left to protect there
+    drop(locks);
+
+    log::info!("Data moved to {}", new_dir.display());
+    eprintln!(
+        "The launcher will follow the redirect at {}, you can also use `--env {}` directly.",
+        old_dir.join(DKN_MOVED_MARKER_FILE).display(),
+        new_dir
+            .join(env_path.file_name().unwrap_or(".env".as_ref()))
+            .display()
+    );
+
+    Ok(())
+}
+
+/// Copies a file or a directory recursively, preserving permissions of files.
+fn copy_recursive(from: &Path, to: &Path) -> std::io::Result<()> {
+    if from.is_dir() {
+        fs::create_dir_all(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+        }
+    } else {
+        fs::copy(from, to)?;
+    }
+
+    Ok(())
+}
+
+/// Removes a file or a directory recursively.
+fn remove_recursive(path: &Path) -> std::io::Result<()> {
+    if path.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
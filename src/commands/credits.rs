@@ -0,0 +1,204 @@
+use colored::Colorize;
+use eyre::Context;
+use std::path::Path;
+
+use crate::utils::{DriaEnv, TracedSend, LAUNCHER_USER_AGENT};
+
+/// Remaining credits (in USD) below which the credits are considered nearly exhausted.
+const LOW_CREDITS_THRESHOLD: f64 = 1.0;
+
+#[derive(Debug, serde::Deserialize)]
+struct OpenRouterRes<T> {
+    data: T,
+}
+
+/// Information about an OpenRouter API key, from `GET /api/v1/key`.
+#[derive(Debug, serde::Deserialize)]
+pub struct OpenRouterKeyInfo {
+    /// Human-friendly label of the key.
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Credit limit of the key, `None` if unlimited.
+    #[serde(default)]
+    pub limit: Option<f64>,
+    /// Credits used by the key.
+    #[serde(default)]
+    pub usage: f64,
+    /// Remaining credits of the key w.r.t its limit, `None` if unlimited.
+    #[serde(default)]
+    pub limit_remaining: Option<f64>,
+    /// Whether the account has only used the free tier.
+    #[serde(default)]
+    pub is_free_tier: bool,
+    /// Rate limit of the key.
+    #[serde(default)]
+    pub rate_limit: Option<OpenRouterRateLimit>,
+}
+
+/// Rate limit of an OpenRouter API key.
+#[derive(Debug, serde::Deserialize)]
+pub struct OpenRouterRateLimit {
+    /// Number of requests allowed within the interval.
+    pub requests: i64,
+    /// Interval of the rate limit, e.g. `10s`.
+    pub interval: String,
+}
+
+/// Credits of an OpenRouter account, from `GET /api/v1/credits`.
+#[derive(Debug, serde::Deserialize)]
+pub struct OpenRouterCredits {
+    /// Total credits purchased.
+    pub total_credits: f64,
+    /// Total credits used.
+    pub total_usage: f64,
+}
+
+impl OpenRouterCredits {
+    /// Returns the remaining credits of the account.
+    #[inline]
+    pub fn remaining(&self) -> f64 {
+        self.total_credits - self.total_usage
+    }
+}
+
+/// Shows the credit balance & rate limits of the OpenRouter API key within the given env file.
+///
+/// ### Errors
+/// - If the env file could not be read
+/// - If the OpenRouter API key is not set
+/// - If the key information could not be fetched
+pub async fn show_credits(env_path: &Path) -> eyre::Result<()> {
+    let (dria_env, _) = DriaEnv::new_from_file(env_path)?;
+    let Some(api_key) = dria_env.get(DriaEnv::OPENROUTER_APIKEY_KEY) else {
+        eyre::bail!(
+            "{} is not set, you can set it with `dkn-compute-launcher settings`",
+            DriaEnv::OPENROUTER_APIKEY_KEY
+        );
+    };
+
+    let (key_info, credits) = tokio::join!(
+        get_openrouter_key_info(api_key),
+        get_openrouter_credits(api_key)
+    );
+    let key_info = key_info.wrap_err("could not get OpenRouter key information")?;
+
+    eprintln!(
+        "{} {}",
+        "OpenRouter key".bold(),
+        key_info.label.as_deref().unwrap_or_default()
+    );
+    match credits {
+        Ok(credits) => {
+            let remaining = format!("${:.2}", credits.remaining());
+            eprintln!(
+                "  Credits:    {} remaining of ${:.2}",
+                if credits.remaining() < LOW_CREDITS_THRESHOLD {
+                    remaining.red()
+                } else {
+                    remaining.green()
+                },
+                credits.total_credits
+            );
+        }
+        Err(err) => log::warn!("Could not get OpenRouter credits: {err}"),
+    }
+    match (key_info.limit, key_info.limit_remaining) {
+        (Some(limit), Some(remaining)) => {
+            eprintln!("  Key limit:  ${remaining:.2} remaining of ${limit:.2}")
+        }
+        _ => eprintln!("  Key limit:  unlimited"),
+    }
+    eprintln!("  Key usage:  ${:.2}", key_info.usage);
+    if let Some(rate_limit) = &key_info.rate_limit {
+        eprintln!(
+            "  Rate limit: {} requests per {}",
+            rate_limit.requests, rate_limit.interval
+        );
+    }
+    if key_info.is_free_tier {
+        eprintln!("  Free tier:  yes, free models have stricter rate limits");
+    }
+
+    Ok(())
+}
+
+/// Warns if the credits of the given OpenRouter API key are nearly exhausted.
+///
+/// Errors are ignored, as this is only a courtesy check before starting the node.
+pub async fn warn_if_low_credits(api_key: &str) {
+    let (key_info, credits) = tokio::join!(
+        get_openrouter_key_info(api_key),
+        get_openrouter_credits(api_key)
+    );
+
+    if let Ok(credits) = credits {
+        if credits.remaining() < LOW_CREDITS_THRESHOLD {
+            log::warn!(
+                "OpenRouter credits are nearly exhausted: ${:.2} remaining, see `dkn-compute-launcher credits`",
+                credits.remaining()
+            );
+        }
+    }
+    if let Ok(OpenRouterKeyInfo {
+        limit_remaining: Some(remaining),
+        ..
+    }) = key_info
+    {
+        if remaining < LOW_CREDITS_THRESHOLD {
+            log::warn!(
+                "OpenRouter key limit is nearly reached: ${remaining:.2} remaining, see `dkn-compute-launcher credits`"
+            );
+        }
+    }
+}
+
+/// Returns the information of the given OpenRouter API key.
+async fn get_openrouter_key_info(api_key: &str) -> eyre::Result<OpenRouterKeyInfo> {
+    get_openrouter("https://openrouter.ai/api/v1/key", api_key).await
+}
+
+/// Returns the credits of the account that owns the given OpenRouter API key.
+async fn get_openrouter_credits(api_key: &str) -> eyre::Result<OpenRouterCredits> {
+    get_openrouter("https://openrouter.ai/api/v1/credits", api_key).await
+}
+
+async fn get_openrouter<T: serde::de::DeserializeOwned>(
+    url: &str,
+    api_key: &str,
+) -> eyre::Result<T> {
+    let client = reqwest::Client::builder()
+        .user_agent(LAUNCHER_USER_AGENT)
+        .build()
+        .wrap_err("could not create reqwest client")?;
+
+    let res = client
+        .get(url)
+        .bearer_auth(api_key)
+        .send_traced()
+        .await
+        .wrap_err("could not make request")?
+        .error_for_status()?;
+
+    let body = res
+        .json::<OpenRouterRes<T>>()
+        .await
+        .wrap_err("could not parse body")?;
+
+    Ok(body.data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_key_info() {
+        let body = r#"{"data":{"label":"sk-or-v1-abc...xyz","limit":null,"usage":1.25,"limit_remaining":null,"is_free_tier":false,"rate_limit":{"requests":10,"interval":"10s"}}}"#;
+        let key_info = serde_json::from_str::<OpenRouterRes<OpenRouterKeyInfo>>(body)
+            .unwrap()
+            .data;
+        assert_eq!(key_info.limit, None);
+        assert_eq!(key_info.usage, 1.25);
+        assert_eq!(key_info.rate_limit.unwrap().requests, 10);
+    }
+}
@@ -0,0 +1,297 @@
+use colored::Colorize;
+use eyre::Result;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::utils::{
+    check_for_compute_node_update, check_for_launcher_update, get_network_env, get_points, DriaEnv,
+    DriaRelease, NodeLock, DKN_LAUNCHER_VERSION,
+};
+
+/// The file that keeps the points seen by the dashboard, to show their change over time.
+const POINTS_HISTORY_FILE: &str = ".dkn-points-history";
+/// The period over which the change of points is shown.
+const POINTS_DELTA_PERIOD: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+/// Points older than this are dropped from the history.
+const POINTS_HISTORY_RETENTION: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Prints a compact overview of the node with the given environment file: its status & uptime,
+/// versions & available updates, points and their 7-day change, configured models and any
+/// active warnings.
+///
+/// Network requests are made concurrently, and a failed request is shown as a warning
+/// instead of failing the command.
+///
+/// ### Errors
+/// - If the env file could not be read
+pub async fn show_dashboard(exe_dir: &Path, env_path: &Path) -> Result<()> {
+    let (dria_env, _) = DriaEnv::new_from_file(env_path)?;
+    let mut warnings = dria_env
+        .validate()
+        .into_iter()
+        .map(|(key, err)| format!("{key}: {err}"))
+        .collect::<Vec<_>>();
+
+    let address = dria_env.get_account().ok().map(|(_, _, address)| address);
+    let models = dria_env.get_models();
+    let health_port = NodeLock::holder(exe_dir, env_path).and(dria_env.get_health_port());
+    let (health_report, launcher_update, compute_update, points) = tokio::join!(
+        async {
+            match health_port {
+                Some(port) => Some(super::status::get_health_report(port).await),
+                None => None,
+            }
+        },
+        check_for_launcher_update(DKN_LAUNCHER_VERSION),
+        check_for_compute_node_update(exe_dir),
+        async {
+            match &address {
+                Some(address) => Some(get_points(address).await),
+                None => None,
+            }
+        },
+    );
+
+    // node
+    match NodeLock::holder(exe_dir, env_path) {
+        Some(pid) => {
+            let uptime = NodeLock::held_since(exe_dir, env_path)
+                .and_then(|since| since.elapsed().ok())
+                .map(|uptime| format!(", up {}", format_duration(uptime)))
+                .unwrap_or_default();
+            eprintln!(
+                "{} {} (launcher PID {pid}){uptime}",
+                "Node:".bold(),
+                "running".green()
+            );
+
+            match health_report {
+                Some(Ok(report)) => {
+                    let field = |name: &str| {
+                        report[name]
+                            .as_u64()
+                            .map(|value| value.to_string())
+                            .unwrap_or_else(|| "unknown".to_string())
+                    };
+                    eprintln!(
+                        "  peers: {}, tasks completed: {}, errors: {}",
+                        field("peers"),
+                        field("tasks_completed"),
+                        field("errors")
+                    );
+                    if report["healthy"].as_bool() == Some(false) {
+                        warnings.push("node is running, but not healthy".to_string());
+                    }
+                }
+                Some(Err(err)) => warnings.push(format!("could not get node statistics: {err}")),
+                None => eprintln!(
+                    "  {}",
+                    format!(
+                        "set {} to see peers & task statistics",
+                        DriaEnv::DKN_HEALTH_PORT_KEY
+                    )
+                    .dimmed()
+                ),
+            }
+        }
+        None => {
+            eprintln!("{} {}", "Node:".bold(), "not running".red());
+            warnings.push("node is not running".to_string());
+        }
+    }
+
+    // versions
+    eprintln!("{}", "Versions:".bold());
+    let version_status = |update: Result<(DriaRelease, bool)>| match update {
+        Ok((latest, true)) => format!("update available: v{}", latest.version())
+            .yellow()
+            .to_string(),
+        Ok((_, false)) => "latest".green().to_string(),
+        Err(err) => format!("could not check updates: {err}").red().to_string(),
+    };
+    if launcher_update
+        .as_ref()
+        .is_ok_and(|(_, required)| *required)
+    {
+        warnings.push("launcher update is available, run `dkn-compute-launcher update`".into());
+    }
+    if compute_update.as_ref().is_ok_and(|(_, required)| *required) {
+        warnings.push("compute node update is available, it is applied on start".into());
+    }
+    eprintln!(
+        "  launcher: v{DKN_LAUNCHER_VERSION} ({})",
+        version_status(launcher_update)
+    );
+    eprintln!(
+        "  compute node: {} ({})",
+        DriaRelease::get_compute_version(exe_dir)
+            .map(|version| format!("v{}", version.trim()))
+            .unwrap_or_else(|| "not installed".to_string()),
+        version_status(compute_update)
+    );
+
+    // points
+    match points {
+        Some(Ok(points)) => {
+            let delta = record_points(
+                exe_dir,
+                &points_key(&get_network_env(), address.as_deref().unwrap_or_default()),
+                points.score,
+            )
+            .map(|(delta, since)| {
+                let period = if since >= POINTS_DELTA_PERIOD {
+                    "in the last 7 days".to_string()
+                } else {
+                    format!("in the last {}", format_duration(since))
+                };
+                format!(", {delta:+} {period}")
+            })
+            .unwrap_or_default();
+            eprintln!(
+                "{} {} (top {}%){delta}",
+                "Points:".bold(),
+                points.score.to_string().purple(),
+                points.percentile
+            );
+        }
+        Some(Err(err)) => {
+            eprintln!("{} {}", "Points:".bold(), "unknown".yellow());
+            warnings.push(format!("could not get points: {err}"));
+        }
+        None => {
+            eprintln!("{} {}", "Points:".bold(), "no wallet configured".red());
+            warnings.push("no wallet configured".to_string());
+        }
+    }
+
+    // models
+    if models.is_empty() {
+        eprintln!("{} {}", "Models:".bold(), "no models configured".red());
+        warnings.push("no models configured".to_string());
+    } else {
+        eprintln!("{}", "Models:".bold());
+        for model in &models {
+            eprintln!("  - {model} ({})", model.provider());
+        }
+    }
+
+    // warnings
+    if warnings.is_empty() {
+        eprintln!("{} {}", "Warnings:".bold(), "none".green());
+    } else {
+        eprintln!("{}", "Warnings:".bold());
+        for warning in warnings {
+            eprintln!("  - {}", warning.yellow());
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the key of the points of the given address on the given network within the history,
+/// as the same address has different points on each network.
+fn points_key(network: &str, address: &str) -> String {
+    format!("{network}:{address}")
+}
+
+/// Records the given points within the history under the given key (see [`points_key`]), and
+/// returns the change of points since the oldest record within the last 7 days along with how
+/// long ago it was.
+///
+/// The history is best-effort, errors are only logged.
+fn record_points(exe_dir: &Path, key: &str, score: f64) -> Option<(f64, Duration)> {
+    let path = exe_dir.join(POINTS_HISTORY_FILE);
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+
+    let mut history = std::fs::read_to_string(&path)
+        .map(|content| parse_points_history(&content))
+        .unwrap_or_default();
+    history.retain(|(time, _, _)| now.saturating_sub(*time) <= POINTS_HISTORY_RETENTION.as_secs());
+    let delta = points_delta(&history, key, score, now);
+
+    history.push((now, key.to_string(), score));
+    let content = history
+        .iter()
+        .map(|(time, key, score)| format!("{time} {key} {score}\n"))
+        .collect::<String>();
+    if let Err(err) = std::fs::write(&path, content) {
+        log::warn!("Could not write points history: {err}");
+    }
+
+    delta
+}
+
+/// Parses the points history, with a `<unix-seconds> <network>:<address> <score>` entry per line.
+fn parse_points_history(content: &str) -> Vec<(u64, String, f64)> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let time = parts.next()?.parse().ok()?;
+            let key = parts.next()?.to_string();
+            let score = parts.next()?.parse().ok()?;
+            Some((time, key, score))
+        })
+        .collect()
+}
+
+/// Returns the change of points since the oldest record of the key within the last 7 days,
+/// along with how long ago it was.
+fn points_delta(
+    history: &[(u64, String, f64)],
+    key: &str,
+    score: f64,
+    now: u64,
+) -> Option<(f64, Duration)> {
+    history
+        .iter()
+        .filter(|(time, record_key, _)| {
+            record_key == key && now.saturating_sub(*time) <= POINTS_DELTA_PERIOD.as_secs()
+        })
+        .min_by_key(|(time, _, _)| *time)
+        .map(|(time, _, old_score)| {
+            (
+                score - old_score,
+                Duration::from_secs(now.saturating_sub(*time)),
+            )
+        })
+}
+
+/// Formats a duration in days, hours & minutes, e.g. `2d 3h 15m`.
+pub(super) fn format_duration(duration: Duration) -> String {
+    let minutes = duration.as_secs() / 60;
+    let (days, hours, minutes) = (minutes / (24 * 60), (minutes / 60) % 24, minutes % 60);
+    match (days, hours) {
+        (0, 0) => format!("{minutes}m"),
+        (0, _) => format!("{hours}h {minutes}m"),
+        _ => format!("{days}d {hours}h {minutes}m"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_points_delta() {
+        let day = 24 * 60 * 60;
+        let now = 30 * day;
+        let history = parse_points_history(&format!(
+            "{} mainnet:0xabc 10\n{} mainnet:0xabc 50\n{} testnet:0xabc 1000\ninvalid line\n",
+            now - 8 * day,
+            now - 6 * day,
+            now - day
+        ));
+        assert_eq!(history.len(), 3);
+
+        // the record older than 7 days and the other network are ignored
+        let key = points_key("mainnet", "0xabc");
+        let (delta, since) = points_delta(&history, &key, 80.0, now).unwrap();
+        assert_eq!(delta, 30.0);
+        assert_eq!(since, Duration::from_secs(6 * day));
+        assert!(points_delta(&history, &points_key("mainnet", "0x123"), 80.0, now).is_none());
+
+        assert_eq!(format_duration(Duration::from_secs(6 * day)), "6d 0h 0m");
+        assert_eq!(format_duration(Duration::from_secs(3700)), "1h 1m");
+    }
+}
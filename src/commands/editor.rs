@@ -2,7 +2,11 @@ use inquire::Editor;
 use std::fs;
 use std::path::Path;
 
-/// Edit the environment file at the given path.
+use crate::tr;
+use crate::DriaEnv;
+
+/// Edit the environment file at the given path, and preview the changed values with their
+/// secrets masked.
 ///
 /// ### Arguments
 /// - `env_path`: path to the environment file
@@ -22,15 +26,22 @@ pub fn edit_environment_file(env_path: &Path) -> eyre::Result<()> {
     let Some(new_env_content) =
         Editor::new(&format!("Edit environment file at {}:", env_path.display()))
             .with_predefined_text(&existing_env_content)
-            .with_help_message("ESC to go back")
+            .with_help_message(tr!("ESC to go back"))
             .prompt_skippable()?
     else {
         return Ok(());
     };
 
     if existing_env_content != new_env_content {
+        let (original, _) = DriaEnv::new_from_file(env_path)?;
         fs::write(env_path, new_env_content)?;
         log::info!("Environment file updated successfully.");
+
+        // preview the changes, with secrets masked
+        let (updated, _) = DriaEnv::new_from_file(env_path)?;
+        for change in updated.changes_from(&original) {
+            eprintln!("  {change}");
+        }
     } else {
         log::info!("No changes made to the file.");
     }
@@ -0,0 +1,292 @@
+use colored::Colorize;
+use eyre::{eyre, Context, Result};
+use reqwest::Client;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::utils::{column_widths, DriaEnv, TracedSend};
+
+/// Fleet commands, for operators running nodes on several machines.
+#[derive(clap::Subcommand)]
+pub enum FleetCommands {
+    /// Show the versions, uptime, models & points of each node, via their management APIs.
+    Status {
+        /// Comma-separated management APIs, as URLs or `localhost:<port>` of SSH tunnels.
+        #[arg(long, value_delimiter = ',', required = true)]
+        hosts: Vec<String>,
+        /// File to read the token of the management APIs from, defaults to `DKN_MANAGEMENT_TOKEN`
+        /// of the environment; the token is not taken as an argument to keep it out of `ps`.
+        #[arg(long)]
+        token_file: Option<PathBuf>,
+        /// Print the output as JSON.
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+}
+
+/// Handles the fleet commands.
+pub async fn handle_fleet(command: &FleetCommands) -> Result<()> {
+    match command {
+        FleetCommands::Status {
+            hosts,
+            token_file,
+            json,
+        } => {
+            // the environment file is loaded into the environment, unless the variable is exported
+            let token = match token_file {
+                Some(path) => std::fs::read_to_string(path)
+                    .wrap_err_with(|| format!("could not read token at {}", path.display()))?,
+                None => std::env::var(DriaEnv::DKN_MANAGEMENT_TOKEN_KEY).unwrap_or_default(),
+            };
+            let token = token.trim();
+            if token.is_empty() {
+                eyre::bail!(
+                    "No token given, set {} in your environment file or use --token-file.",
+                    DriaEnv::DKN_MANAGEMENT_TOKEN_KEY
+                );
+            }
+
+            show_fleet_status(hosts, token, *json).await
+        }
+    }
+}
+
+/// Status of a node within the fleet, each part is `Err` if it could not be queried.
+struct NodeStatus {
+    host: String,
+    status: Result<serde_json::Value>,
+    models: Result<serde_json::Value>,
+    points: Result<serde_json::Value>,
+}
+
+impl NodeStatus {
+    fn to_json(&self) -> serde_json::Value {
+        let part = |part: &Result<serde_json::Value>| match part {
+            Ok(value) => value.clone(),
+            Err(err) => serde_json::json!({ "error": format!("{err:#}") }),
+        };
+        serde_json::json!({
+            "host": self.host,
+            "status": part(&self.status),
+            "models": part(&self.models),
+            "points": part(&self.points),
+        })
+    }
+
+    /// Returns the cells of this node within the table, without colors.
+    fn to_row(&self) -> [String; 7] {
+        let unknown = || "-".to_string();
+        let (state, launcher, compute, uptime) = match &self.status {
+            Ok(status) => (
+                match status["healthy"].as_bool() {
+                    Some(true) => "healthy",
+                    _ => "unhealthy",
+                }
+                .to_string(),
+                status["launcher_version"]
+                    .as_str()
+                    .map_or_else(unknown, |version| format!("v{version}")),
+                status["compute_version"]
+                    .as_str()
+                    .map_or_else(unknown, |version| format!("v{version}")),
+                status["uptime_secs"].as_u64().map_or_else(unknown, |secs| {
+                    super::dashboard::format_duration(Duration::from_secs(secs))
+                }),
+            ),
+            Err(_) => ("unreachable".to_string(), unknown(), unknown(), unknown()),
+        };
+        let models = match &self.models {
+            Ok(models) => models["models"]
+                .as_array()
+                .map(|models| {
+                    models
+                        .iter()
+                        .filter_map(|model| model["name"].as_str())
+                        .collect::<Vec<_>>()
+                        .join(",")
+                })
+                .filter(|models| !models.is_empty())
+                .unwrap_or_else(unknown),
+            Err(_) => unknown(),
+        };
+        let points = match &self.points {
+            Ok(points) => points["score"]
+                .as_f64()
+                .map_or_else(unknown, |score| score.to_string()),
+            Err(_) => unknown(),
+        };
+
+        [
+            self.host.clone(),
+            state,
+            launcher,
+            compute,
+            uptime,
+            models,
+            points,
+        ]
+    }
+}
+
+/// Queries the management API of each host concurrently, and prints an aggregated table; or
+/// the statuses as JSON to stdout.
+async fn show_fleet_status(hosts: &[String], token: &str, json: bool) -> Result<()> {
+    let policy = DriaEnv::new_from_env().get_api_client_policy();
+    let client = policy
+        .client()
+        .wrap_err("could not create reqwest client")?;
+    let base_urls = hosts
+        .iter()
+        .map(|host| management_base_url(host))
+        .collect::<Result<Vec<_>>>()?;
+
+    let nodes = futures::future::join_all(hosts.iter().zip(&base_urls).map(|(host, base_url)| {
+        let client = &client;
+        async move {
+            let (status, models, points) = tokio::join!(
+                get_management(client, base_url, "status", token),
+                get_management(client, base_url, "models", token),
+                get_management(client, base_url, "points", token),
+            );
+            NodeStatus {
+                host: host.clone(),
+                status,
+                models,
+                points,
+            }
+        }
+    }))
+    .await;
+
+    if json {
+        let nodes = nodes.iter().map(NodeStatus::to_json).collect::<Vec<_>>();
+        println!("{}", serde_json::to_string_pretty(&nodes)?);
+        return Ok(());
+    }
+
+    let header = [
+        "HOST", "STATUS", "LAUNCHER", "COMPUTE", "UPTIME", "MODELS", "POINTS",
+    ]
+    .map(String::from);
+    let rows = nodes.iter().map(NodeStatus::to_row).collect::<Vec<_>>();
+    let widths = column_widths(std::iter::once(&header).chain(&rows));
+
+    let line = |cells: &[String; 7]| {
+        cells
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+    eprintln!("{}", line(&header).bold());
+    for (row, node) in rows.iter().zip(&nodes) {
+        let line = line(row);
+        eprintln!(
+            "{}",
+            match row[1].as_str() {
+                "healthy" => line.normal(),
+                "unhealthy" => line.yellow(),
+                _ => line.red(),
+            }
+        );
+        if let Err(err) = &node.status {
+            eprintln!("  {}", format!("{err:#}").dimmed());
+        }
+    }
+
+    let healthy = rows.iter().filter(|row| row[1] == "healthy").count();
+    eprintln!("{healthy}/{} nodes are healthy.", rows.len());
+
+    Ok(())
+}
+
+/// Makes an authorized `GET` request to the given management API endpoint.
+async fn get_management(
+    client: &Client,
+    base_url: &str,
+    endpoint: &str,
+    token: &str,
+) -> Result<serde_json::Value> {
+    let res = client
+        .get(format!("{base_url}/{endpoint}"))
+        .bearer_auth(token)
+        .send_traced()
+        .await
+        .wrap_err("could not reach the management API")?;
+
+    let status = res.status();
+    let body = res
+        .json::<serde_json::Value>()
+        .await
+        .wrap_err("could not parse body")?;
+    if !status.is_success() {
+        return Err(eyre!(
+            "{status}: {}",
+            body["error"].as_str().unwrap_or("unknown error")
+        ));
+    }
+
+    Ok(body)
+}
+
+/// Returns the base URL of the management API of the given host, which can be a URL, or
+/// `localhost:<port>` e.g. of an SSH tunnel.
+///
+/// ### Errors
+/// - If the host is neither, as the management API only listens on localhost and a bare host
+///   would send the token in cleartext to a port that can not be reached
+fn management_base_url(host: &str) -> Result<String> {
+    let host = host.trim().trim_end_matches('/');
+    if host.starts_with("http://") || host.starts_with("https://") {
+        return Ok(host.to_string());
+    }
+
+    let is_loopback = |addr: &str| {
+        addr == "localhost"
+            || addr
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .parse::<IpAddr>()
+                .is_ok_and(|ip| ip.is_loopback())
+    };
+    match host.rsplit_once(':') {
+        Some((addr, port)) if is_loopback(addr) && port.parse::<u16>().is_ok() => {
+            Ok(format!("http://{host}"))
+        }
+        _ => Err(eyre!(
+            "invalid host {host}, use a URL or localhost:<port> of an SSH tunnel to the machine"
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_management_base_url() {
+        assert_eq!(
+            management_base_url("localhost:18788").unwrap(),
+            "http://localhost:18788"
+        );
+        assert_eq!(
+            management_base_url("127.0.0.1:9000").unwrap(),
+            "http://127.0.0.1:9000"
+        );
+        assert_eq!(
+            management_base_url("[::1]:9000").unwrap(),
+            "http://[::1]:9000"
+        );
+        assert_eq!(
+            management_base_url("https://node-b.example.com/").unwrap(),
+            "https://node-b.example.com"
+        );
+        assert!(management_base_url("node-a").is_err());
+        assert!(management_base_url("10.0.0.2:9000").is_err());
+        assert!(management_base_url("localhost").is_err());
+    }
+}
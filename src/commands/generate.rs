@@ -0,0 +1,328 @@
+use eyre::{Context, Result};
+use std::path::{Path, PathBuf};
+
+use crate::utils::{DriaEnv, DKN_LAUNCHER_VERSION};
+
+/// Data directory of the launcher on the deployed machines, relative to the home directory of
+/// the node user.
+const DEPLOY_DATA_DIR: &str = ".dria/dkn-compute-launcher";
+/// User that runs the node on the deployed machines.
+const DEPLOY_USER: &str = "dria";
+
+/// Generator commands.
+#[derive(clap::Subcommand)]
+pub enum GenerateCommands {
+    /// Print deployment snippets for the current environment, with its secrets referenced as
+    /// variables instead of their values.
+    Deploy {
+        /// The deployment tool to generate for.
+        #[arg(long, value_enum)]
+        format: DeployFormat,
+        /// Write to the given file instead of stdout.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+/// Deployment tools that snippets can be generated for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum DeployFormat {
+    /// An Ansible playbook.
+    Ansible,
+    /// A cloud-init user-data file, with secrets as `${VAR}` placeholders e.g. for `envsubst`.
+    CloudInit,
+    /// Terraform variables & a cloud-init user-data to attach to the instances.
+    Terraform,
+}
+
+/// Handles the generator commands.
+pub fn handle_generate(command: &GenerateCommands, env_path: &Path) -> Result<()> {
+    match command {
+        GenerateCommands::Deploy { format, output } => {
+            let (dria_env, _) = DriaEnv::new_from_file(env_path)?;
+            let entries = deploy_entries(&dria_env);
+            let content = match format {
+                DeployFormat::Ansible => render_ansible(&entries),
+                DeployFormat::CloudInit => render_cloud_init_file(&entries),
+                DeployFormat::Terraform => render_terraform(&entries),
+            };
+
+            match output {
+                Some(output) => {
+                    std::fs::write(output, content)
+                        .wrap_err_with(|| format!("could not write to {}", output.display()))?;
+                    eprintln!(
+                        "Deployment for {format:?} is written to {}.",
+                        output.display()
+                    );
+                }
+                None => print!("{content}"),
+            }
+
+            Ok(())
+        }
+    }
+}
+
+/// Returns the keys that are set within the environment along with their values, in the order
+/// of [`DriaEnv::KEY_NAMES`]; secrets have no value, so that they are referenced as variables.
+fn deploy_entries(dria_env: &DriaEnv) -> Vec<(&'static str, Option<String>)> {
+    DriaEnv::KEY_NAMES
+        .into_iter()
+        .filter_map(|key| {
+            let value = dria_env.get(key)?;
+            Some((key, (!DriaEnv::is_secret(key)).then(|| value.to_string())))
+        })
+        .collect()
+}
+
+/// Returns the name of the variable that holds the given secret key, e.g. `dkn_wallet_secret_key`.
+fn secret_variable(key: &str) -> String {
+    key.to_lowercase()
+}
+
+/// Returns the content of the environment file, with secrets given by `secret` and plain values
+/// escaped by `escape` w.r.t the templating of the format.
+fn render_env(
+    entries: &[(&'static str, Option<String>)],
+    secret: impl Fn(&str) -> String,
+    escape: impl Fn(&str) -> String,
+) -> String {
+    entries
+        .iter()
+        .map(|(key, value)| match value {
+            Some(value) => format!("{key}={}\n", escape(value)),
+            None => format!("{key}={}\n", secret(key)),
+        })
+        .collect()
+}
+
+/// Indents each non-empty line of the given text with the given number of spaces.
+fn indent(text: &str, spaces: usize) -> String {
+    text.lines()
+        .map(|line| match line.is_empty() {
+            true => "\n".to_string(),
+            false => format!("{:spaces$}{line}\n", ""),
+        })
+        .collect()
+}
+
+/// Returns an Ansible playbook that deploys the node to the `dria_nodes` hosts; secrets are read
+/// from variables, e.g. within an Ansible Vault.
+fn render_ansible(entries: &[(&'static str, Option<String>)]) -> String {
+    let env = render_env(
+        entries,
+        |key| format!("{{{{ {} }}}}", secret_variable(key)),
+        |value| {
+            value
+                .replace("{{", "{{ '{{' }}")
+                .replace("{%", "{{ '{%' }}")
+                .replace("{#", "{{ '{#' }}")
+        },
+    );
+    let unit = super::remote::systemd_unit(
+        "{{ dkn_user }}",
+        "{{ dkn_dir }}/dkn-compute-launcher",
+        "{{ dkn_dir }}/.env",
+    );
+    let secrets = entries
+        .iter()
+        .filter(|(_, value)| value.is_none())
+        .map(|(key, _)| secret_variable(key))
+        .collect::<Vec<_>>();
+
+    format!(
+        r#"# Deploys a Dria compute node, generated by `dkn-compute-launcher generate deploy`.
+# Define the secret variables before running, e.g. within an Ansible Vault: {secrets}
+- name: Deploy Dria compute node
+  hosts: dria_nodes
+  become: true
+  vars:
+    dkn_user: {DEPLOY_USER}
+    dkn_launcher_version: "{DKN_LAUNCHER_VERSION}"
+    dkn_dir: "/home/{{{{ dkn_user }}}}/{DEPLOY_DATA_DIR}"
+  tasks:
+    - name: Create the node user
+      ansible.builtin.user:
+        name: "{{{{ dkn_user }}}}"
+        create_home: true
+    - name: Create the data directory
+      ansible.builtin.file:
+        path: "{{{{ dkn_dir }}}}"
+        state: directory
+        owner: "{{{{ dkn_user }}}}"
+        mode: "0700"
+    - name: Download the launcher
+      ansible.builtin.get_url:
+        url: "https://github.com/firstbatchxyz/dkn-compute-launcher/releases/download/v{{{{ dkn_launcher_version }}}}/dkn-compute-launcher-linux-{{{{ 'arm64' if ansible_architecture == 'aarch64' else 'amd64' }}}}"
+        dest: "{{{{ dkn_dir }}}}/dkn-compute-launcher"
+        owner: "{{{{ dkn_user }}}}"
+        mode: "0755"
+    - name: Write the environment file
+      ansible.builtin.copy:
+        dest: "{{{{ dkn_dir }}}}/.env"
+        owner: "{{{{ dkn_user }}}}"
+        mode: "0600"
+        content: |
+{env}      # the content has the secrets, which must not be shown with `--diff` or `-v`
+      no_log: true
+      diff: false
+      notify: Restart the node
+    - name: Install the service
+      ansible.builtin.copy:
+        dest: /etc/systemd/system/dkn-compute-launcher.service
+        mode: "0644"
+        content: |
+{unit}      notify: Restart the node
+    - name: Start the service
+      ansible.builtin.systemd_service:
+        name: dkn-compute-launcher
+        enabled: true
+        state: started
+        daemon_reload: true
+  handlers:
+    - name: Restart the node
+      ansible.builtin.systemd_service:
+        name: dkn-compute-launcher
+        state: restarted
+        daemon_reload: true
+"#,
+        secrets = match secrets.is_empty() {
+            true => "none".to_string(),
+            false => secrets.join(", "),
+        },
+        env = indent(&env, 10),
+        unit = indent(&unit, 10),
+    )
+}
+
+/// Returns a cloud-init user-data that deploys the node on the first boot.
+///
+/// ### Arguments
+/// - `secret`: the reference of a secret key, w.r.t the templating around the user-data
+/// - `escape`: escapes a plain value, w.r.t the templating around the user-data
+fn render_cloud_init(
+    entries: &[(&'static str, Option<String>)],
+    secret: impl Fn(&str) -> String,
+    escape: impl Fn(&str) -> String,
+) -> String {
+    let dir = format!("/home/{DEPLOY_USER}/{DEPLOY_DATA_DIR}");
+    let env = render_env(entries, secret, escape);
+    let unit = super::remote::systemd_unit(
+        DEPLOY_USER,
+        &format!("{dir}/dkn-compute-launcher"),
+        &format!("{dir}/.env"),
+    );
+
+    format!(
+        r#"#cloud-config
+# Deploys a Dria compute node, generated by `dkn-compute-launcher generate deploy`.
+users:
+  - default
+  - name: {DEPLOY_USER}
+    shell: /bin/bash
+write_files:
+  - path: {dir}/.env
+    permissions: "0600"
+    defer: true
+    content: |
+{env}  - path: /etc/systemd/system/dkn-compute-launcher.service
+    permissions: "0644"
+    content: |
+{unit}runcmd:
+  - |
+    case "$(uname -m)" in aarch64|arm64) arch=arm64 ;; *) arch=amd64 ;; esac
+    curl -fsSL -o {dir}/dkn-compute-launcher "https://github.com/firstbatchxyz/dkn-compute-launcher/releases/download/v{DKN_LAUNCHER_VERSION}/dkn-compute-launcher-linux-$arch"
+    chmod 0755 {dir}/dkn-compute-launcher
+    chown -R {DEPLOY_USER}:{DEPLOY_USER} /home/{DEPLOY_USER}/.dria
+    systemctl daemon-reload
+    systemctl enable --now dkn-compute-launcher
+"#,
+        env = indent(&env, 6),
+        unit = indent(&unit, 6),
+    )
+}
+
+/// Returns a cloud-init user-data with the secrets as `${VAR}` placeholders, along with how to
+/// substitute them; only the secrets are substituted, as the user-data has shell variables.
+fn render_cloud_init_file(entries: &[(&'static str, Option<String>)]) -> String {
+    let secrets = entries
+        .iter()
+        .filter(|(_, value)| value.is_none())
+        .map(|(key, _)| format!("${{{key}}}"))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let user_data = render_cloud_init(entries, |key| format!("${{{key}}}"), ToString::to_string);
+    if secrets.is_empty() {
+        return user_data;
+    }
+
+    user_data.replacen(
+        "#cloud-config\n",
+        &format!("#cloud-config\n# Substitute the secrets before use: envsubst '{secrets}' < user-data.yaml\n"),
+        1,
+    )
+}
+
+/// Returns Terraform variables for the secrets, and the cloud-init user-data that references
+/// them as a local value to attach to the instances, e.g. with `user_data`.
+fn render_terraform(entries: &[(&'static str, Option<String>)]) -> String {
+    let variables = entries
+        .iter()
+        .filter(|(_, value)| value.is_none())
+        .map(|(key, _)| {
+            format!(
+                "variable \"{}\" {{\n  description = \"{key} of the Dria compute node\"\n  type        = string\n  sensitive   = true\n}}\n\n",
+                secret_variable(key)
+            )
+        })
+        .collect::<String>();
+    let user_data = render_cloud_init(
+        entries,
+        |key| format!("${{var.{}}}", secret_variable(key)),
+        |value| value.replace("${", "$${").replace("%{", "%%{"),
+    );
+
+    format!(
+        r#"# Deploys a Dria compute node, generated by `dkn-compute-launcher generate deploy`.
+# Attach the user-data to your instances, e.g. `user_data = local.dkn_user_data`.
+{variables}locals {{
+  dkn_user_data = <<-EOT
+{user_data}  EOT
+}}
+"#,
+        user_data = indent(&user_data, 4),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_deploy() {
+        let entries = [
+            (DriaEnv::DKN_WALLET_KEY, None),
+            (DriaEnv::DKN_MODELS_KEY, Some("gemma3:4b".to_string())),
+            (DriaEnv::DKN_NODE_NAME_KEY, Some("a ${b} {#c".to_string())),
+        ];
+
+        let ansible = render_ansible(&entries);
+        assert!(ansible.contains("          DKN_NODE_NAME=a ${b} {{ '{#' }}c\n"));
+        assert!(ansible.contains("      no_log: true\n      diff: false\n"));
+        assert!(ansible.contains("          DKN_WALLET_SECRET_KEY={{ dkn_wallet_secret_key }}\n"));
+        assert!(ansible.contains("          DKN_MODELS=gemma3:4b\n"));
+        assert!(ansible.contains("e.g. within an Ansible Vault: dkn_wallet_secret_key\n"));
+
+        let cloud_init = render_cloud_init_file(&entries);
+        assert!(cloud_init.starts_with(
+            "#cloud-config\n# Substitute the secrets before use: envsubst '${DKN_WALLET_SECRET_KEY}'"
+        ));
+        assert!(cloud_init.contains("      DKN_WALLET_SECRET_KEY=${DKN_WALLET_SECRET_KEY}\n"));
+
+        let terraform = render_terraform(&entries);
+        assert!(terraform.contains("variable \"dkn_wallet_secret_key\" {"));
+        assert!(terraform.contains("DKN_WALLET_SECRET_KEY=${var.dkn_wallet_secret_key}\n"));
+        assert!(terraform.contains("DKN_NODE_NAME=a $${b} {#c\n"));
+    }
+}
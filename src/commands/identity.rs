@@ -0,0 +1,200 @@
+use colored::Colorize;
+use eyre::{Context, Result};
+use inquire::Confirm;
+use std::path::{Path, PathBuf};
+
+use crate::tr;
+use crate::utils::{
+    get_points, referrals::ReferralsClient, DriaEnv, DriaRelease, NodeIdentity, NodeLock,
+};
+
+/// Identity commands, to move a node to another machine.
+#[derive(clap::Subcommand)]
+pub enum IdentityCommands {
+    /// Export the wallet, referral state & versions of this node to a file.
+    Export {
+        /// Path of the identity file, defaults to `dria-identity-<first 8 characters of the address>.json`
+        /// in the current directory.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Import an identity that is exported on another machine into the environment file.
+    Import {
+        /// Path of the identity file.
+        path: PathBuf,
+        /// Import without asking for confirmation.
+        #[arg(short, long, default_value_t = false)]
+        yes: bool,
+    },
+}
+
+/// Handles the identity commands.
+pub async fn handle_identity(
+    command: &IdentityCommands,
+    exe_dir: &Path,
+    env_path: &Path,
+) -> Result<()> {
+    match command {
+        IdentityCommands::Export { output } => {
+            export_identity(exe_dir, env_path, output.as_deref()).await
+        }
+        IdentityCommands::Import { path, yes } => {
+            import_identity(exe_dir, env_path, path, *yes).await
+        }
+    }
+}
+
+/// Exports the identity of the node within the given environment file, along with its referrer
+/// and points which are fetched on a best-effort basis.
+///
+/// The node must be stopped, so that its points do not increase after the export; otherwise the
+/// import would report it as running elsewhere.
+///
+/// ### Errors
+/// - If the node is running with the environment file
+/// - If there is no wallet within the environment file
+/// - If the identity file already exists or could not be written
+async fn export_identity(exe_dir: &Path, env_path: &Path, output: Option<&Path>) -> Result<()> {
+    // the lock is held until the export is written, so that the node is not started meanwhile
+    let _lock = NodeLock::acquire(exe_dir, env_path, false)
+        .wrap_err("please stop the node before exporting its identity")?;
+    let (dria_env, _) = DriaEnv::new_from_file(env_path)?;
+    let secret_key = dria_env
+        .get(DriaEnv::DKN_WALLET_KEY)
+        .ok_or_else(|| eyre::eyre!("No wallet is configured within {}", env_path.display()))?;
+
+    let mut identity = NodeIdentity::new(secret_key)?;
+    identity.compute_version = DriaRelease::get_compute_version(exe_dir);
+    identity.network = dria_env.get(DriaEnv::DKN_NETWORK_KEY).map(String::from);
+    identity.node_name = dria_env.get_node_name().map(String::from);
+    match ReferralsClient::default()
+        .get_referred_by(&identity.address)
+        .await
+    {
+        Ok(referred_by) => identity.referred_by = referred_by,
+        Err(err) => log::warn!(
+            "Could not get the referrer of 0x{}: {err}",
+            identity.address
+        ),
+    }
+    match get_points(&identity.address).await {
+        Ok(points) => identity.points = Some(points.score),
+        Err(err) => log::warn!("Could not get the points of 0x{}: {err}", identity.address),
+    }
+
+    let path = output
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from(format!("dria-identity-{}.json", &identity.address[..8])));
+    identity.save(&path)?;
+
+    eprintln!(
+        "Exported the identity of 0x{} to {}",
+        identity.address,
+        path.display()
+    );
+    eprintln!(
+        "{}",
+        "This file holds your secret key, keep it safe & delete it once it is imported.".yellow()
+    );
+
+    Ok(())
+}
+
+/// Imports the identity at the given path into the environment file, after checking that it is
+/// not running elsewhere: a node that is running on this machine with the environment file must
+/// be stopped first, and an identity that has earned points since its export is still running on
+/// the machine it was exported from.
+///
+/// ### Errors
+/// - If the identity file is invalid
+/// - If the node is running with the environment file
+/// - If a prompt fails, or the environment file could not be saved
+async fn import_identity(exe_dir: &Path, env_path: &Path, path: &Path, yes: bool) -> Result<()> {
+    let identity = NodeIdentity::load(path)?;
+    if NodeLock::holder(exe_dir, env_path).is_some() {
+        eyre::bail!(
+            "The node is running with {}, please stop it before importing an identity.",
+            env_path.display()
+        );
+    }
+
+    eprintln!("Identity: 0x{}", identity.address);
+    eprintln!(
+        "Exported by launcher v{} (compute node {})",
+        identity.launcher_version,
+        identity.compute_version.as_deref().unwrap_or("unknown")
+    );
+    if let Some(referred_by) = &identity.referred_by {
+        eprintln!("Referred by: {referred_by}");
+    }
+    if identity.is_from_newer_launcher() {
+        log::warn!("The identity is exported by a newer launcher, consider updating this one.");
+    }
+
+    // the points of a running node increase over time, serving as its heartbeat
+    let mut running_elsewhere = false;
+    match get_points(&identity.address).await {
+        Ok(points) => match identity.points_earned_since_export(points.score) {
+            Some(earned) => {
+                running_elsewhere = true;
+                eprintln!(
+                    "{}",
+                    format!("This identity has earned {earned} points since its export, so it is most likely still running elsewhere!").red()
+                );
+            }
+            None if identity.points.is_none() => {
+                log::warn!("Could not verify that the identity is not running elsewhere, as its points at the export are unknown.")
+            }
+            None => {}
+        },
+        Err(err) => log::warn!(
+            "Could not verify that the identity is not running elsewhere, as its points could not be fetched: {err}"
+        ),
+    }
+    eprintln!(
+        "{}",
+        "Running the same identity on more than one machine at once may be penalized, make sure the old node is stopped.".yellow()
+    );
+
+    let (mut dria_env, _) = DriaEnv::new_from_file(env_path)?;
+    if let Ok((_, _, current)) = dria_env.get_account() {
+        if current != identity.address {
+            eprintln!(
+                "{}",
+                format!("The wallet 0x{current} within {} will be replaced, save it with `settings` > Wallet first if you need it.", env_path.display()).yellow()
+            );
+        }
+    }
+    if !yes
+        && !Confirm::new(&tr!(
+            "Import 0x{address} into {path}?",
+            address = identity.address,
+            path = env_path.display()
+        ))
+        .with_default(!running_elsewhere)
+        .prompt()?
+    {
+        log::info!("Aborting, nothing is imported.");
+        return Ok(());
+    }
+
+    dria_env.set(DriaEnv::DKN_WALLET_KEY, &identity.secret_key);
+    if let Some(network) = &identity.network {
+        dria_env.set(DriaEnv::DKN_NETWORK_KEY, network);
+    }
+    if let Some(node_name) = &identity.node_name {
+        if dria_env.get_node_name().is_none() {
+            dria_env.set(DriaEnv::DKN_NODE_NAME_KEY, node_name);
+        }
+    }
+    dria_env.save_to_file(env_path)?;
+
+    eprintln!(
+        "Imported 0x{} into {}, you can delete {} now.",
+        identity.address,
+        env_path.display(),
+        path.display()
+    );
+
+    Ok(())
+}
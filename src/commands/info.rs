@@ -1,36 +1,220 @@
-use crate::utils::DriaEnv;
+use colored::Colorize;
+use indicatif::HumanBytes;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::Path;
 
-/// Show information about the current environment.
-pub fn show_info() {
-    let dria_env = DriaEnv::new_from_env();
+use crate::utils::{
+    check_ollama, launcher_log_path, profile_name, read_last_update_check, DriaEnv, DriaRelease,
+    EnvSource, DKN_LATEST_COMPUTE_FILE,
+};
+
+/// Show information about the environment within the given env file.
+///
+/// Values are read from the env file itself rather than the process environment, so that
+/// the selected `--env` / `--profile` is shown even if another env file was loaded before.
+/// Values that are not set within the file but come from the process environment are marked.
+///
+/// ### Arguments
+/// - `exe_dir`: directory where the compute node binaries are located
+/// - `env_path`: path to the environment file
+/// - `verbose`: show the runtime & filesystem details as well, see [`format_verbose_info`]
+///
+/// ### Errors
+/// - If the env file could not be read.
+pub async fn show_info(exe_dir: &Path, env_path: &Path, verbose: bool) -> eyre::Result<()> {
+    eprint!("{}", format_info(env_path)?);
+    if verbose {
+        eprint!("{}", format_verbose_info(exe_dir, env_path).await?);
+    }
+
+    Ok(())
+}
+
+/// Returns the information shown by [`show_info`], see `report` as well.
+///
+/// ### Errors
+/// - If the env file could not be read.
+pub(super) fn format_info(env_path: &Path) -> eyre::Result<String> {
+    let mut info = String::new();
+    let (dria_env, sources) = DriaEnv::new_from_file(env_path)?;
+    let source = |key: &str| source_suffix(&sources, key);
+
+    writeln!(info, "Env File: {}", env_path.display())?;
+
+    // name & labels
+    writeln!(
+        info,
+        "Name: {}{}",
+        dria_env.get_node_name().unwrap_or("none"),
+        source(DriaEnv::DKN_NODE_NAME_KEY)
+    )?;
+    let labels = dria_env.get_labels();
+    if !labels.is_empty() {
+        writeln!(
+            info,
+            "Labels: {}{}",
+            labels
+                .iter()
+                .map(|(k, v)| format!("{k}={v}"))
+                .collect::<Vec<_>>()
+                .join(", "),
+            source(DriaEnv::DKN_LABELS_KEY)
+        )?;
+    }
 
     // wallet
     if let Ok((_, _, addr)) = dria_env.get_account() {
-        eprintln!("Address: {}", addr);
+        writeln!(info, "Address: {}{}", addr, source(DriaEnv::DKN_WALLET_KEY))?;
     } else {
-        eprintln!("Address: no wallet configured!");
+        writeln!(info, "Address: no wallet configured!")?;
+    }
+
+    // API keys, masked
+    let masked_env = dria_env.masked();
+    let api_keys = DriaEnv::API_KEY_NAMES
+        .into_iter()
+        .filter_map(|key| {
+            masked_env
+                .get(key)
+                .filter(|value| !value.is_empty())
+                .map(|value| format!("{key}={value}{}", source(key)))
+        })
+        .collect::<Vec<_>>();
+    if !api_keys.is_empty() {
+        writeln!(info, "API Keys:\n - {}", api_keys.join("\n - "))?;
     }
 
     // log levels
-    eprintln!(
-        "Log Levels: {}",
-        dria_env.get(DriaEnv::LOG_LEVEL_KEY).unwrap_or("none")
-    );
+    writeln!(
+        info,
+        "Log Levels: {}{}",
+        dria_env.get(DriaEnv::LOG_LEVEL_KEY).unwrap_or("none"),
+        source(DriaEnv::LOG_LEVEL_KEY)
+    )?;
 
     // models
     let models = dria_env.get_models();
     if models.is_empty() {
-        eprintln!("Models: no models configured!");
+        writeln!(info, "Models: no models configured!")?;
     } else {
-        eprintln!(
-            "Models:\n - {}",
+        writeln!(
+            info,
+            "Models{}:\n - {}",
+            source(DriaEnv::DKN_MODELS_KEY),
             models
                 .iter()
                 .map(|m| m.to_string())
                 .collect::<Vec<String>>()
                 .join("\n - ")
-        );
+        )?;
     }
 
-    eprintln!("Version: {}", env!("CARGO_PKG_VERSION"));
+    writeln!(info, "Version: {}", env!("CARGO_PKG_VERSION"))?;
+
+    Ok(info)
+}
+
+/// Returns the runtime & filesystem details shown by `info --verbose` as a table: the env file
+/// & profile, OS, the downloaded compute node binaries, the last update check and whether the
+/// Ollama server is reachable.
+///
+/// ### Errors
+/// - If the env file could not be read.
+async fn format_verbose_info(exe_dir: &Path, env_path: &Path) -> eyre::Result<String> {
+    let (dria_env, sources) = DriaEnv::new_from_file(env_path)?;
+    let mut rows: Vec<(&str, String)> = Vec::new();
+
+    // env file & profile
+    let from_process = sources
+        .values()
+        .filter(|source| matches!(source, EnvSource::Process))
+        .count();
+    rows.push(("Profile", profile_name(env_path)));
+    rows.push(("Env file", env_path.display().to_string()));
+    rows.push((
+        "Env keys",
+        format!(
+            "{} from the file, {from_process} from the process environment",
+            sources.len() - from_process
+        ),
+    ));
+    rows.push((
+        "Launcher log",
+        launcher_log_path(env_path).display().to_string(),
+    ));
+    rows.push((
+        "OS",
+        format!("{}/{}", std::env::consts::OS, std::env::consts::ARCH),
+    ));
+
+    // compute node binaries & updates
+    rows.push(("Executables dir", exe_dir.display().to_string()));
+    rows.push((
+        "Version tracker",
+        DriaRelease::get_compute_version(exe_dir)
+            .map(|version| version.trim().to_string())
+            .unwrap_or_else(|| "none".to_string()),
+    ));
+    if let Ok(metadata) = std::fs::metadata(exe_dir.join(DKN_LATEST_COMPUTE_FILE)) {
+        rows.push((
+            "Latest binary",
+            format!(
+                "{} ({})",
+                DKN_LATEST_COMPUTE_FILE,
+                HumanBytes(metadata.len())
+            ),
+        ));
+    }
+    let binaries = super::releases::find_local_binaries(exe_dir).unwrap_or_default();
+    rows.push((
+        "Downloaded versions",
+        if binaries.is_empty() {
+            "none".to_string()
+        } else {
+            binaries
+                .iter()
+                .map(|binary| format!("v{} ({})", binary.version, HumanBytes(binary.size)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        },
+    ));
+    rows.push((
+        "Last update check",
+        read_last_update_check(exe_dir)
+            .and_then(|time| time.elapsed().ok())
+            .map(|elapsed| format!("{} ago", super::dashboard::format_duration(elapsed)))
+            .unwrap_or_else(|| "never".to_string()),
+    ));
+
+    // ollama
+    let (host, port) = dria_env.get_ollama_config();
+    rows.push((
+        "Ollama",
+        format!(
+            "{host}:{port}: {}",
+            if check_ollama(&dria_env).await {
+                "reachable".green()
+            } else {
+                "unreachable".red()
+            }
+        ),
+    ));
+
+    let width = rows.iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+    let mut info = String::new();
+    writeln!(info, "{}", "Details:".bold())?;
+    for (label, value) in rows {
+        writeln!(info, "  {label:<width$}  {value}")?;
+    }
+
+    Ok(info)
+}
+
+/// Returns a suffix to mark the values that do not come from the env file itself.
+fn source_suffix(sources: &HashMap<&'static str, EnvSource>, key: &str) -> String {
+    match sources.get(key) {
+        Some(EnvSource::Process) => format!(" ({})", EnvSource::Process).dimmed().to_string(),
+        _ => String::new(),
+    }
 }
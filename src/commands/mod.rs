@@ -1,6 +1,8 @@
 mod start;
 use std::path::PathBuf;
 
+use crate::utils::shared_data_dir;
+
 pub use start::run_compute_node;
 
 mod editor;
@@ -13,16 +15,16 @@ mod specific;
 pub use specific::download_specific_release;
 
 mod update;
-pub use update::update;
+pub use update::{check_updates, update, update_selected};
 
 mod setup;
-pub use setup::setup_environment;
+pub use setup::{setup_environment, setup_environment_from_config};
 
 mod info;
 pub use info::show_info;
 
 mod referrals;
-pub use referrals::handle_referrals;
+pub use referrals::{handle_referrals, ReferralsCommands};
 
 mod uninstall;
 pub use uninstall::uninstall_launcher;
@@ -30,19 +32,142 @@ pub use uninstall::uninstall_launcher;
 mod points;
 pub use points::show_points;
 
+mod credits;
+pub use credits::show_credits;
+
+mod config;
+pub use config::{handle_config, ConfigCommands};
+
+mod stop;
+pub use stop::stop_node;
+
+mod reload;
+pub use reload::reload_node;
+
+mod status;
+pub use status::show_status;
+
+mod dashboard;
+pub use dashboard::show_dashboard;
+
+mod report;
+pub use report::create_report;
+
+mod apikeys;
+pub use apikeys::{handle_apikeys, ApiKeysCommands};
+
+mod releases;
+pub use releases::{handle_releases, ReleasesCommands};
+
+mod sign;
+pub use sign::{sign_message, verify_message};
+
+mod identity;
+pub use identity::{handle_identity, IdentityCommands};
+
+mod models;
+pub use models::{handle_models, ModelsCommands};
+
+mod ollama;
+pub use ollama::{handle_ollama, OllamaCommands};
+
+mod fleet;
+pub use fleet::{handle_fleet, FleetCommands};
+
+mod remote;
+pub use remote::{handle_remote, RemoteCommands};
+
+mod generate;
+pub use generate::{handle_generate, GenerateCommands};
+
 /// Launcher commands.
 #[derive(clap::Subcommand)]
 pub enum Commands {
     /// Change node settings: models, api keys, network settings.
     Settings,
     /// Setup the environment file from scratch (will overwrite existing values).
-    Setup,
+    Setup {
+        /// Set up without prompts, with the values of the given environment file.
+        #[arg(long)]
+        config: Option<PathBuf>,
+    },
     /// Start the latest compute node
-    Start,
+    Start {
+        /// Take over the lock of another launcher running with the same environment file.
+        #[arg(long, default_value_t = false)]
+        take_over: bool,
+        /// Start without measuring the TPS of the Ollama models, which skips the models that
+        /// are too slow on this machine otherwise.
+        #[arg(long, default_value_t = false)]
+        skip_perf_check: bool,
+    },
+    /// Stop the node that is running with the selected environment file.
+    Stop,
+    /// Stop the running node, and start it again in this terminal.
+    Restart,
+    /// Reload the environment file of the running node, restarting the compute node if needed.
+    Reload,
+    /// Show the peer count & P2P reachability of the running node.
+    Status,
+    /// Show an overview of the node: status, versions, points, models & warnings.
+    Dashboard,
     /// Generate or enter a referral code.
-    Referrals,
+    Referrals {
+        /// Sign the referral challenges with a Ledger device instead of the wallet of the node (Linux only).
+        #[arg(long, default_value_t = false)]
+        ledger: bool,
+        /// Derivation path of the Ledger account.
+        #[arg(long, default_value = crate::utils::crypto::DEFAULT_DERIVATION_PATH, requires = "ledger")]
+        ledger_path: String,
+        #[command(subcommand)]
+        command: Option<ReferralsCommands>,
+    },
+    /// Sign a message with your wallet, e.g. to prove the ownership of your node.
+    Sign {
+        /// The message to sign.
+        #[arg(short, long)]
+        message: String,
+        /// Sign with a Ledger device instead of the wallet of the node (Linux only).
+        #[arg(long, default_value_t = false)]
+        ledger: bool,
+        /// Derivation path of the Ledger account.
+        #[arg(long, default_value = crate::utils::crypto::DEFAULT_DERIVATION_PATH, requires = "ledger")]
+        ledger_path: String,
+        /// Print the output as JSON.
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+    /// Verify that a message is signed by an address.
+    Verify {
+        /// The signed message.
+        #[arg(short, long)]
+        message: String,
+        /// The address that is expected to have signed the message.
+        #[arg(short, long)]
+        address: String,
+        /// The signature in hexadecimal, as printed by `sign`.
+        #[arg(short, long)]
+        sig: String,
+    },
+    /// Export or import the identity of the node, to move it to another machine.
+    Identity {
+        #[command(subcommand)]
+        command: IdentityCommands,
+    },
+    /// List, edit, measure, remove or pull models without the interactive menus.
+    Models {
+        #[command(subcommand)]
+        command: ModelsCommands,
+    },
+    /// Install Ollama for the launcher, if it is not installed on this machine.
+    Ollama {
+        #[command(subcommand)]
+        command: OllamaCommands,
+    },
     /// Show your $DRIA points.
     Points,
+    /// Show the credit balance & rate limits of your OpenRouter API key.
+    Credits,
     /// Uninstall the launcher & its files.
     Uninstall {
         /// Backup the environment file to the given path.
@@ -50,9 +175,32 @@ pub enum Commands {
         backup: Option<PathBuf>,
     },
     /// Show information about the current environment.
-    Info,
+    Info {
+        /// Show runtime & filesystem details as well, e.g. downloaded versions & Ollama status.
+        #[arg(short, long, default_value_t = false)]
+        verbose: bool,
+    },
+    /// Collect logs, the environment (secrets masked) & system details into a zip for bug reports.
+    Report {
+        /// Path of the zip file, defaults to `dria-report-<time>.zip` in the current directory.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
     /// Manually update the compute node & launcher.
-    Update,
+    Update {
+        /// Update the launcher only.
+        #[arg(long, default_value_t = false, conflicts_with = "compute_only")]
+        launcher_only: bool,
+        /// Update the compute node only.
+        #[arg(long, default_value_t = false)]
+        compute_only: bool,
+        /// Print the available versions without downloading anything.
+        #[arg(long, default_value_t = false)]
+        check: bool,
+        /// Update without showing the release notes for confirmation, e.g. for unattended runs.
+        #[arg(long, default_value_t = false)]
+        auto_approve: bool,
+    },
     /// Run a specific compute node version.
     Specific {
         /// Run the chosen executable immediately.
@@ -64,6 +212,36 @@ pub enum Commands {
     },
     /// Open a command-line text editor for your environment file (advanced).
     EnvEditor,
+    /// Manage the API keys of the current environment.
+    Apikeys {
+        #[command(subcommand)]
+        command: ApiKeysCommands,
+    },
+    /// Show the status of the nodes on several machines, via their management APIs.
+    Fleet {
+        #[command(subcommand)]
+        command: FleetCommands,
+    },
+    /// Install the launcher on other machines over SSH.
+    Remote {
+        #[command(subcommand)]
+        command: RemoteCommands,
+    },
+    /// Generate deployment snippets for the current environment, e.g. for Ansible.
+    Generate {
+        #[command(subcommand)]
+        command: GenerateCommands,
+    },
+    /// Manage the launcher configuration & data directory.
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+    /// List or prune the compute node versions that are downloaded locally.
+    Releases {
+        #[command(subcommand)]
+        command: ReleasesCommands,
+    },
 }
 
 /// Returns the default targeted environment file.
@@ -84,15 +262,13 @@ pub fn default_env() -> String {
     if cfg!(debug_assertions) {
         env_filename
     } else {
-        match homedir::my_home() {
-            Ok(Some(home)) => home
-                .join(".dria")
-                .join("dkn-compute-launcher")
+        match shared_data_dir() {
+            Some(dir) => dir
                 .join(&env_filename)
                 .into_os_string()
                 .into_string()
                 .unwrap_or(env_filename),
-            Ok(None) | Err(_) => env_filename,
+            None => env_filename,
         }
     }
 }
@@ -0,0 +1,365 @@
+use colored::Colorize;
+use dkn_executor::{Model, ModelProvider};
+use eyre::Result;
+use inquire::Confirm;
+use std::collections::HashSet;
+use std::path::Path;
+
+use crate::settings::{
+    edit_models, find_compat_issues, local_memory_mb, measure_models, DriaApiKeyKind,
+};
+use crate::tr;
+use crate::utils::{
+    check_ollama, connect_ollama, list_local_model_names, pull_models_with_progress, spawn_ollama,
+    OllamaPidFile,
+};
+use crate::DriaEnv;
+
+/// Model commands, the non-interactive counterparts of the models menu within `settings`.
+#[derive(clap::Subcommand)]
+pub enum ModelsCommands {
+    /// Print the chosen models, one per line.
+    List,
+    /// Change the model selection, or edit it interactively if no option is given.
+    Edit {
+        /// Replace the selection with the given models, separated by commas.
+        #[arg(long, value_delimiter = ',', conflicts_with_all = ["add", "remove"])]
+        set: Option<Vec<String>>,
+        /// Add the given models to the selection, separated by commas.
+        #[arg(long, value_delimiter = ',')]
+        add: Vec<String>,
+        /// Remove the given models from the selection, separated by commas.
+        #[arg(long, value_delimiter = ',')]
+        remove: Vec<String>,
+        /// Save the selection even if some models can not run on this machine.
+        #[arg(long, default_value_t = false)]
+        force: bool,
+    },
+    /// Measure the TPS of Ollama models on this machine.
+    Measure {
+        /// Names of the models to measure, defaults to the chosen Ollama models.
+        models: Vec<String>,
+        /// Measure again the models that are measured before, instead of showing the earlier
+        /// measurements.
+        #[arg(long, default_value_t = false)]
+        fresh: bool,
+    },
+    /// Remove models from Ollama (same as `ollama rm`).
+    Remove {
+        /// Names of the local models to remove.
+        #[arg(required = true)]
+        models: Vec<String>,
+        /// Remove without asking for confirmation.
+        #[arg(short, long, default_value_t = false)]
+        yes: bool,
+    },
+    /// Pull models to Ollama without starting the node, e.g. to prepare a machine beforehand.
+    Pull {
+        /// Names of the Ollama models to pull.
+        #[arg(
+            required_unless_present = "all_selected",
+            conflicts_with = "all_selected"
+        )]
+        models: Vec<String>,
+        /// Pull all of the chosen Ollama models.
+        #[arg(long, default_value_t = false)]
+        all_selected: bool,
+    },
+}
+
+/// Handles the model commands.
+pub async fn handle_models(command: &ModelsCommands, env_path: &Path) -> Result<()> {
+    let dria_env = DriaEnv::new_from_env();
+    match command {
+        ModelsCommands::List => {
+            let mut models = dria_env
+                .get_models()
+                .into_iter()
+                .map(|model| model.to_string())
+                .collect::<Vec<_>>();
+            models.sort();
+            for model in models {
+                println!("{model}");
+            }
+            Ok(())
+        }
+        ModelsCommands::Edit {
+            set,
+            add,
+            remove,
+            force,
+        } => edit_selection(dria_env, env_path, set.as_deref(), add, remove, *force),
+        ModelsCommands::Measure { models, fresh } => {
+            let models = if models.is_empty() {
+                chosen_ollama_models(&dria_env)
+            } else {
+                parse_ollama_models(models)?
+            };
+            if models.is_empty() {
+                eyre::bail!("No Ollama models are chosen, please give the models to measure.");
+            }
+
+            measure_models(&dria_env, &models, Some(*fresh)).await
+        }
+        ModelsCommands::Remove { models, yes } => remove_models(&dria_env, models, *yes).await,
+        ModelsCommands::Pull {
+            models,
+            all_selected,
+        } => {
+            let models = if *all_selected {
+                chosen_ollama_models(&dria_env)
+            } else {
+                parse_ollama_models(models)?
+            };
+            if models.is_empty() {
+                eyre::bail!("No Ollama models are chosen, please give the models to pull.");
+            }
+
+            pull_models(&dria_env, &models).await
+        }
+    }
+}
+
+/// Returns the chosen Ollama models, sorted by name.
+fn chosen_ollama_models(dria_env: &DriaEnv) -> Vec<Model> {
+    let mut models = dria_env
+        .get_models()
+        .into_iter()
+        .filter(|model| model.provider() == ModelProvider::Ollama)
+        .collect::<Vec<_>>();
+    models.sort_by_key(|model| model.to_string());
+
+    models
+}
+
+/// Parses the given model names.
+///
+/// ### Errors
+/// - If a name is not a known model
+fn parse_models(names: &[String]) -> Result<Vec<Model>> {
+    names
+        .iter()
+        .map(|name| {
+            Model::try_from(name.trim().to_string()).map_err(|_| {
+                eyre::eyre!("Unknown model {name}, see `models edit` for the available models.")
+            })
+        })
+        .collect()
+}
+
+/// Parses the given model names, which must be served by Ollama.
+///
+/// ### Errors
+/// - If a name is not a known model, or is not an Ollama model
+fn parse_ollama_models(names: &[String]) -> Result<Vec<Model>> {
+    let models = parse_models(names)?;
+    if let Some(model) = models
+        .iter()
+        .find(|model| model.provider() != ModelProvider::Ollama)
+    {
+        eyre::bail!("{model} is not an Ollama model.");
+    }
+
+    Ok(models)
+}
+
+/// Applies the given changes to the model selection and saves it, or edits the selection
+/// interactively if there are no changes given.
+///
+/// The selection is checked against this machine & the API keys before it is saved, see
+/// [`find_compat_issues`]; it is not saved if some models can not run, unless `force` is set.
+///
+/// ### Errors
+/// - If a model is unknown, or the selection becomes empty
+/// - If some models can not run on this machine, unless `force` is set
+/// - If the environment file could not be saved
+fn edit_selection(
+    mut dria_env: DriaEnv,
+    env_path: &Path,
+    set: Option<&[String]>,
+    add: &[String],
+    remove: &[String],
+    force: bool,
+) -> Result<()> {
+    if set.is_none() && add.is_empty() && remove.is_empty() {
+        edit_models(&mut dria_env)?;
+        if dria_env.is_changed() {
+            dria_env.save_to_file(env_path)?;
+        }
+        return Ok(());
+    }
+
+    let mut models = match set {
+        Some(set) => parse_models(set)?.into_iter().collect(),
+        None => dria_env.get_models(),
+    };
+    models.extend(parse_models(add)?);
+    for model in parse_models(remove)? {
+        models.remove(&model);
+    }
+    if models.is_empty() {
+        eyre::bail!("You must choose at least 1 model!");
+    }
+
+    let mut models = models.into_iter().collect::<Vec<_>>();
+    models.sort_by_key(|model| model.to_string());
+    let (gpu_memory_mb, memory_mb) = local_memory_mb(&dria_env, &models);
+    let issues = find_compat_issues(
+        &models,
+        dria_env.get(DriaEnv::DKN_MODELS_KEY).unwrap_or_default(),
+        gpu_memory_mb,
+        memory_mb,
+        |kind: &DriaApiKeyKind| {
+            dria_env
+                .get(kind.name())
+                .is_some_and(|key| !key.trim().is_empty())
+        },
+    );
+    for issue in &issues {
+        if issue.is_blocking() {
+            eprintln!("{} {issue}", "✗".red());
+        } else {
+            eprintln!("{} {issue}", "!".yellow());
+        }
+    }
+    if !force && issues.iter().any(|issue| issue.is_blocking()) {
+        eyre::bail!("Some models can not run on this machine, use --force to save them anyway.");
+    }
+
+    let names = models
+        .iter()
+        .map(|model| model.to_string())
+        .collect::<Vec<_>>();
+    if dria_env.get(DriaEnv::DKN_MODELS_KEY) == Some(names.join(",").as_str()) {
+        log::info!("No changes made.");
+        return Ok(());
+    }
+    dria_env.set(DriaEnv::DKN_MODELS_KEY, names.join(","));
+    dria_env.save_to_file(env_path)?;
+    log::info!("Chosen models:\n - {}", names.join("\n - "));
+
+    Ok(())
+}
+
+/// Removes the given models from Ollama, after a confirmation unless `yes` is set.
+///
+/// ### Errors
+/// - If Ollama is not available, or a model is not found locally
+async fn remove_models(dria_env: &DriaEnv, models: &[String], yes: bool) -> Result<()> {
+    if !check_ollama(dria_env).await {
+        eyre::bail!("Ollama is not available, please run Ollama server.");
+    }
+    let ollama = connect_ollama(dria_env)?;
+    let local_models = ollama
+        .list_local_models()
+        .await?
+        .into_iter()
+        .map(|model| model.name)
+        .collect::<HashSet<_>>();
+    if let Some(model) = models.iter().find(|model| !local_models.contains(*model)) {
+        eyre::bail!("Model {model} does not exist locally.");
+    }
+
+    let chosen = dria_env
+        .get_models()
+        .into_iter()
+        .map(|model| model.to_string())
+        .collect::<HashSet<_>>();
+    for model in models.iter().filter(|model| chosen.contains(*model)) {
+        log::warn!("{model} is a chosen model, it will be pulled again when the node starts.");
+    }
+    if !yes
+        && !Confirm::new(&tr!("Remove {models}?", models = models.join(", ")))
+            .with_default(false)
+            .prompt()?
+    {
+        log::info!("Aborting, nothing is removed.");
+        return Ok(());
+    }
+
+    for model in models {
+        ollama.delete_model(model.clone()).await?;
+        log::info!("Removed model {}", model);
+    }
+
+    Ok(())
+}
+
+/// Pulls the given models to Ollama with progress bars, skipping the ones that exist locally.
+///
+/// Ollama is started for the pulls if it is not running, as it is when the node starts; it is
+/// stopped afterwards, also if the pulls fail.
+///
+/// ### Errors
+/// - If Ollama is not available and could not be started
+/// - If a model could not be pulled
+async fn pull_models(dria_env: &DriaEnv, models: &[Model]) -> Result<()> {
+    let mut ollama_process = if check_ollama(dria_env).await {
+        None
+    } else {
+        log::info!("Ollama is not running, starting it for the pulls.");
+        Some(spawn_ollama(dria_env).await?)
+    };
+
+    let result = pull_missing_models(dria_env, models).await;
+
+    if let Some(process) = &mut ollama_process {
+        let pid = process.id();
+        if let Err(err) = process.kill().await {
+            log::warn!("Could not stop Ollama: {err}");
+        } else if let Some(pid) = pid {
+            OllamaPidFile::forget(pid);
+        }
+    }
+
+    result
+}
+
+/// See [`pull_models`], pulls the given models with a running Ollama.
+async fn pull_missing_models(dria_env: &DriaEnv, models: &[Model]) -> Result<()> {
+    let local_models = list_local_model_names(dria_env).await?;
+    let model_names = models
+        .iter()
+        .map(|model| model.to_string())
+        .filter(|model_name| {
+            let exists = local_models.contains(model_name);
+            if exists {
+                log::info!("Model {} already exists locally.", model_name);
+            }
+            !exists
+        })
+        .collect::<Vec<_>>();
+    if model_names.is_empty() {
+        return Ok(());
+    }
+
+    log::info!("Pulling models from Ollama...");
+    pull_models_with_progress(
+        &connect_ollama(dria_env)?,
+        model_names,
+        dria_env.get_pull_retries(),
+        dria_env.get_pull_parallelism(),
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_models() {
+        let ollama = Model::all_with_provider(&ModelProvider::Ollama)
+            .next()
+            .unwrap();
+        let openai = Model::all_with_provider(&ModelProvider::OpenAI)
+            .next()
+            .unwrap();
+        let names = [ollama.to_string(), format!(" {openai} ")];
+
+        assert_eq!(parse_models(&names).unwrap(), [ollama, openai]);
+        assert!(parse_models(&["foo".to_string()]).is_err());
+        assert_eq!(parse_ollama_models(&names[..1]).unwrap(), [ollama]);
+        assert!(parse_ollama_models(&names).is_err());
+    }
+}
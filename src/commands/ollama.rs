@@ -0,0 +1,33 @@
+use eyre::Result;
+
+use crate::utils::{install_ollama, is_ollama_installed};
+
+/// Ollama commands.
+#[derive(clap::Subcommand)]
+pub enum OllamaCommands {
+    /// Download the latest Ollama release for this machine and install it for the launcher.
+    Install {
+        /// Install without asking for confirmation.
+        #[arg(short, long, default_value_t = false)]
+        yes: bool,
+    },
+}
+
+/// Handles the Ollama commands.
+pub async fn handle_ollama(command: &OllamaCommands) -> Result<()> {
+    match command {
+        OllamaCommands::Install { yes } => {
+            if is_ollama_installed() {
+                log::warn!("Ollama is already installed, the one on PATH is used if there is any.");
+            }
+
+            match install_ollama(*yes).await? {
+                Some(exe_path) => {
+                    eprintln!("Ollama is installed at {}", exe_path.display())
+                }
+                None => log::info!("Aborting, Ollama is not installed."),
+            }
+            Ok(())
+        }
+    }
+}
@@ -1,28 +1,15 @@
 use colored::Colorize;
 use eyre::Context;
+use std::path::Path;
 
-use crate::utils::{get_network_env, DriaEnv, LAUNCHER_USER_AGENT};
-
-#[inline]
-fn get_points_api_url(address: &str) -> String {
-    let network = get_network_env();
-    let address = address.trim_start_matches("0x");
-    format!("https://{network}.dkn.dria.co/points/v0/total/node/0x{address}")
-}
-
-#[derive(Debug, serde::Deserialize)]
-pub struct PointsRes {
-    /// Indicates in which top percentile your points are.
-    pub percentile: usize,
-    /// The total number of points you have accumulated.
-    pub score: f64,
-}
+use crate::utils::{get_points, DriaEnv};
 
 /// Returns the $DRIA points for the users address.
 ///
+/// - Reads the wallet from the given env file itself, see [`DriaEnv::new_from_file`].
 /// - Will ask for user to enter their secret key if it is not set.
-pub async fn show_points() -> eyre::Result<()> {
-    let mut dria_env = DriaEnv::new_from_env();
+pub async fn show_points(env_path: &Path) -> eyre::Result<()> {
+    let (mut dria_env, _) = DriaEnv::new_from_file(env_path)?;
     dria_env.ask_for_key_if_required()?;
     let (_, _, address) = dria_env.get_account()?;
 
@@ -46,38 +33,3 @@ pub async fn show_points() -> eyre::Result<()> {
 
     Ok(())
 }
-
-async fn get_points(address: &str) -> eyre::Result<PointsRes> {
-    let url = get_points_api_url(address);
-
-    let client = reqwest::Client::builder()
-        .user_agent(LAUNCHER_USER_AGENT)
-        .build()
-        .wrap_err("could not create reqwest client")?;
-
-    let res = client
-        .get(&url)
-        .send()
-        .await
-        .wrap_err("could not make request")?;
-
-    let points = res
-        .json::<PointsRes>()
-        .await
-        .wrap_err("could not parse body")?;
-
-    Ok(points)
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[tokio::test]
-    async fn test_get_points() {
-        let address = "0x1234567890abcdef1234567890abcdef12345678";
-        let points = get_points(address).await.unwrap();
-        assert!(points.score >= 0.0);
-        assert!(points.percentile <= 100);
-    }
-}
@@ -1,23 +1,82 @@
 use colored::Colorize;
-use inquire::{Select, Text};
+use eyre::Context;
+use inquire::{Confirm, Select, Text};
+use qrcode::render::unicode::Dense1x2;
+use qrcode::{Color, QrCode};
+use std::path::PathBuf;
 
-use crate::utils::{referrals::*, DriaEnv, Selectable};
+use crate::tr;
+use crate::utils::{crypto::Signer, referrals::*, DriaEnv, LedgerSigner, Selectable};
 
-/// Referrals-related commands.
+/// Length of a referral code, w.r.t the referrals API.
+pub(super) const REFERRAL_CODE_LENGTH: usize = 20;
+
+/// Non-interactive referral commands, for scripts & bots.
+#[derive(clap::Subcommand)]
+pub enum ReferralsCommands {
+    /// Print your referral code.
+    GetCode {
+        /// Print the output as JSON.
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+    /// Enter a referral code to be referred.
+    Enter {
+        /// The referral code to enter.
+        #[arg(long)]
+        code: String,
+        /// Print the output as JSON.
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+    /// List the addresses referred by you, and the address that referred you.
+    List {
+        /// Print the output as JSON.
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+}
+
+/// Referrals-related commands, shows an interactive menu if no command is given.
 ///
-/// - Will ask for user to enter their secret key if it is not set.
-pub async fn handle_referrals() -> eyre::Result<()> {
+/// The challenges are signed with the wallet of the node, or with the account at the given
+/// derivation path of a Ledger device if `ledger_path` is given.
+///
+/// - Will ask for user to enter their secret key if it is not set, only within the menu.
+pub async fn handle_referrals(
+    command: Option<&ReferralsCommands>,
+    ledger_path: Option<&str>,
+) -> eyre::Result<()> {
     // ensure system is healthy
     let client = ReferralsClient::default();
 
-    // get wallet secret from env
-    let mut dria_env = DriaEnv::new_from_env();
-    dria_env.ask_for_key_if_required()?;
-    let (sk, _, addr) = dria_env.get_account()?;
+    let signer = match ledger_path {
+        Some(ledger_path) => {
+            let ledger = LedgerSigner::connect(ledger_path)?;
+            eprintln!("Using the Ledger account 0x{}", ledger.address());
+            Signer::Ledger(ledger)
+        }
+        None => {
+            // get wallet secret from env
+            let mut dria_env = DriaEnv::new_from_env();
+            if command.is_none() {
+                dria_env.ask_for_key_if_required()?;
+            }
+            let (secret_key, _, address) = dria_env.get_account()?;
+            Signer::SecretKey {
+                secret_key,
+                address,
+            }
+        }
+    };
+    if let Some(command) = command {
+        return handle_referrals_command(command, &client, &signer).await;
+    }
+    let addr = signer.address().to_string();
 
     loop {
         let Selectable::Some(choice) = Select::new(
-            "Choose a command below:",
+            tr!("Choose a command below:"),
             Selectable::new(vec![
                 ReferralCommands::GetReferralCode,
                 ReferralCommands::EnterReferralCode,
@@ -25,7 +84,7 @@ pub async fn handle_referrals() -> eyre::Result<()> {
                 ReferralCommands::ShowReferredBy,
             ]),
         )
-        .with_help_message("↑↓ to move, ENTER to select")
+        .with_help_message(tr!("↑↓ to move, ENTER to select"))
         .prompt()?
         else {
             break;
@@ -50,8 +109,10 @@ pub async fn handle_referrals() -> eyre::Result<()> {
                 }
 
                 // get the referral code
-                let code = client.get_referral_code(&sk, &addr).await?;
+                let code = client.get_referral_code(&signer).await?;
                 eprintln!("\nYour referral code is: {}", code.bold().blue());
+                let qr = QrCode::new(&code).wrap_err("could not create QR code")?;
+                eprintln!("{}", render_terminal_qr(&qr));
 
                 if referrals.len() >= max_uses {
                     eprintln!("You have reached the maximum number of referrals! You cannot refer more users.");
@@ -76,6 +137,22 @@ Use my referral code {} to get started: https://dria.co/join"#,
                         "Share on Twitter by clicking the link below!\n{}",
                         tweet_url
                     );
+
+                    if Confirm::new(tr!("Save a shareable card with your referral code?"))
+                        .with_help_message(tr!(
+                            "An SVG image with the QR code, referral code & your address"
+                        ))
+                        .with_default(false)
+                        .prompt()?
+                    {
+                        let path = Text::new(tr!("Enter the path to save the card:"))
+                            .with_default(&format!("dria-referral-{code}.svg"))
+                            .prompt()?;
+                        let path = PathBuf::from(path);
+                        std::fs::write(&path, render_referral_card(&qr, &code, &addr))
+                            .wrap_err("could not write referral card")?;
+                        eprintln!("Referral card is saved at {}", path.display());
+                    }
                 }
             }
             ReferralCommands::EnterReferralCode => {
@@ -83,19 +160,23 @@ Use my referral code {} to get started: https://dria.co/join"#,
                 if let Some(referred_by) = client.get_referred_by(&addr).await? {
                     eprintln!("You are already referred by 0x{}", referred_by);
                 } else {
-                    let code = Text::new("Enter the referral code:")
+                    let code = Text::new(tr!("Enter the referral code:"))
                         .with_validator(|code: &str| {
                             // code length here is hardcoded w.r.t referrals API
-                            if code.len() == 20 {
+                            if code.len() == REFERRAL_CODE_LENGTH {
                                 Ok(inquire::validator::Validation::Valid)
                             } else {
                                 Ok(inquire::validator::Validation::Invalid(
-                                    "The referral code must be 20 characters long.".into(),
+                                    tr!(
+                                        "The referral code must be {length} characters long.",
+                                        length = REFERRAL_CODE_LENGTH
+                                    )
+                                    .into(),
                                 ))
                             }
                         })
                         .prompt()?;
-                    client.enter_referral_code(&sk, &code).await?;
+                    client.enter_referral_code(&signer, &code).await?;
                 }
             }
             ReferralCommands::ShowReferrals => {
@@ -122,6 +203,127 @@ Use my referral code {} to get started: https://dria.co/join"#,
     Ok(())
 }
 
+/// Handles a non-interactive referral command, printing its result to stdout as plain text or JSON.
+///
+/// ### Errors
+/// - If a request to the referrals API fails
+/// - If the referral code is invalid, or you are already referred
+async fn handle_referrals_command(
+    command: &ReferralsCommands,
+    client: &ReferralsClient,
+    signer: &Signer,
+) -> eyre::Result<()> {
+    let addr = signer.address();
+    match command {
+        ReferralsCommands::GetCode { json } => {
+            let (code, max_uses, referrals) = tokio::try_join!(
+                client.get_referral_code(signer),
+                client.get_max_uses(addr),
+                client.get_referrals(addr)
+            )?;
+            let uses = referrals.unwrap_or_default().len();
+            if *json {
+                println!(
+                    "{}",
+                    serde_json::json!({ "code": code, "uses": uses, "max_uses": max_uses })
+                );
+            } else {
+                println!("{code}");
+                eprintln!("Used {uses} of {max_uses} times.");
+            }
+        }
+        ReferralsCommands::Enter { code, json } => {
+            if code.len() != REFERRAL_CODE_LENGTH {
+                eyre::bail!("The referral code must be {REFERRAL_CODE_LENGTH} characters long.");
+            }
+            if let Some(referred_by) = client.get_referred_by(addr).await? {
+                eyre::bail!("You are already referred by 0x{referred_by}");
+            }
+
+            client.enter_referral_code(signer, code).await?;
+            if *json {
+                println!("{}", serde_json::json!({ "entered": true, "code": code }));
+            }
+        }
+        ReferralsCommands::List { json } => {
+            let (referrals, referred_by) =
+                tokio::join!(client.get_referrals(addr), client.get_referred_by(addr));
+            let referrals = referrals?.unwrap_or_default();
+            let referred_by = referred_by?;
+            if *json {
+                println!(
+                    "{}",
+                    serde_json::json!({ "referrals": referrals, "referred_by": referred_by })
+                );
+            } else {
+                for referral in &referrals {
+                    println!("{referral}");
+                }
+                match referred_by {
+                    Some(referred_by) => eprintln!("You are referred by 0x{referred_by}"),
+                    None => eprintln!("You are not referred by anyone."),
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Size of a QR code module within the referral card, in pixels.
+const CARD_MODULE_SIZE: usize = 8;
+/// Padding around the QR code within the referral card, in pixels.
+const CARD_PADDING: usize = 32;
+/// Height of the text area below the QR code within the referral card, in pixels.
+const CARD_TEXT_HEIGHT: usize = 96;
+
+/// Renders the QR code for the terminal, with light modules on dark so that it scans on dark terminals.
+fn render_terminal_qr(qr: &QrCode) -> String {
+    qr.render::<Dense1x2>()
+        .dark_color(Dense1x2::Light)
+        .light_color(Dense1x2::Dark)
+        .quiet_zone(true)
+        .build()
+}
+
+/// Renders a shareable SVG card with the QR code, the referral code and the address.
+fn render_referral_card(qr: &QrCode, code: &str, address: &str) -> String {
+    let width = qr.width();
+    let qr_size = width * CARD_MODULE_SIZE;
+    let card_width = qr_size + 2 * CARD_PADDING;
+    let card_height = qr_size + 2 * CARD_PADDING + CARD_TEXT_HEIGHT;
+
+    // one rectangle per dark module
+    let modules = qr
+        .to_colors()
+        .into_iter()
+        .enumerate()
+        .filter(|(_, color)| *color == Color::Dark)
+        .map(|(i, _)| {
+            format!(
+                r#"<rect x="{}" y="{}" width="{CARD_MODULE_SIZE}" height="{CARD_MODULE_SIZE}"/>"#,
+                CARD_PADDING + (i % width) * CARD_MODULE_SIZE,
+                CARD_PADDING + (i / width) * CARD_MODULE_SIZE
+            )
+        })
+        .collect::<String>();
+
+    let text_y = CARD_PADDING + qr_size + CARD_PADDING;
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{card_width}" height="{card_height}" viewBox="0 0 {card_width} {card_height}">
+<rect width="100%" height="100%" rx="16" fill="#ffffff"/>
+<g fill="#000000">{modules}</g>
+<text x="50%" y="{text_y}" text-anchor="middle" font-family="monospace" font-size="22" font-weight="bold" fill="#000000">{code}</text>
+<text x="50%" y="{}" text-anchor="middle" font-family="monospace" font-size="11" fill="#555555">0x{}</text>
+<text x="50%" y="{}" text-anchor="middle" font-family="sans-serif" font-size="14" fill="#000000">Join Dria at dria.co/join</text>
+</svg>
+"##,
+        text_y + 28,
+        address.trim_start_matches("0x"),
+        text_y + 56,
+    )
+}
+
 enum ReferralCommands {
     GetReferralCode,
     EnterReferralCode,
@@ -132,10 +334,28 @@ enum ReferralCommands {
 impl std::fmt::Display for ReferralCommands {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::GetReferralCode => write!(f, "Get referral code to refer someone"),
-            Self::EnterReferralCode => write!(f, "Enter referral code to be referred"),
-            Self::ShowReferrals => write!(f, "List addresses referred by you"),
-            Self::ShowReferredBy => write!(f, "Show the address that referred you"),
+            Self::GetReferralCode => write!(f, "{}", tr!("Get referral code to refer someone")),
+            Self::EnterReferralCode => write!(f, "{}", tr!("Enter referral code to be referred")),
+            Self::ShowReferrals => write!(f, "{}", tr!("List addresses referred by you")),
+            Self::ShowReferredBy => write!(f, "{}", tr!("Show the address that referred you")),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_referral_card() {
+        let code = "abcdefghij0123456789";
+        let qr = QrCode::new(code).unwrap();
+        let card = render_referral_card(&qr, code, "0x1234");
+        assert!(card.starts_with("<svg"));
+        assert!(card.contains(&format!(">{code}</text>")));
+        assert!(card.contains(">0x1234</text>"));
+
+        let dark_modules = qr.to_colors().iter().filter(|c| **c == Color::Dark).count();
+        assert_eq!(card.matches(r#"width="8""#).count(), dark_modules);
+    }
+}
@@ -0,0 +1,243 @@
+use colored::Colorize;
+use eyre::{Context, Result};
+use indicatif::HumanBytes;
+use inquire::Confirm;
+use std::path::{Path, PathBuf};
+
+use crate::tr;
+use crate::utils::{parse_version, DriaRelease, DKN_LATEST_COMPUTE_FILE};
+
+/// Filename prefix of the compute node binaries that are downloaded for a specific version,
+/// see [`DriaRelease::to_filename`].
+const VERSIONED_BINARY_PREFIX: &str = "dkn-compute-node_v";
+
+/// Local release commands.
+#[derive(clap::Subcommand)]
+pub enum ReleasesCommands {
+    /// List the compute node binaries that are downloaded, along with their sizes.
+    List,
+    /// Delete older compute node binaries, the current & running versions are always kept.
+    Prune {
+        /// Number of the most recent versions to keep.
+        #[arg(long, default_value_t = 2)]
+        keep: usize,
+        /// Delete without asking for confirmation.
+        #[arg(short, long, default_value_t = false)]
+        yes: bool,
+    },
+}
+
+/// Handles the local release commands.
+pub fn handle_releases(command: &ReleasesCommands, exe_dir: &Path) -> Result<()> {
+    match command {
+        ReleasesCommands::List => list_releases(exe_dir),
+        ReleasesCommands::Prune { keep, yes } => prune_releases(exe_dir, *keep, *yes),
+    }
+}
+
+/// A compute node binary that is downloaded for a specific version, e.g. `dkn-compute-node_v0.6.5`.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct LocalBinary {
+    pub path: PathBuf,
+    pub version: String,
+    pub size: u64,
+}
+
+/// Lists the compute node binaries under the given directory, the latest one first.
+fn list_releases(exe_dir: &Path) -> Result<()> {
+    let current = current_version(exe_dir);
+    let latest_path = exe_dir.join(DKN_LATEST_COMPUTE_FILE);
+    let binaries = find_local_binaries(exe_dir)?;
+    if binaries.is_empty() && !latest_path.is_file() {
+        eprintln!("No compute node binaries found in {}", exe_dir.display());
+        return Ok(());
+    }
+
+    eprintln!("Compute node binaries in {}:", exe_dir.display());
+    let mut total = 0;
+    if let Ok(metadata) = std::fs::metadata(&latest_path) {
+        total += metadata.len();
+        eprintln!(
+            "  {:<10} {:>10}  {}",
+            current
+                .as_deref()
+                .map(|v| format!("v{v}"))
+                .unwrap_or_default(),
+            HumanBytes(metadata.len()).to_string(),
+            "latest, used by `start`".green()
+        );
+    }
+    for binary in &binaries {
+        total += binary.size;
+        let note = if is_running(&binary.path) {
+            "running".yellow().to_string()
+        } else {
+            String::new()
+        };
+        eprintln!(
+            "  {:<10} {:>10}  {note}",
+            format!("v{}", binary.version),
+            HumanBytes(binary.size).to_string(),
+        );
+    }
+    eprintln!("Total: {}", HumanBytes(total));
+
+    Ok(())
+}
+
+/// Deletes the versioned binaries under the given directory except the most recent `keep` ones,
+/// the one of the current version and the ones that are running.
+fn prune_releases(exe_dir: &Path, keep: usize, yes: bool) -> Result<()> {
+    let binaries = find_local_binaries(exe_dir)?;
+    let current = current_version(exe_dir);
+    let prunable = select_prunable(&binaries, keep, current.as_deref())
+        .into_iter()
+        .filter(|binary| {
+            let running = is_running(&binary.path);
+            if running {
+                log::info!("Keeping v{} as it is running.", binary.version);
+            }
+            !running
+        })
+        .collect::<Vec<_>>();
+    if prunable.is_empty() {
+        eprintln!("Nothing to prune.");
+        return Ok(());
+    }
+
+    let freed = prunable.iter().map(|binary| binary.size).sum::<u64>();
+    eprintln!(
+        "Deleting {}, which frees {}.",
+        prunable
+            .iter()
+            .map(|binary| format!("v{}", binary.version))
+            .collect::<Vec<_>>()
+            .join(", "),
+        HumanBytes(freed)
+    );
+    if !yes && !Confirm::new(tr!("Continue?")).with_default(true).prompt()? {
+        return Ok(());
+    }
+
+    for binary in prunable {
+        std::fs::remove_file(&binary.path)
+            .wrap_err_with(|| format!("could not delete {}", binary.path.display()))?;
+    }
+    eprintln!("Pruned, {} is freed.", HumanBytes(freed));
+
+    Ok(())
+}
+
+/// Returns the version of the `latest` compute node binary, without the `v` prefix.
+fn current_version(exe_dir: &Path) -> Option<String> {
+    DriaRelease::get_compute_version(exe_dir)
+        .map(|version| version.trim().trim_start_matches('v').to_string())
+}
+
+/// Returns the versioned compute node binaries under the given directory, the most recent first.
+pub(super) fn find_local_binaries(exe_dir: &Path) -> Result<Vec<LocalBinary>> {
+    let mut binaries = std::fs::read_dir(exe_dir)
+        .wrap_err_with(|| format!("could not read {}", exe_dir.display()))?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let version = parse_binary_version(&entry.file_name().to_string_lossy())?;
+            let metadata = entry
+                .metadata()
+                .ok()
+                .filter(|metadata| metadata.is_file())?;
+            Some(LocalBinary {
+                path: entry.path(),
+                version,
+                size: metadata.len(),
+            })
+        })
+        .collect::<Vec<_>>();
+    binaries.sort_by_key(|binary| std::cmp::Reverse(parse_version(&binary.version)));
+
+    Ok(binaries)
+}
+
+/// Returns the version within a versioned binary name like `dkn-compute-node_v0.6.5`.
+fn parse_binary_version(file_name: &str) -> Option<String> {
+    let ext = DriaRelease::get_labels().map_or("", |(_, _, ext)| ext);
+    let version = file_name
+        .strip_prefix(VERSIONED_BINARY_PREFIX)?
+        .strip_suffix(ext)?;
+
+    parse_version(version)
+        .is_some()
+        .then(|| version.to_string())
+}
+
+/// Returns the binaries to delete among the given ones (the most recent first), keeping the
+/// most recent `keep` ones and the one of the current version.
+fn select_prunable<'a>(
+    binaries: &'a [LocalBinary],
+    keep: usize,
+    current: Option<&str>,
+) -> Vec<&'a LocalBinary> {
+    binaries
+        .iter()
+        .skip(keep)
+        .filter(|binary| Some(binary.version.as_str()) != current)
+        .collect()
+}
+
+/// Returns `true` if a process is running the binary at the given path.
+fn is_running(path: &Path) -> bool {
+    use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
+
+    let Ok(path) = path.canonicalize() else {
+        return false;
+    };
+    let mut system = System::new();
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::All,
+        true,
+        ProcessRefreshKind::nothing().with_exe(UpdateKind::OnlyIfNotSet),
+    );
+
+    system
+        .processes()
+        .values()
+        .any(|process| process.exe() == Some(path.as_path()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_prunable() {
+        let ext = DriaRelease::get_labels().map_or("", |(_, _, ext)| ext);
+        assert_eq!(
+            parse_binary_version(&format!("dkn-compute-node_v0.6.5{ext}")).as_deref(),
+            Some("0.6.5")
+        );
+        assert_eq!(parse_binary_version(DKN_LATEST_COMPUTE_FILE), None);
+        assert_eq!(parse_binary_version("dkn-compute-node_vfoo"), None);
+
+        let binaries = ["0.6.6", "0.6.5", "0.6.4", "0.6.3"]
+            .map(|version| LocalBinary {
+                path: PathBuf::from(format!("dkn-compute-node_v{version}")),
+                version: version.to_string(),
+                size: 1,
+            })
+            .to_vec();
+        let versions = |prunable: Vec<&LocalBinary>| {
+            prunable
+                .into_iter()
+                .map(|binary| binary.version.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            versions(select_prunable(&binaries, 2, None)),
+            ["0.6.4", "0.6.3"]
+        );
+        assert_eq!(
+            versions(select_prunable(&binaries, 1, Some("0.6.4"))),
+            ["0.6.5", "0.6.3"]
+        );
+        assert!(select_prunable(&binaries, 4, None).is_empty());
+    }
+}
@@ -0,0 +1,35 @@
+use eyre::{Context, Result};
+use std::path::Path;
+
+use crate::utils::{request_reload, NodeLock};
+
+/// Asks the launcher that is running with the given environment file to reload it, which is
+/// the same as `kill -HUP <launcher PID>`.
+///
+/// The running launcher applies the changes by itself, restarting the compute node if needed;
+/// see its logs for the outcome.
+///
+/// ### Arguments
+/// - `exe_dir`: directory where the lock file is located
+/// - `env_path`: path to the environment file of the running node
+///
+/// ### Returns
+/// `true` if a running launcher was asked to reload, `false` if there was no running launcher.
+///
+/// ### Errors
+/// - If the launcher could not be signalled, e.g. on Windows
+pub fn reload_node(exe_dir: &Path, env_path: &Path) -> Result<bool> {
+    let Some(pid) = NodeLock::holder(exe_dir, env_path) else {
+        log::info!("No running node found for {}", env_path.display());
+        return Ok(false);
+    };
+
+    request_reload(pid)
+        .wrap_err_with(|| format!("could not reload the launcher with PID {pid}"))?;
+    log::info!(
+        "Asked the launcher with PID {pid} to reload {}, see its logs for the changes.",
+        env_path.display()
+    );
+
+    Ok(true)
+}
@@ -0,0 +1,336 @@
+use colored::Colorize;
+use eyre::{eyre, Context, Result};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Name of the systemd service that runs the launcher on remote machines.
+const REMOTE_SERVICE_NAME: &str = "dkn-compute-launcher";
+/// Data directory of the launcher on remote machines, relative to the home directory.
+const REMOTE_DATA_DIR: &str = ".dria/dkn-compute-launcher";
+/// Seconds to wait for an SSH connection.
+const SSH_CONNECT_TIMEOUT_SECS: u64 = 15;
+
+/// Remote machine commands, over SSH.
+#[derive(clap::Subcommand)]
+pub enum RemoteCommands {
+    /// Copy the launcher to a machine over SSH, set it up with the given config, and install it
+    /// as a systemd service.
+    Install {
+        /// The machine to install to, as `user@host`.
+        destination: String,
+        /// Environment file to set up the node with, e.g. with its wallet & models; a new wallet
+        /// is generated if it has none.
+        #[arg(long)]
+        config: PathBuf,
+        /// Launcher binary to copy, defaults to this one; required if the machine has a
+        /// different OS or architecture.
+        #[arg(long)]
+        binary: Option<PathBuf>,
+        /// Port of the SSH server.
+        #[arg(long)]
+        ssh_port: Option<u16>,
+        /// Identity (private key) file for SSH.
+        #[arg(short, long)]
+        identity: Option<PathBuf>,
+        /// Do not install the systemd service, only the launcher & its environment.
+        #[arg(long, default_value_t = false)]
+        no_service: bool,
+    },
+}
+
+/// Handles the remote machine commands.
+pub fn handle_remote(command: &RemoteCommands) -> Result<()> {
+    match command {
+        RemoteCommands::Install {
+            destination,
+            config,
+            binary,
+            ssh_port,
+            identity,
+            no_service,
+        } => {
+            let ssh = Ssh {
+                destination,
+                port: *ssh_port,
+                identity: identity.as_deref(),
+            };
+            remote_install(&ssh, config, binary.as_deref(), !no_service)
+        }
+    }
+}
+
+/// An SSH connection to a remote machine, each command runs with a new `ssh` process.
+struct Ssh<'a> {
+    destination: &'a str,
+    port: Option<u16>,
+    identity: Option<&'a Path>,
+}
+
+impl Ssh<'_> {
+    /// Runs the given shell script on the remote machine, with the given input; returns its
+    /// output, while its errors & logs are shown as is.
+    fn run(&self, script: &str, input: Option<&[u8]>) -> Result<String> {
+        let mut command = Command::new("ssh");
+        command.args(["-o", &format!("ConnectTimeout={SSH_CONNECT_TIMEOUT_SECS}")]);
+        if let Some(port) = self.port {
+            command.args(["-p", &port.to_string()]);
+        }
+        if let Some(identity) = self.identity {
+            command.arg("-i").arg(identity);
+        }
+        command
+            .arg(self.destination)
+            .arg(script)
+            .stdin(if input.is_some() {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit());
+
+        let mut child = command.spawn().wrap_err("could not run ssh")?;
+        if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+            stdin
+                .write_all(input)
+                .wrap_err("could not write to the remote machine")?;
+        }
+        let output = child.wait_with_output().wrap_err("could not run ssh")?;
+
+        if !output.status.success() {
+            return Err(eyre!("remote command failed ({})", output.status));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+}
+
+/// Details of the remote machine, see [`RemoteInfo::PROBE_SCRIPT`].
+#[derive(Debug, PartialEq)]
+struct RemoteInfo {
+    /// OS w.r.t [`std::env::consts::OS`], e.g. `linux`.
+    os: String,
+    /// Architecture w.r.t [`std::env::consts::ARCH`], e.g. `x86_64`.
+    arch: String,
+    home: String,
+    user: String,
+    has_systemd: bool,
+}
+
+impl RemoteInfo {
+    /// Prints the OS, architecture, home directory, user and `systemctl` path; one per line.
+    const PROBE_SCRIPT: &'static str =
+        r#"uname -s; uname -m; printf '%s\n' "$HOME"; id -un; command -v systemctl || true"#;
+
+    /// Parses the output of [`RemoteInfo::PROBE_SCRIPT`].
+    fn parse(output: &str) -> Result<Self> {
+        let mut lines = output.lines().map(str::trim);
+        let mut next = |name: &str| {
+            lines
+                .next()
+                .filter(|line| !line.is_empty())
+                .map(String::from)
+                .ok_or_else(|| eyre!("could not detect the {name} of the remote machine"))
+        };
+
+        let os = match next("OS")?.as_str() {
+            "Linux" => "linux".to_string(),
+            "Darwin" => "macos".to_string(),
+            other => other.to_lowercase(),
+        };
+        let arch = match next("architecture")?.as_str() {
+            "arm64" => "aarch64".to_string(),
+            "amd64" => "x86_64".to_string(),
+            other => other.to_string(),
+        };
+        let home = next("home directory")?;
+        let user = next("user")?;
+        let has_systemd = next("systemd").is_ok();
+
+        Ok(Self {
+            os,
+            arch,
+            home,
+            user,
+            has_systemd,
+        })
+    }
+}
+
+/// Installs the launcher to the remote machine:
+///
+/// 1. Detects the OS & architecture of the machine
+/// 2. Copies the launcher binary to `~/.dria/dkn-compute-launcher`
+/// 3. Sets up the environment with the config, see `setup --config`
+/// 4. Installs & starts the systemd service, if requested and available
+///
+/// The service is installed as root, or with `sudo` that must not ask for a password.
+///
+/// ### Errors
+/// - If `ssh` is not installed, or any remote command fails
+/// - If the machine has a different OS or architecture, and no binary is given
+/// - If the config or the binary could not be read
+fn remote_install(ssh: &Ssh, config: &Path, binary: Option<&Path>, service: bool) -> Result<()> {
+    if ssh.destination.starts_with('-') {
+        eyre::bail!("invalid destination: {}", ssh.destination);
+    }
+    which::which("ssh").wrap_err("ssh is not installed")?;
+    let config = std::fs::read(config)
+        .wrap_err_with(|| format!("could not read config at {}", config.display()))?;
+
+    // platform
+    eprintln!("Connecting to {}...", ssh.destination.bold());
+    let remote = RemoteInfo::parse(&ssh.run(RemoteInfo::PROBE_SCRIPT, None)?)?;
+    let binary = match binary {
+        Some(binary) => binary.to_path_buf(),
+        None if remote.os == std::env::consts::OS && remote.arch == std::env::consts::ARCH => {
+            std::env::current_exe().wrap_err("could not find the launcher binary")?
+        }
+        None => eyre::bail!(
+            "{} is {}-{} while this launcher is {}-{}, download the launcher for it from https://github.com/firstbatchxyz/dkn-compute-launcher/releases and pass it with --binary",
+            ssh.destination,
+            remote.os,
+            remote.arch,
+            std::env::consts::OS,
+            std::env::consts::ARCH
+        ),
+    };
+    let binary_bytes = std::fs::read(&binary)
+        .wrap_err_with(|| format!("could not read binary at {}", binary.display()))?;
+
+    // launcher, renamed into place so that a running launcher is not overwritten
+    let data_dir = format!("{}/{REMOTE_DATA_DIR}", remote.home);
+    let launcher_path = format!("{data_dir}/dkn-compute-launcher");
+    let env_path = format!("{data_dir}/.env");
+    eprintln!("Copying the launcher to {launcher_path}...");
+    ssh.run(
+        &format!(
+            "mkdir -p {dir} && cat > {tmp} && chmod +x {tmp} && mv {tmp} {path}",
+            dir = shell_quote(&data_dir),
+            tmp = shell_quote(&format!("{launcher_path}.tmp")),
+            path = shell_quote(&launcher_path),
+        ),
+        Some(&binary_bytes),
+    )?;
+
+    // environment, the config has secrets so it is only readable by the user and removed after;
+    // its name is not a valid profile, so the port checks do not take it as another profile
+    eprintln!("Setting up the environment at {env_path}...");
+    let config_path = format!("{data_dir}/.env.remote-setup.tmp");
+    ssh.run(
+        &format!(
+            "umask 077 && cat > {config}; {launcher} --env {env} setup --config {config}; status=$?; rm -f {config}; exit $status",
+            config = shell_quote(&config_path),
+            launcher = shell_quote(&launcher_path),
+            env = shell_quote(&env_path),
+        ),
+        Some(&config),
+    )?;
+
+    // service
+    let start_command = format!("{launcher_path} --env {env_path} start");
+    if !service {
+        eprintln!(
+            "Installed, start the node on {} with: {}",
+            ssh.destination,
+            start_command.bold()
+        );
+        return Ok(());
+    }
+    if !remote.has_systemd {
+        log::warn!(
+            "{} has no systemd, start the node there with: {start_command}",
+            ssh.destination
+        );
+        return Ok(());
+    }
+
+    eprintln!("Installing the {REMOTE_SERVICE_NAME} service...");
+    let sudo = if remote.user == "root" {
+        ""
+    } else {
+        "sudo -n "
+    };
+    ssh.run(
+        &format!(
+            "{sudo}tee /etc/systemd/system/{REMOTE_SERVICE_NAME}.service > /dev/null && {sudo}systemctl daemon-reload && {sudo}systemctl enable {REMOTE_SERVICE_NAME} && {sudo}systemctl restart {REMOTE_SERVICE_NAME}"
+        ),
+        Some(systemd_unit(&remote.user, &launcher_path, &env_path).as_bytes()),
+    )
+    .wrap_err("could not install the service, it requires root or sudo without a password")?;
+
+    eprintln!(
+        "{} is running on {}, follow its logs with: {}",
+        REMOTE_SERVICE_NAME.green(),
+        ssh.destination,
+        format!(
+            "ssh {} journalctl -u {REMOTE_SERVICE_NAME} -f",
+            ssh.destination
+        )
+        .bold()
+    );
+
+    Ok(())
+}
+
+/// Returns the systemd unit that runs the launcher as the given user, restarting it on failures
+/// except for configuration errors & unanswered prompts, see [`ExitReason`](crate::utils::ExitReason).
+pub(super) fn systemd_unit(user: &str, launcher_path: &str, env_path: &str) -> String {
+    format!(
+        "[Unit]
+Description=Dria Compute Launcher
+After=network-online.target
+Wants=network-online.target
+
+[Service]
+User={user}
+ExecStart=\"{launcher_path}\" --env \"{env_path}\" start
+Environment=DKN_PROMPT_TIMEOUT_SECS=60
+Restart=on-failure
+RestartPreventExitStatus=10 75
+
+[Install]
+WantedBy=multi-user.target
+"
+    )
+}
+
+/// Quotes the given value for a POSIX shell.
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remote_info() {
+        let info =
+            RemoteInfo::parse("Linux\narm64\n/home/dria\ndria\n/usr/bin/systemctl\n").unwrap();
+        assert_eq!(
+            info,
+            RemoteInfo {
+                os: "linux".to_string(),
+                arch: "aarch64".to_string(),
+                home: "/home/dria".to_string(),
+                user: "dria".to_string(),
+                has_systemd: true,
+            }
+        );
+        assert!(
+            !RemoteInfo::parse("Darwin\nx86_64\n/Users/dria\ndria\n")
+                .unwrap()
+                .has_systemd
+        );
+        assert!(RemoteInfo::parse("Linux\n").is_err());
+
+        assert_eq!(shell_quote("/home/it's"), r"'/home/it'\''s'");
+        let unit = systemd_unit("dria", "/home/dria/launcher", "/home/dria/.env");
+        assert!(unit.contains("User=dria\n"));
+        assert!(
+            unit.contains("ExecStart=\"/home/dria/launcher\" --env \"/home/dria/.env\" start\n")
+        );
+    }
+}
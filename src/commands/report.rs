@@ -0,0 +1,229 @@
+use eyre::{Context, Result};
+use std::fmt::Write as _;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use zip::write::SimpleFileOptions;
+
+use crate::utils::{
+    get_current_key_name, launcher_log_path, read_last_compute_exit, DriaEnv, DriaRelease,
+    HardwareInfo, DKN_ENV_AUDIT_FILE, DKN_LAUNCHER_VERSION,
+};
+
+/// What the secrets are replaced with within the report, without any of their characters.
+const REDACTED: &str = "****";
+
+/// Number of the most recent launcher log lines included within the report.
+const REPORT_LOG_LINES: usize = 2000;
+
+/// Where the issues of the launcher are reported.
+const ISSUES_URL: &str = "https://github.com/firstbatchxyz/dkn-compute-launcher/issues/new";
+
+/// Collects everything that helps debugging an issue into a single zip file, which can be
+/// attached to a GitHub issue:
+///
+/// - `launcher.log`: the most recent launcher logs
+/// - `env.txt`: the environment file, with its secrets (wallet, API keys, tokens) left out
+/// - `info.txt`: the output of the `info` command
+/// - `system.txt`: OS, hardware & versions, along with the last exit of the compute node
+/// - `env-audit.log`: the recorded changes of the environment file, if `DKN_ENV_AUDIT` is enabled
+///
+/// Secrets are left out of every entry, including the last characters that are shown when
+/// they are masked elsewhere, see [`strip_secrets`].
+///
+/// ### Arguments
+/// - `exe_dir`: directory where the compute node binaries are located
+/// - `env_path`: path to the environment file
+/// - `output`: path of the zip file, defaults to `dria-report-<time>.zip` in the current directory
+///
+/// ### Errors
+/// - If the zip file could not be written
+pub fn create_report(exe_dir: &Path, env_path: &Path, output: Option<&Path>) -> Result<()> {
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or_default();
+    let output = output
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from(format!("dria-report-{time}.zip")));
+
+    // each entry is best-effort, a missing piece is explained within the entry instead
+    let dria_env = DriaEnv::new_from_file(env_path)
+        .map(|(dria_env, _)| dria_env)
+        .unwrap_or_else(|_| DriaEnv::new_from_env());
+    let log_path = launcher_log_path(env_path);
+    let logs = std::fs::read_to_string(&log_path)
+        .map(|logs| tail_lines(&logs, REPORT_LOG_LINES))
+        .unwrap_or_else(|err| format!("could not read {}: {err}\n", log_path.display()));
+    let env = std::fs::read_to_string(env_path)
+        .map(|content| sanitize_env(&content))
+        .unwrap_or_else(|err| format!("could not read {}: {err}\n", env_path.display()));
+    let info = super::info::format_info(env_path)
+        .unwrap_or_else(|err| format!("could not get info: {err}\n"));
+    let system = system_details(exe_dir).unwrap_or_default();
+    // the audit log is masked already, and only exists if it was enabled
+    let audit = std::fs::read_to_string(exe_dir.join(DKN_ENV_AUDIT_FILE))
+        .map(|audit| tail_lines(&audit, REPORT_LOG_LINES))
+        .ok();
+
+    let file = std::fs::File::create(&output)
+        .wrap_err_with(|| format!("could not create {}", output.display()))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let entries = [
+        ("launcher.log", logs),
+        ("env.txt", env),
+        ("info.txt", info),
+        ("system.txt", system),
+    ]
+    .into_iter()
+    .chain(audit.map(|audit| ("env-audit.log", audit)));
+    for (name, content) in entries {
+        zip.start_file(name, SimpleFileOptions::default())?;
+        zip.write_all(strip_secrets(&content, &dria_env).as_bytes())?;
+    }
+    zip.finish()?;
+
+    eprintln!("Report is saved at {}", output.display());
+    eprintln!(
+        "Secrets within your env file are left out, but please review the report before sharing it."
+    );
+    eprintln!("You can attach it to an issue at {ISSUES_URL}");
+
+    Ok(())
+}
+
+/// Returns the OS, hardware & version details, along with the last exit of the compute node.
+fn system_details(exe_dir: &Path) -> Result<String, std::fmt::Error> {
+    let mut details = String::new();
+    writeln!(
+        details,
+        "OS: {} ({}/{})",
+        sysinfo::System::long_os_version().unwrap_or_else(|| "unknown".to_string()),
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    )?;
+    if let Some(kernel) = sysinfo::System::kernel_version() {
+        writeln!(details, "Kernel: {kernel}")?;
+    }
+    if let Ok(distro) = std::env::var("WSL_DISTRO_NAME") {
+        writeln!(details, "WSL: {distro}")?;
+    }
+    writeln!(details, "Hardware: {}", HardwareInfo::detect())?;
+    writeln!(details, "Launcher: v{DKN_LAUNCHER_VERSION}")?;
+    writeln!(
+        details,
+        "Compute node: {}",
+        DriaRelease::get_compute_version(exe_dir)
+            .map(|version| format!("v{}", version.trim()))
+            .unwrap_or_else(|| "not installed".to_string())
+    )?;
+    writeln!(
+        details,
+        "Last compute node exit: {}",
+        read_last_compute_exit(exe_dir).unwrap_or_else(|| "none recorded".to_string())
+    )?;
+
+    Ok(details)
+}
+
+/// Returns the given text without the secrets of the environment, and without the last
+/// characters of masked secrets (e.g. `****cdef` within the logs); both become [`REDACTED`].
+fn strip_secrets(text: &str, dria_env: &DriaEnv) -> String {
+    let mut secrets = DriaEnv::KEY_NAMES
+        .into_iter()
+        .filter(|key| DriaEnv::is_secret(key))
+        .filter_map(|key| dria_env.get(key).map(str::trim))
+        .filter(|value| !value.is_empty())
+        .collect::<Vec<_>>();
+    // longer secrets first, in case one contains another
+    secrets.sort_by_key(|secret| std::cmp::Reverse(secret.len()));
+    let text = secrets.into_iter().fold(text.to_string(), |text, secret| {
+        text.replace(secret, REDACTED)
+    });
+
+    // masked secrets keep exactly 4 characters after the mask, see `mask_secret`
+    let mut stripped = String::with_capacity(text.len());
+    let mut rest = text.as_str();
+    while let Some(index) = rest.find(REDACTED) {
+        let (before, after) = rest.split_at(index + REDACTED.len());
+        stripped.push_str(before);
+        let suffix_len = after
+            .char_indices()
+            .take_while(|(_, c)| !c.is_whitespace() && *c != '*')
+            .take(4)
+            .map(|(index, c)| index + c.len_utf8())
+            .last()
+            .unwrap_or_default();
+        rest = match after[..suffix_len].chars().count() {
+            4 => &after[suffix_len..],
+            _ => after,
+        };
+    }
+    stripped.push_str(rest);
+
+    stripped
+}
+
+/// Returns the env file content with the values of secret keys left out, including their legacy
+/// names and commented-out keys (e.g. after a legacy rewrite); other lines are kept as is.
+fn sanitize_env(content: &str) -> String {
+    content
+        .lines()
+        .map(|line| {
+            let Some((key, value)) = line.split_once('=') else {
+                return line.to_string();
+            };
+            let name = key
+                .trim()
+                .trim_start_matches('#')
+                .trim()
+                .trim_start_matches("export ")
+                .trim();
+            let name = get_current_key_name(name).unwrap_or(name);
+            let value = value.trim().trim_matches(['"', '\'']);
+
+            if DriaEnv::is_secret(name) && !value.is_empty() {
+                format!("{key}={REDACTED}")
+            } else {
+                line.to_string()
+            }
+        })
+        .map(|line| line + "\n")
+        .collect()
+}
+
+/// Returns the last `count` lines of the given text.
+fn tail_lines(text: &str, count: usize) -> String {
+    let lines = text.lines().collect::<Vec<_>>();
+    lines[lines.len().saturating_sub(count)..]
+        .iter()
+        .map(|line| format!("{line}\n"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_env() {
+        let content = "# DKN_WALLET_SECRET_KEY=abc\nDKN_WALLET_SECRET_KEY=0x1234567890abcdef\nOPENAI_APIKEY=\"sk-0123456789\"\nDKN_MODELS=gpt-4o\nGEMINI_API_KEY=\n";
+        assert_eq!(
+            sanitize_env(content),
+            "# DKN_WALLET_SECRET_KEY=****\nDKN_WALLET_SECRET_KEY=****\nOPENAI_APIKEY=****\nDKN_MODELS=gpt-4o\nGEMINI_API_KEY=\n"
+        );
+
+        let mut dria_env = DriaEnv::new_from_env();
+        dria_env.set(DriaEnv::DKN_WALLET_KEY, "0x1234567890abcdef");
+        assert_eq!(
+            strip_secrets(
+                "key 0x1234567890abcdef, OPENAI_API_KEY: ****6789 → ****, ****ab\n",
+                &dria_env
+            ),
+            "key ****, OPENAI_API_KEY: **** → ****, ****ab\n"
+        );
+
+        assert_eq!(tail_lines("a\nb\nc\n", 2), "b\nc\n");
+        assert_eq!(tail_lines("a\n", 2), "a\n");
+    }
+}
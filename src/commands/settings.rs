@@ -1,7 +1,7 @@
 use inquire::{Confirm, Select};
 use std::path::Path;
 
-use crate::{settings::*, DriaEnv};
+use crate::{settings::*, tr, utils::Selectable, DriaEnv};
 
 /// Starts the interactive settings editor for the given environment.
 ///
@@ -15,29 +15,32 @@ pub async fn change_settings(env_path: &Path) -> eyre::Result<()> {
         eyre::bail!("Environment file does not exist: {}", env_path.display());
     }
 
-    // an environment object is created from the existing environment variables
+    // an environment object is created from the existing environment variables,
+    // and the original one is kept to review the changes
     let mut dria_env = DriaEnv::new_from_env();
+    let original_env = dria_env.clone();
 
     loop {
         // prompt the user for which setting to change
         let Some(choice) = Select::new(
-            &format!("Choose settings (for {})", env_path.display()),
+            &tr!("Choose settings (for {path})", path = env_path.display()),
             Settings::all(),
         )
-        .with_help_message("↑↓ to move, ENTER to select")
+        .with_help_message(tr!("↑↓ to move, ENTER to select"))
         .with_page_size(Settings::all().len())
         .prompt_skippable()?
         else {
             if dria_env.is_changed() {
                 // continue the loop if user returns `false` from confirmation
-                if let Some(false) =
-                    Confirm::new("You have unsaved changes, are you sure you want to quit (y/n)?")
-                        .with_help_message("You will lose all unsaved changes!")
-                        .prompt_skippable()?
+                if let Some(false) = Confirm::new(tr!(
+                    "You have unsaved changes, are you sure you want to quit (y/n)?"
+                ))
+                .with_help_message(tr!("You will lose all unsaved changes!"))
+                .prompt_skippable()?
                 {
-                    log::info!("Exiting, changes are reverted.");
                     continue;
                 }
+                log::info!("Exiting, changes are discarded.");
             } else {
                 log::info!("Exiting without changes.");
             }
@@ -47,23 +50,38 @@ pub async fn change_settings(env_path: &Path) -> eyre::Result<()> {
 
         match choice {
             Settings::Wallet => {
-                crate::settings::edit_wallet(&mut dria_env, true)?;
+                crate::settings::show_wallet_menu(&mut dria_env).await?;
             }
             Settings::Port => {
                 crate::settings::edit_port(&mut dria_env)?;
             }
+            Settings::Network => {
+                crate::settings::edit_network(&mut dria_env)?;
+            }
+            Settings::Node => {
+                crate::settings::edit_node(&mut dria_env)?;
+            }
+            Settings::Resources => {
+                crate::settings::edit_resource_limits(&mut dria_env)?;
+            }
+            Settings::BatchSize => {
+                crate::settings::edit_batch_size(&mut dria_env).await?;
+            }
             Settings::Models => {
                 crate::settings::show_model_settings_menu(&mut dria_env).await?;
             }
             Settings::Ollama => {
                 crate::settings::edit_ollama(&mut dria_env)?;
             }
-            // Settings::ApiKeys => {
-            //     crate::settings::edit_api_keys(&mut dria_env)?;
-            // }
             Settings::LogLevels => {
                 crate::settings::edit_log_level(&mut dria_env)?;
             }
+            Settings::Language => {
+                crate::settings::edit_language(&mut dria_env)?;
+            }
+            Settings::ManagementApi => {
+                crate::settings::edit_management_api(&mut dria_env)?;
+            }
             Settings::SaveExit => {
                 if dria_env.is_changed() {
                     dria_env.save_to_file(env_path)?;
@@ -72,6 +90,9 @@ pub async fn change_settings(env_path: &Path) -> eyre::Result<()> {
                 }
                 break;
             }
+            Settings::Review => {
+                review_changes(&mut dria_env, &original_env)?;
+            }
             Settings::Abort => {
                 log::info!("Aborting changes.");
                 break;
@@ -81,3 +102,30 @@ pub async fn change_settings(env_path: &Path) -> eyre::Result<()> {
 
     Ok(())
 }
+
+/// Shows the pending changes, and lets the user revert them one by one.
+fn review_changes(dria_env: &mut DriaEnv, original_env: &DriaEnv) -> eyre::Result<()> {
+    loop {
+        let changes = dria_env.changes_from(original_env);
+        if changes.is_empty() {
+            eprintln!("No pending changes.");
+            return Ok(());
+        }
+
+        let Selectable::Some(change) = Select::new(
+            &tr!(
+                "{count} pending changes, choose one to revert:",
+                count = changes.len()
+            ),
+            Selectable::new(changes),
+        )
+        .with_help_message(tr!("↑↓ to move, ENTER to revert"))
+        .prompt()?
+        else {
+            return Ok(());
+        };
+
+        dria_env.revert(change.key, original_env);
+        log::info!("Reverted {}", change.key);
+    }
+}
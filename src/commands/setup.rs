@@ -1,35 +1,276 @@
-use eyre::Result;
+use colored::Colorize;
+use dkn_executor::{Model, ModelProvider};
+use eyre::{Context, Result};
+use inquire::{Confirm, MultiSelect, Select, Text};
 use std::path::Path;
 
-use crate::{settings, utils::DriaEnv};
+use super::referrals::REFERRAL_CODE_LENGTH;
+use crate::{
+    settings, tr,
+    utils::{
+        check_ollama,
+        crypto::{generate_secret_key, Signer},
+        get_current_key_name,
+        referrals::ReferralsClient,
+        DriaEnv, HardwareInfo, KNOWN_NETWORKS,
+    },
+};
 
-/// Asks for the following information for the user environment:
+/// How to set up the wallet of the node.
+enum WalletChoice {
+    Generate,
+    Import,
+    Mnemonic,
+}
+
+impl std::fmt::Display for WalletChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Generate => write!(f, "{}", tr!("Generate a new wallet")),
+            Self::Import => write!(f, "{}", tr!("Import an existing secret key")),
+            Self::Mnemonic => write!(f, "{}", tr!("Import from a mnemonic phrase")),
+        }
+    }
+}
+
+/// Guides the user through setting up the environment from scratch:
 ///
-/// 1. Secret key (Wallet)
-/// 2. Models
-/// 3. Optional API Keys for Jina and Serper
+/// 1. Detects the hardware, to recommend models
+/// 2. Generates or imports a wallet
+/// 3. Chooses the network
+/// 4. Chooses models, along with the API keys of API-based providers
+/// 5. Checks that Ollama is usable, if Ollama models are chosen
+/// 6. Saves the environment file
+/// 7. Optionally enters a referral code
 ///
 /// ### Arguments
 /// - `env_path`: path to the environment file
 ///
 /// ### Errors
-/// - If the environment file is not a file
-pub fn setup_environment(env_path: &Path) -> Result<()> {
+/// - If a prompt fails or is cancelled
+/// - If the environment file could not be saved
+pub async fn setup_environment(env_path: &Path) -> Result<()> {
     let mut dria_env = DriaEnv::new_from_env();
 
-    // ask for a wallet
-    log::info!("Provide a secret key of your wallet.");
-    settings::edit_wallet(&mut dria_env, false)?;
+    // hardware
+    eprintln!("{}", "Detecting hardware...".dimmed());
+    let hardware = HardwareInfo::detect();
+    eprintln!("Hardware: {hardware}");
+
+    // wallet
+    let wallet_choice = Select::new(
+        tr!("How would you like to set up your wallet?"),
+        vec![
+            WalletChoice::Generate,
+            WalletChoice::Import,
+            WalletChoice::Mnemonic,
+        ],
+    )
+    .with_help_message(tr!("↑↓ to move, ENTER to select"))
+    .prompt()?;
+    match wallet_choice {
+        WalletChoice::Generate => {
+            let secret_key = generate_secret_key()?;
+            dria_env.set(DriaEnv::DKN_WALLET_KEY, &secret_key);
+            let (_, _, address) = dria_env.get_account()?;
+            eprintln!(
+                "Your new wallet address is: {}",
+                format!("0x{address}").bold()
+            );
+            eprintln!(
+                "{}",
+                format!("Back up its secret key somewhere safe, it is stored only within {} and can not be recovered:\n{secret_key}", env_path.display()).yellow()
+            );
+            Confirm::new(tr!("I have backed up my secret key"))
+                .with_default(true)
+                .prompt()?;
+        }
+        WalletChoice::Import => settings::edit_wallet(&mut dria_env, false)?,
+        WalletChoice::Mnemonic => {
+            // fall back to a secret key if the derived wallet is not the expected one
+            if !settings::import_mnemonic(&mut dria_env)? {
+                settings::edit_wallet(&mut dria_env, false)?;
+            }
+        }
+    }
+
+    // network
+    let network = Select::new(tr!("Choose the network:"), KNOWN_NETWORKS.to_vec())
+        .with_help_message(tr!("mainnet is recommended, ↑↓ to move, ENTER to select"))
+        .prompt()?;
+    dria_env.set(DriaEnv::DKN_NETWORK_KEY, network);
+
+    // models, with recommendations w.r.t the hardware
+    let recommended = hardware.recommend_models();
+    let ollama_models = Model::all_with_provider(&ModelProvider::Ollama).collect::<Vec<_>>();
+    let defaults = ollama_models
+        .iter()
+        .enumerate()
+        .filter(|(_, model)| recommended.first() == Some(model))
+        .map(|(idx, _)| idx)
+        .collect::<Vec<_>>();
+    let help_message = match recommended.first() {
+        Some(model) => format!(
+            "{model} is recommended for {:.0} GB of memory; SPACE to select, ENTER to confirm",
+            hardware.model_memory_mb() as f64 / 1024.0
+        ),
+        None => "Your memory may be too small for local models, consider API-based models; SPACE to select, ENTER to confirm".to_string(),
+    };
+    let mut models = MultiSelect::new(tr!("Choose local (Ollama) models to run:"), ollama_models)
+        .with_default(&defaults)
+        .with_help_message(&help_message)
+        .with_formatter(&|selected| {
+            selected
+                .iter()
+                .map(|option| option.value.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        })
+        .prompt()?;
+    for model in &models {
+        if !recommended.contains(model) {
+            log::warn!("{model} may not fit in the memory of this machine.");
+        }
+    }
+    dria_env.set(
+        DriaEnv::DKN_MODELS_KEY,
+        models
+            .iter()
+            .map(|model| model.to_string())
+            .collect::<Vec<_>>()
+            .join(","),
+    );
 
-    // ask for models
-    log::info!("Choose models that you would like to run.");
-    settings::edit_models(&mut dria_env)?;
+    if Confirm::new(tr!(
+        "Would you like to add API-based models (OpenAI, Gemini, OpenRouter)?"
+    ))
+    .with_default(models.is_empty())
+    .prompt()?
+    {
+        settings::edit_models(&mut dria_env)?;
+        settings::edit_api_keys(&mut dria_env)?;
+        models = dria_env.get_models().into_iter().collect();
+    }
 
-    // create directories if they dont exist
+    // Ollama sanity check
+    if models.iter().any(|m| m.provider() == ModelProvider::Ollama) {
+        let (host, port) = dria_env.get_ollama_config();
+        if check_ollama(&dria_env).await {
+            eprintln!("Ollama is running at {host}:{port}, missing models are pulled when the node starts.");
+        } else if which::which("ollama").is_ok() {
+            eprintln!("Ollama is installed, the launcher starts it when the node starts.");
+        } else {
+            log::warn!("Ollama is not installed, please install it from https://ollama.com/download before you start the node.");
+        }
+    }
+
+    // create directories if they dont exist, then overwrite it with the new values
     DriaEnv::new_default_file(env_path)?;
+    dria_env.save_to_file(env_path)?;
+    if let Err(err) = dotenvy::from_path_override(env_path) {
+        log::warn!("Failed to override with env: {}", err);
+    }
+    eprintln!("Your node is set up at {}.", env_path.display());
+
+    // referral code
+    let code = Text::new(tr!("Enter a referral code, if you have one:"))
+        .with_help_message(tr!("ENTER without typing to skip"))
+        .with_validator(|code: &str| {
+            if code.is_empty() || code.len() == REFERRAL_CODE_LENGTH {
+                Ok(inquire::validator::Validation::Valid)
+            } else {
+                Ok(inquire::validator::Validation::Invalid(
+                    tr!(
+                        "The referral code must be {length} characters long.",
+                        length = REFERRAL_CODE_LENGTH
+                    )
+                    .into(),
+                ))
+            }
+        })
+        .prompt()?;
+    if !code.is_empty() {
+        let (secret_key, _, address) = dria_env.get_account()?;
+        let signer = Signer::SecretKey {
+            secret_key,
+            address,
+        };
+        if let Err(err) = ReferralsClient::default()
+            .enter_referral_code(&signer, &code)
+            .await
+        {
+            log::error!("{err}, you can try again later with `dkn-compute-launcher referrals`.");
+        }
+    }
+
+    eprintln!(
+        "You can now start your node with {}.",
+        "dkn-compute-launcher start".bold()
+    );
+
+    Ok(())
+}
 
-    // then overwrite it with the new values
+/// Sets up the environment without any prompts, with the values of the given config file; e.g.
+/// for provisioning a machine with `remote install`.
+///
+/// The config file is an environment file, its values are applied on top of the existing
+/// environment; a new wallet is generated if the config has none.
+///
+/// ### Arguments
+/// - `env_path`: path to the environment file
+/// - `config_path`: path to the config file, in the same format as the environment file
+///
+/// ### Errors
+/// - If the config file could not be read, or has invalid values
+/// - If the resulting environment has no models
+/// - If the environment file could not be saved
+pub fn setup_environment_from_config(env_path: &Path, config_path: &Path) -> Result<()> {
+    let mut dria_env = DriaEnv::new_from_env();
+
+    let entries = dotenvy::from_path_iter(config_path)
+        .wrap_err_with(|| format!("could not read config at {}", config_path.display()))?;
+    for entry in entries {
+        let (key, value) = entry.wrap_err("could not parse config")?;
+        let current_key = get_current_key_name(&key)
+            .or_else(|| DriaEnv::KEY_NAMES.into_iter().find(|name| *name == key));
+        match current_key {
+            Some(current_key) => dria_env.set(current_key, value),
+            None => log::warn!("Ignoring unknown key {key} within the config."),
+        }
+    }
+
+    let mut issues = dria_env
+        .validate()
+        .into_iter()
+        .map(|(key, err)| format!("{key}: {err}"))
+        .collect::<Vec<_>>();
+    if dria_env.get_models().is_empty() {
+        issues.push(format!("{}: no models selected", DriaEnv::DKN_MODELS_KEY));
+    }
+    if !issues.is_empty() {
+        for issue in &issues {
+            eprintln!("  - {issue}");
+        }
+        eyre::bail!("config has {} issues", issues.len());
+    }
+
+    if dria_env
+        .get(DriaEnv::DKN_WALLET_KEY)
+        .filter(|key| !key.trim().is_empty())
+        .is_none()
+    {
+        dria_env.set(DriaEnv::DKN_WALLET_KEY, generate_secret_key()?);
+        let (_, _, address) = dria_env.get_account()?;
+        log::warn!(
+            "Generated a new wallet 0x{address}, back up its secret key within {}.",
+            env_path.display()
+        );
+    }
+
+    DriaEnv::new_default_file(env_path)?;
     dria_env.save_to_file(env_path)?;
+    eprintln!("Your node is set up at {}.", env_path.display());
 
     Ok(())
 }
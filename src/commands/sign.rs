@@ -0,0 +1,60 @@
+use colored::Colorize;
+use eyre::Result;
+
+use crate::utils::crypto::{encode_signature, recover_signer, Signer};
+use crate::utils::{DriaEnv, LedgerSigner};
+
+/// Signs the given message with [EIP-191](https://eips.ethereum.org/EIPS/eip-191) (i.e. `personal_sign`),
+/// and prints the signature to stdout; the secret key never leaves the launcher.
+///
+/// The message is signed with the wallet of the node, or with the account at the given
+/// derivation path of a Ledger device if `ledger_path` is given.
+///
+/// ### Errors
+/// - If there is no wallet configured, or the Ledger could not sign the message
+pub async fn sign_message(message: &str, ledger_path: Option<&str>, json: bool) -> Result<()> {
+    let signer = match ledger_path {
+        Some(ledger_path) => Signer::Ledger(LedgerSigner::connect(ledger_path)?),
+        None => {
+            let (secret_key, _, address) = DriaEnv::new_from_env().get_account()?;
+            Signer::SecretKey {
+                secret_key,
+                address,
+            }
+        }
+    };
+
+    let (signature, recovery_id) = signer.sign_message(message).await?;
+    let signature = encode_signature(&signature, &recovery_id);
+    let address = format!("0x{}", signer.address());
+    if json {
+        println!(
+            "{}",
+            serde_json::json!({ "address": address, "message": message, "signature": signature })
+        );
+    } else {
+        println!("{signature}");
+        eprintln!("Signed by {}", address.bold());
+    }
+
+    Ok(())
+}
+
+/// Verifies that the given message is signed by the given address with [EIP-191](https://eips.ethereum.org/EIPS/eip-191),
+/// e.g. a signature of [`sign_message`] or of a wallet.
+///
+/// ### Errors
+/// - If the signature is malformed, or it is signed by another address
+pub fn verify_message(message: &str, address: &str, signature: &str) -> Result<()> {
+    let signer = recover_signer(message, signature)?;
+    let expected = address.trim().trim_start_matches("0x").to_lowercase();
+    if signer != expected {
+        eyre::bail!("signature is invalid, it is signed by 0x{signer} instead of 0x{expected}");
+    }
+
+    eprintln!(
+        "{}",
+        format!("Signature is valid, signed by 0x{signer}").green()
+    );
+    Ok(())
+}
@@ -2,7 +2,7 @@ use inquire::Select;
 use std::path::{Path, PathBuf};
 
 use crate::{
-    get_releases,
+    get_releases, tr,
     utils::{DriaRelease, DriaRepository},
 };
 
@@ -42,21 +42,16 @@ pub async fn download_specific_release(
             .ok_or_else(|| eyre::eyre!("No release found for tag: {}", tag))?,
         // prompt the user for selection
         None => Select::new(
-            "Choose a version and press ENTER:",
+            tr!("Choose a version and press ENTER:"),
             releases
                 .into_iter()
                 .filter(|release: &DriaRelease| {
-                    // we only want releases that are well formed
-                    let parts = release.version().split('.').collect::<Vec<_>>();
-
-                    parts.len() == 3
-                        && parts[0].parse::<u32>().is_ok()
-                        && parts[1].parse::<u32>().is_ok()
-                        && parts[2].parse::<u32>().is_ok()
+                    // we only want releases that are well formed & have an asset for this machine
+                    release.version_numbers().is_some() && release.asset().is_ok()
                 })
                 .collect::<Vec<_>>(),
         )
-        .with_help_message("↑↓ to move, type to filter by name, ENTER to select")
+        .with_help_message(tr!("↑↓ to move, type to filter by name, ENTER to select"))
         .prompt()?,
     };
 
@@ -1,21 +1,26 @@
-use dkn_executor::{ollama_rs::Ollama, ModelProvider};
-use eyre::{Context, Result};
-use std::{env, path::Path};
-use tokio::process::Command;
+use dkn_executor::ModelProvider;
+use eyre::{Result, WrapErr};
+use std::{io::IsTerminal, path::Path, sync::Arc, time::Instant};
 use tokio_util::sync::CancellationToken;
 
+#[cfg(windows)]
+use crate::utils::{is_quarantined, offer_quarantine_redownload};
 use crate::{
-    settings,
+    settings::{self, validate_api_keys, ApiKeyStatus, DriaApiKeyKind},
+    tr,
     utils::{
-        check_ollama, configure_fdlimit, pull_model_with_progress, spawn_ollama, ComputeInstance,
+        assign_profile_ports, audit_network, check_ollama, configure_fdlimit, connect_ollama,
+        find_slow_models, format_startup_banner, install_ollama, is_ollama_installed, is_quiet,
+        is_remote_ollama, launcher_log_path, list_local_model_names, ollama_launcher_only_keys,
+        pull_models_with_progress, record_network, resolve_p2p_port_conflict, serve_health,
+        serve_management, spawn_compute_process, spawn_ollama, with_prompt_timeout,
+        ComputeInstance, EnvWatcher, ExitReason, HealthState, LogBoost, ManagementContext,
+        NodeLock, OllamaPidFile, PortMapping, PreflightChecks, TimingSummary, UpdateScheduler,
+        Watchdog, MINIMUM_EVAL_TPS, PORT_MAPPING_REMOVAL_TIMEOUT,
     },
     DriaEnv, DKN_LAUNCHER_VERSION,
 };
 
-/// An env key that compute node checks to get the path to the environment file.
-/// This is set by the launcher when it spawns the compute node.
-const DKN_COMPUTE_ENV_KEY: &str = "DKN_COMPUTE_ENV";
-
 /// Starts the latest compute node version at the given path.
 ///
 /// If the environment has Ollama models configured, it will check for Ollama as well
@@ -26,41 +31,67 @@ const DKN_COMPUTE_ENV_KEY: &str = "DKN_COMPUTE_ENV";
 ///
 /// ### Arguments
 /// - `exe_path`: path to the compute node binary
+/// - `env_path`: path to the environment file
 /// - `check_updates`: whether to check for updates or not
+/// - `take_over`: take over the node lock even if another launcher seems to hold it
+/// - `skip_perf_check`: do not measure the TPS of the Ollama models before starting
 ///
 /// ### Returns
 /// A [`ComputeInstance`] with the running compute node process.
 ///
 /// ### Errors
-/// - If the compute node process could not be spawned
+/// - If another launcher is already running with the same environment file, see [`NodeLock`]
+/// - If the setup prompts are not answered within `DKN_PROMPT_TIMEOUT_SECS`
+/// - If the compute node process could not be spawned, e.g. quarantined by an antivirus
 /// - If the Ollama process is required but could not be spawned
+/// - If none of the Ollama models are fast enough on this machine, and there are no other models
 /// - If the file-descriptor limits could not be set
 pub async fn run_compute_node(
     exe_path: &Path,
     env_path: &Path,
     check_updates: bool,
+    take_over: bool,
+    skip_perf_check: bool,
 ) -> Result<ComputeInstance> {
     // get the executables directory back from the path
     let exe_dir = exe_path.parent().expect("must be a file");
 
-    // check the update if requested, similar to calling `update` command
-    if check_updates {
-        super::update(exe_dir).await;
-    }
+    // make sure that this node is not running already, before touching any files
+    let lock = NodeLock::acquire(exe_dir, env_path, take_over)?;
+    let mut timings = TimingSummary::new("start");
 
     // read existing env
     let mut dria_env = DriaEnv::new_from_env();
 
     // ensure there are models
     let mut models = dria_env.get_models();
-    while models.is_empty() {
-        log::warn!("No models configured. Please choose at least one model to run.");
-        settings::edit_models(&mut dria_env)?;
+
+    // prompts below would block an unattended launcher forever, so they may time out
+    if models.is_empty() || dria_env.get(DriaEnv::DKN_WALLET_KEY).is_none() {
+        let prompt_timeout = dria_env.get_prompt_timeout();
+        dria_env = with_prompt_timeout(prompt_timeout, "node setup", move || {
+            while dria_env.get_models().is_empty() {
+                log::warn!(
+                    "{}",
+                    tr!("No models configured. Please choose at least one model to run.")
+                );
+                settings::edit_models(&mut dria_env)?;
+            }
+
+            // ensure key is set
+            dria_env
+                .ask_for_key_if_required()
+                .wrap_err(ExitReason::Config)?;
+
+            Ok(dria_env)
+        })?;
         models = dria_env.get_models();
     }
 
-    // ensure key is set
-    dria_env.ask_for_key_if_required()?;
+    // avoid port conflicts with the other profiles on this machine
+    assign_profile_ports(&mut dria_env, env_path);
+    // the compute node would fail to bind a port that is in use by another process
+    resolve_p2p_port_conflict(&mut dria_env).wrap_err(ExitReason::Config)?;
 
     // check API keys for the providers that are used with the selected models
     // let providers = models
@@ -75,97 +106,390 @@ pub async fn run_compute_node(
     //     }
     // }
 
-    // check if Ollama is required & running, and run it if not
-    let ollama_models = models
+    // Ollama is only required if there are Ollama models selected
+    let mut ollama_models = models
         .iter()
         .cloned()
         .filter(|m| m.provider() == ModelProvider::Ollama)
         .collect::<Vec<_>>();
-    let ollama_process = if !ollama_models.is_empty() {
-        // spawn Ollama if needed
-        let ollama_process_opt = if check_ollama(&dria_env).await {
+    // the compute node does not send credentials to Ollama, so its requests would be rejected
+    let launcher_only_keys = ollama_launcher_only_keys(&dria_env);
+    if !ollama_models.is_empty() && !launcher_only_keys.is_empty() {
+        return Err(eyre::eyre!(
+            "The compute node does not support {} yet, so it can not reach Ollama with them. Serve Ollama to this machine without credentials and a custom CA, e.g. over an SSH tunnel, and unset them.",
+            launcher_only_keys.join(", ")
+        ))
+        .wrap_err(ExitReason::Config);
+    }
+
+    // run the independent preflight checks concurrently, these do not prompt the user
+    let network_change = record_network(exe_dir);
+    let preflight = PreflightChecks::new();
+    let mut update_timings = TimingSummary::new("update");
+    let p2p_port = dria_env.get_port().wrap_err(ExitReason::Config)?;
+    let (_, _, ollama_check, api_key_statuses, _, port_mapping) = tokio::join!(
+        // check the update if requested, similar to calling `update` command
+        async {
+            if check_updates {
+                preflight
+                    .run("updates", super::update(exe_dir, &mut update_timings))
+                    .await;
+            }
+        },
+        // audit the derived URLs if the network has changed since the last run
+        async {
+            if let Some(previous) = &network_change {
+                preflight
+                    .run("network audit", audit_network(Some(previous)))
+                    .await;
+            }
+        },
+        // check if Ollama is running, and list its local models if so
+        async {
+            if ollama_models.is_empty() {
+                return Ok(None);
+            }
+
+            preflight
+                .run("ollama", async {
+                    if !check_ollama(&dria_env).await {
+                        return Ok(None);
+                    }
+                    let local_model_names = list_local_model_names(&dria_env).await?;
+                    Ok::<_, eyre::Report>(Some(local_model_names))
+                })
+                .await
+        },
+        // validate the configured API keys with their providers
+        preflight.run("api keys", validate_api_keys(&dria_env)),
+        // warn if the OpenRouter credits are nearly exhausted
+        async {
+            if let Some(api_key) = dria_env.get(DriaEnv::OPENROUTER_APIKEY_KEY) {
+                preflight
+                    .run(
+                        "openrouter credits",
+                        super::credits::warn_if_low_credits(api_key),
+                    )
+                    .await;
+            }
+        },
+        // map the P2P port on the router, if enabled
+        async {
+            if !dria_env.is_auto_port_forward_enabled() {
+                return None;
+            }
+            if p2p_port == 0 {
+                log::warn!("A random P2P port is used, skipping auto port-forward.");
+                return None;
+            }
+
+            Some(
+                preflight
+                    .run("port mapping", PortMapping::create(p2p_port))
+                    .await,
+            )
+        },
+    );
+    preflight.finish(&mut timings);
+    timings.extend(update_timings);
+
+    let port_mapping = match port_mapping {
+        Some(Ok((port_mapping, external_addr))) => {
+            log::info!("Mapped P2P port with {port_mapping}, external address: {external_addr}");
+            Some(port_mapping)
+        }
+        Some(Err(err)) => {
+            log::warn!("Could not map P2P port {p2p_port} on the router: {err}");
             None
-        } else {
-            Some(spawn_ollama(&dria_env).await?)
-        };
-
-        // create ollama instance
-        let (host, port) = dria_env.get_ollama_config();
-        let ollama = Ollama::new(host, port);
-
-        // get local models
-        let local_model_names = ollama
-            .list_local_models()
-            .await?
-            .into_iter()
-            .map(|m| m.name)
-            .collect::<Vec<_>>();
-
-        // find models that are not available locally
-        let models_to_be_pulled = ollama_models
-            .iter()
-            .filter(|model| !local_model_names.contains(&model.to_string()))
-            .collect::<Vec<_>>();
-
-        // pull all selected & non-pulled models
-        if !models_to_be_pulled.is_empty() {
-            log::info!(
-                "The following models are selected but not found locally:\n{}",
-                models_to_be_pulled
-                    .iter()
-                    .map(|m| format!("  - {}", m))
-                    .collect::<Vec<_>>()
-                    .join("\n")
-            );
+        }
+        None => None,
+    };
 
-            log::info!("Pulling models from Ollama...");
-            for model in models_to_be_pulled {
-                pull_model_with_progress(&ollama, model.to_string()).await?;
+    // keep the port mapping alive while the node runs, it is removed on shutdown
+    let cancellation = CancellationToken::new();
+    let port_mapping = port_mapping.map(|port_mapping| port_mapping.spawn(&cancellation));
+
+    // the Ollama spawned and the port mapping created here are cleaned up if the node fails to start
+    let mut ollama_process = None;
+    let mut adopted_ollama_pid = None;
+    let started = async {
+        // an invalid key of a selected provider would fail the compute node later with an opaque error
+        let used_api_keys =
+            DriaApiKeyKind::from_providers(models.iter().map(|m| m.provider())).collect::<Vec<_>>();
+        for (kind, status) in api_key_statuses {
+            match status {
+                ApiKeyStatus::Valid => log::debug!("{kind} {status}."),
+                ApiKeyStatus::Invalid(_) if used_api_keys.contains(&kind) => {
+                    return Err(eyre::eyre!(
+                        "{kind} {status}, please update it with `dkn-compute-launcher settings`"
+                    )
+                    .wrap_err(ExitReason::Config));
+                }
+                status => log::warn!("{kind} {status}."),
             }
         }
 
-        ollama_process_opt
-    } else {
-        None // no need for Ollama
-    };
+        // run Ollama if its required but not running
+        if !ollama_models.is_empty() {
+            let local_model_names = match ollama_check
+                .wrap_err(ExitReason::OllamaUnavailable)?
+            {
+                Some(local_model_names) => {
+                    // server variables only apply when Ollama is spawned by the launcher
+                    let server_env = dria_env.get_ollama_server_env();
+                    if !server_env.is_empty() {
+                        log::warn!(
+                            "Ollama is already running, so {} will not be applied; restart Ollama with them to take effect.",
+                            server_env
+                                .iter()
+                                .map(|(key, _)| *key)
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        );
+                    }
+
+                    // an Ollama left behind by an earlier launcher (e.g. before a restart) is ours,
+                    // while one started by the user is never stopped
+                    let (_, port) = dria_env.get_ollama_config();
+                    if let Some(pid) = OllamaPidFile::find_orphan(port) {
+                        log::info!("Adopting Ollama (PID {pid}) started by an earlier launcher.");
+                        OllamaPidFile::record(port, pid);
+                        adopted_ollama_pid = Some(pid);
+                    }
+
+                    local_model_names
+                }
+                None => {
+                    // offer to install Ollama instead of failing to spawn it
+                    if !is_ollama_installed()
+                        && !is_remote_ollama(&dria_env)
+                        && std::io::stdin().is_terminal()
+                    {
+                        log::warn!("{}", tr!("Ollama is not installed."));
+                        install_ollama(false).await.wrap_err(ExitReason::Download)?;
+                    }
+
+                    ollama_process = Some(
+                        timings
+                            .time("ollama spawn", spawn_ollama(&dria_env))
+                            .await
+                            .wrap_err(ExitReason::OllamaUnavailable)?,
+                    );
+                    timings
+                        .time("ollama model listing", list_local_model_names(&dria_env))
+                        .await
+                        .wrap_err(ExitReason::OllamaUnavailable)?
+                }
+            };
+
+            // find models that are not available locally
+            let models_to_be_pulled = ollama_models
+                .iter()
+                .filter(|model| !local_model_names.contains(&model.to_string()))
+                .collect::<Vec<_>>();
 
-    // save to file if there were any changes
-    if dria_env.is_changed() {
-        dria_env.save_to_file(env_path)?;
+            // pull all selected & non-pulled models
+            if !models_to_be_pulled.is_empty() {
+                log::info!(
+                    "{}",
+                    tr!(
+                        "The following models are selected but not found locally:\n{models}",
+                        models = models_to_be_pulled
+                            .iter()
+                            .map(|m| format!("  - {}", m))
+                            .collect::<Vec<_>>()
+                            .join("\n")
+                    )
+                );
 
-        // override the env file with the new values, needed for the compute node
-        // as even if it reads from env again, it will not override existing values
-        if let Err(err) = dotenvy::from_path_override(env_path) {
-            log::warn!("Failed to override with env: {}", err);
+                log::info!("{}", tr!("Pulling models from Ollama..."));
+                let ollama = connect_ollama(&dria_env)?;
+                timings
+                    .time(
+                        "pull models",
+                        pull_models_with_progress(
+                            &ollama,
+                            models_to_be_pulled.iter().map(|m| m.to_string()).collect(),
+                            dria_env.get_pull_retries(),
+                            dria_env.get_pull_parallelism(),
+                        ),
+                    )
+                    .await
+                    .wrap_err(ExitReason::Download)?;
+            }
         }
-    }
 
-    // set file-descriptor limits in Unix, not needed in Windows
-    configure_fdlimit();
+        // models that are too slow on this machine would fail their tasks, so they are skipped
+        if !ollama_models.is_empty() && !skip_perf_check {
+            let slow_models = timings
+                .time("perf check", find_slow_models(&dria_env, &ollama_models))
+                .await?;
+            if !slow_models.is_empty() {
+                log::warn!(
+                    "Skipping models that are below the minimum of {MINIMUM_EVAL_TPS} TPS on this machine: {} (use --skip-perf-check to run them anyway)",
+                    slow_models
+                        .iter()
+                        .map(|(model, tps)| format!("{model} ({tps:.2} TPS)"))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+                models.retain(|m| !slow_models.iter().any(|(slow, _)| slow == m));
+                ollama_models.retain(|m| !slow_models.iter().any(|(slow, _)| slow == m));
+                if models.is_empty() {
+                    eyre::bail!("None of the selected models are fast enough on this machine, please choose smaller models with `dkn-compute-launcher settings`.");
+                }
+            }
+        }
 
-    // add cancellation check, note that this must run BEFORE the compute is spawned
-    let cancellation = CancellationToken::new();
-    let cancellation_clone = cancellation.clone();
-    tokio::spawn(async move { crate::utils::wait_for_termination(cancellation_clone).await });
-
-    // spawn compute node
-    let exec_platform = env::var("DKN_EXEC_PLATFORM")
-        .unwrap_or_else(|_| format!("launcher/v{DKN_LAUNCHER_VERSION}")); // default to launcher value if not set
-    let compute_process = Command::new(exe_path)
-        // add env variable for the path, respecting the `--profile` option
-        .env(DKN_COMPUTE_ENV_KEY, env_path)
-        // let compute node know that it is started by the launcher
-        // see: https://github.com/firstbatchxyz/dkn-compute-node/blob/master/compute/src/config.rs#L126
-        .env("DKN_EXEC_PLATFORM", exec_platform)
-        .spawn()
-        .wrap_err("failed to spawn compute node")?;
+        // save to file if there were any changes
+        if dria_env.is_changed() {
+            dria_env.save_to_file(env_path)?;
+
+            // override the env file with the new values, needed for the compute node
+            // as even if it reads from env again, it will not override existing values
+            if let Err(err) = dotenvy::from_path_override(env_path) {
+                log::warn!("Failed to override with env: {}", err);
+            }
+        }
+
+        // set file-descriptor limits in Unix, not needed in Windows
+        configure_fdlimit();
+
+        // add cancellation check, note that this must run BEFORE the compute is spawned
+        let cancellation_clone = cancellation.clone();
+        tokio::spawn(async move { crate::utils::wait_for_termination(cancellation_clone).await });
+
+        // serve the health-check endpoint if configured, Ollama is only checked if its required
+        let health = Arc::new(HealthState::new(&dria_env, !ollama_models.is_empty()));
+        // unload Ollama models while the node is idle, if enabled
+        if let Some(idle_after) = dria_env
+            .get_idle_unload()
+            .filter(|_| !ollama_models.is_empty())
+        {
+            let models = ollama_models.iter().map(|m| m.to_string()).collect();
+            tokio::spawn(crate::utils::run_idle_unloader(
+                dria_env.clone(),
+                models,
+                idle_after,
+                health.output.clone(),
+                cancellation.clone(),
+            ));
+        }
+
+        if let Some(port) = dria_env.get_health_port() {
+            let health = health.clone();
+            let cancellation = cancellation.clone();
+            tokio::spawn(async move {
+                if let Err(err) = serve_health(port, health, cancellation).await {
+                    log::error!("Health-check server failed: {err}");
+                }
+            });
+        }
+
+        if let Some((port, token)) = dria_env.get_management_api() {
+            let health = health.clone();
+            let cancellation = cancellation.clone();
+            let context = ManagementContext {
+                token,
+                health,
+                log_path: launcher_log_path(env_path),
+                exe_dir: exe_dir.into(),
+                started: Instant::now(),
+            };
+            tokio::spawn(async move {
+                if let Err(err) = serve_management(port, context, cancellation).await {
+                    log::error!("Management API server failed: {err}");
+                }
+            });
+        }
+
+        // summary of the effective configuration, to catch misconfigurations at a glance
+        if !is_quiet() {
+            eprint!(
+                "{}",
+                format_startup_banner(&dria_env, env_path, &models, check_updates)
+            );
+        }
+
+        // spawn compute node
+        let spawn_started = Instant::now();
+        let output = health.output.clone();
+        let limits = dria_env.get_resource_limits();
+        if !limits.is_empty() {
+            log::info!("Applying resource limits to compute node: {limits}");
+        }
+        let compute_process =
+            match spawn_compute_process(exe_path, env_path, &models, None, &output, &limits) {
+                Ok(compute_process) => compute_process,
+                // antivirus software may quarantine the binary right after it is downloaded
+                #[cfg(windows)]
+                Err(err) if is_quarantined(exe_path, err.downcast_ref()) => {
+                    log::error!("{err}");
+                    if !offer_quarantine_redownload(exe_path).await? {
+                        return Err(err);
+                    }
+                    spawn_compute_process(exe_path, env_path, &models, None, &output, &limits)?
+                }
+                // the binary is missing if it could not be downloaded
+                Err(err) if !exe_path.exists() => return Err(err.wrap_err(ExitReason::Download)),
+                Err(err) => return Err(err),
+            };
+        timings.record("compute spawn", spawn_started);
+
+        Ok((compute_process, health, output, limits))
+    }
+    .await;
+    let (compute_process, health, output, limits) = match started {
+        Ok(started) => started,
+        Err(err) => {
+            cancellation.cancel();
+            if let Some(process) = &mut ollama_process {
+                let pid = process.id();
+                if let Err(err) = process.kill().await {
+                    log::warn!("Could not stop Ollama: {err}");
+                } else if let Some(pid) = pid {
+                    OllamaPidFile::forget(pid);
+                }
+            }
+            if let Some(port_mapping) = port_mapping {
+                port_mapping.remove(PORT_MAPPING_REMOVAL_TIMEOUT).await;
+            }
+
+            return Err(err);
+        }
+    };
+    timings.print();
 
     Ok(ComputeInstance {
         compute_dir: exe_dir.into(),
+        compute_path: exe_path.into(),
+        env_path: env_path.into(),
+        env_snapshot: DriaEnv::new_from_file(env_path)
+            .map(|(file_env, _)| file_env)
+            .unwrap_or_else(|_| dria_env.clone()),
+        models,
         launcher_version: DKN_LAUNCHER_VERSION.into(),
         compute_process,
         ollama_process,
+        adopted_ollama_pid,
         check_updates,
+        perf_check: !skip_perf_check,
+        pending_reload: None,
         cancellation,
+        log_boost: LogBoost::default(),
+        ollama_exits: 0,
+        health,
+        output,
+        shutdown_timeout: dria_env.get_shutdown_timeout(),
+        limits,
+        update_scheduler: {
+            let (compute_interval, launcher_interval) = dria_env.get_update_intervals();
+            UpdateScheduler::new(compute_interval, launcher_interval)
+        },
+        watchdog: Watchdog::new(dria_env.get_watchdog_timeout()),
+        env_watcher: (dria_env.is_env_watch_enabled() || dria_env.is_env_audit_enabled())
+            .then(|| EnvWatcher::new(env_path)),
+        port_mapping,
+        _lock: lock,
     })
 }
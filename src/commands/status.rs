@@ -0,0 +1,201 @@
+use colored::Colorize;
+use eyre::Result;
+use multiaddr::Protocol;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::Path;
+use std::time::Duration;
+use tokio::net::TcpStream;
+
+use crate::utils::{
+    get_local_ip, parse_listen_addrs, DriaEnv, NodeLock, TracedSend, LAUNCHER_USER_AGENT,
+};
+
+/// Service that responds with the public IP address of the caller, as plain text.
+const PUBLIC_IP_URL: &str = "https://api.ipify.org";
+/// Timeout for each of the HTTP requests & TCP connections.
+const STATUS_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Shows the status of the node running with the given environment file: whether it is running,
+/// its peer count & TPS, and whether its P2P listen address is dialable from the outside.
+///
+/// The peer count & the TPS of the completed tasks are read from the health-check endpoint of the
+/// running launcher, so they are only available if `DKN_HEALTH_PORT` is set.
+///
+/// ### Errors
+/// - If the env file could not be read
+pub async fn show_status(exe_dir: &Path, env_path: &Path) -> Result<()> {
+    let (dria_env, _) = DriaEnv::new_from_file(env_path)?;
+
+    // launcher
+    let Some(pid) = NodeLock::holder(exe_dir, env_path) else {
+        eprintln!("Node: {}", "not running".red());
+        return Ok(());
+    };
+    eprintln!("Node: {} (launcher PID {pid})", "running".green());
+
+    // peers, from the health-check of the running launcher
+    match dria_env.get_health_port() {
+        Some(port) => match get_health_report(port).await {
+            Ok(report) => {
+                let peers = report["peers"].as_u64();
+                eprintln!(
+                    "Peers: {}",
+                    peers
+                        .map(|peers| peers.to_string())
+                        .unwrap_or_else(|| "unknown".to_string())
+                );
+                if let Some(tps) = report["tps"].as_object().filter(|tps| !tps.is_empty()) {
+                    let tps = tps
+                        .iter()
+                        .map(|(model, tps)| {
+                            format!("{model}: {:.2}", tps.as_f64().unwrap_or_default())
+                        })
+                        .collect::<Vec<_>>();
+                    eprintln!("TPS (moving average): {}", tps.join(", "));
+                }
+            }
+            Err(err) => eprintln!("Peers: {} ({err})", "unknown".yellow()),
+        },
+        None => eprintln!(
+            "Peers: {} (set {} to see the peer count)",
+            "unknown".yellow(),
+            DriaEnv::DKN_HEALTH_PORT_KEY
+        ),
+    }
+
+    // P2P listen address
+    let listen_addr = dria_env
+        .get_or_default(DriaEnv::DKN_P2P_LISTEN_ADDR_KEY)
+        .unwrap_or_default();
+    let port = dria_env.get_port()?;
+    eprintln!("Listen Address: {listen_addr}");
+    if port == 0 {
+        eprintln!("  a random port is used, reachability can not be checked.");
+        return Ok(());
+    }
+
+    for addr in local_dial_addrs(listen_addr) {
+        eprintln!(
+            "  local {addr}: {}",
+            if is_dialable(addr).await {
+                "listening".green()
+            } else {
+                "not listening".red()
+            }
+        );
+    }
+
+    // NAT reachability, by dialing our own public address
+    let public_ip = match get_public_ip().await {
+        Ok(public_ip) => public_ip,
+        Err(err) => {
+            eprintln!("  public: {} ({err})", "unknown".yellow());
+            return Ok(());
+        }
+    };
+    if is_dialable(SocketAddr::new(public_ip, port)).await {
+        eprintln!("  public: {} at {public_ip}:{port}", "reachable".green());
+    } else {
+        eprintln!("  public: {} at {public_ip}:{port}", "unreachable".red());
+        eprintln!("{}", port_forwarding_hint(port, get_local_ip()).dimmed());
+    }
+
+    Ok(())
+}
+
+/// Returns the health report of the launcher serving the health-check at the given port.
+pub(super) async fn get_health_report(port: u16) -> Result<serde_json::Value> {
+    let client = reqwest::Client::builder()
+        .user_agent(LAUNCHER_USER_AGENT)
+        .timeout(STATUS_TIMEOUT)
+        .build()?;
+
+    // unhealthy nodes respond with `503`, but the report is still there
+    let report = client
+        .get(format!("http://127.0.0.1:{port}/healthz"))
+        .send_traced()
+        .await?
+        .json()
+        .await?;
+
+    Ok(report)
+}
+
+/// Returns the public IP address of this machine, as seen from the outside.
+async fn get_public_ip() -> Result<IpAddr> {
+    let client = reqwest::Client::builder()
+        .user_agent(LAUNCHER_USER_AGENT)
+        .timeout(STATUS_TIMEOUT)
+        .build()?;
+    let ip = client
+        .get(PUBLIC_IP_URL)
+        .send_traced()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+
+    Ok(ip.trim().parse()?)
+}
+
+/// Returns the addresses to dial locally for the given listen addresses; the ones that listen on
+/// all interfaces are dialed at the loopback address, and the ones with a random port are skipped.
+fn local_dial_addrs(listen_addrs: &str) -> Vec<SocketAddr> {
+    let Ok(listen_addrs) = parse_listen_addrs(listen_addrs) else {
+        return Vec::new();
+    };
+
+    listen_addrs
+        .iter()
+        .filter_map(|addr| {
+            let mut protocols = addr.iter();
+            let ip = match protocols.next()? {
+                Protocol::Ip4(ip) if ip.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+                Protocol::Ip6(ip) if ip.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+                Protocol::Ip4(ip) => IpAddr::V4(ip),
+                Protocol::Ip6(ip) => IpAddr::V6(ip),
+                _ => return None,
+            };
+            match protocols.next()? {
+                Protocol::Tcp(port) if port != 0 => Some(SocketAddr::new(ip, port)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Returns `true` if a TCP connection can be made to the given address in time.
+async fn is_dialable(addr: SocketAddr) -> bool {
+    tokio::time::timeout(STATUS_TIMEOUT, TcpStream::connect(addr))
+        .await
+        .is_ok_and(|result| result.is_ok())
+}
+
+/// Returns a hint on how to make the given port reachable from the outside.
+fn port_forwarding_hint(port: u16, local_ip: Option<IpAddr>) -> String {
+    let target = local_ip
+        .map(|ip| ip.to_string())
+        .unwrap_or_else(|| "this machine".to_string());
+    format!(
+        "  Your node may be behind a NAT or a firewall; forward TCP port {port} on your router to {target}:{port}, \
+        and allow it within your firewall; or enable auto port-forward within settings > Port. Some routers can not dial their own public address, so this may be a false negative."
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_dial_addrs() {
+        assert_eq!(
+            local_dial_addrs("/ip4/0.0.0.0/tcp/4001,/ip6/::/tcp/4002,/ip4/192.168.1.5/tcp/4003/ws"),
+            vec![
+                "127.0.0.1:4001".parse().unwrap(),
+                "[::1]:4002".parse().unwrap(),
+                "192.168.1.5:4003".parse().unwrap(),
+            ]
+        );
+        assert!(local_dial_addrs("/ip4/0.0.0.0/tcp/0").is_empty());
+    }
+}
@@ -0,0 +1,51 @@
+use eyre::Result;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::utils::{is_process_running, request_termination, DriaEnv, NodeLock};
+
+/// Interval between checks of whether the launcher has exited.
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Extra time given to the launcher on top of its own shutdown timeouts.
+const STOP_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+/// Stops the launcher that is running with the given environment file, if any.
+///
+/// The launcher is asked to terminate, which in turn terminates its compute node (and Ollama, if
+/// it was launched by it) gracefully; this function waits until the launcher exits.
+///
+/// ### Arguments
+/// - `exe_dir`: directory where the lock file is located
+/// - `env_path`: path to the environment file of the running node
+///
+/// ### Returns
+/// `true` if a running launcher was stopped, `false` if there was no running launcher.
+///
+/// ### Errors
+/// - If the launcher could not be signalled
+/// - If the launcher did not exit in time
+pub async fn stop_node(exe_dir: &Path, env_path: &Path) -> Result<bool> {
+    let Some(pid) = NodeLock::holder(exe_dir, env_path) else {
+        log::info!("No running node found for {}", env_path.display());
+        return Ok(false);
+    };
+
+    log::info!("Stopping the launcher with PID {pid}.");
+    request_termination(pid)?;
+
+    // the launcher waits for the compute node and Ollama separately
+    let timeout = DriaEnv::new_from_env().get_shutdown_timeout() * 2 + STOP_GRACE_PERIOD;
+    let started = Instant::now();
+    while is_process_running(pid) {
+        if started.elapsed() > timeout {
+            eyre::bail!(
+                "Launcher with PID {pid} did not exit within {}s, you may have to kill it manually.",
+                timeout.as_secs()
+            );
+        }
+        tokio::time::sleep(STOP_POLL_INTERVAL).await;
+    }
+
+    log::info!("Node stopped.");
+    Ok(true)
+}
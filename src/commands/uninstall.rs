@@ -1,7 +1,8 @@
 use inquire::Confirm;
 use std::path::Path;
 
-use crate::utils::DKN_VERSION_TRACKER_FILE;
+use crate::tr;
+use crate::utils::{FsTransaction, DKN_VERSION_TRACKER_FILE};
 
 /// Uninstalls the launcher and its environment file, along with the compute node binaries & its version tracker.
 ///
@@ -21,6 +22,7 @@ use crate::utils::DKN_VERSION_TRACKER_FILE;
 ///
 /// ### Notes
 /// - The user is asked for confirmation before uninstalling.
+/// - Files are removed within a [`FsTransaction`], so a failure halfway restores the removed files.
 pub async fn uninstall_launcher(
     env_dir: &Path,
     env_path: &Path,
@@ -31,22 +33,22 @@ pub async fn uninstall_launcher(
     // provide a help message to prompt the user to backup their env file
     // if the backup path is not given
     let help_message = if let Some(backup_path) = backup_path {
-        format!(
-            "{} will be saved to {}",
-            env_path.display(),
-            backup_path.display()
+        tr!(
+            "{env} will be saved to {backup}",
+            env = env_path.display(),
+            backup = backup_path.display()
         )
     } else {
-        "Make sure you have backed up your secret key within the environment file!".to_string()
+        tr!("Make sure you have backed up your secret key within the environment file!").to_string()
     };
 
     // ask for confirmation
     let answer =
-        Confirm::new(&format!(
-          "Are you sure you want to uninstall the launcher \"{}\", env \"{}\" and all related files within \"{}\"? (y/n)",
-          launcher_path.display(),
-          env_path.display(),
-          env_dir.display(),
+        Confirm::new(&tr!(
+          "Are you sure you want to uninstall the launcher \"{launcher}\", env \"{env}\" and all related files within \"{dir}\"? (y/n)",
+          launcher = launcher_path.display(),
+          env = env_path.display(),
+          dir = env_dir.display(),
         ))
             .with_help_message(help_message.as_str())
             .prompt()?;
@@ -58,31 +60,35 @@ pub async fn uninstall_launcher(
         log::info!("Uninstalling the launcher");
     }
 
+    // all removals are journaled, so that a failure halfway does not leave a half-deleted installation
+    let mut transaction = FsTransaction::begin(env_dir)?;
+
     // remove the compute node binaries within the directory
     log::info!(
         "Removing compute node binaries within: {}",
         env_dir.display()
     );
-    for path in std::fs::read_dir(env_dir)?.flatten().map(|e| e.path()) {
-        if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-            if name.starts_with("dkn-compute-node") {
-                log::info!("Removing: {}", path.display());
-                std::fs::remove_file(&path)?;
-            }
-        }
+    let compute_paths = std::fs::read_dir(env_dir)?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|name| name.starts_with("dkn-compute-node"))
+        })
+        .collect::<Vec<_>>();
+    for path in compute_paths {
+        log::info!("Removing: {}", path.display());
+        transaction.remove(&path)?;
     }
 
     // remove version tracker
     let version_tracker = env_dir.join(DKN_VERSION_TRACKER_FILE);
     if version_tracker.exists() {
         log::info!("Removing version tracker: {}", version_tracker.display());
-        std::fs::remove_file(&version_tracker)?;
+        transaction.remove(&version_tracker)?;
     }
 
-    // remove the executable with `self_replace`
-    log::info!("Removing the launcher itself: {}", launcher_path.display());
-    self_update::self_replace::self_delete()?;
-
     // remove .env file within the directory
     if env_path.exists() {
         // if there is a backup path, copy the env file to it
@@ -91,11 +97,17 @@ pub async fn uninstall_launcher(
                 "Backing up the environment file to: {}",
                 backup_path.display()
             );
-            std::fs::copy(env_path, backup_path)?;
+            transaction.copy(env_path, backup_path)?;
         }
         log::info!("Removing environment file: {}", env_path.display());
-        std::fs::remove_file(env_path)?;
+        transaction.remove(env_path)?;
     }
 
+    // remove the executable with `self_replace`, this is done last as it can not be undone
+    log::info!("Removing the launcher itself: {}", launcher_path.display());
+    self_update::self_replace::self_delete()?;
+
+    transaction.commit()?;
+
     Ok(())
 }
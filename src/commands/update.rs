@@ -1,53 +1,143 @@
+use colored::Colorize;
 use eyre::Result;
 use self_update::self_replace;
 use std::path::Path;
 
 use crate::utils::{
-    check_for_compute_node_update, check_for_launcher_update, DriaRelease, DKN_LATEST_COMPUTE_FILE,
-    DKN_LAUNCHER_VERSION,
+    check_for_compute_node_update, check_for_launcher_update, check_launcher_replaceable,
+    confirm_update, ensure_launcher_supports, DriaRelease, FsTransaction, TimingSummary,
+    DKN_LATEST_COMPUTE_FILE, DKN_LAUNCHER_VERSION, DKN_VERSION_TRACKER_FILE,
 };
 
 /// Updates the compute node and launcher to the latest version.
 ///
-/// See [`update_compute`] and [`update_launcher`] for more details.
+/// The launcher is updated only in release mode, otherwise this would try to update the binary
+/// when you are running with `cargo run` etc. Release notes are shown, but updates are applied
+/// without confirmation as this runs on `start`.
+///
+/// See [`update_selected`] for more details.
 ///
 /// ### Arguments
 /// - `exe_dir`: directory where the binary is located
+/// - `timings`: timing summary to record the update steps into
 #[inline]
-pub async fn update(exe_dir: &Path) {
-    log::info!("Checking compute node updates.");
-    if let Err(e) = update_compute(exe_dir).await {
-        log::error!("Error updating compute node: {}", e);
-    }
+pub async fn update(exe_dir: &Path, timings: &mut TimingSummary) {
+    update_selected(exe_dir, timings, !cfg!(debug_assertions), true, true).await;
+}
 
-    // update the launcher only in release mode, otherwise this will try to update
-    // when you are running with `cargo run` etc.
-    if !cfg!(debug_assertions) {
+/// Updates the selected binaries to the latest version, see [`update_compute`] and
+/// [`update_launcher`] for more details.
+///
+/// The launcher is updated first, as a compute node release may require a newer launcher.
+///
+/// ### Arguments
+/// - `exe_dir`: directory where the binary is located
+/// - `timings`: timing summary to record the update steps into
+/// - `launcher`: whether to update the launcher
+/// - `compute`: whether to update the compute node
+/// - `auto_approve`: whether to update without asking for confirmation, see [`confirm_update`]
+///
+/// ### Returns
+/// `false` if any of the selected updates has failed, the errors are logged.
+pub async fn update_selected(
+    exe_dir: &Path,
+    timings: &mut TimingSummary,
+    launcher: bool,
+    compute: bool,
+    auto_approve: bool,
+) -> bool {
+    let mut updated = true;
+    if launcher {
         log::info!("Checking launcher updates.");
-        if let Err(e) = update_launcher(exe_dir).await {
+        if let Err(e) = check_launcher_replaceable() {
+            log::warn!("{}", e);
+        } else if let Err(e) = update_launcher(exe_dir, timings, auto_approve).await {
             log::error!("Error updating launcher: {}", e);
+            updated = false;
+        }
+    }
+
+    if compute {
+        log::info!("Checking compute node updates.");
+        if let Err(e) = update_compute(exe_dir, timings, auto_approve).await {
+            log::error!("Error updating compute node: {}", e);
+            updated = false;
         }
     }
+
+    updated
+}
+
+/// Prints the current & latest versions of the selected binaries, without downloading anything.
+///
+/// ### Arguments
+/// - `exe_dir`: directory where the binary is located
+/// - `launcher`: whether to check the launcher
+/// - `compute`: whether to check the compute node
+pub async fn check_updates(exe_dir: &Path, launcher: bool, compute: bool) {
+    let status = |update: Result<(DriaRelease, bool)>| match update {
+        Ok((latest, true)) => format!("v{} is available", latest.version())
+            .yellow()
+            .to_string(),
+        Ok((latest, false)) => format!("up to date with v{}", latest.version())
+            .green()
+            .to_string(),
+        Err(err) => format!("could not check updates: {err}").red().to_string(),
+    };
+
+    if launcher {
+        eprintln!(
+            "Launcher: v{DKN_LAUNCHER_VERSION} ({})",
+            status(check_for_launcher_update(DKN_LAUNCHER_VERSION).await)
+        );
+    }
+    if compute {
+        eprintln!(
+            "Compute node: {} ({})",
+            DriaRelease::get_compute_version(exe_dir)
+                .map(|version| format!("v{}", version.trim()))
+                .unwrap_or_else(|| "not installed".to_string()),
+            status(check_for_compute_node_update(exe_dir).await)
+        );
+    }
 }
 
 /// Updates the launcher node, replacing the current binary with the latest one via `self_replace`.
 ///
 /// ### Arguments
 /// - `exe_dir`: directory where the binary is located
+/// - `timings`: timing summary to record the update steps into
+/// - `auto_approve`: whether to update without asking for confirmation
 ///
 /// ### Errors
+/// - If the confirmation prompt fails
 /// - If latest release could not be downloaded
 /// - If self-replace fails
 /// - If the temporary file fails to be removed.
-async fn update_launcher(exe_dir: &Path) -> Result<()> {
+async fn update_launcher(
+    exe_dir: &Path,
+    timings: &mut TimingSummary,
+    auto_approve: bool,
+) -> Result<()> {
     // the local version is read from the constant value in the binary
-    let (latest_release, requires_update) = check_for_launcher_update(DKN_LAUNCHER_VERSION).await?;
+    let (latest_release, requires_update) = timings
+        .time(
+            "launcher release check",
+            check_for_launcher_update(DKN_LAUNCHER_VERSION),
+        )
+        .await?;
 
     if requires_update {
+        if !confirm_update(&latest_release, Some(DKN_LAUNCHER_VERSION), auto_approve)? {
+            return Ok(());
+        }
         log::info!("Updating launcher to version: {}", latest_release.version());
 
-        let latest_path = latest_release
-            .download_release(exe_dir, ".tmp_launcher", true)
+        let latest_path = timings
+            .time(
+                "launcher download",
+                latest_release.download_release(exe_dir, ".tmp_launcher", true),
+            )
             .await?;
 
         // replace its own binary with the latest version
@@ -69,24 +159,57 @@ async fn update_launcher(exe_dir: &Path) -> Result<()> {
 ///
 /// ### Arguments
 /// - `exe_dir`: directory where the binary is located
+/// - `timings`: timing summary to record the update steps into
+/// - `auto_approve`: whether to update without asking for confirmation
 ///
 /// ### Errors
+/// - If the confirmation prompt fails
 /// - If latest release could not be downloaded
 /// - If local version tracker update does not complete
-async fn update_compute(exe_dir: &Path) -> Result<()> {
-    let (latest_release, requires_update) = check_for_compute_node_update(exe_dir).await?;
+async fn update_compute(
+    exe_dir: &Path,
+    timings: &mut TimingSummary,
+    auto_approve: bool,
+) -> Result<()> {
+    let (latest_release, requires_update) = timings
+        .time(
+            "compute release check",
+            check_for_compute_node_update(exe_dir),
+        )
+        .await?;
     if requires_update {
+        let current_version = DriaRelease::get_compute_version(exe_dir);
+        if let Err(err) = ensure_launcher_supports(&latest_release) {
+            // the installed compute node keeps running, if there is one
+            if current_version.is_none() || !exe_dir.join(DKN_LATEST_COMPUTE_FILE).exists() {
+                return Err(err);
+            }
+            log::warn!("{err:#}");
+            return Ok(());
+        }
+        if !confirm_update(&latest_release, current_version.as_deref(), auto_approve)? {
+            return Ok(());
+        }
         log::info!(
             "Updating compute node to version: {}",
             latest_release.version()
         );
 
-        latest_release
-            .download_release(exe_dir, DKN_LATEST_COMPUTE_FILE, true)
+        // the binary and its version tracker are replaced together, restored on failure
+        let mut transaction = FsTransaction::begin(exe_dir)?;
+        transaction.modify(&exe_dir.join(DKN_LATEST_COMPUTE_FILE))?;
+        transaction.modify(&exe_dir.join(DKN_VERSION_TRACKER_FILE))?;
+
+        timings
+            .time(
+                "compute download",
+                latest_release.download_release(exe_dir, DKN_LATEST_COMPUTE_FILE, true),
+            )
             .await?;
 
         // store the version as well
         DriaRelease::set_compute_version(exe_dir, latest_release.version())?;
+        transaction.commit()?;
     } else {
         log::info!(
             "Compute node already at latest version: {}",
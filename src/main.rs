@@ -1,5 +1,8 @@
 use clap::Parser;
+use eyre::WrapErr;
+use std::io::IsTerminal;
 use std::path::PathBuf;
+use std::process::ExitCode;
 
 mod commands;
 use commands::Commands;
@@ -23,14 +26,35 @@ struct Cli {
     /// Profile name for the environment file
     #[arg(short, long, value_parser = parse_profile)]
     profile: Option<String>,
+
+    /// Print a breakdown of where time went at the end of the command.
+    #[arg(long, global = true, default_value_t = false)]
+    summary: bool,
+
+    /// Output format for the launcher logs.
+    #[arg(long, global = true, value_enum, default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
+
+    /// Disable colors, also disabled with `NO_COLOR` or when the output is not a terminal.
+    #[arg(long, global = true, default_value_t = false)]
+    no_color: bool,
+
+    /// Only print warnings & errors, without progress bars; e.g. for cron or systemd.
+    #[arg(short, long, global = true, default_value_t = false)]
+    quiet: bool,
+
+    /// Log the method, URL, status & duration of HTTP requests, with their bodies redacted.
+    #[arg(long, global = true, default_value_t = false)]
+    debug_http: bool,
+
+    /// Fetch fresh points & referrals data instead of the cached responses.
+    #[arg(long, global = true, default_value_t = false)]
+    no_cache: bool,
 }
 
 /// Ensures that the profile name contains only alphanumeric characters, '-', or '_'.
 fn parse_profile(profile: &str) -> eyre::Result<String> {
-    if !profile
-        .chars()
-        .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
-    {
+    if !is_valid_profile_name(profile) {
         eyre::bail!("Profile name must contain only alphanumeric characters, '-', or '_'");
     }
 
@@ -38,43 +62,99 @@ fn parse_profile(profile: &str) -> eyre::Result<String> {
 }
 
 #[tokio::main]
-async fn main() -> eyre::Result<()> {
+async fn main() -> ExitCode {
+    match run().await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Error: {err:?}");
+            ExitCode::from(exit_code(&err))
+        }
+    }
+}
+
+/// Runs the command given by the arguments; errors are classified by [`ExitReason`] so that
+/// the launcher exits with a distinct code per failure class.
+async fn run() -> eyre::Result<()> {
     // default commands such as version and help exit at this point
     let cli = Cli::parse();
+    if cli.summary {
+        enable_timing_summary();
+    }
+    if cli.debug_http {
+        enable_http_tracing();
+    }
+    if cli.no_cache {
+        disable_api_cache();
+    }
 
     // env is given by the path
     let mut env_path = cli.env;
 
     // `.<profile>` is appended to the path if given
-    if let Some(profile) = cli.profile {
+    if let Some(profile) = &cli.profile {
         // we expect this to work because the path is checked to be a file
         let existing_file_name = env_path.file_name().unwrap().to_str().unwrap();
         env_path.set_file_name(format!("{existing_file_name}.{profile}"));
     }
 
+    // follow the redirect if the data directory has been moved, see `config move-data`
+    let moved_env_path = follow_moved_data(&env_path);
+    if let Some(moved_env_path) = &moved_env_path {
+        env_path = moved_env_path.clone();
+    }
+
     // read env w.r.t cli argument
     let dotenv_result = dotenvy::from_path(&env_path);
 
+    // colors are decided before anything is printed
+    init_terminal(cli.no_color, cli.quiet);
+
     // init env logger
-    env_logger::builder()
-        .format_timestamp(Some(env_logger::TimestampPrecision::Seconds))
-        .filter(None, log::LevelFilter::Off)
-        .filter_module("dkn_compute_launcher", log::LevelFilter::Info)
-        .parse_default_env()
-        .init();
+    init_logger(
+        cli.log_format,
+        cli.profile.clone(),
+        Some(&launcher_log_path(&env_path)),
+        cli.quiet,
+    );
+
+    // prompts & key log lines are shown in the language of the env file or the system
+    set_language(Language::from_env(&DriaEnv::new_from_env()));
 
     // log about env usage after env logger init is executed
+    if moved_env_path.is_some() {
+        log::info!("Data directory has been moved, following to the new location.");
+    }
     match dotenv_result {
-        Ok(_) => log::info!("Loaded env file at: {}", env_path.display()),
+        Ok(_) => {
+            log::info!(
+                "{}",
+                tr!("Loaded env file at: {path}", path = env_path.display())
+            );
+            // the env file is only rewritten by the commands that are run to set up & run the node
+            if matches!(cli.command, Commands::Start { .. } | Commands::Setup { .. }) {
+                offer_legacy_rewrite(&env_path).wrap_err(ExitReason::Config)?;
+            }
+        }
         Err(_) => {
             log::warn!(
-                "No env file found at {}, creating a new one",
-                env_path.display()
+                "{}",
+                tr!(
+                    "No env file found at {path}, creating a new one",
+                    path = env_path.display()
+                )
             );
-            DriaEnv::new_default_file(&env_path)?;
+            DriaEnv::new_default_file(&env_path).wrap_err(ExitReason::Config)?;
+
+            // guide the user through the setup before the first start
+            if matches!(cli.command, Commands::Start { .. }) && std::io::stdin().is_terminal() {
+                commands::setup_environment(&env_path).await?;
+            }
         }
     }
 
+    // env files of the dockerized compute node may have legacy key names
+    apply_legacy_env();
+
     // get the directory w.r.t env file, which will be used for the executable's directory
     // when a given path is relative, the parent may be empty; this is handled by checking
     // if the underlying `OsStr` is empty or not, in which case the fallback is given by
@@ -87,41 +167,134 @@ async fn main() -> eyre::Result<()> {
 
     match &cli.command {
         Commands::Settings => commands::change_settings(&env_path).await?,
-        Commands::Setup => commands::setup_environment(&env_path)?,
-        Commands::Points => commands::show_points().await?,
+        Commands::Setup { config: None } => commands::setup_environment(&env_path).await?,
+        Commands::Setup {
+            config: Some(config_path),
+        } => commands::setup_environment_from_config(&env_path, config_path)
+            .wrap_err(ExitReason::Config)?,
+        Commands::Points => commands::show_points(&env_path).await?,
+        Commands::Credits => commands::show_credits(&env_path).await?,
         Commands::EnvEditor => commands::edit_environment_file(&env_path)?,
         Commands::Uninstall { backup } => {
             let backup_path = backup.as_ref().map(|p| p.as_path());
             commands::uninstall_launcher(&exe_dir, &env_path, backup_path).await?
         }
-        Commands::Info => commands::show_info(),
-        Commands::Update => commands::update(&exe_dir).await,
+        Commands::Info { verbose } => commands::show_info(&exe_dir, &env_path, *verbose).await?,
+        Commands::Report { output } => {
+            commands::create_report(&exe_dir, &env_path, output.as_deref())?
+        }
+        Commands::Update {
+            launcher_only,
+            compute_only,
+            check,
+            auto_approve,
+        } => {
+            // both are updated if none is chosen
+            let launcher = !compute_only;
+            let compute = !launcher_only;
+            if *check {
+                commands::check_updates(&exe_dir, launcher, compute).await;
+            } else {
+                let mut timings = TimingSummary::new("update");
+                // the launcher is updated only in release mode, unless it is explicitly chosen
+                let launcher = *launcher_only || (launcher && !cfg!(debug_assertions));
+                let updated = commands::update_selected(
+                    &exe_dir,
+                    &mut timings,
+                    launcher,
+                    compute,
+                    *auto_approve,
+                )
+                .await;
+                timings.print();
+                if !updated {
+                    return Err(ExitReason::Update.into());
+                }
+            }
+        }
         Commands::Specific { run, tag } => {
             // downloads the specific version under the `exedir`, with the filename including the version tag
             // e.g. `./my/dir/dkn-compute-node_v0.3.6`
-            let exe_path = commands::download_specific_release(&exe_dir, tag.as_ref()).await?;
+            let exe_path = commands::download_specific_release(&exe_dir, tag.as_ref())
+                .await
+                .wrap_err(ExitReason::Download)?;
 
             // if `run` is true, the binary is executed immediately
             if *run {
-                commands::run_compute_node(&exe_path, &env_path, false)
+                commands::run_compute_node(&exe_path, &env_path, false, false, false)
                     .await?
                     .monitor_process()
-                    .await;
+                    .await?;
             } else {
-                log::info!("Executable is ready at {}", exe_path.display());
+                log::info!(
+                    "{}",
+                    tr!("Executable is ready at {path}", path = exe_path.display())
+                );
             }
         }
-        Commands::Start => {
+        Commands::Start {
+            take_over,
+            skip_perf_check,
+        } => {
             // downloads the latest version under the `exedir`, with the filename including "latest"
             // e.g. `./my/dir/dkn-compute-node_latest`
             let exe_path = exe_dir.join(DKN_LATEST_COMPUTE_FILE);
 
-            commands::run_compute_node(&exe_path, &env_path, true)
+            commands::run_compute_node(&exe_path, &env_path, true, *take_over, *skip_perf_check)
                 .await?
                 .monitor_process()
-                .await;
+                .await?;
+        }
+        Commands::Stop => {
+            commands::stop_node(&exe_dir, &env_path).await?;
+        }
+        Commands::Restart => {
+            commands::stop_node(&exe_dir, &env_path).await?;
+
+            // the env file is loaded by this launcher, so the node restarts with its latest values
+            let exe_path = exe_dir.join(DKN_LATEST_COMPUTE_FILE);
+            commands::run_compute_node(&exe_path, &env_path, true, false, false)
+                .await?
+                .monitor_process()
+                .await?;
+        }
+        Commands::Reload => {
+            commands::reload_node(&exe_dir, &env_path)?;
+        }
+        Commands::Sign {
+            message,
+            ledger,
+            ledger_path,
+            json,
+        } => commands::sign_message(message, ledger.then_some(ledger_path.as_str()), *json).await?,
+        Commands::Verify {
+            message,
+            address,
+            sig,
+        } => commands::verify_message(message, address, sig)?,
+        Commands::Identity { command } => {
+            commands::handle_identity(command, &exe_dir, &env_path).await?
+        }
+        Commands::Models { command } => commands::handle_models(command, &env_path).await?,
+        Commands::Ollama { command } => commands::handle_ollama(command).await?,
+        Commands::Status => commands::show_status(&exe_dir, &env_path).await?,
+        Commands::Dashboard => commands::show_dashboard(&exe_dir, &env_path).await?,
+        Commands::Referrals {
+            ledger,
+            ledger_path,
+            command,
+        } => {
+            commands::handle_referrals(command.as_ref(), ledger.then_some(ledger_path.as_str()))
+                .await?
+        }
+        Commands::Apikeys { command } => commands::handle_apikeys(command, &env_path)?,
+        Commands::Fleet { command } => commands::handle_fleet(command).await?,
+        Commands::Remote { command } => commands::handle_remote(command)?,
+        Commands::Generate { command } => commands::handle_generate(command, &env_path)?,
+        Commands::Config { command } => {
+            commands::handle_config(command, &exe_dir, &env_path).await?
         }
-        Commands::Referrals => commands::handle_referrals().await?,
+        Commands::Releases { command } => commands::handle_releases(command, &exe_dir)?,
     };
 
     Ok(())
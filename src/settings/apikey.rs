@@ -1,17 +1,26 @@
 use dkn_executor::ModelProvider;
 use inquire::{error::InquireResult, Select};
+use reqwest::StatusCode;
 use std::collections::HashSet;
+use std::time::Duration;
 
-use crate::{utils::Selectable, DriaEnv};
+use crate::{
+    tr,
+    utils::{Selectable, TracedSend, LAUNCHER_USER_AGENT},
+    DriaEnv,
+};
+
+/// Timeout for the API key validation requests.
+const API_KEY_VALIDATION_TIMEOUT: Duration = Duration::from_secs(10);
 
 pub fn edit_api_keys(dria_env: &mut DriaEnv) -> eyre::Result<()> {
     loop {
         // choose an API key name
         let Selectable::Some(chosen_api_key) = Select::new(
-            "Select an API key to change:",
+            tr!("Select an API key to change:"),
             Selectable::new(DriaApiKeyKind::all()),
         )
-        .with_help_message("↑↓ to move, ENTER to select, type to filter")
+        .with_help_message(tr!("↑↓ to move, ENTER to select, type to filter"))
         .prompt()?
         else {
             break;
@@ -42,6 +51,10 @@ pub enum DriaApiKeyKind {
     OpenAI,
     Gemini,
     OpenRouter,
+    /// Serper, used by search tools.
+    Serper,
+    /// Jina, used by the web reader tools.
+    Jina,
 }
 
 impl DriaApiKeyKind {
@@ -56,6 +69,8 @@ impl DriaApiKeyKind {
             Self::OpenAI => DriaEnv::OPENAI_APIKEY_KEY,
             Self::Gemini => DriaEnv::GEMINI_APIKEY_KEY,
             Self::OpenRouter => DriaEnv::OPENROUTER_APIKEY_KEY,
+            Self::Serper => DriaEnv::SERPER_APIKEY_KEY,
+            Self::Jina => DriaEnv::JINA_APIKEY_KEY,
         }
     }
 
@@ -65,10 +80,63 @@ impl DriaApiKeyKind {
             Self::OpenAI => "Get yours at https://platform.openai.com/api-keys",
             Self::Gemini => "Get yours at https://aistudio.google.com/app/apikey",
             Self::OpenRouter => "Get yours at https://openrouter.ai/keys",
+            Self::Serper => "Optional, for search tools. Get yours at https://serper.dev/api-key",
+            Self::Jina => {
+                "Optional, for web reader tools. Get yours at https://jina.ai/api-dashboard"
+            }
+        }
+    }
+
+    /// Validates the API key with a cheap request to the provider, e.g. listing the models.
+    ///
+    /// The key is always sent within a header, so that it does not end up in the logged URLs.
+    pub async fn validate(&self, api_key: &str) -> ApiKeyStatus {
+        let client = match reqwest::Client::builder()
+            .user_agent(LAUNCHER_USER_AGENT)
+            .timeout(API_KEY_VALIDATION_TIMEOUT)
+            .build()
+        {
+            Ok(client) => client,
+            Err(err) => return ApiKeyStatus::Unknown(err.to_string()),
+        };
+
+        let request = match self {
+            Self::OpenAI => client
+                .get("https://api.openai.com/v1/models")
+                .bearer_auth(api_key),
+            Self::Gemini => client
+                .get("https://generativelanguage.googleapis.com/v1beta/models")
+                .header("x-goog-api-key", api_key),
+            Self::OpenRouter => client
+                .get("https://openrouter.ai/api/v1/key")
+                .bearer_auth(api_key),
+            Self::Serper => client
+                .get("https://google.serper.dev/account")
+                .header("X-API-KEY", api_key),
+            Self::Jina => client
+                .get("https://embeddings-dashboard-api.jina.ai/api/v1/api_key/user")
+                .bearer_auth(api_key),
+        };
+
+        match request.send_traced().await {
+            Ok(response) if response.status().is_success() => ApiKeyStatus::Valid,
+            // Gemini responds with 400 for invalid keys
+            Ok(response)
+                if matches!(
+                    response.status(),
+                    StatusCode::BAD_REQUEST | StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN
+                ) =>
+            {
+                ApiKeyStatus::Invalid(response.status().to_string())
+            }
+            Ok(response) => ApiKeyStatus::Unknown(format!("unexpected {}", response.status())),
+            Err(err) => ApiKeyStatus::Unknown(err.to_string()),
         }
     }
 
     /// Given a list of providers (can contain duplicates) returns the unique set of API key kinds.
+    ///
+    /// Tool keys (Serper & Jina) are not tied to a model provider, so they are never returned here.
     pub fn from_providers(
         providers: impl Iterator<Item = ModelProvider>,
     ) -> impl Iterator<Item = Self> {
@@ -86,11 +154,11 @@ impl DriaApiKeyKind {
     /// A wrapper for `inquire::Text` for prompting the user to enter the API key.
     #[inline]
     pub fn prompt_api(&self, dria_env: &DriaEnv) -> InquireResult<String> {
-        inquire::Text::new(&format!("Enter your {}:", self.name()))
+        inquire::Text::new(&tr!("Enter your {key}:", key = self.name()))
             .with_default(dria_env.get(self.name()).unwrap_or_default())
-            .with_help_message(&format!(
-                "{} | type 'delete' to remove the API key",
-                self.help_message()
+            .with_help_message(&tr!(
+                "{help} | type 'delete' to remove the API key",
+                help = self.help_message()
             ))
             .prompt()
     }
@@ -101,3 +169,46 @@ impl std::fmt::Display for DriaApiKeyKind {
         write!(f, "{}", self.name())
     }
 }
+
+/// Result of an API key validation, see [`DriaApiKeyKind::validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApiKeyStatus {
+    /// The provider accepted the key.
+    Valid,
+    /// The provider rejected the key, e.g. it is wrong or expired.
+    Invalid(String),
+    /// The key could not be validated, e.g. the provider is unreachable.
+    Unknown(String),
+}
+
+impl std::fmt::Display for ApiKeyStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Valid => write!(f, "is valid"),
+            Self::Invalid(reason) => write!(f, "is invalid or expired ({reason})"),
+            Self::Unknown(reason) => write!(f, "could not be validated ({reason})"),
+        }
+    }
+}
+
+/// Validates all configured API keys concurrently, returns the status of each.
+pub async fn validate_api_keys(dria_env: &DriaEnv) -> Vec<(DriaApiKeyKind, ApiKeyStatus)> {
+    let configured = DriaApiKeyKind::all().into_iter().filter_map(|kind| {
+        dria_env
+            .get(kind.name())
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .map(|key| (kind, key))
+    });
+
+    futures::future::join_all(configured.map(|(kind, key)| async move {
+        // providers may echo the key back within their errors
+        let status = match kind.validate(key).await {
+            ApiKeyStatus::Invalid(reason) => ApiKeyStatus::Invalid(dria_env.redact(&reason)),
+            ApiKeyStatus::Unknown(reason) => ApiKeyStatus::Unknown(dria_env.redact(&reason)),
+            status => status,
+        };
+        (kind, status)
+    }))
+    .await
+}
@@ -0,0 +1,273 @@
+use colored::Colorize;
+use dkn_executor::ollama_rs::{
+    generation::completion::request::GenerationRequest, models::ModelOptions, Ollama,
+};
+use dkn_executor::ModelProvider;
+use inquire::{validator::Validation, Confirm, Select, Text};
+use std::time::Instant;
+
+use crate::tr;
+use crate::utils::{
+    check_ollama, connect_ollama, eval_tps, list_local_model_names, MINIMUM_EVAL_TPS,
+};
+use crate::DriaEnv;
+
+/// Batch sizes that are tried while auto-tuning, in order.
+const BATCH_SIZE_CANDIDATES: [usize; 5] = [1, 2, 4, 8, 16];
+/// Number of tokens to generate for each request while auto-tuning.
+const BATCH_TUNE_TOKENS: i32 = 64;
+
+/// Ways to edit the batch size.
+#[derive(Debug, Clone, Copy, enum_iterator::Sequence)]
+enum BatchSizeOption {
+    /// Enter the batch size manually.
+    Enter,
+    /// Measure the TPS with increasing batch sizes, and recommend the largest safe one.
+    AutoTune,
+    /// Remove the batch size, so that the compute node uses its default.
+    Reset,
+}
+
+impl std::fmt::Display for BatchSizeOption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Enter => write!(f, "{}", tr!("Enter batch size")),
+            Self::AutoTune => write!(f, "{}", tr!("Auto-tune for this machine")),
+            Self::Reset => write!(f, "{}", tr!("Reset to default")),
+        }
+    }
+}
+
+/// Throughput of a batch of concurrent requests to a model.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct BatchSample {
+    batch_size: usize,
+    /// Average evaluation TPS of a single request, comparable to [`MINIMUM_EVAL_TPS`].
+    request_tps: f64,
+    /// TPS of the whole batch, i.e. all generated tokens over the time of the batch.
+    total_tps: f64,
+}
+
+/// Prompts the user to edit the batch size of the compute node, i.e. how many tasks are
+/// processed at the same time; the batch size can be auto-tuned with an Ollama model.
+pub async fn edit_batch_size(dria_env: &mut DriaEnv) -> eyre::Result<()> {
+    let Some(option) = Select::new(
+        tr!("How would you like to set the batch size?"),
+        enum_iterator::all::<BatchSizeOption>().collect(),
+    )
+    .with_help_message(tr!("↑↓ to move, ENTER to select, ESC to go back"))
+    .prompt_skippable()?
+    else {
+        return Ok(());
+    };
+
+    let existing_batch_size = dria_env
+        .get(DriaEnv::DKN_BATCH_SIZE_KEY)
+        .unwrap_or_default()
+        .to_string();
+    let new_batch_size = match option {
+        BatchSizeOption::Enter => Text::new(tr!("Enter batch size:"))
+            .with_default(&existing_batch_size)
+            .with_validator(|value: &str| {
+                match value.trim().is_empty() || value.trim().parse::<u32>().is_ok_and(|v| v > 0) {
+                    true => Ok(Validation::Valid),
+                    false => Ok(Validation::Invalid(
+                        tr!("Must be a positive integer.").into(),
+                    )),
+                }
+            })
+            .with_help_message(tr!(
+                "Number of tasks processed at the same time, leave empty for default"
+            ))
+            .prompt()?
+            .trim()
+            .to_string(),
+        BatchSizeOption::AutoTune => match auto_tune_batch_size(dria_env).await? {
+            Some(batch_size) => batch_size.to_string(),
+            None => return Ok(()),
+        },
+        BatchSizeOption::Reset => String::new(),
+    };
+
+    if new_batch_size != existing_batch_size {
+        dria_env.set(DriaEnv::DKN_BATCH_SIZE_KEY, new_batch_size);
+    }
+
+    Ok(())
+}
+
+/// Measures the TPS of a chosen Ollama model with increasing batch sizes, and returns the
+/// recommended batch size if the user accepts it.
+///
+/// ### Errors
+/// - If Ollama is not available
+async fn auto_tune_batch_size(dria_env: &DriaEnv) -> eyre::Result<Option<usize>> {
+    if !check_ollama(dria_env).await {
+        eyre::bail!("Ollama is not available, please run Ollama server.");
+    }
+
+    // the model must be local, so that pulling it is not measured
+    let local_models = list_local_model_names(dria_env).await?;
+    let mut models = dria_env
+        .get_models()
+        .into_iter()
+        .filter(|model| model.provider() == ModelProvider::Ollama)
+        .map(|model| model.to_string())
+        .filter(|model| local_models.contains(model))
+        .collect::<Vec<_>>();
+    models.sort();
+    if models.is_empty() {
+        log::warn!("Auto-tuning requires a chosen Ollama model that is pulled, please choose & pull one first.");
+        return Ok(None);
+    }
+    let Some(model) = Select::new(tr!("Choose the model to auto-tune with:"), models)
+        .with_help_message(tr!("Choose your largest model, as it is the slowest one"))
+        .prompt_skippable()?
+    else {
+        return Ok(None);
+    };
+
+    let ollama = connect_ollama(dria_env)?;
+    log::info!("Loading {}", model.bold());
+    ollama
+        .generate(
+            GenerationRequest::new(model.clone(), "Say hello.")
+                .options(ModelOptions::default().num_predict(1)),
+        )
+        .await?;
+
+    let mut samples = Vec::new();
+    for batch_size in BATCH_SIZE_CANDIDATES {
+        log::info!("Measuring TPS with batch size {batch_size}");
+        let sample = measure_batch(&ollama, &model, batch_size).await?;
+        samples.push(sample);
+
+        // larger batches would only be slower
+        if recommend_batch_size(&samples) != Some(batch_size) {
+            break;
+        }
+    }
+
+    eprintln!(
+        "{:<12} {:<16} {}",
+        "Batch size".bold(),
+        "Request TPS".bold(),
+        "Total TPS".bold()
+    );
+    for sample in &samples {
+        let request_tps = format!("{:<16.4}", sample.request_tps);
+        eprintln!(
+            "{:<12} {} {:.4}",
+            sample.batch_size,
+            if sample.request_tps >= MINIMUM_EVAL_TPS {
+                request_tps.green()
+            } else {
+                request_tps.red()
+            },
+            sample.total_tps
+        );
+    }
+
+    let Some(batch_size) = recommend_batch_size(&samples) else {
+        log::warn!(
+            "{model} is below {MINIMUM_EVAL_TPS} TPS even without batching, consider a smaller model."
+        );
+        return Ok(None);
+    };
+    let accepted = Confirm::new(&tr!("Set the batch size to {size}?", size = batch_size))
+        .with_default(true)
+        .with_help_message(tr!(
+            "The largest batch size that keeps each task fast enough on this machine"
+        ))
+        .prompt()?;
+
+    Ok(accepted.then_some(batch_size))
+}
+
+/// Sends `batch_size` concurrent requests to the given model and measures their throughput.
+///
+/// The TPS of each request is measured with the evaluation duration reported by Ollama, as the
+/// minimum TPS applies to the evaluation only; the total TPS is measured with the whole duration
+/// of the batch, including the requests that wait beyond `OLLAMA_NUM_PARALLEL`.
+async fn measure_batch(
+    ollama: &Ollama,
+    model: &str,
+    batch_size: usize,
+) -> eyre::Result<BatchSample> {
+    let started = Instant::now();
+    let requests = (0..batch_size).map(|i| async move {
+        let res = ollama
+            .generate(
+                GenerationRequest::new(
+                    model.to_string(),
+                    format!("Write a short story about the number {i}."),
+                )
+                .options(ModelOptions::default().num_predict(BATCH_TUNE_TOKENS)),
+            )
+            .await?;
+
+        Ok::<_, eyre::Report>((res.eval_count.unwrap_or_default() as f64, eval_tps(&res)?))
+    });
+    let results = futures::future::try_join_all(requests).await?;
+    let elapsed = started.elapsed().as_secs_f64();
+
+    Ok(BatchSample {
+        batch_size,
+        request_tps: results.iter().map(|(_, tps)| tps).sum::<f64>() / batch_size as f64,
+        total_tps: results.iter().map(|(tokens, _)| tokens).sum::<f64>()
+            / elapsed.max(f64::EPSILON),
+    })
+}
+
+/// Returns the largest batch size among the samples (in increasing order of batch size) that is
+/// safe, i.e. each request is still above [`MINIMUM_EVAL_TPS`] and the total throughput has
+/// increased over the smaller batch sizes; `None` if even the first sample is too slow.
+fn recommend_batch_size(samples: &[BatchSample]) -> Option<usize> {
+    let mut recommended: Option<&BatchSample> = None;
+    for sample in samples {
+        if sample.request_tps < MINIMUM_EVAL_TPS {
+            break;
+        }
+        if recommended.is_some_and(|best| sample.total_tps <= best.total_tps) {
+            break;
+        }
+        recommended = Some(sample);
+    }
+
+    recommended.map(|sample| sample.batch_size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recommend_batch_size() {
+        let sample = |batch_size, request_tps, total_tps| BatchSample {
+            batch_size,
+            request_tps,
+            total_tps,
+        };
+
+        // per-request TPS drops below the minimum at 8
+        let samples = [
+            sample(1, 60.0, 60.0),
+            sample(2, 50.0, 100.0),
+            sample(4, 30.0, 120.0),
+            sample(8, 10.0, 80.0),
+        ];
+        assert_eq!(recommend_batch_size(&samples), Some(4));
+
+        // total throughput stops increasing at 4
+        let samples = [
+            sample(1, 60.0, 60.0),
+            sample(2, 40.0, 80.0),
+            sample(4, 20.0, 80.0),
+        ];
+        assert_eq!(recommend_batch_size(&samples), Some(2));
+
+        // too slow even without batching
+        assert_eq!(recommend_batch_size(&[sample(1, 5.0, 5.0)]), None);
+        assert_eq!(recommend_batch_size(&[]), None);
+    }
+}
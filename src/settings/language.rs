@@ -0,0 +1,59 @@
+use inquire::Select;
+
+use crate::tr;
+use crate::utils::{language, set_language, Language};
+use crate::DriaEnv;
+
+/// A language to choose, or the language of the system.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LanguageChoice {
+    System,
+    Language(Language),
+}
+
+impl std::fmt::Display for LanguageChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::System => write!(f, "{}", tr!("System default")),
+            Self::Language(language) => write!(f, "{language} ({})", language.code()),
+        }
+    }
+}
+
+/// Prompts the user to choose the language of the launcher, which is applied right away.
+pub fn edit_language(dria_env: &mut DriaEnv) -> eyre::Result<()> {
+    let existing = dria_env
+        .get(DriaEnv::DKN_LANG_KEY)
+        .and_then(Language::from_code)
+        .map_or(LanguageChoice::System, LanguageChoice::Language);
+    let choices = std::iter::once(LanguageChoice::System)
+        .chain(enum_iterator::all::<Language>().map(LanguageChoice::Language))
+        .collect::<Vec<_>>();
+    let cursor = choices
+        .iter()
+        .position(|choice| *choice == existing)
+        .unwrap_or_default();
+
+    let Some(choice) = Select::new(tr!("Choose the language:"), choices)
+        .with_starting_cursor(cursor)
+        .with_help_message(tr!("↑↓ to move, ENTER to select, ESC to go back"))
+        .prompt_skippable()?
+    else {
+        return Ok(());
+    };
+
+    if choice != existing {
+        let code = match choice {
+            LanguageChoice::System => "",
+            LanguageChoice::Language(language) => language.code(),
+        };
+        dria_env.set(DriaEnv::DKN_LANG_KEY, code);
+        set_language(Language::from_env(dria_env));
+        log::info!(
+            "{}",
+            tr!("Language is set to {language}.", language = language())
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,85 @@
+use inquire::{validator::Validation, Text};
+
+use crate::tr;
+use crate::utils::ResourceLimits;
+use crate::DriaEnv;
+
+/// Prompts the user to edit the resource limits of the compute node process.
+pub fn edit_resource_limits(dria_env: &mut DriaEnv) -> eyre::Result<()> {
+    // change CPU affinity
+    let existing_cpus = dria_env
+        .get(DriaEnv::DKN_CPU_AFFINITY_KEY)
+        .unwrap_or_default()
+        .to_string();
+    let new_cpus = Text::new(tr!("Enter CPU cores to run on:"))
+        .with_default(&existing_cpus)
+        .with_validator(|cpus: &str| {
+            if cpus.trim().is_empty() {
+                return Ok(Validation::Valid);
+            }
+            match ResourceLimits::parse_cpu_list(cpus) {
+                Ok(_) => Ok(Validation::Valid),
+                Err(err) => Ok(Validation::Invalid(err.to_string().into())),
+            }
+        })
+        .with_help_message(tr!(
+            "Comma-separated cores & ranges, e.g. 0-3,6, leave empty for all cores"
+        ))
+        .prompt()?;
+    if new_cpus != existing_cpus {
+        dria_env.set(DriaEnv::DKN_CPU_AFFINITY_KEY, new_cpus.trim());
+    }
+
+    // change niceness
+    let existing_niceness = dria_env
+        .get(DriaEnv::DKN_NICENESS_KEY)
+        .unwrap_or_default()
+        .to_string();
+    let new_niceness = Text::new(tr!("Enter niceness:"))
+        .with_default(&existing_niceness)
+        .with_validator(|niceness: &str| {
+            match niceness.trim().is_empty()
+                || niceness
+                    .trim()
+                    .parse::<i32>()
+                    .is_ok_and(|n| (-20..=19).contains(&n))
+            {
+                true => Ok(Validation::Valid),
+                false => Ok(Validation::Invalid(
+                    tr!("Niceness must be between -20 and 19").into(),
+                )),
+            }
+        })
+        .with_help_message(tr!(
+            "From -20 (highest priority) to 19 (lowest priority), leave empty for default"
+        ))
+        .prompt()?;
+    if new_niceness != existing_niceness {
+        dria_env.set(DriaEnv::DKN_NICENESS_KEY, new_niceness.trim());
+    }
+
+    // change memory limit
+    let existing_memory = dria_env
+        .get(DriaEnv::DKN_MEMORY_LIMIT_KEY)
+        .unwrap_or_default()
+        .to_string();
+    let new_memory = Text::new(tr!("Enter memory limit (MB):"))
+        .with_default(&existing_memory)
+        .with_validator(|memory: &str| {
+            match memory.trim().is_empty() || memory.trim().parse::<u64>().is_ok_and(|m| m > 0) {
+                true => Ok(Validation::Valid),
+                false => Ok(Validation::Invalid(
+                    tr!("Memory limit must be a positive number").into(),
+                )),
+            }
+        })
+        .with_help_message(tr!(
+            "Maximum memory of the compute node in megabytes, leave empty for no limit"
+        ))
+        .prompt()?;
+    if new_memory != existing_memory {
+        dria_env.set(DriaEnv::DKN_MEMORY_LIMIT_KEY, new_memory.trim());
+    }
+
+    Ok(())
+}
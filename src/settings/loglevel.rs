@@ -1,6 +1,6 @@
 use inquire::Select;
 
-use crate::{utils::Selectable, DriaEnv};
+use crate::{tr, utils::Selectable, DriaEnv};
 
 // the log levels are stored within `RUST_LOG` as used by `env_logger`
 const LOG_LEVELS_KEY: &str = "RUST_LOG";
@@ -21,10 +21,10 @@ pub fn edit_log_level(dria_env: &mut DriaEnv) -> eyre::Result<()> {
     loop {
         // choose a module
         let Selectable::Some(module) = Select::new(
-            "Select a module to change log level:",
+            tr!("Select a module to change log level:"),
             Selectable::new(LogModules::all()),
         )
-        .with_help_message("↑↓ to move, ENTER to select")
+        .with_help_message(tr!("↑↓ to move, ENTER to select"))
         .prompt()?
         else {
             break;
@@ -45,8 +45,8 @@ pub fn edit_log_level(dria_env: &mut DriaEnv) -> eyre::Result<()> {
 
         // choose a log level
         let Selectable::Some(choice) =
-            Select::new("Choose log level:", Selectable::new(LogLevels::all()))
-                .with_help_message("↑↓ to move, ENTER to select")
+            Select::new(tr!("Choose log level:"), Selectable::new(LogLevels::all()))
+                .with_help_message(tr!("↑↓ to move, ENTER to select"))
                 .with_starting_cursor(starting_cursor)
                 .prompt()?
         else {
@@ -0,0 +1,63 @@
+use eyre::Context;
+use inquire::{validator::Validation, Confirm, Text};
+
+use crate::tr;
+use crate::DriaEnv;
+
+/// Number of random bytes of a generated management API token.
+const MANAGEMENT_TOKEN_BYTES: usize = 32;
+
+/// Prompts the user to enable the management API, and to choose its port; a token is generated
+/// when there is none.
+pub fn edit_management_api(dria_env: &mut DriaEnv) -> eyre::Result<()> {
+    let is_enabled = dria_env.get_bool(DriaEnv::DKN_MANAGEMENT_API_KEY);
+    let enable = Confirm::new(tr!("Enable the management API?"))
+        .with_default(is_enabled)
+        .with_help_message(tr!(
+            "Serve status, logs, restart & settings at 127.0.0.1 for a web UI or mobile app"
+        ))
+        .prompt()?;
+    if enable != is_enabled {
+        dria_env.set(DriaEnv::DKN_MANAGEMENT_API_KEY, enable.to_string());
+    }
+    if !enable {
+        return Ok(());
+    }
+
+    let validator = |port_str: &str| match port_str.parse::<u16>() {
+        Ok(port) if port != 0 => Ok(Validation::Valid),
+        _ => Ok(Validation::Invalid(
+            tr!("Port must be a valid 16-bit unsigned integer.").into(),
+        )),
+    };
+    let existing_port = dria_env
+        .get_parsed::<u16>(DriaEnv::DKN_MANAGEMENT_PORT_KEY)
+        .expect("has a default")
+        .to_string();
+    let port = Text::new(tr!("Enter management API port:"))
+        .with_validator(validator)
+        .with_default(&existing_port)
+        .prompt()?;
+    if port != existing_port {
+        dria_env.set(DriaEnv::DKN_MANAGEMENT_PORT_KEY, port);
+    }
+
+    if dria_env
+        .get(DriaEnv::DKN_MANAGEMENT_TOKEN_KEY)
+        .filter(|token| !token.trim().is_empty())
+        .is_none()
+    {
+        let mut bytes = [0u8; MANAGEMENT_TOKEN_BYTES];
+        getrandom::getrandom(&mut bytes).wrap_err("could not generate random bytes")?;
+        dria_env.set(DriaEnv::DKN_MANAGEMENT_TOKEN_KEY, hex::encode(bytes));
+        log::info!(
+            "{}",
+            tr!(
+                "Generated a management API token, see {key} within your environment file.",
+                key = DriaEnv::DKN_MANAGEMENT_TOKEN_KEY
+            )
+        );
+    }
+
+    Ok(())
+}
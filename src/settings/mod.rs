@@ -1,32 +1,61 @@
 use colored::Colorize;
 
+use crate::tr;
+
 mod models;
 pub use models::edit_models; // used by `setup` command
 pub use models::show_model_settings_menu;
+pub use models::{find_compat_issues, local_memory_mb, measure_models}; // used by `models` command
 
-// TODO: we no longer have an API key requirement
-// mod apikey;
-// pub use apikey::{edit_api_keys, DriaApiKeyKind};
+// API keys are no longer required to run a node, but they are validated if configured
+mod apikey;
+pub use apikey::{edit_api_keys, validate_api_keys, ApiKeyStatus, DriaApiKeyKind};
 
 mod wallet;
-pub use wallet::edit_wallet;
+pub use wallet::{edit_wallet, import_mnemonic, show_wallet_menu};
 
 mod port;
 pub use port::edit_port;
 
+mod network;
+pub use network::edit_network;
+
+mod node;
+pub use node::edit_node;
+
 mod ollama;
 pub use ollama::edit_ollama;
 
+mod limits;
+pub use limits::edit_resource_limits;
+
+mod batch;
+pub use batch::edit_batch_size;
+
 mod loglevel;
 pub use loglevel::edit_log_level;
 
+mod language;
+pub use language::edit_language;
+
+mod management;
+pub use management::edit_management_api;
+
 /// Compute node setting commands.
 #[derive(Debug, Clone, enum_iterator::Sequence)]
 pub enum Settings {
-    /// Configure your wallet (secret key).
+    /// Configure your wallet (secret key), or switch between saved wallets.
     Wallet,
     /// Configure the selected port.
     Port,
+    /// Configure the network (mainnet / testnet).
+    Network,
+    /// Configure the node name & labels.
+    Node,
+    /// Configure the resource limits of the compute node.
+    Resources,
+    /// Configure the batch size of the compute node, or auto-tune it.
+    BatchSize,
     /// Configure the selected models.
     Models,
     /// Configure Ollama settings.
@@ -35,8 +64,14 @@ pub enum Settings {
     // ApiKeys,
     /// Configure log-levels.
     LogLevels,
+    /// Choose the language of the launcher.
+    Language,
+    /// Configure the local management API.
+    ManagementApi,
     /// Quit settings menu.
     SaveExit,
+    /// Review the pending changes, and revert them individually.
+    Review,
     /// Abort all changes.
     Abort,
 }
@@ -51,14 +86,21 @@ impl Settings {
 impl std::fmt::Display for Settings {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Wallet => write!(f, "Wallet"),
-            Self::Port => write!(f, "Port"),
-            Self::Models => write!(f, "Models"),
+            Self::Wallet => write!(f, "{}", tr!("Wallet")),
+            Self::Port => write!(f, "{}", tr!("Port")),
+            Self::Network => write!(f, "{}", tr!("Network")),
+            Self::Node => write!(f, "{}", tr!("Node Name & Labels")),
+            Self::Resources => write!(f, "{}", tr!("Resource Limits")),
+            Self::BatchSize => write!(f, "{}", tr!("Batch Size")),
+            Self::Models => write!(f, "{}", tr!("Models")),
             Self::Ollama => write!(f, "Ollama"),
-            // Self::ApiKeys => write!(f, "API Keys"),
-            Self::LogLevels => write!(f, "Log Levels"),
-            Self::SaveExit => write!(f, "{}", "✓ Save & Exit".bold().green()),
-            Self::Abort => write!(f, "{}", "✗ Abort Changes".bold().red()),
+            // Self::ApiKeys => write!(f, "{}", tr!("API Keys")),
+            Self::LogLevels => write!(f, "{}", tr!("Log Levels")),
+            Self::Language => write!(f, "{}", tr!("Language")),
+            Self::ManagementApi => write!(f, "{}", tr!("Management API")),
+            Self::SaveExit => write!(f, "{}", tr!("✓ Save & Exit").bold().green()),
+            Self::Review => write!(f, "{}", tr!("↺ Review Changes").bold().yellow()),
+            Self::Abort => write!(f, "{}", tr!("✗ Abort Changes").bold().red()),
         }
     }
 }
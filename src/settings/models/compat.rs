@@ -0,0 +1,284 @@
+use colored::Colorize;
+use dkn_executor::{Model, ModelProvider};
+use inquire::Confirm;
+
+use crate::settings::DriaApiKeyKind;
+use crate::tr;
+use crate::utils::{estimate_model_memory_mb, is_remote_ollama, HardwareInfo};
+use crate::DriaEnv;
+
+/// Model names that were served by older compute nodes, along with their replacements.
+const DEPRECATED_MODELS: [(&str, &str); 4] = [
+    ("llama3.1:latest", "llama3.1:8b-instruct-q4_K_M"),
+    ("llama3.2:1b", "llama3.2:1b-instruct-q4_K_M"),
+    ("gemini-1.5-flash", "gemini-2.0-flash"),
+    ("gemini-1.5-pro", "gemini-2.0-flash"),
+];
+
+/// A problem with a model selection, found before it is saved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompatIssue {
+    /// An Ollama model that needs more memory than this machine has at all.
+    TooLarge { model: Model, needed_mb: u64 },
+    /// An Ollama model that does not fit in the GPU memory, so it runs partially on the CPU.
+    ExceedsGpu { model: Model, needed_mb: u64 },
+    /// An API-based model whose provider has no API key.
+    MissingApiKey { model: Model, kind: DriaApiKeyKind },
+    /// A model name within the environment that the compute node no longer serves, which is
+    /// dropped once the selection is saved.
+    Deprecated {
+        name: String,
+        replacement: Option<&'static str>,
+    },
+}
+
+impl CompatIssue {
+    /// Returns `true` if the model can not possibly run, so the selection must not be saved.
+    pub fn is_blocking(&self) -> bool {
+        matches!(self, Self::TooLarge { .. } | Self::MissingApiKey { .. })
+    }
+}
+
+impl std::fmt::Display for CompatIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let gb = |mb: &u64| *mb as f64 / 1024.0;
+        match self {
+            Self::TooLarge { model, needed_mb } => write!(
+                f,
+                "{model} needs about {:.1} GB of memory, which this machine does not have",
+                gb(needed_mb)
+            ),
+            Self::ExceedsGpu { model, needed_mb } => write!(
+                f,
+                "{model} needs about {:.1} GB of memory, more than your GPU has; it will run partially on the CPU and may be too slow",
+                gb(needed_mb)
+            ),
+            Self::MissingApiKey { model, kind } => {
+                write!(f, "{model} requires {kind}, which is not set")
+            }
+            Self::Deprecated {
+                name,
+                replacement: Some(replacement),
+            } => write!(
+                f,
+                "{name} is no longer served, use {replacement} instead; it will be removed"
+            ),
+            Self::Deprecated {
+                name,
+                replacement: None,
+            } => write!(f, "{name} is not a known model; it will be removed"),
+        }
+    }
+}
+
+/// Finds the problems with the given model selection:
+///
+/// - Ollama models are compared against the memory of the machine, i.e. the largest GPU memory
+///   (`gpu_memory_mb`) and the total memory (`memory_mb`); this is skipped if the memory is not
+///   known, e.g. when Ollama runs on another machine
+/// - API-based models must have the API key of their provider, as checked with `has_key`
+/// - Names within `raw_models` (i.e. the current value of `DKN_MODELS`) that are not known
+///   models are flagged as deprecated
+pub fn find_compat_issues(
+    models: &[Model],
+    raw_models: &str,
+    gpu_memory_mb: Option<u64>,
+    memory_mb: Option<u64>,
+    has_key: impl Fn(&DriaApiKeyKind) -> bool,
+) -> Vec<CompatIssue> {
+    let mut issues = Vec::new();
+
+    for name in raw_models.split(',').map(str::trim) {
+        if !name.is_empty() && Model::try_from(name.to_string()).is_err() {
+            issues.push(CompatIssue::Deprecated {
+                name: name.to_string(),
+                replacement: DEPRECATED_MODELS
+                    .iter()
+                    .find(|(deprecated, _)| *deprecated == name)
+                    .map(|(_, replacement)| *replacement),
+            });
+        }
+    }
+
+    for model in models {
+        if model.provider() == ModelProvider::Ollama {
+            let Some(memory_mb) = memory_mb else {
+                continue;
+            };
+            let Some(needed_mb) = estimate_model_memory_mb(&model.to_string()) else {
+                continue;
+            };
+            let total_mb = gpu_memory_mb.unwrap_or_default().max(memory_mb);
+            if needed_mb > total_mb {
+                issues.push(CompatIssue::TooLarge {
+                    model: *model,
+                    needed_mb,
+                });
+            } else if gpu_memory_mb.is_some_and(|gpu_mb| needed_mb > gpu_mb) {
+                issues.push(CompatIssue::ExceedsGpu {
+                    model: *model,
+                    needed_mb,
+                });
+            }
+        } else if let Some(kind) = DriaApiKeyKind::from_providers(std::iter::once(model.provider()))
+            .find(|kind| !has_key(kind))
+        {
+            issues.push(CompatIssue::MissingApiKey {
+                model: *model,
+                kind,
+            });
+        }
+    }
+
+    issues
+}
+
+/// Checks the model selection before it is saved, explaining each problem; missing API keys
+/// can be entered right away.
+///
+/// ### Returns
+/// `true` if the selection can be saved, `false` if some models can not possibly run and must
+/// be deselected.
+pub fn precheck_models(dria_env: &mut DriaEnv, models: &[Model]) -> eyre::Result<bool> {
+    let (gpu_memory_mb, memory_mb) = local_memory_mb(dria_env, models);
+    let issues = find_compat_issues(
+        models,
+        dria_env.get(DriaEnv::DKN_MODELS_KEY).unwrap_or_default(),
+        gpu_memory_mb,
+        memory_mb,
+        |kind| {
+            dria_env
+                .get(kind.name())
+                .is_some_and(|key| !key.trim().is_empty())
+        },
+    );
+
+    let mut can_save = true;
+    let mut prompted = Vec::new();
+    for issue in issues {
+        match &issue {
+            CompatIssue::MissingApiKey { kind, .. } if prompted.contains(kind) => {}
+            CompatIssue::MissingApiKey { kind, .. } => {
+                eprintln!("{} {issue}", "✗".red());
+                prompted.push(kind.clone());
+                let api_key = kind.prompt_api(dria_env)?;
+                if api_key.trim().is_empty() || api_key.eq_ignore_ascii_case("delete") {
+                    can_save = false;
+                } else {
+                    dria_env.set(kind.name(), api_key.trim());
+                }
+            }
+            issue if issue.is_blocking() => {
+                eprintln!("{} {issue}", "✗".red());
+                can_save = false;
+            }
+            issue => eprintln!("{} {issue}", "!".yellow()),
+        }
+    }
+
+    if !can_save {
+        eprintln!(
+            "{}",
+            "Some models can not run on this machine, please deselect them.".red()
+        );
+        // estimates may be off for unusual setups, e.g. with swap
+        return Ok(Confirm::new(tr!("Save the selection anyway?"))
+            .with_help_message(tr!("The node will most likely fail to serve these models"))
+            .with_default(false)
+            .prompt()?);
+    }
+
+    Ok(true)
+}
+
+/// Returns the largest GPU memory & the total memory of this machine, to check the Ollama
+/// models against; both are `None` if Ollama is remote, as its machine is not known, in which
+/// case a warning is shown if there are Ollama models within the selection.
+pub fn local_memory_mb(dria_env: &DriaEnv, models: &[Model]) -> (Option<u64>, Option<u64>) {
+    if is_remote_ollama(dria_env) {
+        if models
+            .iter()
+            .any(|model| model.provider() == ModelProvider::Ollama)
+        {
+            eprintln!(
+                "{} Ollama is remote, so the memory of its machine is not checked against the models",
+                "!".yellow()
+            );
+        }
+        return (None, None);
+    }
+
+    let hardware = HardwareInfo::detect();
+    (
+        hardware.gpus.iter().map(|gpu| gpu.memory_mb).max(),
+        Some(hardware.memory_mb),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_compat_issues() {
+        let first = |provider| Model::all_with_provider(&provider).next().unwrap();
+        let (ollama, needed_mb) = Model::all_with_provider(&ModelProvider::Ollama)
+            .find_map(|model| Some((model, estimate_model_memory_mb(&model.to_string())?)))
+            .unwrap();
+        let (openai, gemini) = (first(ModelProvider::OpenAI), first(ModelProvider::Gemini));
+        let models = [ollama, openai, gemini];
+        let has_key = |kind: &DriaApiKeyKind| *kind == DriaApiKeyKind::Gemini;
+
+        let issues = find_compat_issues(
+            &models,
+            &format!("{ollama},llama3.1:latest,foo"),
+            None,
+            Some(needed_mb - 1),
+            has_key,
+        );
+        assert_eq!(
+            issues,
+            [
+                CompatIssue::Deprecated {
+                    name: "llama3.1:latest".to_string(),
+                    replacement: Some("llama3.1:8b-instruct-q4_K_M")
+                },
+                CompatIssue::Deprecated {
+                    name: "foo".to_string(),
+                    replacement: None
+                },
+                CompatIssue::TooLarge {
+                    model: ollama,
+                    needed_mb
+                },
+                CompatIssue::MissingApiKey {
+                    model: openai,
+                    kind: DriaApiKeyKind::OpenAI
+                },
+            ]
+        );
+        assert_eq!(issues.iter().filter(|issue| issue.is_blocking()).count(), 2);
+
+        // fits in the system memory but not in the GPU
+        let issues = find_compat_issues(
+            &models[..1],
+            "",
+            Some(needed_mb - 1),
+            Some(needed_mb),
+            has_key,
+        );
+        assert_eq!(
+            issues,
+            [CompatIssue::ExceedsGpu {
+                model: ollama,
+                needed_mb
+            }]
+        );
+        assert!(!issues[0].is_blocking());
+
+        assert!(find_compat_issues(&models[..1], "", Some(needed_mb), Some(0), has_key).is_empty());
+
+        // remote Ollama, where the memory is not known
+        assert!(find_compat_issues(&models[..1], "", None, None, has_key).is_empty());
+    }
+}
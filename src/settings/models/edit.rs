@@ -1,7 +1,8 @@
 use dkn_executor::{Model, ModelProvider};
 use inquire::{MultiSelect, Select};
 
-use crate::{utils::Selectable, DriaEnv};
+use super::precheck_models;
+use crate::{tr, utils::Selectable, DriaEnv};
 
 /// Edit the chosen models.
 pub fn edit_models(dria_env: &mut DriaEnv) -> eyre::Result<()> {
@@ -11,15 +12,17 @@ pub fn edit_models(dria_env: &mut DriaEnv) -> eyre::Result<()> {
     let mut chosen_models = dria_env.get_models().into_iter().collect::<Vec<_>>();
     loop {
         let Selectable::Some(provider) = Select::new(
-            "Select a model provider:",
+            tr!("Select a model provider:"),
             Selectable::new(ModelProvider::all().collect()),
         )
-        .with_help_message("↑↓ to move, type to filter provider, ENTER to select")
+        .with_help_message(tr!("↑↓ to move, type to filter provider, ENTER to select"))
         .prompt()?
         else {
             if chosen_models.is_empty() {
                 log::error!("You must choose at least 1 model!");
                 continue;
+            } else if is_changed && !precheck_models(dria_env, &chosen_models)? {
+                continue;
             } else {
                 break;
             }
@@ -44,14 +47,11 @@ pub fn edit_models(dria_env: &mut DriaEnv) -> eyre::Result<()> {
             })
             .collect::<Vec<_>>();
 
-        let  selected_prov_models = MultiSelect::new(
-          "Choose your models with SPACE, then press ENTER:",
+        let  selected_prov_models = MultiSelect::new(tr!("Choose your models with SPACE, then press ENTER:"),
           all_prov_models.clone(),
       )
       .with_default(&default_selected_idxs)
-      .with_help_message(
-          "↑↓ to move, SPACE to select one, ←/→ to select all/none, type to filter models, ENTER to confirm"
-      )
+      .with_help_message(tr!("↑↓ to move, SPACE to select one, ←/→ to select all/none, type to filter models, ENTER to confirm"))
       .prompt()?;
 
         is_changed = true;
@@ -1,22 +1,21 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use colored::Colorize;
-use dkn_executor::ollama_rs::{
-    generation::completion::{request::GenerationRequest, GenerationResponse},
-    Ollama,
-};
+use dkn_executor::ollama_rs::{generation::completion::request::GenerationRequest, Ollama};
 use dkn_executor::{Model, ModelProvider};
-use inquire::MultiSelect;
+use inquire::{error::InquireResult, Confirm, MultiSelect};
 
-use crate::utils::{check_ollama, pull_model_with_progress, DriaEnv};
+use crate::tr;
+use crate::utils::{
+    benchmark_hardware, check_ollama, connect_ollama, is_remote_ollama, pull_model_with_progress,
+    BenchmarkCache, BenchmarkResult, DriaEnv, TimingSummary, UtilizationSampler, MINIMUM_EVAL_TPS,
+};
 
-const MINIMUM_EVAL_TPS: f64 = 15.0;
 const MINIMUM_DURATION_MS: u64 = 120 * 1000;
 
 /// Prompts the user to select Ollama models, and measures the TPS for each one.
 /// The user can select multiple models to be benchmarked.
 ///
-///
 /// ### Errors
 /// - If Ollama is not available / something is wrong about the chosen model.
 pub async fn measure_tps(dria_env: &DriaEnv) -> eyre::Result<()> {
@@ -25,59 +24,82 @@ pub async fn measure_tps(dria_env: &DriaEnv) -> eyre::Result<()> {
         eyre::bail!("Ollama is not available, please run Ollama server.");
     }
 
-    // get all Ollama models available
-    let all_ollama_models = Model::all_with_provider(&ModelProvider::Ollama).collect::<Vec<_>>();
-
-    // get users ollama models
-    let models = dria_env.get_models();
-    let my_ollama_models = models
-        .iter()
-        .filter(|m| m.provider() == ModelProvider::Ollama)
-        .collect::<HashSet<_>>();
-
-    // find indexes of existing chosen ollama models on the user
-    let default_selected_idxs = all_ollama_models
-        .iter()
-        .enumerate()
-        .filter_map(|(idx, model)| {
-            if my_ollama_models.contains(model) {
-                Some(idx)
-            } else {
-                None
-            }
-        })
-        .collect::<Vec<_>>();
-
     // prompt the user to select models to be benchmarked
-    let selected_ollama_models = MultiSelect::new(
+    let selected_ollama_models = select_ollama_models(
+        dria_env,
         "Choose the Ollama models that you would like to measure:",
-        all_ollama_models,
-    )
-    .with_default(&default_selected_idxs)
-    .with_help_message(
-        "↑↓ to move, SPACE to select one, ←/→ to select all/none, type to filter models, ENTER to confirm",
-    )
-    .prompt()?;
-
+    )?;
     if selected_ollama_models.is_empty() {
         log::info!("No models selected, exiting.");
         return Ok(());
     }
 
+    measure_models(dria_env, &selected_ollama_models, None).await
+}
+
+/// Measures the TPS of the given Ollama models, pulling the ones that do not exist locally,
+/// and prints a table of the results.
+///
+/// Measurements are kept within the [`BenchmarkCache`], and a model that is measured before
+/// with its current version on this hardware is shown from there unless `fresh` is `Some(true)`;
+/// the user is asked whether to measure them again if `fresh` is `None`.
+///
+/// ### Errors
+/// - If Ollama is not available, or a model could not be pulled.
+pub async fn measure_models(
+    dria_env: &DriaEnv,
+    models: &[Model],
+    fresh: Option<bool>,
+) -> eyre::Result<()> {
+    if !check_ollama(dria_env).await {
+        eyre::bail!("Ollama is not available, please run Ollama server.");
+    }
+
     // create a table
     let mut table = Table::default();
+    let mut timings = TimingSummary::new("measure");
 
     // create ollama instance
-    let (host, port) = dria_env.get_ollama_config();
-    let ollama = Ollama::new(host, port);
+    let ollama = connect_ollama(dria_env)?;
 
-    // get local models
-    let local_model_names = ollama
+    // pull the models that do not exist locally
+    pull_missing_models(dria_env, &ollama, models, &mut timings).await?;
+
+    // models are measured again only if they are pulled again or the hardware has changed
+    let hardware = benchmark_hardware(dria_env);
+    let model_versions = ollama
         .list_local_models()
         .await?
         .into_iter()
-        .map(|m| m.name)
+        .map(|m| (m.name, m.modified_at))
+        .collect::<HashMap<_, _>>();
+    let mut cache = BenchmarkCache::load();
+    let mut cached = models
+        .iter()
+        .filter_map(|model| {
+            let name = model.to_string();
+            let version = model_versions.get(&name)?;
+            cache.get(&name, version, &hardware).cloned()
+        })
         .collect::<Vec<_>>();
+    let fresh = match fresh {
+        _ if cached.is_empty() => false,
+        Some(fresh) => fresh,
+        None => {
+            for result in &cached {
+                eprintln!("{} is measured {}", result.model, result.age());
+            }
+            Confirm::new(tr!("Measure these models again?"))
+                .with_help_message(tr!(
+                    "They are measured again once they are updated, or the hardware changes"
+                ))
+                .with_default(false)
+                .prompt()?
+        }
+    };
+    if fresh {
+        cached.clear();
+    }
 
     // iterate over selected models and run a benchmark on each one
     log::info!(
@@ -85,29 +107,25 @@ pub async fn measure_tps(dria_env: &DriaEnv) -> eyre::Result<()> {
         MINIMUM_EVAL_TPS,
         MINIMUM_DURATION_MS
     );
-    for model in selected_ollama_models
-        .into_iter()
-        .filter(|m| m.provider() == ModelProvider::Ollama)
-    {
+    // utilization of a remote Ollama can not be sampled from here
+    let sample_utilization = !is_remote_ollama(dria_env);
+    for model in models {
         let model_name = model.to_string();
-
-        if !local_model_names.contains(&model_name) {
-            log::info!(
-                "Model {} does not exist locally, pulling it from Ollama.",
-                model_name
-            );
-
-            // pull the model with nice logs
-            pull_model_with_progress(&ollama, model_name).await?;
+        if let Some(result) = cached.iter().find(|result| result.model == model_name) {
+            table.add_row(result.clone());
+            continue;
         }
 
         // run a dummy generation for warm-up
         log::debug!("Warming up Ollama for model {}", model);
-        if let Err(e) = ollama
-            .generate(GenerationRequest::new(
-                model.to_string(),
-                "Write a short poem about hedgehogs and squirrels.".to_string(),
-            ))
+        if let Err(e) = timings
+            .time(
+                format!("warm-up {model}"),
+                ollama.generate(GenerationRequest::new(
+                    model.to_string(),
+                    "Write a short poem about hedgehogs and squirrels.".to_string(),
+                )),
+            )
             .await
         {
             log::warn!("Ignoring model {}: Workflow failed with error {}", model, e);
@@ -116,15 +134,28 @@ pub async fn measure_tps(dria_env: &DriaEnv) -> eyre::Result<()> {
 
         // generate a prompt
         log::info!("Measuring {}", model.to_string().bold());
-        match ollama
-            .generate(GenerationRequest::new(
-                model.to_string(),
-                "Write a poem about Kapadokya.".to_string(),
-            ))
-            .await
-        {
+        let sampler = sample_utilization.then(UtilizationSampler::start);
+        let response = timings
+            .time(
+                format!("measure {model}"),
+                ollama.generate(GenerationRequest::new(
+                    model.to_string(),
+                    "Write a poem about Kapadokya.".to_string(),
+                )),
+            )
+            .await;
+        let utilization = match sampler {
+            Some(sampler) => sampler.stop().await,
+            None => None,
+        };
+        match response {
             Ok(response) => {
-                table.add_row(response.into());
+                let version = model_versions.get(&model_name).cloned().unwrap_or_default();
+                let mut result =
+                    BenchmarkResult::new(&response, model_name, version, hardware.clone());
+                result.utilization = utilization;
+                cache.insert(result.clone());
+                table.add_row(result);
             }
             Err(e) => {
                 log::warn!("Model {} failed with error {}", model, e);
@@ -132,79 +163,133 @@ pub async fn measure_tps(dria_env: &DriaEnv) -> eyre::Result<()> {
             }
         }
     }
+    if let Err(err) = cache.save() {
+        log::warn!("Could not save the measurements: {err}");
+    }
 
     // print the final result
     log::info!("Finished TPS measurements.");
     eprintln!("{}", table);
+    timings.print();
 
     Ok(())
 }
 
-struct TableRow {
-    model: String,
-    prompt_tps: f64,
-    prompt_dur_ms: u64,
-    eval_tps: f64,
-    eval_dur_ms: u64,
-    total_dur_ms: u64,
+/// Prompts the user to select Ollama models, where the models of the user are selected by default.
+pub(super) fn select_ollama_models(dria_env: &DriaEnv, message: &str) -> InquireResult<Vec<Model>> {
+    // get all Ollama models available
+    let all_ollama_models = Model::all_with_provider(&ModelProvider::Ollama).collect::<Vec<_>>();
+
+    // get users ollama models
+    let models = dria_env.get_models();
+    let my_ollama_models = models
+        .iter()
+        .filter(|m| m.provider() == ModelProvider::Ollama)
+        .collect::<HashSet<_>>();
+
+    // find indexes of existing chosen ollama models on the user
+    let default_selected_idxs = all_ollama_models
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, model)| {
+            if my_ollama_models.contains(model) {
+                Some(idx)
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+
+    MultiSelect::new(message, all_ollama_models)
+        .with_default(&default_selected_idxs)
+        .with_help_message(tr!("↑↓ to move, SPACE to select one, ←/→ to select all/none, type to filter models, ENTER to confirm"))
+        .prompt()
 }
 
-impl From<GenerationResponse> for TableRow {
-    fn from(res: GenerationResponse) -> Self {
-        let prompt_tps = (res.prompt_eval_count.unwrap_or_default() as f64)
-            / (res.prompt_eval_duration.unwrap_or(1) as f64)
-            * 1e9;
-
-        let eval_tps = (res.eval_count.unwrap_or_default() as f64)
-            / (res.eval_duration.unwrap_or(1) as f64)
-            * 1e9;
-
-        Self {
-            model: res.model,
-            prompt_tps,
-            prompt_dur_ms: res.prompt_eval_duration.unwrap_or_default() / 1e6 as u64,
-            eval_tps,
-            eval_dur_ms: res.eval_duration.unwrap_or_default() / 1e6 as u64,
-            total_dur_ms: res.total_duration.unwrap_or_default() / 1e6 as u64,
+/// Pulls the given models that do not exist locally, with progress bars.
+pub(super) async fn pull_missing_models(
+    dria_env: &DriaEnv,
+    ollama: &Ollama,
+    models: &[Model],
+    timings: &mut TimingSummary,
+) -> eyre::Result<()> {
+    let local_model_names = ollama
+        .list_local_models()
+        .await?
+        .into_iter()
+        .map(|m| m.name)
+        .collect::<Vec<_>>();
+
+    for model in models {
+        let model_name = model.to_string();
+
+        if !local_model_names.contains(&model_name) {
+            log::info!(
+                "Model {} does not exist locally, pulling it from Ollama.",
+                model_name
+            );
+
+            // pull the model with nice logs
+            timings
+                .time(
+                    format!("pull {model_name}"),
+                    pull_model_with_progress(ollama, model_name, dria_env.get_pull_retries()),
+                )
+                .await?;
         }
     }
+
+    Ok(())
 }
 
-impl TableRow {
-    fn print_row(&self) -> String {
-        let eval_tps = self.eval_tps;
-        let dur = self.total_dur_ms;
-        format!(
-            "{:<36} {:<12.4} {:<12} {} {:<12} {}",
-            self.model,
-            self.prompt_tps,
-            self.prompt_dur_ms,
-            if eval_tps > 1.5 * MINIMUM_EVAL_TPS {
-                format!("{:<12.4}", eval_tps).green()
-            } else if eval_tps > MINIMUM_EVAL_TPS {
-                format!("{:<12.4}", eval_tps).yellow()
-            } else {
-                format!("{:<12.4}", eval_tps).red()
-            },
-            self.eval_dur_ms,
-            if dur > MINIMUM_DURATION_MS {
-                dur.to_string().red()
-            } else if dur > MINIMUM_DURATION_MS / 2 {
-                dur.to_string().yellow()
-            } else {
-                dur.to_string().green()
-            },
-        )
-    }
+/// Returns a line of the table for the given result.
+fn print_row(result: &BenchmarkResult) -> String {
+    let eval_tps = result.eval_tps;
+    let dur = result.total_dur_ms;
+    let utilization = result.utilization.unwrap_or_default();
+    let or_dash = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+    format!(
+        "{:<36} {:<12.4} {:<12} {} {:<12} {} {:<14} {:<8} {:<8} {}",
+        result.model,
+        result.prompt_tps,
+        result.prompt_dur_ms,
+        if eval_tps > 1.5 * MINIMUM_EVAL_TPS {
+            format!("{:<12.4}", eval_tps).green()
+        } else if eval_tps > MINIMUM_EVAL_TPS {
+            format!("{:<12.4}", eval_tps).yellow()
+        } else {
+            format!("{:<12.4}", eval_tps).red()
+        },
+        result.eval_dur_ms,
+        if dur > MINIMUM_DURATION_MS {
+            format!("{:<12}", dur).red()
+        } else if dur > MINIMUM_DURATION_MS / 2 {
+            format!("{:<12}", dur).yellow()
+        } else {
+            format!("{:<12}", dur).green()
+        },
+        or_dash(utilization.peak_gpu_memory_mb.map(|mb| mb.to_string())),
+        or_dash(
+            utilization
+                .gpu_percent
+                .map(|percent| format!("{percent:.0}%"))
+        ),
+        or_dash(
+            result
+                .utilization
+                .map(|utilization| format!("{:.0}%", utilization.cpu_percent))
+        ),
+        result.age().dimmed(),
+    )
 }
 
 #[derive(Default)]
 struct Table {
-    rows: Vec<TableRow>,
+    rows: Vec<BenchmarkResult>,
 }
 impl Table {
     #[inline]
-    pub fn add_row(&mut self, row: TableRow) {
+    pub fn add_row(&mut self, row: BenchmarkResult) {
         self.rows.push(row);
     }
 
@@ -212,13 +297,17 @@ impl Table {
     #[inline]
     fn get_header() -> String {
         format!(
-            "{:<36} {:<12} {:<12} {:<12} {:<12} {}",
+            "{:<36} {:<12} {:<12} {:<12} {:<12} {:<12} {:<14} {:<8} {:<8} {}",
             "Model".bold(),
             "Prompt TPS".bold().dimmed(),
             "Time (ms)".bold().dimmed(),
             "Eval TPS".bold(),
             "Time (ms)".bold(),
             "Total (ms)".bold(),
+            "GPU mem (MB)".bold(),
+            "GPU %".bold(),
+            "CPU %".bold(),
+            "Measured".bold().dimmed(),
         )
     }
 }
@@ -228,7 +317,7 @@ impl std::fmt::Display for Table {
         writeln!(f, "{}", Self::get_header())?;
 
         for row in &self.rows {
-            writeln!(f, "{}", row.print_row(),)?;
+            writeln!(f, "{}", print_row(row))?;
         }
 
         Ok(())
@@ -1,6 +1,10 @@
 use inquire::Select;
 
-use crate::{utils::Selectable, DriaEnv};
+use crate::{tr, utils::Selectable, DriaEnv};
+
+mod compat;
+use compat::precheck_models;
+pub use compat::{find_compat_issues, local_memory_mb}; // used by `models` command
 
 mod edit;
 pub use edit::edit_models; // also used by `setup` command
@@ -9,11 +13,18 @@ mod list;
 use list::list_models;
 
 mod measure;
+pub use measure::measure_models; // used by `models` command
 use measure::measure_tps;
 
+mod workflow;
+use workflow::benchmark_workflows;
+
 mod remove;
 use remove::remove_local_models;
 
+mod usage;
+use usage::show_disk_usage;
+
 #[derive(Debug, Clone, enum_iterator::Sequence)]
 enum ModelSettings {
     Edit,
@@ -21,6 +32,10 @@ enum ModelSettings {
     Remove,
     /// Measure performance (TPS) of Ollama models on your machine.
     Measure,
+    /// Measure the latency of a representative workflow with Ollama models on your machine.
+    Workflow,
+    /// Show the disk usage of Ollama models, and clean up the unused ones.
+    DiskUsage,
 }
 
 impl ModelSettings {
@@ -33,10 +48,12 @@ impl ModelSettings {
 impl std::fmt::Display for ModelSettings {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Edit => write!(f, "Edit model selection"),
-            Self::List => write!(f, "List chosen models"),
-            Self::Remove => write!(f, "Remove local models"),
-            Self::Measure => write!(f, "Measure local models"),
+            Self::Edit => write!(f, "{}", tr!("Edit model selection")),
+            Self::List => write!(f, "{}", tr!("List chosen models")),
+            Self::Remove => write!(f, "{}", tr!("Remove local models")),
+            Self::Measure => write!(f, "{}", tr!("Measure local models")),
+            Self::Workflow => write!(f, "{}", tr!("Benchmark workflow latency")),
+            Self::DiskUsage => write!(f, "{}", tr!("Disk usage")),
         }
     }
 }
@@ -44,10 +61,10 @@ impl std::fmt::Display for ModelSettings {
 pub async fn show_model_settings_menu(dria_env: &mut DriaEnv) -> eyre::Result<()> {
     loop {
         let Selectable::Some(choice) = Select::new(
-            "Choose model settings:",
+            tr!("Choose model settings:"),
             Selectable::new(ModelSettings::all()),
         )
-        .with_help_message("↑↓ to move, ENTER to select")
+        .with_help_message(tr!("↑↓ to move, ENTER to select"))
         .prompt()?
         else {
             return Ok(());
@@ -66,6 +83,12 @@ pub async fn show_model_settings_menu(dria_env: &mut DriaEnv) -> eyre::Result<()
             ModelSettings::Measure => {
                 measure_tps(dria_env).await?;
             }
+            ModelSettings::Workflow => {
+                benchmark_workflows(dria_env).await?;
+            }
+            ModelSettings::DiskUsage => {
+                show_disk_usage(dria_env).await?;
+            }
         }
     }
 }
@@ -1,7 +1,10 @@
-use dkn_executor::ollama_rs::Ollama;
 use inquire::MultiSelect;
 
-use crate::{utils::check_ollama, DriaEnv};
+use crate::{
+    tr,
+    utils::{check_ollama, connect_ollama},
+    DriaEnv,
+};
 
 /// Remove local models (same as `ollama rm`).
 pub async fn remove_local_models(dria_env: &mut DriaEnv) -> eyre::Result<()> {
@@ -11,8 +14,7 @@ pub async fn remove_local_models(dria_env: &mut DriaEnv) -> eyre::Result<()> {
     }
 
     // create ollama instance
-    let (host, port) = dria_env.get_ollama_config();
-    let ollama = Ollama::new(host, port);
+    let ollama = connect_ollama(dria_env)?;
 
     // get local models
     let local_models = ollama
@@ -23,13 +25,10 @@ pub async fn remove_local_models(dria_env: &mut DriaEnv) -> eyre::Result<()> {
         .collect::<Vec<_>>();
 
     // prompt the user to select models to be removed
-    let selected_models = MultiSelect::new(
-        "Choose the models that you would like to remove:",
+    let selected_models = MultiSelect::new(tr!("Choose the models that you would like to remove:"),
         local_models.clone(),
     )
-    .with_help_message(
-        "↑↓ to move, SPACE to select one, ←/→ to select all/none, type to filter models, ENTER to confirm",
-    )
+    .with_help_message(tr!("↑↓ to move, SPACE to select one, ←/→ to select all/none, type to filter models, ENTER to confirm"))
     .prompt()?;
     if selected_models.is_empty() {
         log::info!("No models selected, exiting.");
@@ -0,0 +1,93 @@
+use colored::Colorize;
+use indicatif::HumanBytes;
+use inquire::Confirm;
+
+use crate::{
+    tr,
+    utils::{check_ollama, connect_ollama},
+    DriaEnv,
+};
+
+/// Lists local Ollama models along with their sizes on disk, flagging the ones that are not
+/// selected in `DKN_MODELS`, and offers to remove those unused models.
+pub async fn show_disk_usage(dria_env: &DriaEnv) -> eyre::Result<()> {
+    // ensure Ollama is available
+    if !check_ollama(dria_env).await {
+        eyre::bail!("Ollama is not available, please run Ollama server.");
+    }
+
+    // create ollama instance
+    let ollama = connect_ollama(dria_env)?;
+
+    // get local models, largest first
+    let mut local_models = ollama.list_local_models().await?;
+    if local_models.is_empty() {
+        eprintln!("No local models found.");
+        return Ok(());
+    }
+    local_models.sort_by_key(|model| std::cmp::Reverse(model.size));
+
+    let selected_models = dria_env
+        .get_models()
+        .into_iter()
+        .map(|model| model.to_string())
+        .collect::<Vec<_>>();
+    let (selected, unused): (Vec<_>, Vec<_>) = local_models
+        .into_iter()
+        .partition(|model| selected_models.contains(&model.name));
+
+    let name_width = selected
+        .iter()
+        .chain(unused.iter())
+        .map(|model| model.name.len())
+        .max()
+        .unwrap_or_default();
+    for model in &selected {
+        eprintln!(
+            "{:<name_width$}  {:>10}",
+            model.name,
+            HumanBytes(model.size).to_string()
+        );
+    }
+    for model in &unused {
+        eprintln!(
+            "{:<name_width$}  {:>10}  {}",
+            model.name,
+            HumanBytes(model.size).to_string(),
+            "not selected".yellow()
+        );
+    }
+
+    let selected_size = selected.iter().map(|model| model.size).sum::<u64>();
+    let unused_size = unused.iter().map(|model| model.size).sum::<u64>();
+    eprintln!(
+        "\nTotal: {} ({} used by selected models)",
+        HumanBytes(selected_size + unused_size),
+        HumanBytes(selected_size)
+    );
+    if unused.is_empty() {
+        return Ok(());
+    }
+
+    // offer to remove all unused models at once
+    let answer = Confirm::new(&tr!(
+        "Remove {count} models that are not selected to reclaim {size}?",
+        count = unused.len(),
+        size = HumanBytes(unused_size)
+    ))
+    .with_default(false)
+    .prompt()?;
+    if !answer {
+        return Ok(());
+    }
+
+    for model in unused {
+        if let Err(e) = ollama.delete_model(model.name.clone()).await {
+            log::error!("Failed to remove model {}: {}", model.name, e);
+        } else {
+            log::info!("Removed model {}", model.name);
+        }
+    }
+
+    Ok(())
+}
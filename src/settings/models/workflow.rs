@@ -0,0 +1,254 @@
+use colored::Colorize;
+use dkn_executor::ollama_rs::{
+    generation::{
+        chat::{request::ChatMessageRequest, ChatMessage},
+        tools::ToolInfo,
+    },
+    models::ModelOptions,
+    Ollama,
+};
+use dkn_executor::Model;
+use eyre::Context;
+use std::time::{Duration, Instant};
+
+use super::measure::{pull_missing_models, select_ollama_models};
+use crate::utils::{check_ollama, connect_ollama, DriaEnv, TimingSummary, MINIMUM_EVAL_TPS};
+
+/// Number of times the workflow is run per model, after a warm-up run.
+const WORKFLOW_RUNS: usize = 5;
+/// Maximum number of tokens of the short answers within the workflow.
+const SHORT_ANSWER_TOKENS: i32 = 64;
+/// Maximum number of tokens of the long generation within the workflow.
+const LONG_ANSWER_TOKENS: i32 = 512;
+
+/// The latencies of the workflow runs of a model.
+#[derive(Debug, Default)]
+struct WorkflowStats {
+    model: String,
+    /// Latency of each run.
+    latencies: Vec<Duration>,
+    /// Evaluation TPS of each run, over all of its steps.
+    eval_tps: Vec<f64>,
+    /// Number of runs where the model has called the tool as expected.
+    tool_calls: usize,
+}
+
+impl WorkflowStats {
+    fn print_row(&self) -> String {
+        let ms = |p| percentile(&self.latencies, p).map_or(0, |d| d.as_millis());
+        let tps = self.eval_tps.iter().sum::<f64>() / self.eval_tps.len().max(1) as f64;
+        format!(
+            "{:<36} {:<12} {:<12} {} {}/{}",
+            self.model,
+            ms(50.0),
+            ms(95.0),
+            if tps > MINIMUM_EVAL_TPS {
+                format!("{:<12.4}", tps).green()
+            } else {
+                format!("{:<12.4}", tps).red()
+            },
+            self.tool_calls,
+            self.latencies.len()
+        )
+    }
+}
+
+/// Prompts the user to select Ollama models, and benchmarks a representative workflow for each
+/// one: a short prompt, a tool call along with the answer to its result, and a longer
+/// generation; the p50 & p95 latencies of the whole workflow are reported along with its TPS.
+///
+/// ### Errors
+/// - If Ollama is not available, or a model could not be pulled.
+pub async fn benchmark_workflows(dria_env: &DriaEnv) -> eyre::Result<()> {
+    if !check_ollama(dria_env).await {
+        eyre::bail!("Ollama is not available, please run Ollama server.");
+    }
+
+    let models = select_ollama_models(
+        dria_env,
+        "Choose the Ollama models that you would like to benchmark:",
+    )?;
+    if models.is_empty() {
+        log::info!("No models selected, exiting.");
+        return Ok(());
+    }
+
+    let mut timings = TimingSummary::new("workflow benchmark");
+    let ollama = connect_ollama(dria_env)?;
+    pull_missing_models(dria_env, &ollama, &models, &mut timings).await?;
+
+    let mut rows = Vec::new();
+    for model in models {
+        log::info!(
+            "Benchmarking {} with {WORKFLOW_RUNS} workflow runs",
+            model.to_string().bold()
+        );
+
+        // warm-up, so that loading the model is not measured
+        if let Err(err) = run_workflow(&ollama, &model).await {
+            log::warn!("Ignoring model {model}: workflow failed with error {err}");
+            continue;
+        }
+
+        let mut stats = WorkflowStats {
+            model: model.to_string(),
+            ..Default::default()
+        };
+        let started = Instant::now();
+        for _ in 0..WORKFLOW_RUNS {
+            match run_workflow(&ollama, &model).await {
+                Ok(run) => {
+                    stats.latencies.push(run.latency);
+                    stats.eval_tps.push(run.eval_tps);
+                    stats.tool_calls += run.called_tool as usize;
+                }
+                Err(err) => log::warn!("Workflow of {model} failed with error {err}"),
+            }
+        }
+        timings.record(format!("benchmark {model}"), started);
+        rows.push(stats);
+    }
+
+    log::info!("Finished workflow benchmarks.");
+    eprintln!(
+        "{:<36} {:<12} {:<12} {:<12} {}",
+        "Model".bold(),
+        "p50 (ms)".bold(),
+        "p95 (ms)".bold(),
+        "Eval TPS".bold(),
+        "Tool calls".bold()
+    );
+    for row in &rows {
+        eprintln!("{}", row.print_row());
+    }
+    timings.print();
+
+    Ok(())
+}
+
+/// A single run of the workflow.
+struct WorkflowRun {
+    latency: Duration,
+    eval_tps: f64,
+    /// Whether the model has called the tool, models that do not support tools answer directly.
+    called_tool: bool,
+}
+
+/// Runs the workflow once with the given model.
+async fn run_workflow(ollama: &Ollama, model: &Model) -> eyre::Result<WorkflowRun> {
+    let started = Instant::now();
+    let (mut eval_count, mut eval_duration) = (0u64, 0u64);
+    let short = ModelOptions::default().num_predict(SHORT_ANSWER_TOKENS);
+
+    // 1. a short prompt
+    let res = ollama
+        .send_chat_messages(
+            ChatMessageRequest::new(
+                model.to_string(),
+                vec![
+                    ChatMessage::system("You are a helpful assistant.".to_string()),
+                    ChatMessage::user("In one sentence, what is a knowledge network?".to_string()),
+                ],
+            )
+            .options(short.clone()),
+        )
+        .await?;
+    if let Some(data) = res.final_data {
+        (eval_count, eval_duration) = (data.eval_count, data.eval_duration);
+    }
+
+    // 2. a tool call, whose result is given back to the model
+    let mut messages = vec![ChatMessage::user(
+        "Search the web for the latest news about decentralized AI, then summarize it.".to_string(),
+    )];
+    let res = ollama
+        .send_chat_messages(
+            ChatMessageRequest::new(model.to_string(), messages.clone())
+                .tools(vec![search_tool()?])
+                .options(short.clone()),
+        )
+        .await?;
+    let called_tool = !res.message.tool_calls.is_empty();
+    if let Some(data) = res.final_data {
+        eval_count += data.eval_count;
+        eval_duration += data.eval_duration;
+    }
+    messages.push(res.message);
+    messages.push(ChatMessage::tool(
+        "Dria has launched a decentralized network where anyone can serve AI models to earn rewards.".to_string(),
+    ));
+    let res = ollama
+        .send_chat_messages(ChatMessageRequest::new(model.to_string(), messages).options(short))
+        .await?;
+    if let Some(data) = res.final_data {
+        eval_count += data.eval_count;
+        eval_duration += data.eval_duration;
+    }
+
+    // 3. a longer generation
+    let res = ollama
+        .send_chat_messages(
+            ChatMessageRequest::new(
+                model.to_string(),
+                vec![ChatMessage::user(
+                    "Write a detailed essay about the history of Cappadocia.".to_string(),
+                )],
+            )
+            .options(ModelOptions::default().num_predict(LONG_ANSWER_TOKENS)),
+        )
+        .await?;
+    if let Some(data) = res.final_data {
+        eval_count += data.eval_count;
+        eval_duration += data.eval_duration;
+    }
+
+    Ok(WorkflowRun {
+        latency: started.elapsed(),
+        eval_tps: eval_count as f64 / eval_duration.max(1) as f64 * 1e9,
+        called_tool,
+    })
+}
+
+/// Returns a web search tool, similar to the search tools used within Dria workflows.
+fn search_tool() -> eyre::Result<ToolInfo> {
+    serde_json::from_value(serde_json::json!({
+        "type": "Function",
+        "function": {
+            "name": "web_search",
+            "description": "Searches the web, returns the top results.",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string", "description": "The search query." }
+                },
+                "required": ["query"]
+            }
+        }
+    }))
+    .wrap_err("invalid tool definition")
+}
+
+/// Returns the `p`-th percentile of the given durations with the nearest-rank method, `None` if
+/// there are none.
+fn percentile(durations: &[Duration], p: f64) -> Option<Duration> {
+    let mut sorted = durations.to_vec();
+    sorted.sort();
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted.get(rank.clamp(1, sorted.len().max(1)) - 1).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentile() {
+        let durations = [5, 1, 4, 2, 3].map(Duration::from_secs).to_vec();
+        assert_eq!(percentile(&durations, 50.0), Some(Duration::from_secs(3)));
+        assert_eq!(percentile(&durations, 95.0), Some(Duration::from_secs(5)));
+        assert_eq!(percentile(&durations, 0.0), Some(Duration::from_secs(1)));
+        assert_eq!(percentile(&[], 50.0), None);
+
+        assert!(search_tool().is_ok());
+    }
+}
@@ -0,0 +1,89 @@
+use inquire::{validator::Validation, Select, Text};
+use reqwest::Url;
+
+use crate::{
+    tr,
+    utils::{get_network_urls_with, EnvKey, KNOWN_NETWORKS},
+    DriaEnv,
+};
+
+/// Prompts the user to edit the network, the base URL overrides of the points & referrals APIs,
+/// and the mirrors to download releases from.
+pub fn edit_network(dria_env: &mut DriaEnv) -> eyre::Result<()> {
+    let existing_network = dria_env
+        .get_or_default(DriaEnv::DKN_NETWORK_KEY)
+        .unwrap_or_default()
+        .to_string();
+    let starting_cursor = KNOWN_NETWORKS
+        .iter()
+        .position(|network| *network == existing_network)
+        .unwrap_or(0);
+
+    // change network
+    let Some(new_network) = Select::new(tr!("Choose the network:"), KNOWN_NETWORKS.to_vec())
+        .with_help_message(tr!("↑↓ to move, ENTER to select, ESC to go back"))
+        .with_starting_cursor(starting_cursor)
+        .prompt_skippable()?
+    else {
+        return Ok(());
+    };
+    if new_network != existing_network {
+        log::info!("Network will be {new_network} after the node is restarted.");
+        dria_env.set(DriaEnv::DKN_NETWORK_KEY, new_network);
+    }
+
+    // change base URL overrides
+    for (key, message) in [
+        (DriaEnv::DKN_POINTS_API_URL_KEY, "Enter points API URL:"),
+        (
+            DriaEnv::DKN_REFERRALS_API_URL_KEY,
+            "Enter referrals API URL:",
+        ),
+    ] {
+        let existing_value = dria_env.get(key).unwrap_or_default().to_string();
+        let new_value = Text::new(message)
+            .with_default(&existing_value)
+            .with_validator(|value: &str| {
+                match value.trim().is_empty() || Url::parse(value.trim()).is_ok() {
+                    true => Ok(Validation::Valid),
+                    false => Ok(Validation::Invalid(tr!("Must be a valid URL.").into())),
+                }
+            })
+            .with_help_message(tr!("For staging environments or internal mirrors, leave empty to use the network's URL"))
+            .prompt()?;
+        if new_value != existing_value {
+            dria_env.set(key, new_value.trim());
+        }
+    }
+
+    // change download mirrors
+    let existing_mirrors = dria_env
+        .get(DriaEnv::DKN_DOWNLOAD_MIRRORS_KEY)
+        .unwrap_or_default()
+        .to_string();
+    let new_mirrors = Text::new(tr!("Enter download mirrors:"))
+        .with_default(&existing_mirrors)
+        .with_validator(|value: &str| {
+            let kind = EnvKey::find(DriaEnv::DKN_DOWNLOAD_MIRRORS_KEY)
+                .expect("must be registered")
+                .kind;
+            match kind.validate(value) {
+                Ok(()) => Ok(Validation::Valid),
+                Err(err) => Ok(Validation::Invalid(err.into())),
+            }
+        })
+        .with_help_message(tr!("Comma-separated URLs tried before GitHub, with {repo}, {version} & {asset} placeholders; leave empty for GitHub only"))
+        .prompt()?;
+    if new_mirrors != existing_mirrors {
+        dria_env.set(DriaEnv::DKN_DOWNLOAD_MIRRORS_KEY, new_mirrors.trim());
+    }
+
+    // show the URLs that will be used as a result
+    eprintln!("The following URLs will be used on {new_network}:");
+    for (name, url) in get_network_urls_with(new_network, |key| dria_env.get(key).map(String::from))
+    {
+        eprintln!("  {name}: {url}");
+    }
+
+    Ok(())
+}
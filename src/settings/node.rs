@@ -0,0 +1,46 @@
+use inquire::{validator::Validation, Text};
+
+use crate::tr;
+use crate::DriaEnv;
+
+/// Prompts the user to edit the node name & labels.
+pub fn edit_node(dria_env: &mut DriaEnv) -> eyre::Result<()> {
+    // change name
+    let existing_name = dria_env.get_node_name().unwrap_or_default().to_string();
+    let new_name = Text::new(tr!("Enter node name:"))
+        .with_default(&existing_name)
+        .with_help_message(tr!(
+            "A human-friendly name for this node, leave empty for none"
+        ))
+        .prompt()?;
+    if new_name != existing_name {
+        dria_env.set(DriaEnv::DKN_NODE_NAME_KEY, new_name.trim());
+    }
+
+    // change labels
+    let existing_labels = dria_env
+        .get(DriaEnv::DKN_LABELS_KEY)
+        .unwrap_or_default()
+        .to_string();
+    let new_labels = Text::new(tr!("Enter node labels:"))
+        .with_default(&existing_labels)
+        .with_validator(|labels: &str| match DriaEnv::parse_labels(labels) {
+            Ok(_) => Ok(Validation::Valid),
+            Err(err) => Ok(Validation::Invalid(err.to_string().into())),
+        })
+        .with_help_message(tr!(
+            "Comma-separated key=value pairs, e.g. region=eu,gpu=3090"
+        ))
+        .prompt()?;
+    if new_labels != existing_labels {
+        // store in a normalized form
+        let labels = DriaEnv::parse_labels(&new_labels)?
+            .into_iter()
+            .map(|(k, v)| format!("{k}={v}"))
+            .collect::<Vec<_>>()
+            .join(",");
+        dria_env.set(DriaEnv::DKN_LABELS_KEY, labels);
+    }
+
+    Ok(())
+}
@@ -1,21 +1,26 @@
-use inquire::{validator::Validation, Text};
+use inquire::{validator::Validation, Confirm, Text};
 use reqwest::Url;
 
-use crate::DriaEnv;
+use crate::{
+    tr,
+    utils::{is_valid_keep_alive, ollama_log_path},
+    DriaEnv,
+};
 
-/// Prompts the user to edit the Ollama server settings (host & port).
+/// Prompts the user to edit the Ollama server settings (host, port, keep-alive, context length & parallelism),
+/// after how long models are unloaded when the node is idle, and whether its output is streamed to the launcher logs.
 pub fn edit_ollama(dria_env: &mut DriaEnv) -> eyre::Result<()> {
     let (existing_host, existing_port) = dria_env.get_ollama_config();
     let existing_host = existing_host.to_string();
     let existing_port = existing_port.to_string();
 
     // change host
-    let new_host = Text::new("Enter host:")
+    let new_host = Text::new(tr!("Enter host:"))
         .with_default(&existing_host)
         .with_validator(|host_str: &str| match Url::parse(host_str) {
             Ok(_) => Ok(Validation::Valid),
             Err(err) => Ok(Validation::Invalid(
-                format!("Host must be a valid URL: {}", err).into(),
+                tr!("Host must be a valid URL: {err}", err = err).into(),
             )),
         })
         .prompt()?;
@@ -24,12 +29,12 @@ pub fn edit_ollama(dria_env: &mut DriaEnv) -> eyre::Result<()> {
     }
 
     // change port
-    let new_port = Text::new("Enter port:")
+    let new_port = Text::new(tr!("Enter port:"))
         .with_default(&existing_port)
         .with_validator(|port_str: &str| match port_str.parse::<u16>() {
             Ok(_) => Ok(Validation::Valid),
             Err(_) => Ok(Validation::Invalid(
-                "Port must be a valid 16-bit unsigned integer.".into(),
+                tr!("Port must be a valid 16-bit unsigned integer.").into(),
             )),
         })
         .prompt()?;
@@ -37,5 +42,72 @@ pub fn edit_ollama(dria_env: &mut DriaEnv) -> eyre::Result<()> {
         dria_env.set(DriaEnv::OLLAMA_PORT_KEY, new_port);
     }
 
+    // change keep-alive
+    let existing_keep_alive = dria_env
+        .get(DriaEnv::OLLAMA_KEEP_ALIVE_KEY)
+        .unwrap_or_default()
+        .to_string();
+    let new_keep_alive = Text::new(tr!("Enter keep-alive duration:"))
+        .with_default(&existing_keep_alive)
+        .with_validator(|keep_alive: &str| match keep_alive.trim().is_empty() || is_valid_keep_alive(keep_alive.trim()) {
+            true => Ok(Validation::Valid),
+            false => Ok(Validation::Invalid(
+                tr!("Keep-alive must be a duration like 30m, 24h, or -1 to keep models loaded forever.")
+                    .into(),
+            )),
+        })
+        .with_help_message(tr!("How long models stay loaded after a request (Ollama default: 5m), leave empty for default"))
+        .prompt()?;
+    if new_keep_alive != existing_keep_alive {
+        dria_env.set(DriaEnv::OLLAMA_KEEP_ALIVE_KEY, new_keep_alive.trim());
+    }
+
+    // change context length, parallelism & idle unload
+    for (key, message, help) in [
+        (
+            DriaEnv::OLLAMA_CONTEXT_LENGTH_KEY,
+            tr!("Enter context length:"),
+            tr!("Context length of the models in tokens (e.g. 8192), leave empty for default"),
+        ),
+        (
+            DriaEnv::OLLAMA_NUM_PARALLEL_KEY,
+            tr!("Enter number of parallel requests:"),
+            tr!("Number of requests each model serves in parallel, leave empty for default"),
+        ),
+        (
+            DriaEnv::OLLAMA_IDLE_UNLOAD_KEY,
+            tr!("Enter idle minutes before unloading models:"),
+            tr!("Unload models from memory after this many minutes without tasks, leave empty to keep them loaded"),
+        ),
+    ] {
+        let existing_value = dria_env.get(key).unwrap_or_default().to_string();
+        let new_value = Text::new(message)
+            .with_default(&existing_value)
+            .with_validator(|value: &str| {
+                match value.trim().is_empty() || value.trim().parse::<u32>().is_ok_and(|v| v > 0) {
+                    true => Ok(Validation::Valid),
+                    false => Ok(Validation::Invalid(tr!("Must be a positive integer.").into())),
+                }
+            })
+            .with_help_message(help)
+            .prompt()?;
+        if new_value != existing_value {
+            dria_env.set(key, new_value.trim());
+        }
+    }
+
+    // change output streaming
+    let existing_stream = dria_env.is_ollama_log_stream_enabled();
+    let new_stream = Confirm::new(tr!("Stream Ollama output to the launcher logs?"))
+        .with_default(existing_stream)
+        .with_help_message(&tr!(
+            "Shown at debug level, the output is always written to {path}",
+            path = ollama_log_path().unwrap_or_default().display()
+        ))
+        .prompt()?;
+    if new_stream != existing_stream {
+        dria_env.set(DriaEnv::OLLAMA_LOG_STREAM_KEY, new_stream.to_string());
+    }
+
     Ok(())
 }
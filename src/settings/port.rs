@@ -1,44 +1,279 @@
-use inquire::{validator::Validation, Text};
+use inquire::{validator::Validation, Confirm, MultiSelect, Select, Text};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
+use crate::tr;
+use crate::utils::{offer_free_port, parse_listen_addrs, parse_listen_port};
 use crate::DriaEnv;
 
-const DEFAULT_LISTEN_ADDR: &str = "/ip4/0.0.0.0/tcp/4001";
+/// The default P2P port, used when the existing listen address has no valid port.
+const DEFAULT_P2P_PORT: u16 = 4001;
 
+/// Where the compute node listens for P2P connections.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ListenBinding {
+    /// All IPv4 interfaces, i.e. `0.0.0.0`.
+    AllIpv4,
+    /// All IPv6 interfaces, i.e. `::`.
+    AllIpv6,
+    /// All IPv4 & IPv6 interfaces.
+    AllIpv4AndIpv6,
+    /// Specific IP addresses of the network interfaces.
+    Interfaces,
+    /// Listen addresses entered as is.
+    Custom,
+}
+
+impl ListenBinding {
+    const ALL: [Self; 5] = [
+        Self::AllIpv4,
+        Self::AllIpv6,
+        Self::AllIpv4AndIpv6,
+        Self::Interfaces,
+        Self::Custom,
+    ];
+
+    /// Returns the IP addresses to listen on, `None` for specific or custom addresses.
+    fn unspecified_ips(&self) -> Option<Vec<IpAddr>> {
+        match self {
+            Self::AllIpv4 => Some(vec![Ipv4Addr::UNSPECIFIED.into()]),
+            Self::AllIpv6 => Some(vec![Ipv6Addr::UNSPECIFIED.into()]),
+            Self::AllIpv4AndIpv6 => Some(vec![
+                Ipv4Addr::UNSPECIFIED.into(),
+                Ipv6Addr::UNSPECIFIED.into(),
+            ]),
+            Self::Interfaces | Self::Custom => None,
+        }
+    }
+
+    /// Returns the binding of the given listen addresses.
+    fn of(listen_addrs: &str, port: u16) -> Self {
+        if let Some(binding) = Self::ALL.into_iter().find(|binding| {
+            binding
+                .unspecified_ips()
+                .is_some_and(|ips| listen_addrs_on(&ips, port) == listen_addrs)
+        }) {
+            return binding;
+        }
+
+        let ips = interface_ips()
+            .into_iter()
+            .map(|interface| interface.ip)
+            .collect::<Vec<_>>();
+        let is_on_interfaces = parse_listen_addrs(listen_addrs).is_ok_and(|_| {
+            listen_addrs.split(',').all(|addr| {
+                ips.iter()
+                    .any(|ip| listen_addrs_on(&[*ip], port) == addr.trim())
+            })
+        });
+        if is_on_interfaces {
+            Self::Interfaces
+        } else {
+            Self::Custom
+        }
+    }
+}
+
+impl std::fmt::Display for ListenBinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::AllIpv4 => write!(f, "{}", tr!("All IPv4 interfaces (0.0.0.0)")),
+            Self::AllIpv6 => write!(f, "{}", tr!("All IPv6 interfaces (::)")),
+            Self::AllIpv4AndIpv6 => write!(f, "{}", tr!("All IPv4 & IPv6 interfaces")),
+            Self::Interfaces => write!(f, "{}", tr!("Specific interfaces")),
+            Self::Custom => write!(f, "{}", tr!("Custom addresses")),
+        }
+    }
+}
+
+/// An IP address of a network interface.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct InterfaceIp {
+    name: String,
+    ip: IpAddr,
+}
+
+impl std::fmt::Display for InterfaceIp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.ip, self.name)
+    }
+}
+
+/// Returns the IP addresses of the network interfaces of this machine.
+fn interface_ips() -> Vec<InterfaceIp> {
+    match if_addrs::get_if_addrs() {
+        Ok(interfaces) => interfaces
+            .into_iter()
+            .map(|interface| InterfaceIp {
+                ip: interface.ip(),
+                name: interface.name,
+            })
+            .collect(),
+        Err(err) => {
+            log::warn!("Could not list network interfaces: {err}");
+            Vec::new()
+        }
+    }
+}
+
+/// Returns comma-separated listen addresses on the given IP addresses & TCP port.
+fn listen_addrs_on(ips: &[IpAddr], port: u16) -> String {
+    ips.iter()
+        .map(|ip| match ip {
+            IpAddr::V4(ip) => format!("/ip4/{ip}/tcp/{port}"),
+            IpAddr::V6(ip) => format!("/ip6/{ip}/tcp/{port}"),
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Prompts the user to edit the P2P listen addresses of the compute node: the interfaces to
+/// listen on (IPv4, IPv6 or specific IP addresses) and the port, or custom multiaddrs as is.
 pub fn edit_port(dria_env: &mut DriaEnv) -> eyre::Result<()> {
-    // get existing address
-    let addr = &dria_env
-        .get(DriaEnv::DKN_P2P_LISTEN_ADDR_KEY)
-        .unwrap_or(DEFAULT_LISTEN_ADDR);
-
-    // ensure the address starts with `/ip4/0.0.0.0/tcp/` and ends with a number
-    let mut parts = addr.split('/').collect::<Vec<_>>();
-    if parts[1] != "ip4" || parts[2] != "0.0.0.0" || parts[3] != "tcp" {
-        eyre::bail!("The listen address must start with /ip4/0.0.0.0/tcp");
-    }
-    let port = parts[4].parse::<u16>().unwrap();
-
-    // validate the port
-    let validator = |port_str: &str| match port_str.parse::<u16>() {
-        Ok(_) => Ok(Validation::Valid),
-        Err(_) => Ok(Validation::Invalid(
-            "Port must be a valid 16-bit unsigned integer.".into(),
-        )),
+    // get existing addresses, an invalid value is replaced entirely
+    let existing_addrs = dria_env
+        .get_or_default(DriaEnv::DKN_P2P_LISTEN_ADDR_KEY)
+        .unwrap_or_default()
+        .to_string();
+    if let Err(err) = parse_listen_addrs(&existing_addrs) {
+        log::warn!("Existing listen address is invalid: {err}");
+    }
+    let existing_port = parse_listen_port(&existing_addrs).unwrap_or(DEFAULT_P2P_PORT);
+    let existing_binding = ListenBinding::of(&existing_addrs, existing_port);
+
+    // choose the interfaces
+    let Some(binding) = Select::new(
+        tr!("Choose where the compute node listens:"),
+        ListenBinding::ALL.to_vec(),
+    )
+    .with_help_message(tr!("↑↓ to move, ENTER to select, ESC to go back"))
+    .with_starting_cursor(
+        ListenBinding::ALL
+            .iter()
+            .position(|binding| *binding == existing_binding)
+            .unwrap_or(0),
+    )
+    .prompt_skippable()?
+    else {
+        return Ok(());
     };
 
-    let existing_port_str = port.to_string();
-    let new_port = Text::new("Enter compute node port:")
-        .with_validator(validator)
-        .with_default(&existing_port_str)
-        .with_help_message("Enter 0 to use a random port everytime")
-        .prompt()?;
+    let new_addrs = if binding == ListenBinding::Custom {
+        let validator = |addrs: &str| match parse_listen_addrs(addrs) {
+            Ok(_) => Ok(Validation::Valid),
+            Err(err) => Ok(Validation::Invalid(err.into())),
+        };
+
+        Text::new(tr!("Enter listen addresses:"))
+            .with_validator(validator)
+            .with_default(&existing_addrs)
+            .with_help_message(tr!(
+                "Comma-separated multiaddrs, e.g. /ip4/192.168.1.10/tcp/4001,/ip6/::/tcp/4001"
+            ))
+            .prompt()?
+            .split(',')
+            .map(str::trim)
+            .filter(|addr| !addr.is_empty())
+            .collect::<Vec<_>>()
+            .join(",")
+    } else {
+        let ips = match binding.unspecified_ips() {
+            Some(ips) => ips,
+            None => {
+                let interfaces = interface_ips();
+                if interfaces.is_empty() {
+                    eyre::bail!("No network interfaces found, choose custom addresses instead.");
+                }
+                let selected = interfaces
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, interface)| {
+                        existing_addrs.split(',').any(|addr| {
+                            addr.trim() == listen_addrs_on(&[interface.ip], existing_port)
+                        })
+                    })
+                    .map(|(idx, _)| idx)
+                    .collect::<Vec<_>>();
+
+                let validator = |selected: &[inquire::list_option::ListOption<&InterfaceIp>]| {
+                    if selected.is_empty() {
+                        Ok(Validation::Invalid(
+                            tr!("Choose at least one address.").into(),
+                        ))
+                    } else {
+                        Ok(Validation::Valid)
+                    }
+                };
+                MultiSelect::new(tr!("Choose the addresses to listen on:"), interfaces)
+                    .with_default(&selected)
+                    .with_validator(validator)
+                    .with_help_message(tr!("↑↓ to move, SPACE to select, ENTER to confirm"))
+                    .prompt()?
+                    .into_iter()
+                    .map(|interface| interface.ip)
+                    .collect()
+            }
+        };
+
+        // validate the port
+        let validator = |port_str: &str| match port_str.parse::<u16>() {
+            Ok(_) => Ok(Validation::Valid),
+            Err(_) => Ok(Validation::Invalid(
+                tr!("Port must be a valid 16-bit unsigned integer.").into(),
+            )),
+        };
+        let port = Text::new(tr!("Enter compute node port:"))
+            .with_validator(validator)
+            .with_default(&existing_port.to_string())
+            .with_help_message(tr!("Enter 0 to use a random port everytime"))
+            .prompt()?
+            .parse::<u16>()?;
+        let port = offer_free_port(port)?;
 
-    if new_port != existing_port_str {
-        // update the port in the address
-        parts[4] = &new_port;
-        let new_listen_addr = parts.join("/");
-        log::info!("New listen address: {:?}", new_listen_addr);
-        dria_env.set(DriaEnv::DKN_P2P_LISTEN_ADDR_KEY, new_listen_addr);
+        listen_addrs_on(&ips, port)
+    };
+
+    if new_addrs != existing_addrs {
+        log::info!("New listen address: {:?}", new_addrs);
+        dria_env.set(DriaEnv::DKN_P2P_LISTEN_ADDR_KEY, new_addrs);
+    }
+
+    // toggle port mapping on the router
+    let auto_port_forward = Confirm::new(tr!("Auto port-forward (UPnP/NAT-PMP)?"))
+        .with_default(dria_env.is_auto_port_forward_enabled())
+        .with_help_message(tr!("Map the port on your router when the node starts, so that it can receive inbound connections"))
+        .prompt()?;
+    if auto_port_forward != dria_env.is_auto_port_forward_enabled() {
+        dria_env.set(
+            DriaEnv::DKN_AUTO_PORT_FORWARD_KEY,
+            auto_port_forward.to_string(),
+        );
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_listen_binding() {
+        let both = listen_addrs_on(
+            &[Ipv4Addr::UNSPECIFIED.into(), Ipv6Addr::UNSPECIFIED.into()],
+            4001,
+        );
+        assert_eq!(both, "/ip4/0.0.0.0/tcp/4001,/ip6/::/tcp/4001");
+        assert_eq!(
+            ListenBinding::of(&both, 4001),
+            ListenBinding::AllIpv4AndIpv6
+        );
+        assert_eq!(
+            ListenBinding::of("/ip4/0.0.0.0/tcp/4001", 4001),
+            ListenBinding::AllIpv4
+        );
+        assert_eq!(
+            ListenBinding::of("/ip4/0.0.0.0/tcp/4001/ws", 4001),
+            ListenBinding::Custom
+        );
+    }
+}
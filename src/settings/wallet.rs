@@ -1,7 +1,250 @@
-use inquire::{validator::Validation, Password};
+use colored::Colorize;
+use inquire::{validator::Validation, Confirm, Password, Select, Text};
+use std::path::PathBuf;
 
+use crate::tr;
+use crate::utils::crypto::{
+    mnemonic_to_secret_key, parse_derivation_path, secret_key_to_account, DEFAULT_DERIVATION_PATH,
+};
+use crate::utils::{get_points, AddressBook, SavedWallet, Selectable};
 use crate::DriaEnv;
 
+/// Wallet settings.
+enum WalletMenu {
+    /// Enter a secret key for this profile.
+    Enter,
+    /// Derive the secret key of this profile from a mnemonic phrase.
+    Mnemonic,
+    /// Switch to a wallet within the address book.
+    Switch,
+    /// Save the wallet of this profile to the address book.
+    SaveCurrent,
+    /// Save a wallet from an encrypted keystore file to the address book.
+    ImportKeystore,
+    /// Remove a wallet from the address book.
+    Remove,
+}
+
+impl std::fmt::Display for WalletMenu {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Enter => write!(f, "{}", tr!("Enter a secret key")),
+            Self::Mnemonic => write!(f, "{}", tr!("Import from a mnemonic phrase")),
+            Self::Switch => write!(f, "{}", tr!("Switch to a saved wallet")),
+            Self::SaveCurrent => write!(f, "{}", tr!("Save the current wallet")),
+            Self::ImportKeystore => write!(f, "{}", tr!("Save a wallet from a keystore file")),
+            Self::Remove => write!(f, "{}", tr!("Remove a saved wallet")),
+        }
+    }
+}
+
+/// Shows the wallet settings, where the wallet of this profile can be changed directly or by
+/// switching to one of the wallets saved within the [`AddressBook`].
+pub async fn show_wallet_menu(dria_env: &mut DriaEnv) -> eyre::Result<()> {
+    loop {
+        let current = dria_env.get_account().ok().map(|(_, _, address)| address);
+        let mut book = AddressBook::load()?;
+        let current_label = current
+            .as_deref()
+            .and_then(|address| book.find_by_address(address))
+            .map(|wallet| wallet.label.clone());
+        match (&current, &current_label) {
+            (Some(address), Some(label)) => eprintln!("Current wallet: {label} (0x{address})"),
+            (Some(address), None) => eprintln!("Current wallet: 0x{address} (not saved)"),
+            (None, _) => eprintln!("Current wallet: none"),
+        }
+
+        let Selectable::Some(choice) = Select::new(
+            tr!("Choose wallet setting:"),
+            Selectable::new(vec![
+                WalletMenu::Enter,
+                WalletMenu::Mnemonic,
+                WalletMenu::Switch,
+                WalletMenu::SaveCurrent,
+                WalletMenu::ImportKeystore,
+                WalletMenu::Remove,
+            ]),
+        )
+        .with_help_message(tr!("↑↓ to move, ENTER to select"))
+        .prompt()?
+        else {
+            return Ok(());
+        };
+
+        match choice {
+            WalletMenu::Enter => edit_wallet(dria_env, true)?,
+            WalletMenu::Mnemonic => {
+                import_mnemonic(dria_env)?;
+            }
+            WalletMenu::Switch => {
+                if book.wallets.is_empty() {
+                    eprintln!("No wallets are saved yet.");
+                    continue;
+                }
+                let Selectable::Some(wallet) = Select::new(
+                    tr!("Choose the wallet to use:"),
+                    Selectable::new(book.wallets),
+                )
+                .prompt()?
+                else {
+                    continue;
+                };
+                if current.as_deref() == Some(wallet.address.as_str()) {
+                    eprintln!("This profile already uses {wallet}.");
+                    continue;
+                }
+                if let Some(address) = &current {
+                    if !confirm_switch(address, &wallet).await? {
+                        continue;
+                    }
+                }
+
+                let password = if wallet.is_keystore() {
+                    Some(
+                        Password::new(tr!("Enter keystore password:"))
+                            .without_confirmation()
+                            .prompt()?,
+                    )
+                } else {
+                    None
+                };
+                dria_env.set(
+                    DriaEnv::DKN_WALLET_KEY,
+                    wallet.secret_key(password.as_deref())?,
+                );
+                eprintln!("Switched to {wallet}.");
+            }
+            WalletMenu::SaveCurrent => {
+                let Some(secret_key) = dria_env.get(DriaEnv::DKN_WALLET_KEY) else {
+                    eprintln!("No wallet is configured for this profile.");
+                    continue;
+                };
+                if let Some(label) = current_label {
+                    eprintln!("The current wallet is already saved as {label}.");
+                    continue;
+                }
+                let label = Text::new(tr!("Enter a label for the wallet:")).prompt()?;
+                book.add(SavedWallet::from_secret_key(&label, secret_key)?)?;
+                book.save()?;
+            }
+            WalletMenu::ImportKeystore => {
+                let path = Text::new(tr!("Enter the path of the keystore file:"))
+                    .with_validator(|path: &str| match PathBuf::from(path).is_file() {
+                        true => Ok(Validation::Valid),
+                        false => Ok(Validation::Invalid(tr!("File does not exist.").into())),
+                    })
+                    .prompt()?;
+                let password = Password::new(tr!("Enter keystore password:"))
+                    .without_confirmation()
+                    .prompt()?;
+                let label = Text::new(tr!("Enter a label for the wallet:")).prompt()?;
+                let wallet = SavedWallet::from_keystore(&label, &PathBuf::from(path), &password)?;
+                eprintln!(
+                    "{}",
+                    tr!(
+                        "Saved {wallet}, its password is asked whenever you switch to it.",
+                        wallet = wallet
+                    )
+                );
+                book.add(wallet)?;
+                book.save()?;
+            }
+            WalletMenu::Remove => {
+                let Selectable::Some(wallet) = Select::new(
+                    tr!("Choose the wallet to remove:"),
+                    Selectable::new(book.wallets.clone()),
+                )
+                .prompt()?
+                else {
+                    continue;
+                };
+                let message = tr!("Remove {wallet} from the address book?", wallet = wallet);
+                if Confirm::new(&message)
+                    .with_help_message(tr!("Profiles that use it keep their secret key"))
+                    .with_default(false)
+                    .prompt()?
+                {
+                    book.remove(&wallet.label);
+                    book.save()?;
+                }
+            }
+        }
+    }
+}
+
+/// Asks for confirmation before switching away from the wallet at the given address, showing
+/// the points it has accumulated; returns `true` if the switch is confirmed.
+async fn confirm_switch(address: &str, wallet: &SavedWallet) -> eyre::Result<bool> {
+    match get_points(address).await {
+        Ok(points) => eprintln!(
+            "The current wallet 0x{address} has accumulated {} {}, which stay with it but it stops earning once you switch.",
+            points.score,
+            "$DRIA points".purple()
+        ),
+        Err(err) => log::warn!("Could not get the points of 0x{address}: {err}"),
+    }
+
+    Ok(
+        Confirm::new(&tr!("Switch this profile to {wallet}?", wallet = wallet))
+            .with_help_message(tr!("The node must be restarted to use the new wallet"))
+            .with_default(false)
+            .prompt()?,
+    )
+}
+
+/// Derives a secret key from a BIP-39 mnemonic phrase of 12 or 24 words, with an optional
+/// passphrase & derivation path, and uses it for this profile once its address is confirmed.
+///
+/// Only the derived secret key is stored, the mnemonic phrase is never written anywhere.
+///
+/// ### Returns
+/// `true` if the derived wallet is used, `false` if the user did not confirm it.
+pub fn import_mnemonic(dria_env: &mut DriaEnv) -> eyre::Result<bool> {
+    let phrase = Password::new(tr!("Enter your mnemonic phrase:"))
+        .with_validator(|phrase: &str| {
+            match bip39::Mnemonic::parse(phrase.split_whitespace().collect::<Vec<_>>().join(" ")) {
+                Ok(_) => Ok(Validation::Valid),
+                Err(err) => Ok(Validation::Invalid(
+                    tr!("Invalid mnemonic phrase: {err}", err = err).into(),
+                )),
+            }
+        })
+        .without_confirmation()
+        .with_display_mode(inquire::PasswordDisplayMode::Masked)
+        .with_help_message(tr!("The 12 or 24 words separated by spaces"))
+        .prompt()?;
+    let passphrase = Password::new(tr!("Enter the passphrase of the mnemonic:"))
+        .without_confirmation()
+        .with_display_mode(inquire::PasswordDisplayMode::Masked)
+        .with_help_message(tr!(
+            "ENTER without typing if your wallet does not use a passphrase"
+        ))
+        .prompt()?;
+    let path = Text::new(tr!("Enter the derivation path:"))
+        .with_default(DEFAULT_DERIVATION_PATH)
+        .with_validator(|path: &str| match parse_derivation_path(path) {
+            Ok(_) => Ok(Validation::Valid),
+            Err(err) => Ok(Validation::Invalid(err.to_string().into())),
+        })
+        .with_help_message(tr!(
+            "The default is the first account of MetaMask & most wallets"
+        ))
+        .prompt()?;
+
+    let secret_key = mnemonic_to_secret_key(&phrase, &passphrase, &path)?;
+    let (_, _, address) = secret_key_to_account(&secret_key)?;
+    if !Confirm::new(&tr!("Use the wallet 0x{address}?", address = address))
+        .with_help_message(tr!("Check that this is the address shown by your wallet"))
+        .with_default(true)
+        .prompt()?
+    {
+        return Ok(false);
+    }
+
+    dria_env.set(DriaEnv::DKN_WALLET_KEY, secret_key);
+    Ok(true)
+}
+
 pub fn edit_wallet(dria_env: &mut DriaEnv, skippable: bool) -> eyre::Result<()> {
     let existing_secret_opt = dria_env.get(DriaEnv::DKN_WALLET_KEY);
 
@@ -28,23 +271,24 @@ pub fn edit_wallet(dria_env: &mut DriaEnv, skippable: bool) -> eyre::Result<()>
     // validates the secret key to be 64 characters hexadecimal, with or without 0x prefix
     // empty string is ok, as it means the user wants to skip
     let existing_secret_is_some = existing_secret_opt.is_some();
-    let validator = move |secret_key: &str| {
-        if secret_key.trim_start_matches("0x").len() != 64 {
-            if secret_key.is_empty() && (skippable || existing_secret_is_some) {
-                // empty string is ok if skippable, or there is an existing value
-                Ok(Validation::Valid)
-            } else {
-                Ok(Validation::Invalid(
-                    "Key must be exactly 64 characters hexadecimal, with or without 0x prefix."
+    let validator =
+        move |secret_key: &str| {
+            if secret_key.trim_start_matches("0x").len() != 64 {
+                if secret_key.is_empty() && (skippable || existing_secret_is_some) {
+                    // empty string is ok if skippable, or there is an existing value
+                    Ok(Validation::Valid)
+                } else {
+                    Ok(Validation::Invalid(
+                    tr!("Key must be exactly 64 characters hexadecimal, with or without 0x prefix.")
                         .into(),
                 ))
+                }
+            } else {
+                Ok(Validation::Valid)
             }
-        } else {
-            Ok(Validation::Valid)
-        }
-    };
+        };
 
-    let new_secret = Password::new("Enter wallet secret key:")
+    let new_secret = Password::new(tr!("Enter wallet secret key:"))
         .with_validator(validator)
         .with_formatter(&|s| {
             if s.is_empty() {
@@ -59,8 +303,11 @@ pub fn edit_wallet(dria_env: &mut DriaEnv, skippable: bool) -> eyre::Result<()>
         .without_confirmation()
         .with_display_mode(inquire::PasswordDisplayMode::Masked)
         .with_help_message(&match existing_secret_opt {
-            Some(secret) => format!("ENTER without typing to keep using {}", mask(secret)),
-            None => "You can get it from a wallet like MetaMask".to_string(),
+            Some(secret) => tr!(
+                "ENTER without typing to keep using {secret}",
+                secret = mask(secret)
+            ),
+            None => tr!("You can get it from a wallet like MetaMask").to_string(),
             // Error: Operation was interrupted by the user
         })
         .prompt()?;
@@ -0,0 +1,157 @@
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::shared_data_dir;
+
+/// How long a cached response of the points & referrals APIs is used before it is fetched again.
+const API_CACHE_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// Whether cached responses are used, cleared once by the `--no-cache` flag.
+static API_CACHE_ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Ignores the cached responses for the rest of the process, so that fresh data is fetched; the
+/// fresh responses are still cached for the later commands.
+#[inline]
+pub fn disable_api_cache() {
+    API_CACHE_ENABLED.store(false, Ordering::Relaxed);
+}
+
+/// A cached response along with when it was fetched, in seconds since the Unix epoch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedEntry {
+    fetched_at: u64,
+    value: serde_json::Value,
+}
+
+/// Responses of the points & referrals APIs that are cached on disk with a TTL, so that repeated
+/// `dashboard` or `identity` calls do not hammer the APIs. The responses are keyed by their URLs,
+/// which include the address & the network.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ApiCache {
+    entries: HashMap<String, CachedEntry>,
+}
+
+impl ApiCache {
+    /// Returns the path of the cache, `~/.dria/dkn-compute-launcher/cache/api.json`, which is
+    /// shared by all profiles.
+    /// `None` without a home directory, in which case nothing is cached.
+    fn path() -> Option<PathBuf> {
+        shared_data_dir().map(|dir| dir.join("cache").join("api.json"))
+    }
+
+    /// Loads the cache, which is empty if it does not exist or could not be read.
+    fn load() -> Self {
+        Self::path()
+            .and_then(|path| std::fs::read(path).ok())
+            .and_then(|content| serde_json::from_slice(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Saves the cache, which is best-effort.
+    fn save(&self) {
+        let Some(path) = Self::path() else {
+            return;
+        };
+        if let Err(err) = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::write(&path, serde_json::to_vec(self)?))
+        {
+            log::debug!("Could not save the API cache: {err}");
+        }
+    }
+
+    /// Returns the cached value of the key if it is fetched within the TTL.
+    fn get(&self, key: &str, now: u64) -> Option<&serde_json::Value> {
+        self.entries
+            .get(key)
+            .filter(|entry| now.saturating_sub(entry.fetched_at) < API_CACHE_TTL.as_secs())
+            .map(|entry| &entry.value)
+    }
+
+    /// Caches the value of the key, removing the expired entries along the way.
+    fn insert(&mut self, key: &str, value: serde_json::Value, now: u64) {
+        self.entries
+            .retain(|_, entry| now.saturating_sub(entry.fetched_at) < API_CACHE_TTL.as_secs());
+        self.entries.insert(
+            key.to_string(),
+            CachedEntry {
+                fetched_at: now,
+                value,
+            },
+        );
+    }
+}
+
+/// Returns the current time in seconds since the Unix epoch.
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Returns the cached value of the key if it is fresh, otherwise awaits `fetch` and caches its
+/// result if it succeeds; errors are never cached.
+///
+/// ### Arguments
+/// - `key`: the key of the value, e.g. the URL of the request
+/// - `fetch`: the request of the value, which is awaited only if there is no fresh value
+pub async fn cached<T, E>(key: &str, fetch: impl Future<Output = Result<T, E>>) -> Result<T, E>
+where
+    T: Serialize + DeserializeOwned,
+{
+    if API_CACHE_ENABLED.load(Ordering::Relaxed) {
+        if let Some(value) = ApiCache::load()
+            .get(key, now_secs())
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+        {
+            log::debug!("Using the cached response of {key}");
+            return Ok(value);
+        }
+    }
+
+    let value = fetch.await?;
+    if let Ok(json) = serde_json::to_value(&value) {
+        let mut cache = ApiCache::load();
+        cache.insert(key, json, now_secs());
+        cache.save();
+    }
+
+    Ok(value)
+}
+
+/// Removes the cached values whose keys start with the given prefix, e.g. after a change that
+/// makes them stale.
+pub fn invalidate_cached(prefix: &str) {
+    let mut cache = ApiCache::load();
+    let len = cache.entries.len();
+    cache.entries.retain(|key, _| !key.starts_with(prefix));
+    if cache.entries.len() != len {
+        cache.save();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_api_cache() {
+        let ttl = API_CACHE_TTL.as_secs();
+        let mut cache = ApiCache::default();
+        cache.insert("points/0x1", serde_json::json!({ "score": 1.0 }), 1000);
+        assert!(cache.get("points/0x1", 1000 + ttl - 1).is_some());
+        assert!(cache.get("points/0x1", 1000 + ttl).is_none());
+        assert!(cache.get("points/0x2", 1000).is_none());
+
+        // expired entries are removed on insertion
+        cache.insert("points/0x2", serde_json::json!(null), 1000 + ttl);
+        assert_eq!(cache.entries.len(), 1);
+        assert!(cache.get("points/0x2", 1000 + ttl).is_some());
+    }
+}
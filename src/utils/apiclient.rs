@@ -0,0 +1,104 @@
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use std::time::Duration;
+
+use super::{TracedSend, LAUNCHER_USER_AGENT};
+
+/// Delay before the first retry of a failed request, doubled for each retry after.
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+/// Maximum delay between retries of a failed request.
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(8);
+
+/// Timeouts & retries of the clients of the Dria APIs (points & referrals), so that a stuck API
+/// does not hang a command forever.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ApiClientPolicy {
+    /// How long to wait for a connection to the API.
+    pub connect_timeout: Duration,
+    /// How long to wait for a whole request, including the connection.
+    pub timeout: Duration,
+    /// How many times an idempotent `GET` request is retried, see [`send_with_retries`].
+    pub retries: u32,
+}
+
+impl ApiClientPolicy {
+    /// Creates a client with the timeouts of this policy.
+    pub fn client(&self) -> reqwest::Result<Client> {
+        Client::builder()
+            .user_agent(LAUNCHER_USER_AGENT)
+            .connect_timeout(self.connect_timeout)
+            .timeout(self.timeout)
+            .build()
+    }
+}
+
+/// Sends the request, retrying it with an exponential backoff when it times out, can not connect,
+/// or the API responds with a server error or `429 Too Many Requests`.
+///
+/// Only use this for idempotent requests such as `GET`s, as a retried request may have been
+/// processed by the API already; requests that can not be cloned (e.g. with a streamed body) are
+/// sent once.
+///
+/// ### Arguments
+/// - `request`: the request to send
+/// - `retries`: how many times the request is retried at most
+///
+/// ### Returns
+/// The response of the last attempt, which may still be an error status.
+pub async fn send_with_retries(request: RequestBuilder, retries: u32) -> reqwest::Result<Response> {
+    let mut attempt = 0;
+    loop {
+        let Some(next) = request.try_clone() else {
+            return request.send_traced().await;
+        };
+
+        let result = next.send_traced().await;
+        let reason = match &result {
+            Ok(response) if is_retryable_status(response.status()) => response.status().to_string(),
+            Err(err) if err.is_timeout() => "timed out".to_string(),
+            Err(err) if err.is_connect() => "could not connect".to_string(),
+            _ => return result,
+        };
+        if attempt >= retries {
+            return result;
+        }
+
+        let delay = retry_delay(attempt);
+        attempt += 1;
+        log::warn!(
+            "Request failed ({reason}), retrying in {:.1}s ({attempt}/{retries})",
+            delay.as_secs_f64()
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Returns `true` if a request with the given response status may succeed when retried.
+fn is_retryable_status(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+/// Returns the delay before the retry after the given (zero-indexed) failed attempt.
+fn retry_delay(attempt: u32) -> Duration {
+    RETRY_BASE_DELAY
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(RETRY_MAX_DELAY)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_policy() {
+        assert_eq!(retry_delay(0), Duration::from_millis(500));
+        assert_eq!(retry_delay(1), Duration::from_secs(1));
+        assert_eq!(retry_delay(3), Duration::from_secs(4));
+        assert_eq!(retry_delay(10), RETRY_MAX_DELAY);
+        assert_eq!(retry_delay(u32::MAX), RETRY_MAX_DELAY);
+
+        assert!(is_retryable_status(StatusCode::BAD_GATEWAY));
+        assert!(is_retryable_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(!is_retryable_status(StatusCode::NOT_FOUND));
+        assert!(!is_retryable_status(StatusCode::OK));
+    }
+}
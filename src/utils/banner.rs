@@ -0,0 +1,179 @@
+use colored::Colorize;
+use dkn_executor::Model;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+
+use super::{get_network_env, DriaEnv};
+
+/// Returns the summary of the effective configuration that is printed before the compute node
+/// is spawned, so that misconfigurations are caught at a glance; secrets are never shown.
+///
+/// ### Arguments
+/// - `dria_env`: the environment that the node is started with
+/// - `env_path`: path to the environment file, which gives the profile
+/// - `models`: the models that the node serves, i.e. without the skipped ones
+/// - `check_updates`: whether updates are checked while the node runs
+pub fn format_startup_banner<'a>(
+    dria_env: &DriaEnv,
+    env_path: &Path,
+    models: impl IntoIterator<Item = &'a Model>,
+    check_updates: bool,
+) -> String {
+    let mut rows: Vec<(&str, String)> = Vec::new();
+
+    rows.push((
+        "Profile",
+        format!("{} ({})", profile_name(env_path).bold(), env_path.display()),
+    ));
+    if let Some(name) = dria_env.get_node_name() {
+        rows.push(("Name", name.to_string()));
+    }
+    rows.push(("Network", get_network_env()));
+    rows.push((
+        "Address",
+        match dria_env.get_account() {
+            Ok((_, _, address)) => address.to_string(),
+            Err(_) => "no wallet configured!".red().to_string(),
+        },
+    ));
+
+    // models grouped by their providers
+    let mut providers = BTreeMap::<String, Vec<String>>::new();
+    for model in models {
+        providers
+            .entry(model.provider().to_string())
+            .or_default()
+            .push(model.to_string());
+    }
+    for (provider, mut names) in providers {
+        names.sort();
+        rows.push(("Models", format!("{provider}: {}", names.join(", "))));
+    }
+
+    rows.push(("Ollama", ollama_summary(dria_env)));
+    rows.push((
+        "Listen address",
+        dria_env
+            .get_or_default(DriaEnv::DKN_P2P_LISTEN_ADDR_KEY)
+            .unwrap_or_default()
+            .to_string(),
+    ));
+    rows.push((
+        "Updates",
+        if check_updates {
+            let (compute_interval, launcher_interval) = dria_env.get_update_intervals();
+            format!(
+                "compute node every {}m, launcher every {}m",
+                compute_interval.as_secs() / 60,
+                launcher_interval.as_secs() / 60
+            )
+        } else {
+            "disabled".to_string()
+        },
+    ));
+    rows.push((
+        "Log levels",
+        dria_env
+            .get(DriaEnv::LOG_LEVEL_KEY)
+            .filter(|levels| !levels.trim().is_empty())
+            .unwrap_or("default")
+            .to_string(),
+    ));
+
+    // only the names of the API keys are shown
+    let api_keys = DriaEnv::API_KEY_NAMES
+        .into_iter()
+        .filter(|key| {
+            dria_env
+                .get(key)
+                .is_some_and(|value| !value.trim().is_empty())
+        })
+        .collect::<Vec<_>>();
+    if !api_keys.is_empty() {
+        rows.push(("API keys", api_keys.join(", ")));
+    }
+
+    let width = rows.iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+    let mut banner = format!("{}\n", "Starting compute node with:".bold());
+    for (label, value) in rows {
+        let _ = writeln!(banner, "  {:<width$}  {value}", label.dimmed());
+    }
+
+    banner
+}
+
+/// Returns the name of the profile of the given environment file, e.g. `work` for `.env.work`.
+pub fn profile_name(env_path: &Path) -> String {
+    env_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .and_then(|name| name.strip_prefix(".env.").map(String::from))
+        .unwrap_or_else(|| "default".to_string())
+}
+
+/// Returns `true` if the given profile name is valid, i.e. it is not empty and contains only
+/// alphanumeric characters, '-', or '_'.
+pub fn is_valid_profile_name(profile: &str) -> bool {
+    !profile.is_empty()
+        && profile
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Returns the endpoint of the Ollama server, without any credentials.
+fn ollama_summary(dria_env: &DriaEnv) -> String {
+    let (host, port) = dria_env.get_ollama_config();
+    let auth = if dria_env
+        .get(DriaEnv::OLLAMA_AUTH_TOKEN_KEY)
+        .is_some_and(|v| !v.trim().is_empty())
+    {
+        " (bearer token)"
+    } else if dria_env
+        .get(DriaEnv::OLLAMA_BASIC_AUTH_KEY)
+        .is_some_and(|v| !v.trim().is_empty())
+    {
+        " (basic auth)"
+    } else {
+        ""
+    };
+
+    format!("{host}:{port}{auth}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dkn_executor::ModelProvider;
+
+    #[test]
+    fn test_startup_banner() {
+        assert_eq!(
+            profile_name(Path::new("/home/user/.dria/.env.work")),
+            "work"
+        );
+        assert_eq!(profile_name(Path::new(".env")), "default");
+
+        let mut dria_env = DriaEnv::new_from_env();
+        dria_env.set(DriaEnv::OLLAMA_AUTH_TOKEN_KEY, "secret-token");
+        dria_env.set(DriaEnv::OPENAI_APIKEY_KEY, "sk-secret");
+        let models = [
+            Model::all_with_provider(&ModelProvider::Ollama)
+                .next()
+                .unwrap(),
+            Model::all_with_provider(&ModelProvider::OpenAI)
+                .next()
+                .unwrap(),
+        ];
+
+        let banner = format_startup_banner(&dria_env, Path::new(".env.work"), &models, false);
+        assert!(banner.contains("work"));
+        assert!(banner.contains("(bearer token)"));
+        assert!(banner.contains(DriaEnv::OPENAI_APIKEY_KEY));
+        assert!(banner.contains("disabled"));
+        assert!(!banner.contains("secret"));
+        for model in &models {
+            assert!(banner.contains(&model.to_string()));
+        }
+    }
+}
@@ -0,0 +1,204 @@
+use dkn_executor::ollama_rs::generation::completion::GenerationResponse;
+use eyre::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{is_remote_ollama, shared_data_dir, DriaEnv, HardwareInfo, Utilization};
+
+/// The TPS measurement of a model, see the `measure` option within the models settings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchmarkResult {
+    pub model: String,
+    /// Version of the model within Ollama, i.e. when it was last pulled; a model that is pulled
+    /// again (e.g. updated) must be measured again.
+    pub model_version: String,
+    /// The hardware that the model is measured on, see [`benchmark_hardware`].
+    pub hardware: String,
+    /// Time of the measurement, in seconds since the Unix epoch.
+    pub measured_at: u64,
+    pub prompt_tps: f64,
+    pub prompt_dur_ms: u64,
+    pub eval_tps: f64,
+    pub eval_dur_ms: u64,
+    pub total_dur_ms: u64,
+    /// Utilization of the hardware during the measurement, `None` if it is not sampled (e.g.
+    /// Ollama runs on another machine) or the measurement is older than utilization sampling.
+    #[serde(default)]
+    pub utilization: Option<Utilization>,
+}
+
+impl BenchmarkResult {
+    /// Creates the measurement of the model from its generation, measured now.
+    pub fn new(
+        res: &GenerationResponse,
+        model: String,
+        model_version: String,
+        hardware: String,
+    ) -> Self {
+        let prompt_tps = (res.prompt_eval_count.unwrap_or_default() as f64)
+            / (res.prompt_eval_duration.unwrap_or(1) as f64)
+            * 1e9;
+        let eval_tps = (res.eval_count.unwrap_or_default() as f64)
+            / (res.eval_duration.unwrap_or(1) as f64)
+            * 1e9;
+
+        Self {
+            model,
+            model_version,
+            hardware,
+            measured_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|time| time.as_secs())
+                .unwrap_or_default(),
+            prompt_tps,
+            prompt_dur_ms: res.prompt_eval_duration.unwrap_or_default() / 1e6 as u64,
+            eval_tps,
+            eval_dur_ms: res.eval_duration.unwrap_or_default() / 1e6 as u64,
+            total_dur_ms: res.total_duration.unwrap_or_default() / 1e6 as u64,
+            utilization: None,
+        }
+    }
+
+    /// Returns how long ago the model was measured, e.g. `12 days ago`.
+    pub fn age(&self) -> String {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_secs())
+            .unwrap_or_default();
+        format_age(now.saturating_sub(self.measured_at))
+    }
+}
+
+/// Measurements that are kept across runs, so that a model is only measured again when it or
+/// the hardware changes.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BenchmarkCache {
+    results: Vec<BenchmarkResult>,
+}
+
+impl BenchmarkCache {
+    /// Returns the path of the cache, `~/.dria/dkn-compute-launcher/benchmarks.json`; `None`
+    /// without a home directory.
+    fn path() -> Option<PathBuf> {
+        shared_data_dir().map(|dir| dir.join("benchmarks.json"))
+    }
+
+    /// Loads the cache, which is empty if it does not exist or could not be read.
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+        match std::fs::read(&path) {
+            Ok(content) => serde_json::from_slice(&content).unwrap_or_else(|err| {
+                log::warn!("Ignoring invalid benchmarks at {}: {err}", path.display());
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Saves the cache.
+    ///
+    /// ### Errors
+    /// - If the home directory could not be found
+    /// - If the cache could not be written
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path()
+            .ok_or_else(|| eyre::eyre!("could not find the home directory to keep benchmarks"))?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&path, serde_json::to_vec_pretty(self)?)
+            .wrap_err_with(|| format!("could not write {}", path.display()))
+    }
+
+    /// Returns the measurement of the model, if it is measured with the same version of the
+    /// model on the same hardware.
+    pub fn get(
+        &self,
+        model: &str,
+        model_version: &str,
+        hardware: &str,
+    ) -> Option<&BenchmarkResult> {
+        self.results.iter().find(|result| {
+            result.model == model
+                && result.model_version == model_version
+                && result.hardware == hardware
+        })
+    }
+
+    /// Adds the given measurement, replacing the earlier measurement of the model on the same
+    /// hardware.
+    pub fn insert(&mut self, result: BenchmarkResult) {
+        self.results
+            .retain(|r| !(r.model == result.model && r.hardware == result.hardware));
+        self.results.push(result);
+    }
+}
+
+/// Returns the hardware that the Ollama models run on, which keys their measurements: the
+/// fingerprint of this machine (see [`HardwareInfo::fingerprint`]), or the address of Ollama
+/// if it runs on another machine.
+pub fn benchmark_hardware(dria_env: &DriaEnv) -> String {
+    if is_remote_ollama(dria_env) {
+        let (host, port) = dria_env.get_ollama_config();
+        return format!("{host}:{port}");
+    }
+
+    HardwareInfo::detect().fingerprint()
+}
+
+/// Formats the given number of seconds in days, e.g. `today` or `12 days ago`.
+fn format_age(secs: u64) -> String {
+    match secs / (24 * 60 * 60) {
+        0 => "today".to_string(),
+        1 => "1 day ago".to_string(),
+        days => format!("{days} days ago"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_benchmark_cache() {
+        let result = BenchmarkResult {
+            model: "llama3.1:8b".to_string(),
+            model_version: "2025-01-01T00:00:00Z".to_string(),
+            hardware: "abc".to_string(),
+            measured_at: 0,
+            prompt_tps: 100.0,
+            prompt_dur_ms: 100,
+            eval_tps: 20.0,
+            eval_dur_ms: 1000,
+            total_dur_ms: 1200,
+            utilization: None,
+        };
+        let mut cache = BenchmarkCache::default();
+        cache.insert(result.clone());
+        assert_eq!(
+            cache.get("llama3.1:8b", "2025-01-01T00:00:00Z", "abc"),
+            Some(&result)
+        );
+        // the model is pulled again, or the hardware has changed
+        assert!(cache
+            .get("llama3.1:8b", "2025-02-01T00:00:00Z", "abc")
+            .is_none());
+        assert!(cache
+            .get("llama3.1:8b", "2025-01-01T00:00:00Z", "def")
+            .is_none());
+
+        // a new measurement replaces the older one
+        cache.insert(BenchmarkResult {
+            model_version: "2025-02-01T00:00:00Z".to_string(),
+            ..result.clone()
+        });
+        assert_eq!(cache.results.len(), 1);
+
+        assert_eq!(format_age(0), "today");
+        assert_eq!(format_age(36 * 60 * 60), "1 day ago");
+        assert_eq!(format_age(12 * 24 * 60 * 60 + 1), "12 days ago");
+    }
+}
@@ -1,7 +1,76 @@
 use eyre::Context;
-use libsecp256k1::{Message, PublicKey, SecretKey};
+use hmac::{Hmac, Mac};
+use libsecp256k1::{Message, PublicKey, RecoveryId, SecretKey, Signature};
+use sha2::Sha512;
 use sha3::{Digest, Keccak256};
 
+use super::LedgerSigner;
+
+/// The derivation path of the first Ethereum account, as used by MetaMask & Ledger Live.
+pub const DEFAULT_DERIVATION_PATH: &str = "m/44'/60'/0'/0/0";
+
+/// Offset of the hardened indices within a derivation path.
+const HARDENED_OFFSET: u32 = 0x8000_0000;
+
+/// Signs messages on behalf of an address, e.g. the challenges of the referrals API.
+#[derive(Debug, Clone)]
+pub enum Signer {
+    /// A secret key, e.g. the wallet of the node within the environment.
+    SecretKey {
+        secret_key: SecretKey,
+        address: String,
+    },
+    /// An account on a Ledger device, so that the secret key never leaves the device.
+    Ledger(LedgerSigner),
+}
+
+impl Signer {
+    /// Returns the address of the signer, in lowercase hexadecimal without `0x`.
+    pub fn address(&self) -> &str {
+        match self {
+            Self::SecretKey { address, .. } => address,
+            Self::Ledger(ledger) => ledger.address(),
+        }
+    }
+
+    /// Signs the given message with [EIP-191](https://eips.ethereum.org/EIPS/eip-191), see
+    /// [`eip191_hash`]; a Ledger waits for the user to confirm the message on the device.
+    ///
+    /// ### Errors
+    /// - If the Ledger rejects the message, or its signature is not of its address
+    pub async fn sign_message(&self, message: &str) -> eyre::Result<(Signature, RecoveryId)> {
+        match self {
+            Self::SecretKey { secret_key, .. } => {
+                Ok(libsecp256k1::sign(&eip191_hash(message), secret_key))
+            }
+            Self::Ledger(ledger) => {
+                log::info!("Please confirm the message on your Ledger.");
+                let (ledger, bytes) = (ledger.clone(), message.as_bytes().to_vec());
+                let (signature, recovery_id) =
+                    tokio::task::spawn_blocking(move || ledger.sign_personal_message(&bytes))
+                        .await??;
+                let signature = Signature::parse_standard(&signature)
+                    .wrap_err("could not parse the signature of the Ledger")?;
+                let recovery_id = RecoveryId::parse(recovery_id)
+                    .wrap_err("could not parse the signature of the Ledger")?;
+
+                // make sure that the device signed with the expected account
+                let public_key =
+                    libsecp256k1::recover(&eip191_hash(message), &signature, &recovery_id)
+                        .wrap_err("could not recover the signer of the Ledger signature")?;
+                if public_key_to_address(&public_key) != self.address() {
+                    eyre::bail!(
+                        "Ledger signed with another account than 0x{}",
+                        self.address()
+                    );
+                }
+
+                Ok((signature, recovery_id))
+            }
+        }
+    }
+}
+
 /// Given a secp256k1 public key, finds the corresponding Ethereum address.
 ///
 /// Internally, the public key is serialized in uncompressed format at 65 bytes (0x04 || x || y),
@@ -31,6 +100,106 @@ pub fn secret_key_to_account(key: &str) -> eyre::Result<(SecretKey, PublicKey, S
     Ok((secret_key, public_key, address))
 }
 
+/// Generates a new random secp256k1 secret key, returns it as a hexadecimal string with `0x` prefix.
+///
+/// ### Errors
+/// - If the system random number generator fails
+pub fn generate_secret_key() -> eyre::Result<String> {
+    loop {
+        let mut bytes = [0u8; 32];
+        getrandom::getrandom(&mut bytes).wrap_err("could not generate random bytes")?;
+
+        // a random 32-byte value is almost always a valid key, i.e. non-zero & less than the curve order
+        if let Ok(secret_key) = SecretKey::parse(&bytes) {
+            return Ok(format!("0x{}", hex::encode(secret_key.serialize())));
+        }
+    }
+}
+
+/// Parses a BIP-32 derivation path like `m/44'/60'/0'/0/0`.
+pub fn parse_derivation_path(path: &str) -> eyre::Result<Vec<u32>> {
+    let components = path
+        .trim()
+        .strip_prefix("m/")
+        .ok_or_else(|| eyre::eyre!("derivation path \"{path}\" must start with m/"))?
+        .split('/')
+        .map(|component| {
+            let (index, hardened) = match component.strip_suffix(['\'', 'h']) {
+                Some(index) => (index, true),
+                None => (component, false),
+            };
+            let index = index
+                .parse::<u32>()
+                .ok()
+                .filter(|index| *index < HARDENED_OFFSET)
+                .ok_or_else(|| eyre::eyre!("invalid component \"{component}\" in \"{path}\""))?;
+
+            Ok(if hardened {
+                index | HARDENED_OFFSET
+            } else {
+                index
+            })
+        })
+        .collect::<eyre::Result<Vec<_>>>()?;
+    if components.is_empty() || components.len() > 10 {
+        eyre::bail!("derivation path \"{path}\" must have 1 to 10 components");
+    }
+
+    Ok(components)
+}
+
+/// Derives the secret key at the given derivation path from a [BIP-39](https://github.com/bitcoin/bips/blob/master/bip-0039.mediawiki)
+/// mnemonic phrase & its optional passphrase, with [BIP-32](https://github.com/bitcoin/bips/blob/master/bip-0032.mediawiki);
+/// returns it as a hexadecimal string with `0x` prefix.
+///
+/// ### Errors
+/// - If the mnemonic phrase is invalid, e.g. a word is misspelled or its checksum is wrong
+/// - If the derivation path is invalid
+pub fn mnemonic_to_secret_key(
+    phrase: &str,
+    passphrase: &str,
+    derivation_path: &str,
+) -> eyre::Result<String> {
+    let mnemonic = bip39::Mnemonic::parse(phrase.split_whitespace().collect::<Vec<_>>().join(" "))
+        .map_err(|err| eyre::eyre!("invalid mnemonic phrase: {err}"))?;
+    let seed = mnemonic.to_seed(passphrase);
+    let path = parse_derivation_path(derivation_path)?;
+
+    let (mut secret_key, mut chain_code) = bip32_step(b"Bitcoin seed", &seed)?;
+    for index in path {
+        let mut data = if index >= HARDENED_OFFSET {
+            [&[0u8][..], &secret_key.serialize()].concat()
+        } else {
+            PublicKey::from_secret_key(&secret_key)
+                .serialize_compressed()
+                .to_vec()
+        };
+        data.extend_from_slice(&index.to_be_bytes());
+
+        let (tweak, child_chain_code) = bip32_step(&chain_code, &data)?;
+        secret_key
+            .tweak_add_assign(&tweak)
+            .wrap_err("derived an invalid key, try another derivation path")?;
+        chain_code = child_chain_code;
+    }
+
+    Ok(format!("0x{}", hex::encode(secret_key.serialize())))
+}
+
+/// Computes `HMAC-SHA512(key, data)`, and splits it into a secret key & a chain code.
+fn bip32_step(key: &[u8], data: &[u8]) -> eyre::Result<(SecretKey, [u8; 32])> {
+    let mut mac = Hmac::<Sha512>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(data);
+    let output = mac.finalize().into_bytes();
+
+    let secret_key = SecretKey::parse_slice(&output[..32])
+        .wrap_err("derived an invalid key, try another derivation path")?;
+    let mut chain_code = [0u8; 32];
+    chain_code.copy_from_slice(&output[32..]);
+
+    Ok((secret_key, chain_code))
+}
+
 /// Hash a `message` compatible with [EIP-191](https://eips.ethereum.org/EIPS/eip-191),
 /// which prepends `\x19Ethereum Signed Message:\n${message.length}` and hashes it with KECCAK256.
 ///
@@ -41,3 +210,91 @@ pub fn eip191_hash(message: impl AsRef<str>) -> Message {
     let data = format!("\x19Ethereum Signed Message:\n{}{}", message.len(), message);
     Message::parse(&Keccak256::digest(data.as_bytes()).into())
 }
+
+/// Encodes a signature as `0x || r || s || v` in hexadecimal, where `v` is `27` or `28`; this is
+/// the format of `personal_sign` within wallets, e.g. MetaMask & Etherscan.
+pub fn encode_signature(signature: &Signature, recovery_id: &RecoveryId) -> String {
+    let mut bytes = signature.serialize().to_vec();
+    bytes.push(recovery_id.serialize() + 27);
+
+    format!("0x{}", hex::encode(bytes))
+}
+
+/// Recovers the address that signed the given message with [EIP-191](https://eips.ethereum.org/EIPS/eip-191),
+/// w.r.t a signature encoded as in [`encode_signature`]; `v` may be `0` or `1` as well.
+///
+/// Returns the address in lowercase hexadecimal without `0x`.
+///
+/// ### Errors
+/// - If the signature is not a valid 65-byte signature
+pub fn recover_signer(message: &str, signature: &str) -> eyre::Result<String> {
+    let bytes = hex::decode(signature.trim().trim_start_matches("0x"))
+        .wrap_err("signature must be hexadecimal")?;
+    if bytes.len() != 65 {
+        eyre::bail!("signature must be 65 bytes, got {}", bytes.len());
+    }
+    let v = bytes[64];
+    let recovery_id = RecoveryId::parse(if v >= 27 { v - 27 } else { v })
+        .wrap_err("invalid recovery id of the signature")?;
+    let signature = Signature::parse_standard_slice(&bytes[..64]).wrap_err("invalid signature")?;
+    let public_key = libsecp256k1::recover(&eip191_hash(message), &signature, &recovery_id)
+        .wrap_err("could not recover the signer")?;
+
+    Ok(public_key_to_address(&public_key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_and_recover() {
+        let (secret_key, _, address) = secret_key_to_account(
+            "0x6472696164726961647269616472696164726961647269616472696164726961",
+        )
+        .unwrap();
+        let (signature, recovery_id) = libsecp256k1::sign(&eip191_hash("hello dria"), &secret_key);
+        let encoded = encode_signature(&signature, &recovery_id);
+        assert_eq!(encoded.len(), 2 + 130);
+        assert!(encoded.ends_with("1b") || encoded.ends_with("1c"));
+
+        assert_eq!(recover_signer("hello dria", &encoded).unwrap(), address);
+        assert_ne!(recover_signer("hello world", &encoded).unwrap(), address);
+        assert!(recover_signer("hello dria", "0x1234").is_err());
+    }
+
+    #[test]
+    fn test_mnemonic_to_secret_key() {
+        assert_eq!(
+            parse_derivation_path(DEFAULT_DERIVATION_PATH).unwrap(),
+            [0x8000_002C, 0x8000_003C, 0x8000_0000, 0, 0]
+        );
+        assert_eq!(parse_derivation_path("m/44h/1").unwrap(), [0x8000_002C, 1]);
+        assert!(parse_derivation_path("44'/60'").is_err());
+        assert!(parse_derivation_path("m/x").is_err());
+
+        // the well-known test mnemonic of Hardhat & Foundry, and its first account
+        let phrase = "test test test test test test test test test test test junk";
+        let secret_key = mnemonic_to_secret_key(phrase, "", DEFAULT_DERIVATION_PATH).unwrap();
+        assert_eq!(
+            secret_key,
+            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+        );
+        let (_, _, address) = secret_key_to_account(&secret_key).unwrap();
+        assert_eq!(address, "f39fd6e51aad88f6f4ce6ab8827279cfffb92266");
+
+        // the second account, and the extra whitespace is ignored
+        let secret_key = mnemonic_to_secret_key(
+            &format!("  {}\n", phrase.replace(' ', "  ")),
+            "",
+            "m/44'/60'/0'/0/1",
+        )
+        .unwrap();
+        assert_eq!(
+            secret_key,
+            "0x59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d"
+        );
+
+        assert!(mnemonic_to_secret_key("test test test", "", DEFAULT_DERIVATION_PATH).is_err());
+    }
+}
@@ -0,0 +1,74 @@
+use std::path::{Path, PathBuf};
+
+/// The filename of the redirect marker that is left behind when the data directory is moved,
+/// it simply stores the path to the new directory.
+pub const DKN_MOVED_MARKER_FILE: &str = ".dkn-moved";
+
+/// Maximum number of redirects to follow, in case the data directory was moved several times.
+const MAX_REDIRECTS: usize = 8;
+
+/// Returns the data directory that is shared by all profiles, `~/.dria/dkn-compute-launcher`,
+/// e.g. for the caches & the Ollama installed by the launcher.
+///
+/// This is `None` if the home directory can not be found; the shared files are then not kept,
+/// rather than being scattered into the temporary or the current directory.
+pub fn shared_data_dir() -> Option<PathBuf> {
+    match homedir::my_home() {
+        Ok(Some(home)) => Some(home.join(".dria").join("dkn-compute-launcher")),
+        Ok(None) | Err(_) => None,
+    }
+}
+
+/// Returns the environment file path within the new data directory, if the directory of the given
+/// environment file has been moved (see `config move-data`) and the file does not exist anymore.
+///
+/// Redirects are followed in a chain, so moving a directory multiple times is supported.
+pub fn follow_moved_data(env_path: &Path) -> Option<PathBuf> {
+    let file_name = env_path.file_name()?;
+    let mut current = env_path.to_path_buf();
+
+    for _ in 0..MAX_REDIRECTS {
+        if current.exists() {
+            break;
+        }
+
+        let dir = current
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        let new_dir = std::fs::read_to_string(dir.join(DKN_MOVED_MARKER_FILE)).ok()?;
+        current = PathBuf::from(new_dir.trim()).join(file_name);
+    }
+
+    (current != env_path).then_some(current)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_follow_moved_data() {
+        let root = std::env::temp_dir().join(format!(
+            "dkn-test-moved-data-{}-{}",
+            std::process::id(),
+            line!()
+        ));
+        let (old_dir, new_dir) = (root.join("old"), root.join("new"));
+        std::fs::create_dir_all(&old_dir).unwrap();
+        std::fs::create_dir_all(&new_dir).unwrap();
+
+        let old_env = old_dir.join(".env");
+        assert_eq!(follow_moved_data(&old_env), None);
+
+        std::fs::write(
+            old_dir.join(DKN_MOVED_MARKER_FILE),
+            new_dir.to_string_lossy().as_bytes(),
+        )
+        .unwrap();
+        std::fs::write(new_dir.join(".env"), "").unwrap();
+        assert_eq!(follow_moved_data(&old_env), Some(new_dir.join(".env")));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,65 @@
+use std::io::{self, Read};
+
+/// Maximum window size (as a power of 2) accepted when applying a patch, i.e. 1GB; patches made
+/// with `zstd --patch-from` use a window that covers the entire old binary.
+const PATCH_WINDOW_LOG_MAX: u32 = 30;
+
+/// Returns the name of the patch asset that upgrades the given asset from the given version,
+/// e.g. `dkn-compute-binary-linux-amd64.patch-from-v0.6.5.zst`.
+///
+/// Patches are published along with each release, and created with:
+///
+/// ```sh
+/// zstd --patch-from=<old binary> <new binary> -o <asset>.patch-from-v<old version>.zst
+/// ```
+pub fn patch_asset_name(asset_name: &str, base_version: &str) -> String {
+    format!(
+        "{asset_name}.patch-from-v{}.zst",
+        base_version.trim().trim_start_matches('v')
+    )
+}
+
+/// Applies a zstd patch to the given base binary, returning the patched binary.
+///
+/// The content checksum within the patch is verified, so a patch that is applied to a different
+/// base than it was created from fails instead of returning a corrupt binary.
+///
+/// ### Errors
+/// - If the patch is not a valid zstd frame, or its checksum does not match
+pub fn apply_patch(base: &[u8], patch: &[u8]) -> io::Result<Vec<u8>> {
+    let mut decoder = zstd::stream::read::Decoder::with_ref_prefix(patch, base)?;
+    decoder.window_log_max(PATCH_WINDOW_LOG_MAX)?;
+
+    let mut patched = Vec::with_capacity(base.len());
+    decoder.read_to_end(&mut patched)?;
+
+    Ok(patched)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_apply_patch() {
+        let base = b"compute node v0.6.5 ".repeat(1000);
+        let mut new = base.clone();
+        new.extend_from_slice(b"with a new feature in v0.6.6");
+
+        let mut encoder =
+            zstd::stream::write::Encoder::with_ref_prefix(Vec::new(), 3, &base).unwrap();
+        encoder.include_checksum(true).unwrap();
+        encoder.write_all(&new).unwrap();
+        let patch = encoder.finish().unwrap();
+        assert!(patch.len() < new.len() / 10);
+
+        assert_eq!(apply_patch(&base, &patch).unwrap(), new);
+        assert!(apply_patch(b"a different base", &patch).is_err());
+
+        assert_eq!(
+            patch_asset_name("dkn-compute-binary-linux-amd64", "v0.6.5"),
+            "dkn-compute-binary-linux-amd64.patch-from-v0.6.5.zst"
+        );
+    }
+}
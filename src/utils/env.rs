@@ -1,7 +1,9 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     fs, io,
     path::Path,
+    str::FromStr,
+    time::Duration,
 };
 
 use dkn_executor::Model;
@@ -9,7 +11,91 @@ use eyre::OptionExt;
 
 use crate::settings;
 
-use super::crypto::secret_key_to_account;
+use super::{
+    crypto::secret_key_to_account, parse_listen_port, ApiClientPolicy, EnvKey, ReadyCheck,
+    ResourceLimits,
+};
+
+/// A pending change of a key within [`DriaEnv`], see [`DriaEnv::changes_from`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvChange {
+    pub key: &'static str,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+impl std::fmt::Display for EnvChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let display = |value: &Option<String>| match value {
+            None => "(unset)".to_string(),
+            Some(value) => DriaEnv::masked_value(self.key, value),
+        };
+
+        write!(
+            f,
+            "{}: {} → {}",
+            self.key,
+            display(&self.old),
+            display(&self.new)
+        )
+    }
+}
+
+/// Masks a secret value, keeping only its last 4 characters if it is long enough.
+pub fn mask_secret(value: &str) -> String {
+    let chars = value.chars().collect::<Vec<_>>();
+    if chars.len() > 8 {
+        format!(
+            "****{}",
+            chars[chars.len() - 4..].iter().collect::<String>()
+        )
+    } else {
+        "****".to_string()
+    }
+}
+
+/// A view of [`DriaEnv`] with its secrets masked, which is safe to display within terminals,
+/// logs & reports; see [`DriaEnv::masked`].
+#[derive(Debug, Clone, Copy)]
+pub struct MaskedEnv<'a>(&'a DriaEnv);
+
+impl MaskedEnv<'_> {
+    /// Returns the value of the given key, masked if it is a secret.
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.0
+            .get(key)
+            .map(|value| DriaEnv::masked_value(key, value))
+    }
+}
+
+impl std::fmt::Display for MaskedEnv<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for key in DriaEnv::KEY_NAMES {
+            if let Some(value) = self.get(key) {
+                writeln!(f, "{key}={value}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Where a value of [`DriaEnv`] comes from, see [`DriaEnv::new_from_file`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnvSource {
+    /// The env file itself.
+    File,
+    /// The process environment, as the key is not set within the env file.
+    Process,
+}
+
+impl std::fmt::Display for EnvSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::File => write!(f, "env file"),
+            Self::Process => write!(f, "process environment"),
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct DriaEnv {
@@ -26,11 +112,54 @@ impl DriaEnv {
     pub const DKN_MODELS_KEY: &'static str = "DKN_MODELS";
     pub const DKN_P2P_LISTEN_ADDR_KEY: &'static str = "DKN_P2P_LISTEN_ADDR";
     pub const DKN_BATCH_SIZE_KEY: &'static str = "DKN_BATCH_SIZE";
+    pub const DKN_HEALTH_PORT_KEY: &'static str = "DKN_HEALTH_PORT";
+    pub const DKN_NODE_NAME_KEY: &'static str = "DKN_NODE_NAME";
+    pub const DKN_LABELS_KEY: &'static str = "DKN_LABELS";
+    pub const DKN_SHUTDOWN_TIMEOUT_KEY: &'static str = "DKN_SHUTDOWN_TIMEOUT_SECS";
+    pub const DKN_READY_CHECKS_KEY: &'static str = "DKN_READY_CHECKS";
+    pub const DKN_READY_MIN_PEERS_KEY: &'static str = "DKN_READY_MIN_PEERS";
+    pub const DKN_MANAGEMENT_API_KEY: &'static str = "DKN_MANAGEMENT_API";
+    pub const DKN_MANAGEMENT_PORT_KEY: &'static str = "DKN_MANAGEMENT_PORT";
+    pub const DKN_MANAGEMENT_TOKEN_KEY: &'static str = "DKN_MANAGEMENT_TOKEN";
+    pub const DKN_CPU_AFFINITY_KEY: &'static str = "DKN_CPU_AFFINITY";
+    pub const DKN_NICENESS_KEY: &'static str = "DKN_NICENESS";
+    pub const DKN_MEMORY_LIMIT_KEY: &'static str = "DKN_MEMORY_LIMIT_MB";
+    pub const DKN_NETWORK_KEY: &'static str = "DKN_NETWORK";
+    pub const DKN_POINTS_API_URL_KEY: &'static str = "DKN_POINTS_API_URL";
+    pub const DKN_REFERRALS_API_URL_KEY: &'static str = "DKN_REFERRALS_API_URL";
+    pub const DKN_API_CONNECT_TIMEOUT_KEY: &'static str = "DKN_API_CONNECT_TIMEOUT_SECS";
+    pub const DKN_API_TIMEOUT_KEY: &'static str = "DKN_API_TIMEOUT_SECS";
+    pub const DKN_API_RETRIES_KEY: &'static str = "DKN_API_RETRIES";
+    pub const DKN_PROMPT_TIMEOUT_KEY: &'static str = "DKN_PROMPT_TIMEOUT_SECS";
+    pub const DKN_AUTO_PORT_FORWARD_KEY: &'static str = "DKN_AUTO_PORT_FORWARD";
+    pub const DKN_DOWNLOAD_MIRRORS_KEY: &'static str = "DKN_DOWNLOAD_MIRRORS";
+    pub const DKN_DOWNLOAD_TIMEOUT_KEY: &'static str = "DKN_DOWNLOAD_TIMEOUT_SECS";
+    pub const DKN_GITHUB_TOKEN_KEY: &'static str = "DKN_GITHUB_TOKEN";
+    pub const DKN_COMPUTE_UPDATE_INTERVAL_KEY: &'static str = "DKN_COMPUTE_UPDATE_INTERVAL_MINS";
+    pub const DKN_LAUNCHER_UPDATE_INTERVAL_KEY: &'static str = "DKN_LAUNCHER_UPDATE_INTERVAL_MINS";
+    pub const DKN_WATCHDOG_KEY: &'static str = "DKN_WATCHDOG_STALL_MINS";
+    pub const DKN_WATCH_ENV_KEY: &'static str = "DKN_WATCH_ENV";
+    pub const DKN_ENV_AUDIT_KEY: &'static str = "DKN_ENV_AUDIT";
+    pub const DKN_LANG_KEY: &'static str = "DKN_LANG";
+    pub const DKN_NOTIFY_DESKTOP_KEY: &'static str = "DKN_NOTIFY_DESKTOP";
+    pub const DKN_NOTIFY_EMAIL_KEY: &'static str = "DKN_NOTIFY_EMAIL";
+    pub const DKN_NOTIFY_SMTP_URL_KEY: &'static str = "DKN_NOTIFY_SMTP_URL";
+    pub const DKN_NOTIFY_SMTP_AUTH_KEY: &'static str = "DKN_NOTIFY_SMTP_AUTH";
 
     // ollama stuff
     pub const OLLAMA_HOST_KEY: &str = "OLLAMA_HOST";
     pub const OLLAMA_PORT_KEY: &str = "OLLAMA_PORT";
     pub const OLLAMA_AUTO_PULL_KEY: &str = "OLLAMA_AUTO_PULL";
+    pub const OLLAMA_KEEP_ALIVE_KEY: &str = "OLLAMA_KEEP_ALIVE";
+    pub const OLLAMA_CONTEXT_LENGTH_KEY: &str = "OLLAMA_CONTEXT_LENGTH";
+    pub const OLLAMA_NUM_PARALLEL_KEY: &str = "OLLAMA_NUM_PARALLEL";
+    pub const OLLAMA_AUTH_TOKEN_KEY: &str = "OLLAMA_AUTH_TOKEN";
+    pub const OLLAMA_BASIC_AUTH_KEY: &str = "OLLAMA_BASIC_AUTH";
+    pub const OLLAMA_CA_CERT_KEY: &str = "OLLAMA_CA_CERT";
+    pub const OLLAMA_PULL_RETRIES_KEY: &str = "OLLAMA_PULL_RETRIES";
+    pub const OLLAMA_PULL_PARALLELISM_KEY: &str = "OLLAMA_PULL_PARALLELISM";
+    pub const OLLAMA_IDLE_UNLOAD_KEY: &str = "OLLAMA_IDLE_UNLOAD_MINS";
+    pub const OLLAMA_LOG_STREAM_KEY: &str = "OLLAMA_LOG_STREAM";
 
     // api keys
     pub const OPENAI_APIKEY_KEY: &'static str = "OPENAI_API_KEY";
@@ -39,8 +168,17 @@ impl DriaEnv {
     pub const SERPER_APIKEY_KEY: &'static str = "SERPER_API_KEY";
     pub const JINA_APIKEY_KEY: &'static str = "JINA_API_KEY";
 
+    /// Environment keys that store API keys of providers & tools.
+    pub const API_KEY_NAMES: [&str; 5] = [
+        Self::OPENAI_APIKEY_KEY,
+        Self::GEMINI_APIKEY_KEY,
+        Self::OPENROUTER_APIKEY_KEY,
+        Self::SERPER_APIKEY_KEY,
+        Self::JINA_APIKEY_KEY,
+    ];
+
     /// All environment keys that we are interested in.
-    pub const KEY_NAMES: [&str; 13] = [
+    pub const KEY_NAMES: [&str; 56] = [
         // log level
         Self::LOG_LEVEL_KEY,
         // DKN
@@ -48,6 +186,39 @@ impl DriaEnv {
         Self::DKN_MODELS_KEY,
         Self::DKN_P2P_LISTEN_ADDR_KEY,
         Self::DKN_BATCH_SIZE_KEY,
+        Self::DKN_HEALTH_PORT_KEY,
+        Self::DKN_NODE_NAME_KEY,
+        Self::DKN_LABELS_KEY,
+        Self::DKN_SHUTDOWN_TIMEOUT_KEY,
+        Self::DKN_READY_CHECKS_KEY,
+        Self::DKN_READY_MIN_PEERS_KEY,
+        Self::DKN_MANAGEMENT_API_KEY,
+        Self::DKN_MANAGEMENT_PORT_KEY,
+        Self::DKN_MANAGEMENT_TOKEN_KEY,
+        Self::DKN_CPU_AFFINITY_KEY,
+        Self::DKN_NICENESS_KEY,
+        Self::DKN_MEMORY_LIMIT_KEY,
+        Self::DKN_NETWORK_KEY,
+        Self::DKN_POINTS_API_URL_KEY,
+        Self::DKN_REFERRALS_API_URL_KEY,
+        Self::DKN_API_CONNECT_TIMEOUT_KEY,
+        Self::DKN_API_TIMEOUT_KEY,
+        Self::DKN_API_RETRIES_KEY,
+        Self::DKN_PROMPT_TIMEOUT_KEY,
+        Self::DKN_AUTO_PORT_FORWARD_KEY,
+        Self::DKN_DOWNLOAD_MIRRORS_KEY,
+        Self::DKN_DOWNLOAD_TIMEOUT_KEY,
+        Self::DKN_GITHUB_TOKEN_KEY,
+        Self::DKN_COMPUTE_UPDATE_INTERVAL_KEY,
+        Self::DKN_LAUNCHER_UPDATE_INTERVAL_KEY,
+        Self::DKN_WATCHDOG_KEY,
+        Self::DKN_WATCH_ENV_KEY,
+        Self::DKN_ENV_AUDIT_KEY,
+        Self::DKN_LANG_KEY,
+        Self::DKN_NOTIFY_DESKTOP_KEY,
+        Self::DKN_NOTIFY_EMAIL_KEY,
+        Self::DKN_NOTIFY_SMTP_URL_KEY,
+        Self::DKN_NOTIFY_SMTP_AUTH_KEY,
         // API keys
         Self::OPENAI_APIKEY_KEY,
         Self::GEMINI_APIKEY_KEY,
@@ -58,8 +229,103 @@ impl DriaEnv {
         Self::OLLAMA_HOST_KEY,
         Self::OLLAMA_PORT_KEY,
         Self::OLLAMA_AUTO_PULL_KEY,
+        Self::OLLAMA_KEEP_ALIVE_KEY,
+        Self::OLLAMA_CONTEXT_LENGTH_KEY,
+        Self::OLLAMA_NUM_PARALLEL_KEY,
+        Self::OLLAMA_AUTH_TOKEN_KEY,
+        Self::OLLAMA_BASIC_AUTH_KEY,
+        Self::OLLAMA_CA_CERT_KEY,
+        Self::OLLAMA_PULL_RETRIES_KEY,
+        Self::OLLAMA_PULL_PARALLELISM_KEY,
+        Self::OLLAMA_IDLE_UNLOAD_KEY,
+        Self::OLLAMA_LOG_STREAM_KEY,
     ];
 
+    /// Returns `true` if the given key holds a secret, which should not be displayed as is.
+    pub fn is_secret(key: &str) -> bool {
+        key == Self::DKN_WALLET_KEY
+            || key == Self::DKN_GITHUB_TOKEN_KEY
+            || key == Self::OLLAMA_AUTH_TOKEN_KEY
+            || key == Self::OLLAMA_BASIC_AUTH_KEY
+            || key == Self::DKN_MANAGEMENT_TOKEN_KEY
+            || key == Self::DKN_NOTIFY_SMTP_AUTH_KEY
+            || Self::API_KEY_NAMES.contains(&key)
+    }
+
+    /// Returns the given value of a key as it should be displayed, i.e. masked if the key holds
+    /// a secret; empty values are kept as is.
+    pub fn masked_value(key: &str, value: &str) -> String {
+        if Self::is_secret(key) && !value.is_empty() {
+            mask_secret(value)
+        } else {
+            value.to_string()
+        }
+    }
+
+    /// Returns a view of this environment with its secrets masked, to display it or parts of
+    /// it (e.g. within `info` or `report`) without leaking the wallet or API keys.
+    #[inline]
+    pub fn masked(&self) -> MaskedEnv<'_> {
+        MaskedEnv(self)
+    }
+
+    /// Masks the secrets of this environment that appear within the given text, e.g. an error
+    /// message of a provider that echoes the API key back.
+    pub fn redact(&self, text: &str) -> String {
+        let mut secrets = self
+            .kv
+            .iter()
+            .filter(|(key, value)| Self::is_secret(key) && !value.trim().is_empty())
+            .map(|(_, value)| value.trim())
+            .collect::<Vec<_>>();
+        // longer secrets first, in case one contains another
+        secrets.sort_by_key(|secret| std::cmp::Reverse(secret.len()));
+
+        secrets.into_iter().fold(text.to_string(), |text, secret| {
+            text.replace(secret, &mask_secret(secret))
+        })
+    }
+
+    /// Returns the changes of this environment w.r.t the given original one, in the order of [`DriaEnv::KEY_NAMES`].
+    ///
+    /// Empty values are treated as unset.
+    pub fn changes_from(&self, original: &DriaEnv) -> Vec<EnvChange> {
+        let get = |env: &DriaEnv, key| {
+            env.get(key)
+                .filter(|value| !value.is_empty())
+                .map(String::from)
+        };
+
+        Self::KEY_NAMES
+            .into_iter()
+            .filter_map(|key| {
+                let (old, new) = (get(original, key), get(self, key));
+                (old != new).then_some(EnvChange { key, old, new })
+            })
+            .collect()
+    }
+
+    /// Reverts the value of a key to its value within the given original environment.
+    pub fn revert(&mut self, key: &'static str, original: &DriaEnv) {
+        match original.kv.get(key) {
+            Some(value) => self.kv.insert(key, value.clone()),
+            None => self.kv.remove(key),
+        };
+        self.is_changed = !self.changes_from(original).is_empty();
+    }
+
+    /// Applies the given changes to this environment, e.g. the ones that are found within a
+    /// reloaded environment file.
+    pub fn apply(&mut self, changes: &[EnvChange]) {
+        for change in changes {
+            match &change.new {
+                Some(value) => self.kv.insert(change.key, value.clone()),
+                None => self.kv.remove(change.key),
+            };
+        }
+        self.is_changed |= !changes.is_empty();
+    }
+
     /// Check if the environment has been changed.
     #[inline]
     pub fn is_changed(&self) -> bool {
@@ -81,6 +347,79 @@ impl DriaEnv {
         self.is_changed = true;
     }
 
+    /// Get the value of a key, or its default value from the [`ENV_REGISTRY`](super::ENV_REGISTRY).
+    #[inline]
+    pub fn get_or_default(&self, key: &str) -> Option<&str> {
+        self.get(key)
+            .or_else(|| EnvKey::find(key).and_then(|key| key.default))
+    }
+
+    /// Parses the value of a key, or its default value if it is not set.
+    ///
+    /// An invalid value is ignored with a warning, and the default value is used instead.
+    pub fn get_parsed<T: FromStr>(&self, key: &str) -> Option<T> {
+        let default = || {
+            EnvKey::find(key)
+                .and_then(|key| key.default)
+                .and_then(|value| value.parse().ok())
+        };
+
+        match self.get(key) {
+            Some(value) => value.trim().parse().ok().or_else(|| {
+                log::warn!("Ignoring invalid value for {key}: {value}");
+                default()
+            }),
+            None => default(),
+        }
+    }
+
+    /// Returns the boolean value of a key (`true` or `false`, case-insensitive), or its default value.
+    ///
+    /// An invalid value is ignored with a warning, and the default value is used instead.
+    pub fn get_bool(&self, key: &str) -> bool {
+        let default = EnvKey::find(key)
+            .and_then(|key| key.default)
+            .is_some_and(|value| value == "true");
+
+        match self.get(key).map(str::trim) {
+            Some(value) if value.eq_ignore_ascii_case("true") => true,
+            Some(value) if value.eq_ignore_ascii_case("false") => false,
+            Some(value) => {
+                log::warn!("Ignoring invalid value for {key}: {value}");
+                default
+            }
+            None => default,
+        }
+    }
+
+    /// Returns the P2P listen port of the compute node, within the listen address.
+    ///
+    /// ### Errors
+    /// - If the listen addresses are not valid, see [`parse_listen_addrs`](super::parse_listen_addrs)
+    pub fn get_port(&self) -> eyre::Result<u16> {
+        let addr = self
+            .get_or_default(Self::DKN_P2P_LISTEN_ADDR_KEY)
+            .unwrap_or_default();
+        parse_listen_port(addr).ok_or_else(|| eyre::eyre!("invalid listen address: {addr}"))
+    }
+
+    /// Validates the values of all keys w.r.t their kinds in the registry.
+    ///
+    /// Returns a list of invalid keys along with the reason, empty if everything is valid.
+    pub fn validate(&self) -> Vec<(&'static str, String)> {
+        Self::KEY_NAMES
+            .into_iter()
+            .filter_map(|name| {
+                let value = self.get(name)?;
+                let key = EnvKey::find(name)?;
+                key.kind
+                    .validate(value)
+                    .err()
+                    .map(|err| (name, format!("invalid value \"{value}\": {err}")))
+            })
+            .collect()
+    }
+
     /// Create a new instance of `DriaEnv` with the environment variables specified in `KEY_NAMES`.
     ///
     /// - Non-existent variables are ignored.
@@ -99,6 +438,49 @@ impl DriaEnv {
         }
     }
 
+    /// Reads the values from the given env file itself, falling back to the process environment
+    /// for the keys that are not set within the file.
+    ///
+    /// Unlike [`DriaEnv::new_from_env`], the values are not masked by the process environment
+    /// (e.g. an env file that was loaded before), so this should be preferred by read commands.
+    ///
+    /// ### Returns
+    /// The environment, along with where each value comes from.
+    ///
+    /// ### Errors
+    /// - If the env file could not be read.
+    pub fn new_from_file(
+        env_path: &Path,
+    ) -> eyre::Result<(Self, HashMap<&'static str, EnvSource>)> {
+        let file_kv = dotenvy::from_path_iter(env_path)?.collect::<Result<HashMap<_, _>, _>>()?;
+
+        let mut kv = HashMap::new();
+        let mut sources = HashMap::new();
+        for key in Self::KEY_NAMES {
+            let (value, source) = match file_kv.get(key) {
+                Some(value) => (value.clone(), EnvSource::File),
+                None => match std::env::var(key) {
+                    Ok(value) => (value, EnvSource::Process),
+                    Err(_) => continue,
+                },
+            };
+
+            // empty values are removed, as in `new_from_env`
+            if !value.is_empty() {
+                kv.insert(key, value);
+                sources.insert(key, source);
+            }
+        }
+
+        Ok((
+            Self {
+                kv,
+                is_changed: false,
+            },
+            sources,
+        ))
+    }
+
     /// Expects a content string (from an env file) and saves the keys to this content.
     ///
     /// - If a key exists in the content, it will be replaced with the value from the env.
@@ -173,18 +555,362 @@ impl DriaEnv {
     /// Returns the `host` and `port` values for the Ollama server w.r.t Dria environment.
     #[inline]
     pub fn get_ollama_config(&self) -> (&str, u16) {
-        const DEFAULT_OLLAMA_HOST: &str = "http://127.0.0.1";
-        const DEFAULT_OLLAMA_PORT: &str = "11434";
-
         let host = self
-            .get(Self::OLLAMA_HOST_KEY)
-            .unwrap_or(DEFAULT_OLLAMA_HOST);
+            .get_or_default(Self::OLLAMA_HOST_KEY)
+            .expect("has a default");
+        let port = self
+            .get_parsed(Self::OLLAMA_PORT_KEY)
+            .expect("has a default");
+
+        (host, port)
+    }
+
+    /// Returns the configured Ollama server variables (keep-alive, context length & parallelism)
+    /// that are passed to the Ollama server when it is spawned by the launcher.
+    pub fn get_ollama_server_env(&self) -> Vec<(&'static str, &str)> {
+        [
+            Self::OLLAMA_KEEP_ALIVE_KEY,
+            Self::OLLAMA_CONTEXT_LENGTH_KEY,
+            Self::OLLAMA_NUM_PARALLEL_KEY,
+        ]
+        .into_iter()
+        .filter_map(|key| {
+            self.get(key)
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(|value| (key, value))
+        })
+        .collect()
+    }
+
+    /// Returns the port for the health-check server, if it is configured.
+    ///
+    /// An invalid port is ignored with a warning.
+    #[inline]
+    pub fn get_health_port(&self) -> Option<u16> {
+        let port = self.get(Self::DKN_HEALTH_PORT_KEY)?;
+        match port.parse() {
+            Ok(port) => Some(port),
+            Err(_) => {
+                log::warn!("Ignoring invalid health-check port: {port}");
+                None
+            }
+        }
+    }
+
+    /// Returns how long to wait for child processes to exit on shutdown, before killing them.
+    pub fn get_shutdown_timeout(&self) -> Duration {
+        Duration::from_secs(
+            self.get_parsed(Self::DKN_SHUTDOWN_TIMEOUT_KEY)
+                .expect("has a default"),
+        )
+    }
+
+    /// Returns the mirror URL templates to download releases from before GitHub, in order.
+    pub fn get_download_mirrors(&self) -> Vec<String> {
+        self.get(Self::DKN_DOWNLOAD_MIRRORS_KEY)
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|template| !template.is_empty())
+            .map(String::from)
+            .collect()
+    }
+
+    /// Returns the GitHub token to authenticate the GitHub API requests with, which raises their
+    /// rate limit; `DKN_GITHUB_TOKEN` is preferred over the commonly used `GITHUB_TOKEN`.
+    pub fn get_github_token(&self) -> Option<String> {
+        self.get(Self::DKN_GITHUB_TOKEN_KEY)
+            .map(String::from)
+            .filter(|token| !token.trim().is_empty())
+            .or_else(|| std::env::var("GITHUB_TOKEN").ok())
+            .map(|token| token.trim().to_string())
+            .filter(|token| !token.is_empty())
+    }
+
+    /// Returns the intervals between the compute node & launcher update checks of a running node.
+    pub fn get_update_intervals(&self) -> (Duration, Duration) {
+        let minutes = |key| {
+            let mins = self.get_parsed::<u64>(key).expect("has a default");
+            Duration::from_secs(mins.saturating_mul(60))
+        };
+
+        (
+            minutes(Self::DKN_COMPUTE_UPDATE_INTERVAL_KEY),
+            minutes(Self::DKN_LAUNCHER_UPDATE_INTERVAL_KEY),
+        )
+    }
+
+    /// Returns how long the compute node can stay silent before it is considered hung & restarted,
+    /// `None` if the watchdog is disabled (i.e. set to `0`).
+    pub fn get_watchdog_timeout(&self) -> Option<Duration> {
+        self.get_parsed::<u64>(Self::DKN_WATCHDOG_KEY)
+            .filter(|mins| *mins > 0)
+            .map(|mins| Duration::from_secs(mins.saturating_mul(60)))
+    }
+
+    /// Returns how long to wait for a download source to connect or send data, before trying
+    /// the next one.
+    pub fn get_download_timeout(&self) -> Duration {
+        Duration::from_secs(
+            self.get_parsed(Self::DKN_DOWNLOAD_TIMEOUT_KEY)
+                .expect("has a default"),
+        )
+    }
+
+    /// Returns the timeouts & retries of the clients of the points & referrals APIs.
+    pub fn get_api_client_policy(&self) -> ApiClientPolicy {
+        ApiClientPolicy {
+            connect_timeout: Duration::from_secs(
+                self.get_parsed(Self::DKN_API_CONNECT_TIMEOUT_KEY)
+                    .expect("has a default"),
+            ),
+            timeout: Duration::from_secs(
+                self.get_parsed(Self::DKN_API_TIMEOUT_KEY)
+                    .expect("has a default"),
+            ),
+            retries: self
+                .get_parsed(Self::DKN_API_RETRIES_KEY)
+                .expect("has a default"),
+        }
+    }
+
+    /// Returns how long interactive prompts wait for an answer when starting the node,
+    /// `None` if they wait forever.
+    #[inline]
+    pub fn get_prompt_timeout(&self) -> Option<Duration> {
+        self.get(Self::DKN_PROMPT_TIMEOUT_KEY)?;
+        self.get_parsed(Self::DKN_PROMPT_TIMEOUT_KEY)
+            .map(Duration::from_secs)
+    }
+
+    /// Returns the checks that must pass for the health-check to report the node as healthy.
+    ///
+    /// Invalid checks are ignored with a warning, and the default checks are used instead.
+    pub fn get_ready_checks(&self) -> BTreeSet<ReadyCheck> {
+        let default = || {
+            let checks = EnvKey::find(Self::DKN_READY_CHECKS_KEY)
+                .and_then(|key| key.default)
+                .expect("has a default");
+            ReadyCheck::parse_list(checks).expect("default is valid")
+        };
+
+        match self.get(Self::DKN_READY_CHECKS_KEY) {
+            Some(checks) => ReadyCheck::parse_list(checks).unwrap_or_else(|err| {
+                log::warn!("Ignoring invalid ready checks: {err}");
+                default()
+            }),
+            None => default(),
+        }
+    }
+
+    /// Returns the minimum number of peers for the `peers` ready check.
+    pub fn get_ready_min_peers(&self) -> usize {
+        self.get_parsed(Self::DKN_READY_MIN_PEERS_KEY)
+            .expect("has a default")
+    }
+
+    /// Returns the port & token for the management API, if it is enabled.
+    ///
+    /// The API is not served without a token, with a warning.
+    pub fn get_management_api(&self) -> Option<(u16, String)> {
+        if !self.get_bool(Self::DKN_MANAGEMENT_API_KEY) {
+            return None;
+        }
+
+        let Some(token) = self
+            .get(Self::DKN_MANAGEMENT_TOKEN_KEY)
+            .map(str::trim)
+            .filter(|token| !token.is_empty())
+        else {
+            log::warn!(
+                "Management API is enabled without {}, it will not be served.",
+                Self::DKN_MANAGEMENT_TOKEN_KEY
+            );
+            return None;
+        };
 
         let port = self
-            .get(Self::OLLAMA_PORT_KEY)
-            .unwrap_or(DEFAULT_OLLAMA_PORT);
+            .get_parsed(Self::DKN_MANAGEMENT_PORT_KEY)
+            .expect("has a default");
+        Some((port, token.to_string()))
+    }
+
+    /// Returns the resource limits for the compute node process, invalid values are ignored with a warning.
+    pub fn get_resource_limits(&self) -> ResourceLimits {
+        let cpu_affinity = self
+            .get(Self::DKN_CPU_AFFINITY_KEY)
+            .filter(|cpus| !cpus.trim().is_empty())
+            .and_then(|cpus| match ResourceLimits::parse_cpu_list(cpus) {
+                Ok(cpus) => Some(cpus),
+                Err(err) => {
+                    log::warn!("Ignoring invalid CPU affinity: {err}");
+                    None
+                }
+            });
+
+        let niceness = self
+            .get(Self::DKN_NICENESS_KEY)
+            .filter(|niceness| !niceness.trim().is_empty())
+            .and_then(|niceness| match niceness.trim().parse::<i32>() {
+                Ok(niceness) if (-20..=19).contains(&niceness) => Some(niceness),
+                _ => {
+                    log::warn!("Ignoring invalid niceness: {niceness}");
+                    None
+                }
+            });
+
+        let memory_mb = self
+            .get(Self::DKN_MEMORY_LIMIT_KEY)
+            .filter(|memory| !memory.trim().is_empty())
+            .and_then(|memory| match memory.trim().parse::<u64>() {
+                Ok(memory) if memory > 0 => Some(memory),
+                _ => {
+                    log::warn!("Ignoring invalid memory limit: {memory}");
+                    None
+                }
+            });
+
+        ResourceLimits {
+            cpu_affinity,
+            niceness,
+            memory_mb,
+        }
+    }
+
+    /// Returns `true` if the P2P listen port should be mapped on the router with UPnP or NAT-PMP
+    /// when the node starts.
+    #[inline]
+    pub fn is_auto_port_forward_enabled(&self) -> bool {
+        self.get_bool(Self::DKN_AUTO_PORT_FORWARD_KEY)
+    }
+
+    /// Returns `true` if the environment file should be watched by a running node, so that its
+    /// changes (e.g. a new model selection) are applied without a restart.
+    #[inline]
+    pub fn is_env_watch_enabled(&self) -> bool {
+        self.get_bool(Self::DKN_WATCH_ENV_KEY)
+    }
+
+    /// Returns `true` if the changes of the environment file should be recorded within an audit
+    /// log while the node is running, see [`append_env_audit`](super::append_env_audit).
+    #[inline]
+    pub fn is_env_audit_enabled(&self) -> bool {
+        self.get_bool(Self::DKN_ENV_AUDIT_KEY)
+    }
+
+    /// Returns `true` if a desktop notification should be shown when the node stops unattended.
+    #[inline]
+    pub fn is_desktop_notify_enabled(&self) -> bool {
+        self.get_bool(Self::DKN_NOTIFY_DESKTOP_KEY)
+    }
+
+    /// Returns the email address to notify when the node stops unattended, if there is one.
+    #[inline]
+    pub fn get_notify_email(&self) -> Option<&str> {
+        self.get(Self::DKN_NOTIFY_EMAIL_KEY)
+            .map(str::trim)
+            .filter(|email| !email.is_empty())
+    }
+
+    /// Returns the SMTP server to send the notification emails through, along with its
+    /// `username:password` credentials if there are any; the `sendmail` command of the system is
+    /// used without a server.
+    ///
+    /// Only `smtps://` (implicit TLS) and `smtp://` (upgraded with STARTTLS) servers are used, others
+    /// are ignored with a warning.
+    pub fn get_notify_smtp(&self) -> Option<(&str, Option<&str>)> {
+        let url = self
+            .get(Self::DKN_NOTIFY_SMTP_URL_KEY)
+            .map(str::trim)
+            .filter(|url| !url.is_empty())?;
+        if !url.starts_with("smtps://") && !url.starts_with("smtp://") {
+            log::warn!(
+                "Ignoring {} as it is not an smtp:// or smtps:// URL.",
+                Self::DKN_NOTIFY_SMTP_URL_KEY
+            );
+            return None;
+        }
 
-        (host, port.parse().expect("invalid port"))
+        let auth = self
+            .get(Self::DKN_NOTIFY_SMTP_AUTH_KEY)
+            .map(str::trim)
+            .filter(|auth| !auth.is_empty());
+        Some((url, auth))
+    }
+
+    /// Returns `true` if the output of an Ollama spawned by the launcher should be streamed to the
+    /// launcher logs at debug level, in addition to its log file.
+    #[inline]
+    pub fn is_ollama_log_stream_enabled(&self) -> bool {
+        self.get_bool(Self::OLLAMA_LOG_STREAM_KEY)
+    }
+
+    /// Returns how many times a failed model pull is retried.
+    #[inline]
+    pub fn get_pull_retries(&self) -> u32 {
+        self.get_parsed(Self::OLLAMA_PULL_RETRIES_KEY)
+            .expect("has a default")
+    }
+
+    /// Returns how many models are pulled at the same time.
+    #[inline]
+    pub fn get_pull_parallelism(&self) -> usize {
+        self.get_parsed::<usize>(Self::OLLAMA_PULL_PARALLELISM_KEY)
+            .expect("has a default")
+            .max(1)
+    }
+
+    /// Returns how long the node can be idle (without tasks) before its Ollama models are unloaded,
+    /// `None` if models should stay loaded.
+    #[inline]
+    pub fn get_idle_unload(&self) -> Option<Duration> {
+        self.get(Self::OLLAMA_IDLE_UNLOAD_KEY)?;
+        self.get_parsed::<u64>(Self::OLLAMA_IDLE_UNLOAD_KEY)
+            .filter(|mins| *mins > 0)
+            .map(|mins| Duration::from_secs(mins.saturating_mul(60)))
+    }
+
+    /// Returns the human-friendly name of this node, if any.
+    #[inline]
+    pub fn get_node_name(&self) -> Option<&str> {
+        self.get(Self::DKN_NODE_NAME_KEY)
+    }
+
+    /// Returns the labels of this node, invalid labels are ignored with a warning.
+    pub fn get_labels(&self) -> BTreeMap<String, String> {
+        Self::parse_labels(self.get(Self::DKN_LABELS_KEY).unwrap_or_default()).unwrap_or_else(
+            |err| {
+                log::warn!("Ignoring invalid labels: {err}");
+                BTreeMap::new()
+            },
+        )
+    }
+
+    /// Parses labels given as comma-separated `key=value` pairs, e.g. `region=eu,gpu=3090`.
+    ///
+    /// Keys and values are trimmed, and keys must be non-empty and made of alphanumerics, `-`, `_` or `.`.
+    pub fn parse_labels(labels: &str) -> eyre::Result<BTreeMap<String, String>> {
+        labels
+            .split(',')
+            .filter(|label| !label.trim().is_empty())
+            .map(|label| {
+                let (key, value) = label
+                    .split_once('=')
+                    .ok_or_else(|| eyre::eyre!("label \"{label}\" must be like key=value"))?;
+                let (key, value) = (key.trim(), value.trim());
+
+                if key.is_empty()
+                    || !key
+                        .chars()
+                        .all(|c| c.is_alphanumeric() || c == '-' || c == '_' || c == '.')
+                {
+                    eyre::bail!("label key \"{key}\" is not valid");
+                }
+
+                Ok((key.to_string(), value.to_string()))
+            })
+            .collect()
     }
 
     /// Returns the models as they appear in the environment.
@@ -207,11 +933,100 @@ impl DriaEnv {
     }
 }
 
+/// Displays the environment with its secrets masked, see [`DriaEnv::masked`].
 impl std::fmt::Display for DriaEnv {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for (k, v) in &self.kv {
-            writeln!(f, "{}={}", k, v)?;
-        }
-        Ok(())
+        self.masked().fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_labels() {
+        let labels = DriaEnv::parse_labels("region=eu, gpu=3090,,").unwrap();
+        assert_eq!(labels.len(), 2);
+        assert_eq!(labels["region"], "eu");
+        assert_eq!(labels["gpu"], "3090");
+
+        assert!(DriaEnv::parse_labels("").unwrap().is_empty());
+        assert!(DriaEnv::parse_labels("region").is_err());
+        assert!(DriaEnv::parse_labels("=eu").is_err());
+        assert!(DriaEnv::parse_labels("my region=eu").is_err());
+    }
+
+    #[test]
+    fn test_changes_and_revert() {
+        let mut original = DriaEnv::new_from_env();
+        original.kv.clear();
+        original
+            .kv
+            .insert(DriaEnv::DKN_NODE_NAME_KEY, "old".to_string());
+
+        let mut dria_env = original.clone();
+        dria_env.set(DriaEnv::DKN_NODE_NAME_KEY, "new");
+        dria_env.set(DriaEnv::DKN_LABELS_KEY, "gpu=3090");
+        dria_env.set(DriaEnv::OPENAI_APIKEY_KEY, "sk-1234567890");
+
+        let changes = dria_env.changes_from(&original);
+        assert_eq!(changes.len(), 3);
+        assert_eq!(changes[0].to_string(), "DKN_NODE_NAME: old → new");
+        assert_eq!(changes[2].to_string(), "OPENAI_API_KEY: (unset) → ****7890");
+
+        let mut applied = original.clone();
+        applied.apply(&changes);
+        assert!(dria_env.changes_from(&applied).is_empty());
+        applied.apply(&original.changes_from(&dria_env));
+        assert!(applied.changes_from(&original).is_empty());
+
+        dria_env.revert(DriaEnv::DKN_NODE_NAME_KEY, &original);
+        dria_env.revert(DriaEnv::DKN_LABELS_KEY, &original);
+        assert!(dria_env.is_changed());
+        dria_env.revert(DriaEnv::OPENAI_APIKEY_KEY, &original);
+        assert!(!dria_env.is_changed());
+    }
+
+    #[test]
+    fn test_masked() {
+        let mut dria_env = DriaEnv::new_from_env();
+        dria_env.kv.clear();
+        dria_env.set(DriaEnv::DKN_NODE_NAME_KEY, "my-node");
+        dria_env.set(DriaEnv::OPENAI_APIKEY_KEY, "sk-1234567890");
+        dria_env.set(DriaEnv::GEMINI_APIKEY_KEY, "");
+
+        assert_eq!(
+            dria_env.to_string(),
+            "DKN_NODE_NAME=my-node\nOPENAI_API_KEY=****7890\nGEMINI_API_KEY=\n"
+        );
+        assert_eq!(
+            dria_env.masked().get(DriaEnv::DKN_NODE_NAME_KEY).as_deref(),
+            Some("my-node")
+        );
+        assert_eq!(
+            dria_env.redact("Incorrect API key provided: sk-1234567890."),
+            "Incorrect API key provided: ****7890."
+        );
+    }
+
+    #[test]
+    fn test_new_from_file() {
+        let env_path = std::env::temp_dir().join(format!(
+            ".env.test_new_from_file-{}-{}",
+            std::process::id(),
+            line!()
+        ));
+        fs::write(&env_path, "DKN_NODE_NAME=from-file\nDKN_LABELS=\n").unwrap();
+
+        let (dria_env, sources) = DriaEnv::new_from_file(&env_path).unwrap();
+        assert_eq!(dria_env.get_node_name(), Some("from-file"));
+        assert_eq!(
+            sources.get(DriaEnv::DKN_NODE_NAME_KEY),
+            Some(&EnvSource::File)
+        );
+        assert_eq!(dria_env.get(DriaEnv::DKN_LABELS_KEY), None);
+
+        fs::remove_file(&env_path).unwrap();
     }
 }
@@ -0,0 +1,182 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::{DriaEnv, EnvChange};
+
+/// The file that keeps the changes of the environment file, see [`append_env_audit`].
+pub const DKN_ENV_AUDIT_FILE: &str = ".dkn-env-audit.log";
+
+/// Interval between the checks of the environment file.
+const ENV_WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Time that the environment file must stay unchanged before a change is reported, so that a
+/// file that is being written (e.g. by an editor, in several steps) is not read half-way.
+const ENV_WATCH_SETTLE: Duration = Duration::from_secs(1);
+
+/// The modification time & size of a file, which changes whenever the file is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+impl FileStamp {
+    /// Returns the stamp of the file at the given path, `None` if it does not exist.
+    fn of(path: &Path) -> Option<Self> {
+        let metadata = std::fs::metadata(path).ok()?;
+        Some(Self {
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+        })
+    }
+}
+
+/// Watches the environment file of a running node, e.g. for the models that are selected with
+/// `settings` while the node is running.
+///
+/// The file is polled for its modification time & size, which works the same on all platforms
+/// and for files on network mounts or within containers.
+#[derive(Debug)]
+pub struct EnvWatcher {
+    path: PathBuf,
+    stamp: Option<FileStamp>,
+    /// The file as it was last seen, so that each edit is audited only once.
+    seen: DriaEnv,
+}
+
+impl EnvWatcher {
+    /// Starts watching the file at the given path, its current content is considered unchanged.
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            stamp: FileStamp::of(path),
+            seen: DriaEnv::new_from_file(path)
+                .map(|(file_env, _)| file_env)
+                .unwrap_or_else(|_| DriaEnv::new_from_env()),
+        }
+    }
+
+    /// Returns the changes of the given file w.r.t the last time it was seen, e.g. to audit them.
+    pub fn changes_since_seen(&mut self, file_env: &DriaEnv) -> Vec<EnvChange> {
+        let changes = file_env.changes_from(&self.seen);
+        self.seen = file_env.clone();
+        changes
+    }
+
+    /// Waits until the file is changed & has settled.
+    ///
+    /// A file that is removed is not reported until it is written again. This is cancel-safe,
+    /// a change is only consumed once it is returned.
+    pub async fn changed(&mut self) {
+        loop {
+            tokio::time::sleep(ENV_WATCH_INTERVAL).await;
+            let mut stamp = FileStamp::of(&self.path);
+            if stamp == self.stamp {
+                continue;
+            }
+
+            // wait for the writes to settle
+            loop {
+                tokio::time::sleep(ENV_WATCH_SETTLE).await;
+                let settled = FileStamp::of(&self.path);
+                if settled == stamp {
+                    break;
+                }
+                stamp = settled;
+            }
+
+            self.stamp = stamp;
+            if stamp.is_some() {
+                return;
+            }
+        }
+    }
+}
+
+/// Appends the given changes of the environment file at `env_path` to the audit log within
+/// `compute_dir`, without the values of secrets; this is best-effort.
+pub fn append_env_audit(compute_dir: &Path, env_path: &Path, changes: &[EnvChange]) {
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or_default();
+    let entry = format_audit_entry(time, env_path, changes);
+    if let Err(err) = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(compute_dir.join(DKN_ENV_AUDIT_FILE))
+        .and_then(|mut file| file.write_all(entry.as_bytes()))
+    {
+        log::warn!(
+            "Could not record the changes of {}: {err}",
+            env_path.display()
+        );
+    }
+}
+
+/// Formats the given changes as lines of `<unix-seconds> <env-path> <change>`, see [`audit_change`].
+fn format_audit_entry(time: u64, env_path: &Path, changes: &[EnvChange]) -> String {
+    changes
+        .iter()
+        .map(|change| format!("{time} {} {}\n", env_path.display(), audit_change(change)))
+        .collect()
+}
+
+/// Describes the given change for the audit; secrets are only noted as changed, as even a part
+/// of their values should not be kept around.
+pub fn audit_change(change: &EnvChange) -> String {
+    if DriaEnv::is_secret(change.key) {
+        format!("{}: changed", change.key)
+    } else {
+        change.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::DriaEnv;
+
+    #[test]
+    fn test_file_stamp() {
+        let path = std::env::temp_dir().join(format!(
+            "dkn-env-watch-{}-{}.env",
+            std::process::id(),
+            line!()
+        ));
+        std::fs::write(&path, "DKN_MODELS=a").unwrap();
+        let mut watcher = EnvWatcher::new(&path);
+        assert_eq!(watcher.stamp, FileStamp::of(&path));
+
+        std::fs::write(&path, "DKN_MODELS=a,b").unwrap();
+        assert_ne!(watcher.stamp, FileStamp::of(&path));
+
+        // each edit is seen once
+        let (file_env, _) = DriaEnv::new_from_file(&path).unwrap();
+        let changes = watcher.changes_since_seen(&file_env);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].new.as_deref(), Some("a,b"));
+        assert!(watcher.changes_since_seen(&file_env).is_empty());
+
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(FileStamp::of(&path), None);
+
+        let changes = [
+            EnvChange {
+                key: DriaEnv::DKN_MODELS_KEY,
+                old: Some("gpt-4o".to_string()),
+                new: Some("gemma3:4b".to_string()),
+            },
+            EnvChange {
+                key: DriaEnv::OPENAI_APIKEY_KEY,
+                old: None,
+                new: Some("sk-0123456789abcdef".to_string()),
+            },
+        ];
+        assert_eq!(
+            format_audit_entry(42, Path::new(".env"), &changes),
+            "42 .env DKN_MODELS: gpt-4o → gemma3:4b\n42 .env OPENAI_API_KEY: changed\n"
+        );
+    }
+}
@@ -0,0 +1,91 @@
+/// Exit code for errors that do not belong to a failure class below.
+const GENERIC_EXIT_CODE: u8 = 1;
+
+/// Failure classes of the launcher, each with a distinct process exit code so that supervisors
+/// (e.g. systemd with `RestartPreventExitStatus`) can apply different restart policies per class.
+///
+/// A class is attached to an error as its context, e.g. `.wrap_err(ExitReason::Config)`, or
+/// returned as the error itself; see [`exit_code`] for how it is found back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitReason {
+    /// The environment file or a setting within is invalid, restarting will not help.
+    Config,
+    /// The compute node or a model could not be downloaded.
+    Download,
+    /// The compute node crashed more than it is restarted, or could not be brought back.
+    ComputeCrash,
+    /// Ollama is not available and could not be started.
+    OllamaUnavailable,
+    /// The launcher or the compute node could not be updated.
+    Update,
+    /// An interactive prompt was not answered within its timeout.
+    PromptTimeout,
+    /// The launcher was force-quit with a second termination signal during shutdown.
+    ForceQuit,
+}
+
+impl ExitReason {
+    /// Returns the process exit code of this failure class.
+    pub fn code(&self) -> u8 {
+        match self {
+            Self::Config => 10,
+            Self::Download => 11,
+            Self::ComputeCrash => 12,
+            Self::OllamaUnavailable => 13,
+            Self::Update => 14,
+            // as `EX_TEMPFAIL` within `sysexits.h`
+            Self::PromptTimeout => 75,
+            // as in `128 + SIGINT`
+            Self::ForceQuit => 130,
+        }
+    }
+}
+
+impl std::fmt::Display for ExitReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Config => write!(f, "configuration error"),
+            Self::Download => write!(f, "download failure"),
+            Self::ComputeCrash => write!(f, "compute node has crashed"),
+            Self::OllamaUnavailable => write!(f, "Ollama is unavailable"),
+            Self::Update => write!(f, "update failure"),
+            Self::PromptTimeout => write!(f, "prompt timed out"),
+            Self::ForceQuit => write!(f, "force-quit during shutdown"),
+        }
+    }
+}
+
+impl std::error::Error for ExitReason {}
+
+/// Returns the process exit code for the given error, w.r.t the [`ExitReason`] within its
+/// chain; errors without a failure class exit with `1`.
+pub fn exit_code(err: &eyre::Report) -> u8 {
+    err.downcast_ref::<ExitReason>()
+        .map(ExitReason::code)
+        .unwrap_or(GENERIC_EXIT_CODE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eyre::WrapErr;
+
+    #[test]
+    fn test_exit_code() {
+        // as the context of an error, even with further context on top
+        let err = Err::<(), _>(std::io::Error::other("invalid port"))
+            .wrap_err(ExitReason::Config)
+            .wrap_err("could not start")
+            .unwrap_err();
+        assert_eq!(exit_code(&err), 10);
+        assert!(format!("{err:?}").contains("invalid port"));
+
+        // as the error itself
+        assert_eq!(exit_code(&ExitReason::ComputeCrash.into()), 12);
+        assert_eq!(
+            exit_code(&eyre::eyre!("boom").wrap_err(ExitReason::Update)),
+            14
+        );
+        assert_eq!(exit_code(&eyre::eyre!("boom")), GENERIC_EXIT_CODE);
+    }
+}
@@ -0,0 +1,277 @@
+use eyre::{eyre, Context, Result};
+use self_update::update::{Release, ReleaseAsset};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::{shared_data_dir, DriaEnv, TracedSend, LAUNCHER_USER_AGENT};
+
+/// Base URL of the GitHub API.
+const GITHUB_API_URL: &str = "https://api.github.com";
+
+/// Timeout for a single GitHub API request.
+const GITHUB_API_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A GitHub API response that is cached on disk, along with its `ETag` so that it can be
+/// revalidated; a `304 Not Modified` response does not count against the rate limit.
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedResponse {
+    etag: Option<String>,
+    body: serde_json::Value,
+}
+
+/// Returns the directory where GitHub API responses are cached, which is shared by all profiles;
+/// `None` without a home directory, in which case nothing is cached.
+fn github_cache_dir() -> Option<PathBuf> {
+    shared_data_dir().map(|dir| dir.join("cache"))
+}
+
+/// Returns the cache file name of the given endpoint, e.g. `repos_firstbatchxyz_dkn-compute-node_releases.json`.
+fn github_cache_file(endpoint: &str) -> String {
+    format!("{}.json", endpoint.trim_matches('/').replace('/', "_"))
+}
+
+/// Fetches the given GitHub API endpoint (e.g. `repos/firstbatchxyz/dkn-compute-node/releases`),
+/// following the pages of list endpoints.
+///
+/// The requests are authenticated with `DKN_GITHUB_TOKEN` (or `GITHUB_TOKEN`) if it is set, and
+/// the responses are cached on disk & revalidated with their `ETag`, so that an unchanged response
+/// does not count against the rate limit. If GitHub can not be reached or the rate limit is
+/// exceeded, the cached response is returned as is.
+///
+/// ### Errors
+/// - If the request fails, and there is no cached response
+pub(crate) async fn fetch_github_api(endpoint: &str) -> Result<serde_json::Value> {
+    let cache_dir = github_cache_dir();
+    let cache_path = cache_dir
+        .as_ref()
+        .map(|dir| dir.join(github_cache_file(endpoint)));
+    let cached = cache_path
+        .as_ref()
+        .and_then(|path| std::fs::read(path).ok())
+        .and_then(|content| serde_json::from_slice::<CachedResponse>(&content).ok());
+
+    match fetch_github_api_with(endpoint, cached.as_ref()).await {
+        Ok(Some(response)) => {
+            if let (Some(cache_dir), Some(cache_path)) = (&cache_dir, &cache_path) {
+                if let Err(err) = std::fs::create_dir_all(cache_dir)
+                    .and_then(|_| std::fs::write(cache_path, serde_json::to_vec(&response)?))
+                {
+                    log::debug!("Could not cache {endpoint}: {err}");
+                }
+            }
+            Ok(response.body)
+        }
+        // not modified
+        Ok(None) => Ok(cached.expect("only revalidated if cached").body),
+        Err(err) => match cached {
+            Some(cached) => {
+                log::warn!("{err:#}, using the cached response instead.");
+                Ok(cached.body)
+            }
+            None => Err(err),
+        },
+    }
+}
+
+/// Fetches the given endpoint, revalidating the cached response if there is one; returns `None`
+/// if the cached response is not modified.
+async fn fetch_github_api_with(
+    endpoint: &str,
+    cached: Option<&CachedResponse>,
+) -> Result<Option<CachedResponse>> {
+    let token = DriaEnv::new_from_env().get_github_token();
+    let client = reqwest::Client::builder()
+        .user_agent(LAUNCHER_USER_AGENT)
+        .timeout(GITHUB_API_TIMEOUT)
+        .build()?;
+    let request = |url: &str| {
+        let request = client
+            .get(url)
+            .header(reqwest::header::ACCEPT, "application/vnd.github+json");
+        match &token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    };
+
+    let url = format!(
+        "{GITHUB_API_URL}/{}?per_page=100",
+        endpoint.trim_matches('/')
+    );
+    let mut first = request(&url);
+    if let Some(etag) = cached.and_then(|cached| cached.etag.as_deref()) {
+        first = first.header(reqwest::header::IF_NONE_MATCH, etag);
+    }
+    let response = first
+        .send_traced()
+        .await
+        .wrap_err_with(|| format!("could not reach GitHub for {endpoint}"))?;
+    if response.status() == reqwest::StatusCode::NOT_MODIFIED && cached.is_some() {
+        return Ok(None);
+    }
+    let etag = response
+        .headers()
+        .get(reqwest::header::ETAG)
+        .and_then(|etag| etag.to_str().ok())
+        .map(String::from);
+
+    let (mut body, mut next) = read_github_page(response, token.is_some()).await?;
+    while let Some(url) = next {
+        let (page, page_next) =
+            read_github_page(request(&url).send_traced().await?, token.is_some()).await?;
+        if let (Some(body), serde_json::Value::Array(page)) = (body.as_array_mut(), page) {
+            body.extend(page);
+        }
+        next = page_next;
+    }
+
+    Ok(Some(CachedResponse { etag, body }))
+}
+
+/// Reads a page of a GitHub API response, along with the URL of the next page if there is one.
+async fn read_github_page(
+    response: reqwest::Response,
+    has_token: bool,
+) -> Result<(serde_json::Value, Option<String>)> {
+    let status = response.status();
+    if !status.is_success() {
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value: &reqwest::header::HeaderValue| value.to_str().ok())
+        };
+        let is_rate_limited =
+            matches!(status.as_u16(), 403 | 429) && header("x-ratelimit-remaining") == Some("0");
+        if is_rate_limited {
+            let reset_mins = header("x-ratelimit-reset")
+                .and_then(|reset| reset.parse::<u64>().ok())
+                .map(|reset| {
+                    let now = SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .map(|now| now.as_secs())
+                        .unwrap_or_default();
+                    reset.saturating_sub(now).div_ceil(60)
+                });
+            let hint = if has_token {
+                ""
+            } else {
+                ", set DKN_GITHUB_TOKEN to raise the limit"
+            };
+            return Err(match reset_mins {
+                Some(mins) => {
+                    eyre!("GitHub API rate limit exceeded, resets in {mins} minutes{hint}")
+                }
+                None => eyre!("GitHub API rate limit exceeded{hint}"),
+            });
+        }
+
+        eyre::bail!("GitHub API request failed with status {status}");
+    }
+
+    let next = response
+        .headers()
+        .get_all(reqwest::header::LINK)
+        .iter()
+        .filter_map(|link| link.to_str().ok())
+        .find_map(find_next_link)
+        .map(String::from);
+
+    Ok((response.json().await?, next))
+}
+
+/// Returns the URL of the next page within a `Link` header like
+/// `<https://api.github.com/resource?page=2>; rel="next", <...>; rel="last"`.
+fn find_next_link(link: &str) -> Option<&str> {
+    link.split(',').find_map(|part| {
+        let (url, rel) = part.split_once(';')?;
+        (rel.trim() == r#"rel="next""#)
+            .then(|| url.trim().trim_start_matches('<').trim_end_matches('>'))
+    })
+}
+
+/// Authenticates the given request with the GitHub token if it targets the GitHub API, e.g. to
+/// download a release asset by its API URL.
+pub(crate) fn with_github_auth(
+    request: reqwest::RequestBuilder,
+    url: &str,
+) -> reqwest::RequestBuilder {
+    match DriaEnv::new_from_env().get_github_token() {
+        Some(token) if url.starts_with(GITHUB_API_URL) => request.bearer_auth(token),
+        _ => request,
+    }
+}
+
+/// Parses a release object of the GitHub API.
+///
+/// ### Errors
+/// - If the release is missing its tag, creation date or assets
+pub(crate) fn parse_github_release(release: &serde_json::Value) -> Result<Release> {
+    let tag = release["tag_name"]
+        .as_str()
+        .ok_or_else(|| eyre!("release is missing `tag_name`"))?;
+    let date = release["created_at"]
+        .as_str()
+        .ok_or_else(|| eyre!("release {tag} is missing `created_at`"))?;
+    let assets = release["assets"]
+        .as_array()
+        .ok_or_else(|| eyre!("release {tag} has no assets"))?
+        .iter()
+        .map(|asset| {
+            Some(ReleaseAsset {
+                // the API URL of the asset, which is downloaded with `Accept: application/octet-stream`
+                download_url: asset["url"].as_str()?.to_string(),
+                name: asset["name"].as_str()?.to_string(),
+            })
+        })
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| eyre!("release {tag} has an invalid asset"))?;
+
+    Ok(Release {
+        name: release["name"].as_str().unwrap_or(tag).to_string(),
+        version: tag.trim_start_matches('v').to_string(),
+        date: date.to_string(),
+        body: release["body"].as_str().map(String::from),
+        assets,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_github_release() {
+        let release = parse_github_release(&serde_json::json!({
+            "tag_name": "v0.6.6",
+            "name": null,
+            "created_at": "2025-01-01T00:00:00Z",
+            "body": "changes",
+            "assets": [{
+                "url": "https://api.github.com/repos/firstbatchxyz/dkn-compute-node/releases/assets/1",
+                "name": "dkn-compute-binary-linux-amd64",
+            }],
+        }))
+        .unwrap();
+        assert_eq!(release.version, "0.6.6");
+        assert_eq!(release.name, "v0.6.6");
+        assert_eq!(release.assets[0].name, "dkn-compute-binary-linux-amd64");
+        assert!(parse_github_release(&serde_json::json!({ "tag_name": "v0.6.6" })).is_err());
+
+        assert_eq!(
+            find_next_link(
+                r#"<https://api.github.com/x?page=2>; rel="next", <https://api.github.com/x?page=5>; rel="last""#
+            ),
+            Some("https://api.github.com/x?page=2")
+        );
+        assert_eq!(
+            find_next_link(r#"<https://api.github.com/x?page=1>; rel="prev""#),
+            None
+        );
+        assert_eq!(
+            github_cache_file("repos/firstbatchxyz/dkn-compute-node/releases"),
+            "repos_firstbatchxyz_dkn-compute-node_releases.json"
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use dkn_executor::{Model, ModelProvider};
+use sha2::{Digest, Sha256};
+use std::process::Command;
+use sysinfo::{CpuRefreshKind, System};
+
+/// Approximate memory used per billion parameters of a 4-bit quantized model, in MB.
+const MODEL_MB_PER_BILLION_PARAMS: f64 = 700.0;
+/// Approximate memory used by a loaded model regardless of its size (context, runtime), in MB.
+const MODEL_BASE_MB: f64 = 1024.0;
+
+/// A GPU of this machine.
+#[derive(Debug, Clone)]
+pub struct GpuInfo {
+    pub name: String,
+    /// Dedicated memory in MB.
+    pub memory_mb: u64,
+}
+
+/// Hardware of this machine, w.r.t running models.
+#[derive(Debug, Clone)]
+pub struct HardwareInfo {
+    pub cpu_name: String,
+    pub cpu_cores: usize,
+    /// Total system memory in MB.
+    pub memory_mb: u64,
+    /// NVIDIA GPUs, found via `nvidia-smi`.
+    pub gpus: Vec<GpuInfo>,
+}
+
+impl HardwareInfo {
+    /// Detects the hardware of this machine, GPUs are only detected if `nvidia-smi` is available.
+    pub fn detect() -> Self {
+        let mut system = System::new();
+        system.refresh_memory();
+        system.refresh_cpu_list(CpuRefreshKind::nothing());
+
+        Self {
+            cpu_name: system
+                .cpus()
+                .first()
+                .map(|cpu| cpu.brand().trim().to_string())
+                .unwrap_or_else(|| "unknown CPU".to_string()),
+            cpu_cores: system.cpus().len(),
+            memory_mb: system.total_memory() / (1024 * 1024),
+            gpus: detect_nvidia_gpus(),
+        }
+    }
+
+    /// Returns the memory available to models in MB: the largest GPU memory if there is a GPU,
+    /// and the system memory otherwise (e.g. CPU inference, or unified memory on Apple Silicon).
+    pub fn model_memory_mb(&self) -> u64 {
+        self.gpus
+            .iter()
+            .map(|gpu| gpu.memory_mb)
+            .max()
+            .unwrap_or(self.memory_mb)
+    }
+
+    /// Returns the Ollama models that fit in the memory available to models, largest first.
+    pub fn recommend_models(&self) -> Vec<Model> {
+        recommend_models(self.model_memory_mb())
+    }
+
+    /// Returns a short fingerprint of the hardware, which changes when the CPU, memory or GPUs
+    /// change; memory is rounded to GBs as the reported total may vary slightly between boots.
+    pub fn fingerprint(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(format!(
+            "{}|{}|{}",
+            self.cpu_name,
+            self.cpu_cores,
+            self.memory_mb / 1024
+        ));
+        for gpu in &self.gpus {
+            hasher.update(format!("|{}|{}", gpu.name, gpu.memory_mb / 1024));
+        }
+
+        hex::encode(&hasher.finalize()[..8])
+    }
+}
+
+impl std::fmt::Display for HardwareInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({} cores), {:.1} GB RAM",
+            self.cpu_name,
+            self.cpu_cores,
+            self.memory_mb as f64 / 1024.0
+        )?;
+        for gpu in &self.gpus {
+            write!(
+                f,
+                ", {} ({:.1} GB)",
+                gpu.name,
+                gpu.memory_mb as f64 / 1024.0
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the NVIDIA GPUs reported by `nvidia-smi`, empty if it is not available.
+fn detect_nvidia_gpus() -> Vec<GpuInfo> {
+    let Ok(output) = Command::new("nvidia-smi")
+        .args([
+            "--query-gpu=name,memory.total",
+            "--format=csv,noheader,nounits",
+        ])
+        .output()
+    else {
+        return Vec::new();
+    };
+    if !output.status.success() {
+        return Vec::new();
+    }
+
+    parse_nvidia_smi(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Parses the `name, memory` lines of `nvidia-smi`, where memory is in MB.
+fn parse_nvidia_smi(output: &str) -> Vec<GpuInfo> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (name, memory) = line.rsplit_once(',')?;
+            Some(GpuInfo {
+                name: name.trim().to_string(),
+                memory_mb: memory.trim().parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+/// Estimates the memory that an Ollama model needs in MB, from the parameter size within its
+/// name such as `llama3.1:8b`; `None` if the name has no parameter size.
+pub fn estimate_model_memory_mb(model_name: &str) -> Option<u64> {
+    let (_, tag) = model_name.split_once(':')?;
+    let params = tag
+        .split(['-', '_'])
+        .find_map(|part| part.strip_suffix('b')?.parse::<f64>().ok())?;
+
+    Some((params * MODEL_MB_PER_BILLION_PARAMS + MODEL_BASE_MB) as u64)
+}
+
+/// Returns the Ollama models that fit in the given memory, largest first.
+fn recommend_models(memory_mb: u64) -> Vec<Model> {
+    let mut models = Model::all_with_provider(&ModelProvider::Ollama)
+        .filter_map(|model| {
+            let needed = estimate_model_memory_mb(&model.to_string())?;
+            (needed <= memory_mb).then_some((model, needed))
+        })
+        .collect::<Vec<_>>();
+    models.sort_by_key(|(_, needed)| std::cmp::Reverse(*needed));
+
+    models.into_iter().map(|(model, _)| model).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_model_memory() {
+        assert_eq!(estimate_model_memory_mb("llama3.1:8b"), Some(6624));
+        assert_eq!(estimate_model_memory_mb("phi3.5:3.8b-mini"), Some(3684));
+        assert_eq!(estimate_model_memory_mb("gpt-4o"), None);
+
+        let gpus = parse_nvidia_smi("NVIDIA GeForce RTX 3090, 24576\ninvalid\n");
+        assert_eq!(gpus.len(), 1);
+        assert_eq!(gpus[0].memory_mb, 24576);
+    }
+}
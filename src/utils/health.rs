@@ -0,0 +1,271 @@
+use eyre::{Context, Result};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+
+use super::{
+    check_ollama, get_points_api_url, ComputeOutput, DriaEnv, TracedSend, LAUNCHER_USER_AGENT,
+};
+
+/// Maximum number of bytes read from an incoming request, we only care about the request line.
+const MAX_REQUEST_SIZE: usize = 8 * 1024;
+/// Timeout for the Dria API reachability check.
+const API_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A check that must pass for the node to be considered healthy, i.e. ready to serve tasks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ReadyCheck {
+    /// The compute node process is running.
+    Compute,
+    /// Ollama is reachable, only if Ollama models are used.
+    Ollama,
+    /// The Dria points API is reachable.
+    Api,
+    /// The compute node has at least the configured number of peers.
+    Peers,
+}
+
+impl ReadyCheck {
+    /// Parses a comma-separated list of checks, e.g. `compute,ollama,api,peers`.
+    pub fn parse_list(checks: &str) -> Result<BTreeSet<Self>> {
+        checks
+            .split(',')
+            .map(str::trim)
+            .filter(|check| !check.is_empty())
+            .map(|check| match check.to_lowercase().as_str() {
+                "compute" => Ok(Self::Compute),
+                "ollama" => Ok(Self::Ollama),
+                "api" => Ok(Self::Api),
+                "peers" => Ok(Self::Peers),
+                _ => Err(eyre::eyre!(
+                    "unknown check '{check}', expected one of: compute, ollama, api, peers"
+                )),
+            })
+            .collect()
+    }
+}
+
+/// Shared health state between the supervision loop and the health server.
+#[derive(Debug)]
+pub struct HealthState {
+    /// Whether the compute node process is alive, updated by the supervision loop.
+    compute_alive: AtomicBool,
+    /// The environment to check Ollama with, only given if Ollama is required.
+    ollama_env: Option<DriaEnv>,
+    /// Name of the node, included in the report.
+    name: Option<String>,
+    /// Labels of the node, included in the report.
+    labels: BTreeMap<String, String>,
+    /// Checks that must pass for the node to be healthy.
+    checks: BTreeSet<ReadyCheck>,
+    /// Minimum number of peers for the [`ReadyCheck::Peers`] check.
+    min_peers: usize,
+    /// Points API URL of this node, used for the [`ReadyCheck::Api`] check.
+    points_url: Option<String>,
+    /// Captured compute node output, used for the peer count.
+    pub output: ComputeOutput,
+    /// Notified when a restart of the compute node is requested, see [`HealthState::request_restart`].
+    restart: Notify,
+}
+
+impl HealthState {
+    /// Creates a new health state, the compute node is assumed to be alive at first.
+    ///
+    /// The node name, labels and the readiness checks are read from the given environment.
+    pub fn new(dria_env: &DriaEnv, ollama_required: bool) -> Self {
+        Self {
+            compute_alive: AtomicBool::new(true),
+            ollama_env: ollama_required.then(|| dria_env.clone()),
+            name: dria_env.get_node_name().map(String::from),
+            labels: dria_env.get_labels(),
+            checks: dria_env.get_ready_checks(),
+            min_peers: dria_env.get_ready_min_peers(),
+            points_url: dria_env
+                .get_account()
+                .ok()
+                .map(|(_, _, address)| get_points_api_url(&address)),
+            output: ComputeOutput::default(),
+            restart: Notify::new(),
+        }
+    }
+
+    /// Asks the supervision loop to restart the compute node, e.g. from the management API.
+    #[inline]
+    pub fn request_restart(&self) {
+        self.restart.notify_one();
+    }
+
+    /// Waits until a restart of the compute node is requested.
+    pub async fn restart_requested(&self) {
+        self.restart.notified().await;
+    }
+
+    /// Marks the compute node process as alive or not.
+    #[inline]
+    pub fn set_compute_alive(&self, alive: bool) {
+        self.compute_alive.store(alive, Ordering::Relaxed);
+    }
+
+    /// Returns whether the compute node process is alive.
+    #[inline]
+    pub fn is_compute_alive(&self) -> bool {
+        self.compute_alive.load(Ordering::Relaxed)
+    }
+
+    /// Runs the health checks, returns `true` if all configured checks pass along with a JSON report.
+    ///
+    /// Checks that require a network request are only run if they are configured.
+    pub async fn check(&self) -> (bool, serde_json::Value) {
+        let compute = self.is_compute_alive();
+        let ollama = match &self.ollama_env {
+            Some(dria_env) => Some(check_ollama(dria_env).await),
+            None => None, // not required
+        };
+        let api = match self.checks.contains(&ReadyCheck::Api) {
+            true => Some(self.check_api().await),
+            false => None,
+        };
+        let stats = self.output.stats();
+        let peers = stats.peer_count;
+
+        let healthy = self.checks.iter().all(|check| match check {
+            ReadyCheck::Compute => compute,
+            ReadyCheck::Ollama => ollama.unwrap_or(true),
+            ReadyCheck::Api => api.unwrap_or(false),
+            ReadyCheck::Peers => peers.is_some_and(|peers| peers >= self.min_peers),
+        });
+        let report = serde_json::json!({
+            "healthy": healthy,
+            "compute": compute,
+            "ollama": ollama,
+            "api": api,
+            "peers": peers,
+            "tasks_completed": stats.tasks_completed,
+            "errors": stats.errors,
+            "checks": self.checks.iter().map(|c| format!("{c:?}").to_lowercase()).collect::<Vec<_>>(),
+            "name": self.name,
+            "labels": self.labels,
+            "tps": stats.tps.emas(),
+        });
+
+        (healthy, report)
+    }
+
+    /// Returns `true` if the points API responds successfully for this node.
+    async fn check_api(&self) -> bool {
+        let Some(url) = &self.points_url else {
+            return false;
+        };
+
+        let Ok(client) = reqwest::Client::builder()
+            .user_agent(LAUNCHER_USER_AGENT)
+            .timeout(API_CHECK_TIMEOUT)
+            .build()
+        else {
+            return false;
+        };
+
+        client
+            .get(url)
+            .send_traced()
+            .await
+            .is_ok_and(|res| res.status().is_success())
+    }
+}
+
+/// Serves the health-check endpoint at the given port, until the cancellation token is cancelled.
+///
+/// - `GET /healthz` returns `200` if all configured [`ReadyCheck`]s pass, `503` otherwise.
+/// - Any other path returns `404`.
+///
+/// The body is a small JSON report of the individual checks.
+pub async fn serve_health(
+    port: u16,
+    state: Arc<HealthState>,
+    cancellation: CancellationToken,
+) -> Result<()> {
+    let listener = TcpListener::bind(("0.0.0.0", port))
+        .await
+        .wrap_err_with(|| format!("could not bind health server to port {port}"))?;
+    log::info!("Serving health-check at http://0.0.0.0:{port}/healthz");
+
+    loop {
+        tokio::select! {
+            _ = cancellation.cancelled() => break,
+            accepted = listener.accept() => {
+                let (stream, _) = match accepted {
+                    Ok(accepted) => accepted,
+                    Err(err) => {
+                        log::warn!("Could not accept health-check connection: {err}");
+                        continue;
+                    }
+                };
+
+                let state = state.clone();
+                tokio::spawn(async move {
+                    if let Err(err) = handle_health_request(stream, &state).await {
+                        log::debug!("Health-check request failed: {err}");
+                    }
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn handle_health_request(mut stream: TcpStream, state: &HealthState) -> Result<()> {
+    let mut buf = vec![0u8; MAX_REQUEST_SIZE];
+    let len = stream.read(&mut buf).await?;
+    let request = String::from_utf8_lossy(&buf[..len]);
+
+    // request line is like `GET /healthz HTTP/1.1`
+    let mut request_line = request.lines().next().unwrap_or_default().split(' ');
+    let method = request_line.next().unwrap_or_default();
+    let path = request_line.next().unwrap_or_default();
+
+    let (status, body) = match (method, path) {
+        ("GET", "/healthz") => {
+            let (healthy, report) = state.check().await;
+            let status = if healthy {
+                "200 OK"
+            } else {
+                "503 Service Unavailable"
+            };
+            (status, report.to_string())
+        }
+        _ => ("404 Not Found", r#"{"error":"not found"}"#.to_string()),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ready_checks() {
+        let checks = ReadyCheck::parse_list("compute, API,peers,").unwrap();
+        assert_eq!(
+            checks.into_iter().collect::<Vec<_>>(),
+            vec![ReadyCheck::Compute, ReadyCheck::Api, ReadyCheck::Peers]
+        );
+
+        assert!(ReadyCheck::parse_list("compute,gpu").is_err());
+    }
+}
@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+
+use super::DriaEnv;
+
+/// Languages of the interactive prompts, their help messages and the key log lines.
+///
+/// Messages are looked up in a catalog per language, where the English message is the key as in
+/// `gettext`; a message that is not translated (yet) is shown in English. Catalogs are the `.po`
+/// files under `src/locales`, and are embedded into the binary.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, enum_iterator::Sequence)]
+pub enum Language {
+    #[default]
+    English,
+    Spanish,
+    Turkish,
+    Chinese,
+}
+
+/// Language of the launcher, see [`set_language`].
+static LANGUAGE: RwLock<Language> = RwLock::new(Language::English);
+
+impl Language {
+    /// Returns the code of the language, as used by `DKN_LANG`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::English => "en",
+            Self::Spanish => "es",
+            Self::Turkish => "tr",
+            Self::Chinese => "zh",
+        }
+    }
+
+    /// Parses a language code (e.g. `es`) or a locale (e.g. `es_ES.UTF-8` or `zh-CN`), returns
+    /// `None` if the language is not supported.
+    pub fn from_code(code: &str) -> Option<Self> {
+        let code = code
+            .trim()
+            .split(['_', '-', '.', '@'])
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+
+        enum_iterator::all::<Self>().find(|language| language.code() == code)
+    }
+
+    /// Returns the language of the launcher w.r.t `DKN_LANG`, or the locale of the system
+    /// (`LC_ALL`, `LC_MESSAGES` and `LANG` in order) if it is not set; English by default.
+    pub fn from_env(dria_env: &DriaEnv) -> Self {
+        if let Some(code) = dria_env
+            .get(DriaEnv::DKN_LANG_KEY)
+            .filter(|code| !code.trim().is_empty())
+        {
+            return Self::from_code(code).unwrap_or_else(|| {
+                log::warn!("Unknown language {code}, using English.");
+                Self::English
+            });
+        }
+
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .into_iter()
+            .filter_map(|key| std::env::var(key).ok())
+            .find(|locale| !locale.is_empty())
+            .and_then(|locale| Self::from_code(&locale))
+            .unwrap_or_default()
+    }
+
+    /// Returns the catalog of the language, `None` for English.
+    fn catalog(&self) -> Option<&'static str> {
+        match self {
+            Self::English => None,
+            Self::Spanish => Some(include_str!("../locales/es.po")),
+            Self::Turkish => Some(include_str!("../locales/tr.po")),
+            Self::Chinese => Some(include_str!("../locales/zh.po")),
+        }
+    }
+}
+
+impl std::fmt::Display for Language {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::English => write!(f, "English"),
+            Self::Spanish => write!(f, "Español"),
+            Self::Turkish => write!(f, "Türkçe"),
+            Self::Chinese => write!(f, "中文 (简体)"),
+        }
+    }
+}
+
+/// Sets the language of the messages for the rest of the launcher.
+pub fn set_language(language: Language) {
+    *LANGUAGE.write().unwrap_or_else(|err| err.into_inner()) = language;
+}
+
+/// Returns the language of the messages.
+pub fn language() -> Language {
+    *LANGUAGE.read().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Returns the given English message in the language of the launcher, see [`tr`](crate::tr).
+pub fn translate(msgid: &'static str) -> &'static str {
+    static CATALOGS: OnceLock<HashMap<Language, HashMap<String, String>>> = OnceLock::new();
+
+    let language = language();
+    if language == Language::English {
+        return msgid;
+    }
+
+    CATALOGS
+        .get_or_init(|| {
+            enum_iterator::all::<Language>()
+                .filter_map(|language| Some((language, parse_catalog(language.catalog()?))))
+                .collect()
+        })
+        .get(&language)
+        .and_then(|catalog| catalog.get(msgid))
+        .map(String::as_str)
+        .unwrap_or(msgid)
+}
+
+/// Returns the given English message in the language of the launcher, with its `{name}`
+/// placeholders replaced by the given values, see [`tr`](crate::tr).
+pub fn translate_with(msgid: &'static str, args: &[(&str, &dyn std::fmt::Display)]) -> String {
+    args.iter()
+        .fold(translate(msgid).to_string(), |message, (name, value)| {
+            message.replace(&format!("{{{name}}}"), &value.to_string())
+        })
+}
+
+/// Translates an English message literal to the language of the launcher, with optional named
+/// placeholders, e.g. `tr!("Remove {models}?", models = names.join(", "))`.
+///
+/// Returns a `&'static str` without placeholders, and a `String` otherwise.
+#[macro_export]
+macro_rules! tr {
+    ($msgid:literal $(,)?) => {
+        $crate::utils::translate($msgid)
+    };
+    ($msgid:literal, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::utils::translate_with(
+            $msgid,
+            &[$((stringify!($name), &$value as &dyn std::fmt::Display)),+],
+        )
+    };
+}
+
+/// Parses a catalog in the `.po` format, with single-line `msgid` & `msgstr` pairs; messages with
+/// an empty `msgstr` are not translated.
+fn parse_catalog(content: &str) -> HashMap<String, String> {
+    let mut catalog = HashMap::new();
+    let mut msgid = None;
+    for line in content.lines().map(str::trim) {
+        if let Some(value) = line.strip_prefix("msgid ") {
+            msgid = Some(unquote(value));
+        } else if let Some(value) = line.strip_prefix("msgstr ") {
+            let msgstr = unquote(value);
+            if let Some(msgid) = msgid.take().filter(|_| !msgstr.is_empty()) {
+                catalog.insert(msgid, msgstr);
+            }
+        }
+    }
+
+    catalog
+}
+
+/// Removes the quotes around a `.po` string, and unescapes it.
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    let value = value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value);
+
+    let mut unquoted = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unquoted.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => unquoted.push('\n'),
+            Some('t') => unquoted.push('\t'),
+            Some(c) => unquoted.push(c),
+            None => unquoted.push('\\'),
+        }
+    }
+
+    unquoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the `{name}` placeholders within the message, sorted.
+    fn placeholders(message: &str) -> Vec<&str> {
+        let mut names = message
+            .split('{')
+            .skip(1)
+            .filter_map(|part| part.split_once('}').map(|(name, _)| name))
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_languages() {
+        assert_eq!(Language::from_code("es"), Some(Language::Spanish));
+        assert_eq!(Language::from_code("tr_TR.UTF-8"), Some(Language::Turkish));
+        assert_eq!(Language::from_code("zh-CN"), Some(Language::Chinese));
+        assert_eq!(Language::from_code("EN"), Some(Language::English));
+        assert_eq!(Language::from_code("C.UTF-8"), None);
+
+        let catalog = parse_catalog(
+            "# comment\nmsgid \"Say \\\"hi\\\"\"\nmsgstr \"Di \\\"hola\\\"\"\n\nmsgid \"Untranslated\"\nmsgstr \"\"\n",
+        );
+        assert_eq!(catalog.len(), 1);
+        assert_eq!(catalog["Say \"hi\""], "Di \"hola\"");
+
+        // translations must keep the placeholders of their messages
+        for language in enum_iterator::all::<Language>() {
+            let Some(content) = language.catalog() else {
+                continue;
+            };
+            let catalog = parse_catalog(content);
+            assert!(!catalog.is_empty(), "{language} catalog is empty");
+            for (msgid, msgstr) in &catalog {
+                assert_eq!(
+                    placeholders(msgid),
+                    placeholders(msgstr),
+                    "{language} translation of {msgid:?}"
+                );
+            }
+        }
+    }
+}
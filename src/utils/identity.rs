@@ -0,0 +1,174 @@
+use eyre::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::crypto::secret_key_to_account;
+use super::{parse_version, DKN_LAUNCHER_VERSION};
+
+/// Format of the identity bundles written by this launcher, bumped on breaking changes.
+const IDENTITY_FORMAT: u32 = 1;
+
+/// A node identity that is moved between machines with `identity export` & `identity import`.
+///
+/// The referral state belongs to the address within the referrals API, so it moves along with the
+/// wallet; it is recorded here to be shown on import.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeIdentity {
+    pub format: u32,
+    /// Launcher version that exported the identity.
+    pub launcher_version: String,
+    /// Compute node version that was used with the identity, if known.
+    pub compute_version: Option<String>,
+    /// Time of the export, in seconds since the Unix epoch.
+    pub exported_at: u64,
+    /// The address of the wallet, in lowercase hexadecimal without `0x`.
+    pub address: String,
+    /// The secret key of the wallet, in hexadecimal.
+    pub secret_key: String,
+    pub network: Option<String>,
+    pub node_name: Option<String>,
+    /// The address that referred this identity, if any.
+    pub referred_by: Option<String>,
+    /// The $DRIA points of the identity at the time of the export, if they could be fetched.
+    pub points: Option<f64>,
+}
+
+impl NodeIdentity {
+    /// Creates an identity from the given secret key, exported now by this launcher.
+    ///
+    /// ### Errors
+    /// - If the secret key is invalid
+    pub fn new(secret_key: &str) -> Result<Self> {
+        let (_, _, address) = secret_key_to_account(secret_key)?;
+        Ok(Self {
+            format: IDENTITY_FORMAT,
+            launcher_version: DKN_LAUNCHER_VERSION.to_string(),
+            compute_version: None,
+            exported_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|time| time.as_secs())
+                .unwrap_or_default(),
+            address,
+            secret_key: secret_key.to_string(),
+            network: None,
+            node_name: None,
+            referred_by: None,
+            points: None,
+        })
+    }
+
+    /// Reads & verifies the identity at the given path.
+    ///
+    /// ### Errors
+    /// - If the file could not be read or parsed
+    /// - If the identity is invalid, see [`NodeIdentity::verify`]
+    pub fn load(path: &Path) -> Result<Self> {
+        let content =
+            std::fs::read(path).wrap_err_with(|| format!("could not read {}", path.display()))?;
+        let identity: Self = serde_json::from_slice(&content)
+            .wrap_err_with(|| format!("could not parse {}", path.display()))?;
+        identity.verify()?;
+
+        Ok(identity)
+    }
+
+    /// Writes the identity to the given path, which must not exist; it is readable only by the
+    /// owner on Unix as it holds the secret key.
+    ///
+    /// ### Errors
+    /// - If the file already exists or could not be written
+    pub fn save(&self, path: &Path) -> Result<()> {
+        use std::io::Write;
+
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        options
+            .open(path)
+            .and_then(|mut file| file.write_all(&serde_json::to_vec_pretty(self)?))
+            .wrap_err_with(|| format!("could not write {}", path.display()))
+    }
+
+    /// Verifies that the identity can be imported by this launcher.
+    ///
+    /// ### Errors
+    /// - If it is written in a newer format
+    /// - If its secret key is invalid, or does not belong to its address
+    pub fn verify(&self) -> Result<()> {
+        if self.format > IDENTITY_FORMAT {
+            eyre::bail!(
+                "identity was exported by a newer launcher (v{}), please update the launcher first",
+                self.launcher_version
+            );
+        }
+        let (_, _, address) = secret_key_to_account(&self.secret_key)?;
+        if address != self.address.trim_start_matches("0x").to_lowercase() {
+            eyre::bail!(
+                "identity is corrupted, its secret key does not belong to 0x{}",
+                self.address
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Returns `true` if the identity was exported by a newer launcher than this one, in which
+    /// case some of its settings may not be known to this launcher.
+    pub fn is_from_newer_launcher(&self) -> bool {
+        matches!(
+            (parse_version(&self.launcher_version), parse_version(DKN_LAUNCHER_VERSION)),
+            (Some(exported), Some(current)) if exported > current
+        )
+    }
+
+    /// Returns the points earned since the export w.r.t the `current` points, which means that
+    /// the identity is still running elsewhere; `None` if it has not earned any or the points at
+    /// the export are not known.
+    pub fn points_earned_since_export(&self, current: f64) -> Option<f64> {
+        let earned = current - self.points?;
+        (earned > 0.0).then_some(earned)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_node_identity() {
+        let secret_key = "0x6472696164726961647269616472696164726961647269616472696164726961";
+        let mut identity = NodeIdentity::new(secret_key).unwrap();
+        identity.verify().unwrap();
+        assert!(!identity.is_from_newer_launcher());
+
+        let path = std::env::temp_dir().join(format!(
+            "dkn-identity-{}-{}.json",
+            std::process::id(),
+            line!()
+        ));
+        identity.save(&path).unwrap();
+        assert!(identity.save(&path).is_err(), "must not overwrite");
+        assert_eq!(NodeIdentity::load(&path).unwrap(), identity);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(identity.points_earned_since_export(10.0), None);
+        identity.points = Some(10.0);
+        assert_eq!(identity.points_earned_since_export(10.0), None);
+        assert_eq!(identity.points_earned_since_export(12.5), Some(2.5));
+
+        let mut newer = identity.clone();
+        newer.launcher_version = "999.0.0".to_string();
+        assert!(newer.is_from_newer_launcher());
+        newer.format = IDENTITY_FORMAT + 1;
+        assert!(newer.verify().is_err());
+
+        let mut corrupted = identity.clone();
+        corrupted.address = "00".repeat(20);
+        assert!(corrupted.verify().is_err());
+    }
+}
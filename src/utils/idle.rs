@@ -0,0 +1,233 @@
+use eyre::Result;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+use super::output::ComputeStats;
+use super::{get_running_models, ollama_http_client, ComputeOutput, DriaEnv, TracedSend};
+
+/// Interval between idle checks.
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// What to do with the Ollama models after an idle check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IdleAction {
+    /// Unload the models, the node has been idle for long enough.
+    Unload,
+    /// Load the models again, the node has received tasks after being unloaded.
+    Reload,
+}
+
+/// Tracks the activity of the compute node, and decides when to unload & reload the Ollama models.
+#[derive(Debug)]
+struct IdleState {
+    idle_after: Duration,
+    started: Instant,
+    /// Number of completed tasks when the models were unloaded, `None` if they are loaded.
+    unloaded_at: Option<u64>,
+}
+
+impl IdleState {
+    fn new(idle_after: Duration, started: Instant) -> Self {
+        Self {
+            idle_after,
+            started,
+            unloaded_at: None,
+        }
+    }
+
+    /// Returns the action to take w.r.t the given statistics, and updates the state accordingly.
+    fn next_action(&mut self, stats: &ComputeStats, now: Instant) -> Option<IdleAction> {
+        match self.unloaded_at {
+            None => {
+                let last_activity = stats.last_task.unwrap_or(self.started).max(self.started);
+                if now.duration_since(last_activity) >= self.idle_after {
+                    self.unloaded_at = Some(stats.tasks_completed);
+                    Some(IdleAction::Unload)
+                } else {
+                    None
+                }
+            }
+            Some(tasks_completed) if stats.tasks_completed > tasks_completed => {
+                self.unloaded_at = None;
+                Some(IdleAction::Reload)
+            }
+            Some(_) => None,
+        }
+    }
+
+    /// Marks the node as active at the given time, so that the idle period restarts.
+    fn mark_active(&mut self, now: Instant) {
+        self.started = now;
+        self.unloaded_at = None;
+    }
+}
+
+/// Returns the models that Ollama has served a request for in between the given expiries of the
+/// loaded models, see [`get_running_models`]; Ollama refreshes the expiry with each request.
+fn served_since<'a>(
+    models: &'a [String],
+    previous: &HashMap<String, String>,
+    current: &HashMap<String, String>,
+) -> Vec<&'a str> {
+    models
+        .iter()
+        .filter(|model| {
+            current
+                .get(*model)
+                .is_some_and(|expiry| previous.get(*model) != Some(expiry))
+        })
+        .map(String::as_str)
+        .collect()
+}
+
+/// Unloads the given Ollama models when the compute node has not completed any tasks for the
+/// given duration, to free memory on idle; and loads them again once the node becomes active.
+///
+/// The compute node may have received a task that it has not completed yet, so the models are
+/// not unloaded if Ollama has served any of them since the previous check (w.r.t `/api/ps`);
+/// the idle period restarts instead.
+///
+/// Ollama loads a model by itself on the next request as well, reloading ahead of time only
+/// saves that delay for the following tasks.
+pub async fn run_idle_unloader(
+    dria_env: DriaEnv,
+    models: Vec<String>,
+    idle_after: Duration,
+    output: ComputeOutput,
+    cancellation: CancellationToken,
+) {
+    log::info!(
+        "Ollama models will be unloaded after {} minutes without tasks.",
+        idle_after.as_secs() / 60
+    );
+
+    let mut state = IdleState::new(idle_after, Instant::now());
+    let mut expiries = HashMap::new();
+    let mut interval = tokio::time::interval(IDLE_CHECK_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = cancellation.cancelled() => break,
+            _ = interval.tick() => {
+                let previous = std::mem::take(&mut expiries);
+                let current = get_running_models(&dria_env).await;
+                if let Ok(current) = &current {
+                    expiries = current.clone();
+                }
+
+                let now = Instant::now();
+                match state.next_action(&output.stats(), now) {
+                    Some(IdleAction::Unload) => {
+                        let current = match current {
+                            Ok(current) => current,
+                            Err(err) => {
+                                log::warn!("Could not get running Ollama models: {err}");
+                                state.mark_active(now);
+                                continue;
+                            }
+                        };
+                        let served = served_since(&models, &previous, &current);
+                        if !served.is_empty() {
+                            log::debug!("Ollama has served {} since the last check, not unloading.", served.join(", "));
+                            state.mark_active(now);
+                            continue;
+                        }
+
+                        log::info!("Node is idle, unloading Ollama models to free memory.");
+                        for model in models.iter().filter(|m| current.contains_key(*m)) {
+                            if let Err(err) = set_model_loaded(&dria_env, model, false).await {
+                                log::warn!("Could not unload {model}: {err}");
+                            }
+                        }
+                    }
+                    Some(IdleAction::Reload) => {
+                        log::info!("Node is active again, loading Ollama models.");
+                        for model in &models {
+                            if let Err(err) = set_model_loaded(&dria_env, model, true).await {
+                                log::warn!("Could not load {model}: {err}");
+                            }
+                        }
+                    }
+                    None => {}
+                }
+            }
+        }
+    }
+}
+
+/// Loads or unloads the given model, via an empty generation request to Ollama.
+///
+/// Loaded models use the keep-alive of the Ollama server, see `OLLAMA_KEEP_ALIVE`.
+async fn set_model_loaded(dria_env: &DriaEnv, model: &str, loaded: bool) -> Result<()> {
+    let mut body = serde_json::json!({ "model": model });
+    if !loaded {
+        body["keep_alive"] = 0.into();
+    }
+
+    let (host, port) = dria_env.get_ollama_config();
+    ollama_http_client(dria_env)?
+        .post(format!("{host}:{port}/api/generate"))
+        .json(&body)
+        .send_traced()
+        .await?
+        .error_for_status()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_idle_actions() {
+        let started = Instant::now();
+        let idle_after = Duration::from_secs(600);
+        let mut state = IdleState::new(idle_after, started);
+        let mut stats = ComputeStats::default();
+
+        // not idle for long enough yet
+        assert_eq!(state.next_action(&stats, started + idle_after / 2), None);
+
+        // idle since start, unloaded only once
+        let now = started + idle_after;
+        assert_eq!(state.next_action(&stats, now), Some(IdleAction::Unload));
+        assert_eq!(state.next_action(&stats, now), None);
+
+        // a task is completed, models are loaded again and the idle period restarts
+        stats.tasks_completed = 1;
+        stats.last_task = Some(now);
+        assert_eq!(state.next_action(&stats, now), Some(IdleAction::Reload));
+        assert_eq!(state.next_action(&stats, now + idle_after / 2), None);
+        assert_eq!(
+            state.next_action(&stats, now + idle_after),
+            Some(IdleAction::Unload)
+        );
+
+        // a model is served meanwhile, so the idle period restarts instead
+        let now = now + idle_after;
+        state.mark_active(now);
+        assert_eq!(state.next_action(&stats, now + idle_after / 2), None);
+        assert_eq!(
+            state.next_action(&stats, now + idle_after),
+            Some(IdleAction::Unload)
+        );
+    }
+
+    #[test]
+    fn test_served_since() {
+        let models = ["a".to_string(), "b".to_string(), "c".to_string()];
+        let expiries = |entries: &[(&str, &str)]| {
+            entries
+                .iter()
+                .map(|(model, expiry)| (model.to_string(), expiry.to_string()))
+                .collect::<HashMap<_, _>>()
+        };
+        let previous = expiries(&[("a", "t1"), ("b", "t1")]);
+        let current = expiries(&[("a", "t1"), ("b", "t2"), ("c", "t2")]);
+
+        // `b` is served again, `c` is loaded since
+        assert_eq!(served_since(&models, &previous, &current), vec!["b", "c"]);
+        assert!(served_since(&models, &current, &current).is_empty());
+    }
+}
@@ -0,0 +1,295 @@
+// the HID transport is only available on Linux, the framing is unused elsewhere
+#![cfg_attr(not(target_os = "linux"), allow(dead_code))]
+
+use eyre::{eyre, Context, OptionExt, Result};
+use std::path::{Path, PathBuf};
+
+use super::crypto::parse_derivation_path;
+
+/// USB vendor ID of Ledger devices.
+#[cfg(target_os = "linux")]
+const LEDGER_VENDOR_ID: &str = "00002C97";
+/// Channel of the HID packets, fixed for Ledger devices.
+const LEDGER_CHANNEL: u16 = 0x0101;
+/// Tag of the HID packets that carry APDUs.
+const LEDGER_TAG_APDU: u8 = 0x05;
+/// Size of a single HID packet, without the report ID.
+const LEDGER_PACKET_SIZE: usize = 64;
+/// Maximum data size of a single APDU.
+const APDU_MAX_DATA: usize = 255;
+
+/// Class & instructions of the Ethereum app.
+const ETH_CLA: u8 = 0xE0;
+const ETH_INS_GET_ADDRESS: u8 = 0x02;
+const ETH_INS_SIGN_PERSONAL_MESSAGE: u8 = 0x08;
+
+/// Status word of a successful APDU.
+const SW_OK: u16 = 0x9000;
+
+/// Error message of a response that does not match the protocol.
+const MALFORMED_RESPONSE: &str = "unexpected response from the Ledger";
+
+/// An Ethereum account on a Ledger device, connected over HID; the secret key never leaves
+/// the device, and each signature is confirmed on the device by the user.
+///
+/// Only Linux is supported for now, where the device is accessed through its `hidraw` node,
+/// which usually requires the Ledger udev rules to be installed.
+#[derive(Debug, Clone)]
+pub struct LedgerSigner {
+    /// The `hidraw` node of the device.
+    device: PathBuf,
+    /// The derivation path of the account.
+    path: Vec<u32>,
+    /// The address of the account, in lowercase hexadecimal without `0x`.
+    address: String,
+}
+
+impl LedgerSigner {
+    /// Connects to the first Ledger device with the Ethereum app open, and reads the address of
+    /// the account at the given derivation path (e.g. [`DEFAULT_DERIVATION_PATH`](super::crypto::DEFAULT_DERIVATION_PATH)).
+    ///
+    /// ### Errors
+    /// - If the derivation path is invalid
+    /// - If no Ledger device is found, or it is locked or the Ethereum app is not open
+    pub fn connect(derivation_path: &str) -> Result<Self> {
+        let path = parse_derivation_path(derivation_path)?;
+        let device = find_ledger_device()?;
+
+        let response = exchange(
+            &device,
+            &apdu(ETH_INS_GET_ADDRESS, 0x00, &encode_path(&path)),
+        )?;
+        // [pubkey length][pubkey][address length][address as ASCII hex]...
+        let pubkey_len = *response.first().ok_or_eyre(MALFORMED_RESPONSE)? as usize;
+        let address_len = *response
+            .get(1 + pubkey_len)
+            .ok_or_eyre(MALFORMED_RESPONSE)? as usize;
+        let address = response
+            .get(2 + pubkey_len..2 + pubkey_len + address_len)
+            .ok_or_eyre(MALFORMED_RESPONSE)?;
+        let address = String::from_utf8_lossy(address).to_lowercase();
+
+        Ok(Self {
+            device,
+            path,
+            address,
+        })
+    }
+
+    /// Returns the address of the account, in lowercase hexadecimal without `0x`.
+    #[inline]
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// Signs the given message with [EIP-191](https://eips.ethereum.org/EIPS/eip-191) on the
+    /// device, which blocks until the user confirms or rejects it.
+    ///
+    /// Returns the signature as `r || s` along with its recovery ID.
+    ///
+    /// ### Errors
+    /// - If the user rejects the message, or the device is disconnected
+    pub fn sign_personal_message(&self, message: &[u8]) -> Result<([u8; 64], u8)> {
+        let mut data = encode_path(&self.path);
+        data.extend_from_slice(&(message.len() as u32).to_be_bytes());
+        data.extend_from_slice(message);
+
+        let mut response = Vec::new();
+        for (i, chunk) in data.chunks(APDU_MAX_DATA).enumerate() {
+            let p1 = if i == 0 { 0x00 } else { 0x80 };
+            response = exchange(
+                &self.device,
+                &apdu(ETH_INS_SIGN_PERSONAL_MESSAGE, p1, chunk),
+            )?;
+        }
+
+        // [v][r][s]
+        if response.len() < 65 {
+            return Err(eyre!("unexpected signature from the Ledger"));
+        }
+        let mut signature = [0u8; 64];
+        signature.copy_from_slice(&response[1..65]);
+        let v = response[0];
+        let recovery_id = if v >= 27 { v - 27 } else { v };
+
+        Ok((signature, recovery_id))
+    }
+}
+
+/// Encodes a derivation path as its length followed by its big-endian components.
+fn encode_path(path: &[u32]) -> Vec<u8> {
+    std::iter::once(path.len() as u8)
+        .chain(path.iter().flat_map(|index| index.to_be_bytes()))
+        .collect()
+}
+
+/// Builds an APDU of the Ethereum app.
+fn apdu(ins: u8, p1: u8, data: &[u8]) -> Vec<u8> {
+    [ETH_CLA, ins, p1, 0x00, data.len() as u8]
+        .into_iter()
+        .chain(data.iter().copied())
+        .collect()
+}
+
+/// Splits an APDU into HID packets: each has the channel, tag & sequence index, and the first
+/// one has the length of the APDU as well.
+fn frame_apdu(apdu: &[u8]) -> Vec<[u8; LEDGER_PACKET_SIZE]> {
+    let mut payload = (apdu.len() as u16).to_be_bytes().to_vec();
+    payload.extend_from_slice(apdu);
+
+    payload
+        .chunks(LEDGER_PACKET_SIZE - 5)
+        .enumerate()
+        .map(|(seq, chunk)| {
+            let mut packet = [0u8; LEDGER_PACKET_SIZE];
+            packet[..2].copy_from_slice(&LEDGER_CHANNEL.to_be_bytes());
+            packet[2] = LEDGER_TAG_APDU;
+            packet[3..5].copy_from_slice(&(seq as u16).to_be_bytes());
+            packet[5..5 + chunk.len()].copy_from_slice(chunk);
+            packet
+        })
+        .collect()
+}
+
+/// Reads a response from HID packets, which are read with `read_packet` until the response is
+/// complete; returns the response data without its status word.
+///
+/// ### Errors
+/// - If a packet is malformed or out of order
+/// - If the status word is not successful, with a hint for the common ones
+fn unframe_response(
+    mut read_packet: impl FnMut() -> Result<[u8; LEDGER_PACKET_SIZE]>,
+) -> Result<Vec<u8>> {
+    let mut response = Vec::new();
+    let mut expected_len = None;
+    let mut seq = 0u16;
+    while !matches!(expected_len, Some(len) if response.len() >= len) {
+        let packet = read_packet()?;
+        if packet[..2] != LEDGER_CHANNEL.to_be_bytes()
+            || packet[2] != LEDGER_TAG_APDU
+            || packet[3..5] != seq.to_be_bytes()
+        {
+            eyre::bail!("unexpected packet from the Ledger");
+        }
+
+        let data = if seq == 0 {
+            expected_len = Some(u16::from_be_bytes([packet[5], packet[6]]) as usize);
+            &packet[7..]
+        } else {
+            &packet[5..]
+        };
+        response.extend_from_slice(data);
+        seq += 1;
+    }
+    response.truncate(expected_len.unwrap_or_default());
+
+    if response.len() < 2 {
+        eyre::bail!(MALFORMED_RESPONSE);
+    }
+    let status = u16::from_be_bytes([response[response.len() - 2], response[response.len() - 1]]);
+    response.truncate(response.len() - 2);
+    match status {
+        SW_OK => Ok(response),
+        0x6985 => Err(eyre!("request was rejected on the Ledger")),
+        0x5515 | 0x6982 => Err(eyre!("Ledger is locked, please unlock it")),
+        0x6d00 | 0x6e00 | 0x6511 => Err(eyre!("please open the Ethereum app on the Ledger")),
+        status => Err(eyre!("Ledger returned status 0x{status:04x}")),
+    }
+}
+
+/// Sends an APDU to the device through its `hidraw` node & returns its response.
+#[cfg(target_os = "linux")]
+fn exchange(device: &Path, apdu: &[u8]) -> Result<Vec<u8>> {
+    use std::io::{Read, Write};
+
+    let mut file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(device)
+        .wrap_err_with(|| format!("could not open Ledger at {}", device.display()))?;
+    for packet in frame_apdu(apdu) {
+        // prefixed with the report ID, which is 0 for Ledger devices
+        let mut report = [0u8; LEDGER_PACKET_SIZE + 1];
+        report[1..].copy_from_slice(&packet);
+        file.write_all(&report)
+            .wrap_err("could not write to the Ledger")?;
+    }
+
+    unframe_response(|| {
+        let mut packet = [0u8; LEDGER_PACKET_SIZE];
+        file.read_exact(&mut packet)
+            .wrap_err("could not read from the Ledger")?;
+        Ok(packet)
+    })
+}
+
+/// Finds the `hidraw` node of the first Ledger device, using its generic (i.e. first) interface.
+#[cfg(target_os = "linux")]
+fn find_ledger_device() -> Result<PathBuf> {
+    let entries = std::fs::read_dir("/sys/class/hidraw").wrap_err("could not list HID devices")?;
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let device_dir = entry.path().join("device");
+        let is_ledger = std::fs::read_to_string(device_dir.join("uevent"))
+            .is_ok_and(|uevent| uevent.to_uppercase().contains(LEDGER_VENDOR_ID));
+        // the HID device is within its USB interface directory
+        let is_generic_interface = device_dir
+            .canonicalize()
+            .ok()
+            .and_then(|dir| std::fs::read_to_string(dir.parent()?.join("bInterfaceNumber")).ok())
+            .is_some_and(|number| number.trim() == "00");
+
+        if is_ledger && is_generic_interface {
+            return Ok(Path::new("/dev").join(entry.file_name()));
+        }
+    }
+
+    Err(eyre!(
+        "no Ledger device found, please connect & unlock it (the Ledger udev rules may be required)"
+    ))
+}
+
+/// Error of the Ledger transport on the platforms other than Linux.
+#[cfg(not(target_os = "linux"))]
+const LEDGER_UNSUPPORTED: &str = "Ledger signing is only supported on Linux for now";
+
+#[cfg(not(target_os = "linux"))]
+fn exchange(_device: &Path, _apdu: &[u8]) -> Result<Vec<u8>> {
+    Err(eyre!(LEDGER_UNSUPPORTED))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn find_ledger_device() -> Result<PathBuf> {
+    Err(eyre!(LEDGER_UNSUPPORTED))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ledger_framing() {
+        assert_eq!(encode_path(&[1, 2]), [2, 0, 0, 0, 1, 0, 0, 0, 2]);
+
+        // an APDU that spans two packets
+        let apdu = apdu(ETH_INS_SIGN_PERSONAL_MESSAGE, 0x00, &[0xAB; 70]);
+        let packets = frame_apdu(&apdu);
+        assert_eq!(packets.len(), 2);
+        assert_eq!(packets[0][..7], [0x01, 0x01, 0x05, 0x00, 0x00, 0x00, 75]);
+        assert_eq!(packets[0][7..12], [ETH_CLA, 0x08, 0x00, 0x00, 70]);
+        assert_eq!(packets[1][..5], [0x01, 0x01, 0x05, 0x00, 0x01]);
+
+        // responses are framed the same way, ending with the status word
+        let mut response = frame_apdu(&[0x01, 0x02, 0x90, 0x00]).into_iter();
+        assert_eq!(
+            unframe_response(|| response.next().ok_or_eyre(MALFORMED_RESPONSE)).unwrap(),
+            [0x01, 0x02]
+        );
+        let mut rejected = frame_apdu(&[0x69, 0x85]).into_iter();
+        assert!(
+            unframe_response(|| rejected.next().ok_or_eyre(MALFORMED_RESPONSE))
+                .unwrap_err()
+                .to_string()
+                .contains("rejected")
+        );
+    }
+}
@@ -0,0 +1,194 @@
+use eyre::Context;
+use inquire::Confirm;
+use std::io::IsTerminal;
+use std::path::Path;
+
+use super::DriaEnv;
+use crate::tr;
+
+/// Suffix of the backup of a rewritten env file, which can not be mistaken for a profile as
+/// profile names have no dots.
+const LEGACY_BACKUP_SUFFIX: &str = ".legacy.bak";
+
+/// Key names used by the env files of the dockerized compute node, along with their current names.
+pub const LEGACY_KEY_NAMES: [(&str, &str); 8] = [
+    ("DKN_WALLET_PRIVATE_KEY", DriaEnv::DKN_WALLET_KEY),
+    ("DKN_P2P_LISTEN_ADDRESS", DriaEnv::DKN_P2P_LISTEN_ADDR_KEY),
+    ("OLLAMA_AUTOPULL", DriaEnv::OLLAMA_AUTO_PULL_KEY),
+    ("OPENAI_APIKEY", DriaEnv::OPENAI_APIKEY_KEY),
+    ("GEMINI_APIKEY", DriaEnv::GEMINI_APIKEY_KEY),
+    ("OPENROUTER_APIKEY", DriaEnv::OPENROUTER_APIKEY_KEY),
+    ("SERPER_APIKEY", DriaEnv::SERPER_APIKEY_KEY),
+    ("JINA_APIKEY", DriaEnv::JINA_APIKEY_KEY),
+];
+
+/// Key names used by the env files of the dockerized compute node that are no longer used.
+pub const OBSOLETE_KEY_NAMES: [&str; 5] = [
+    "DKN_WALLET_PUBLIC_KEY",
+    "DKN_ADMIN_PUBLIC_KEY",
+    "DKN_TASKS",
+    "DKN_RELAY_NODES",
+    "DKN_BOOTSTRAP_NODES",
+];
+
+/// Returns the current name of the given legacy key, if it is one.
+#[inline]
+pub fn get_current_key_name(legacy_key: &str) -> Option<&'static str> {
+    LEGACY_KEY_NAMES
+        .iter()
+        .find(|(legacy, _)| *legacy == legacy_key)
+        .map(|(_, current)| *current)
+}
+
+/// Copies the values of legacy keys within the process environment to their current names,
+/// unless the current names are set already; so that legacy env files keep working as is.
+///
+/// This must be called after the env file is loaded, and before [`DriaEnv`] is read.
+pub fn apply_legacy_env() {
+    for (legacy, current) in LEGACY_KEY_NAMES {
+        let Ok(value) = std::env::var(legacy) else {
+            continue;
+        };
+        if std::env::var(current).is_ok_and(|current| !current.is_empty()) {
+            continue;
+        }
+
+        log::debug!("Using legacy key {legacy} as {current}");
+        std::env::set_var(current, value);
+    }
+}
+
+/// Returns the legacy & obsolete keys that are set (i.e. uncommented) within the given env file content.
+fn find_legacy_keys(content: &str) -> Vec<&'static str> {
+    let set_keys = content
+        .lines()
+        .filter_map(|line| line.split_once('=').map(|(key, _)| key.trim()))
+        .collect::<Vec<_>>();
+
+    LEGACY_KEY_NAMES
+        .iter()
+        .map(|(legacy, _)| *legacy)
+        .chain(OBSOLETE_KEY_NAMES)
+        .filter(|key| set_keys.contains(key))
+        .collect()
+}
+
+/// Rewrites the given env file content with the current key names.
+///
+/// - Legacy keys are renamed, or moved to the line of the current key if it exists but is empty.
+/// - Legacy keys are commented out if the current key has a value already.
+/// - Obsolete keys are commented out.
+fn rewrite_legacy_keys(content: &str) -> String {
+    let entries = content
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim(), value))
+        .collect::<Vec<_>>();
+    let current_line_exists = |current: &str| entries.iter().any(|(key, _)| *key == current);
+    let legacy_value = |current: &str| {
+        entries.iter().find_map(|(key, value)| {
+            (get_current_key_name(key) == Some(current) && !value.is_empty()).then_some(*value)
+        })
+    };
+
+    content
+        .lines()
+        .map(|line| {
+            let Some((key, value)) = line.split_once('=') else {
+                return line.to_string();
+            };
+
+            let key = key.trim();
+            match get_current_key_name(key) {
+                Some(current) if !current_line_exists(current) => format!("{current}={value}"),
+                Some(current) => format!("# {line} (replaced by {current})"),
+                None if OBSOLETE_KEY_NAMES.contains(&key) => format!("# {line} (no longer used)"),
+                None if value.is_empty() => match legacy_value(key) {
+                    Some(legacy_value) => format!("{key}={legacy_value}"),
+                    None => line.to_string(),
+                },
+                None => line.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Checks the given env file for legacy & obsolete key names, and offers to rewrite the file
+/// with the current names; the original file is kept with a `.legacy.bak` extension.
+///
+/// The rewrite is only offered within an interactive terminal, legacy keys are read as is otherwise.
+///
+/// ### Errors
+/// - If the env file could not be backed up or written
+pub fn offer_legacy_rewrite(env_path: &Path) -> eyre::Result<()> {
+    let Ok(content) = std::fs::read_to_string(env_path) else {
+        return Ok(());
+    };
+    let legacy_keys = find_legacy_keys(&content);
+    if legacy_keys.is_empty() {
+        return Ok(());
+    }
+
+    log::warn!(
+        "{} has legacy keys: {}",
+        env_path.display(),
+        legacy_keys.join(", ")
+    );
+    if !std::io::stdin().is_terminal() {
+        log::warn!("Legacy keys are still read, run the launcher interactively to rewrite them.");
+        return Ok(());
+    }
+
+    let answer = Confirm::new(tr!("Rewrite the env file with the current key names?"))
+        .with_help_message(tr!(
+            "The original file is kept as a backup with .legacy.bak extension"
+        ))
+        .with_default(true)
+        .prompt()?;
+    if !answer {
+        log::info!("Keeping the legacy keys, they are still read.");
+        return Ok(());
+    }
+
+    let mut backup_path = env_path.as_os_str().to_owned();
+    backup_path.push(LEGACY_BACKUP_SUFFIX);
+    std::fs::copy(env_path, &backup_path).wrap_err("could not back up env file")?;
+    std::fs::write(env_path, rewrite_legacy_keys(&content)).wrap_err("could not write env file")?;
+    log::info!(
+        "Env file is rewritten, the original is at {}",
+        Path::new(&backup_path).display()
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite_legacy_keys() {
+        let content = "DKN_WALLET_PRIVATE_KEY=abc\nOPENAI_APIKEY=old\nOPENAI_API_KEY=new\nDKN_ADMIN_PUBLIC_KEY=xyz\n# OLLAMA_AUTOPULL=true\nDKN_MODELS=gpt-4o\nJINA_APIKEY=jina\nJINA_API_KEY=";
+        assert_eq!(
+            find_legacy_keys(content),
+            vec![
+                "DKN_WALLET_PRIVATE_KEY",
+                "OPENAI_APIKEY",
+                "JINA_APIKEY",
+                "DKN_ADMIN_PUBLIC_KEY"
+            ]
+        );
+
+        let rewritten = rewrite_legacy_keys(content);
+        assert_eq!(
+            rewritten,
+            "DKN_WALLET_SECRET_KEY=abc\n# OPENAI_APIKEY=old (replaced by OPENAI_API_KEY)\nOPENAI_API_KEY=new\n# DKN_ADMIN_PUBLIC_KEY=xyz (no longer used)\n# OLLAMA_AUTOPULL=true\nDKN_MODELS=gpt-4o\n# JINA_APIKEY=jina (replaced by JINA_API_KEY)\nJINA_API_KEY=jina"
+        );
+        assert!(find_legacy_keys(&rewritten).is_empty());
+
+        // the backup is not picked up as a profile, e.g. `.env.legacy.bak`
+        let profile = LEGACY_BACKUP_SUFFIX.trim_start_matches('.');
+        assert!(!crate::utils::is_valid_profile_name(profile));
+    }
+}
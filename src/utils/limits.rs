@@ -0,0 +1,320 @@
+use eyre::Result;
+use tokio::process::{Child, Command};
+
+/// Number of CPUs that an affinity can refer to: the bits of the affinity mask on Windows, and
+/// `CPU_SETSIZE` of the `cpu_set_t` elsewhere.
+const MAX_AFFINITY_CPUS: usize = if cfg!(windows) { 64 } else { 1024 };
+
+/// Resource limits for the compute node process, so that it does not starve the rest of the machine.
+///
+/// - CPU affinity & niceness are applied before the process starts executing, so that all
+///   of its threads inherit them (on Windows, they are applied right after spawning).
+/// - Memory is capped with a cgroup on Linux, and a Job Object on Windows.
+///
+/// Limits that can not be applied on the current platform are skipped with a warning.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResourceLimits {
+    /// CPU cores that the process is allowed to run on.
+    pub cpu_affinity: Option<Vec<usize>>,
+    /// Niceness of the process, from `-20` (highest priority) to `19` (lowest priority).
+    pub niceness: Option<i32>,
+    /// Maximum memory of the process, in megabytes.
+    pub memory_mb: Option<u64>,
+}
+
+impl ResourceLimits {
+    /// Parses a list of CPU cores given as comma-separated indices and ranges, e.g. `0-3,6`.
+    ///
+    /// ### Errors
+    /// - If the list is empty or malformed
+    /// - If a CPU does not exist on this machine, or can not be within an affinity
+    pub fn parse_cpu_list(cpus: &str) -> Result<Vec<usize>> {
+        let cpu_count = std::thread::available_parallelism().map_or(1, |count| count.get());
+        Self::parse_cpu_list_within(cpus, cpu_count.min(MAX_AFFINITY_CPUS))
+    }
+
+    /// Parses a list of CPU cores as in [`ResourceLimits::parse_cpu_list`], with indices below
+    /// the given number of CPUs.
+    fn parse_cpu_list_within(cpus: &str, cpu_count: usize) -> Result<Vec<usize>> {
+        let mut result = Vec::new();
+        for part in cpus.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let parse = |cpu: &str| {
+                let index = cpu
+                    .trim()
+                    .parse::<usize>()
+                    .map_err(|_| eyre::eyre!("invalid CPU index '{cpu}'"))?;
+                if index >= cpu_count {
+                    eyre::bail!(
+                        "CPU {index} does not exist, this machine has CPUs 0-{}",
+                        cpu_count - 1
+                    );
+                }
+                Ok(index)
+            };
+
+            match part.split_once('-') {
+                Some((start, end)) => {
+                    let (start, end) = (parse(start)?, parse(end)?);
+                    if start > end {
+                        eyre::bail!("invalid CPU range '{part}'");
+                    }
+                    result.extend(start..=end);
+                }
+                None => result.push(parse(part)?),
+            }
+        }
+
+        result.sort_unstable();
+        result.dedup();
+        if result.is_empty() {
+            eyre::bail!("no CPUs given");
+        }
+
+        Ok(result)
+    }
+
+    /// Returns `true` if no limits are set.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Configures the command so that the CPU affinity & niceness are applied to the process
+    /// before it starts executing.
+    pub fn configure(&self, command: &mut Command) {
+        #[cfg(unix)]
+        {
+            let niceness = self.niceness.filter(|niceness| {
+                // lowering the niceness requires privileges
+                let allowed = *niceness >= 0 || unsafe { libc::geteuid() } == 0;
+                if !allowed {
+                    log::warn!(
+                        "Ignoring negative niceness {niceness}, it requires root privileges."
+                    );
+                }
+                allowed
+            });
+
+            #[cfg(target_os = "linux")]
+            let cpu_set = self.cpu_affinity.as_ref().map(|cpus| {
+                // safe because `cpu_set_t` is a plain bitmask
+                let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+                for cpu in cpus {
+                    unsafe { libc::CPU_SET(*cpu, &mut set) };
+                }
+                set
+            });
+            #[cfg(not(target_os = "linux"))]
+            if self.cpu_affinity.is_some() {
+                log::warn!("CPU affinity is not supported on this platform, ignoring it.");
+            }
+
+            // safe because only async-signal-safe system calls are made within the closure
+            unsafe {
+                command.pre_exec(move || {
+                    // failures are ignored here, as they can not be logged from the child
+                    if let Some(niceness) = niceness {
+                        libc::setpriority(libc::PRIO_PROCESS, 0, niceness);
+                    }
+                    #[cfg(target_os = "linux")]
+                    if let Some(set) = &cpu_set {
+                        libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), set);
+                    }
+                    Ok(())
+                });
+            }
+        }
+
+        #[cfg(not(unix))]
+        let _ = command;
+    }
+
+    /// Applies the limits that can only be set after the process is spawned.
+    ///
+    /// Failures are logged as warnings, as the compute node can still run without the limits.
+    pub fn apply(&self, child: &Child) {
+        #[cfg(target_os = "linux")]
+        if let (Some(memory_mb), Some(pid)) = (self.memory_mb, child.id()) {
+            if let Err(err) = linux::limit_memory(pid, memory_mb) {
+                log::warn!(
+                    "Could not limit compute node memory with a cgroup: {err}\nYou can run the launcher under a cgroup with a memory limit instead, e.g. `systemd-run --user --scope -p MemoryMax={memory_mb}M dkn-compute-launcher start`"
+                );
+            }
+        }
+        #[cfg(all(unix, not(target_os = "linux")))]
+        if self.memory_mb.is_some() {
+            let _ = child;
+            log::warn!("Memory limit is not supported on this platform, ignoring it.");
+        }
+
+        #[cfg(windows)]
+        if let Err(err) = windows::apply(self, child) {
+            log::warn!("Could not apply resource limits to compute node: {err}");
+        }
+    }
+}
+
+impl std::fmt::Display for ResourceLimits {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut limits = Vec::new();
+        if let Some(cpus) = &self.cpu_affinity {
+            let cpus = cpus.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+            limits.push(format!("CPUs: {}", cpus.join(",")));
+        }
+        if let Some(niceness) = self.niceness {
+            limits.push(format!("niceness: {niceness}"));
+        }
+        if let Some(memory_mb) = self.memory_mb {
+            limits.push(format!("memory: {memory_mb} MB"));
+        }
+
+        if limits.is_empty() {
+            write!(f, "none")
+        } else {
+            write!(f, "{}", limits.join(", "))
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::{fs, io, path::PathBuf};
+
+    /// Name of the cgroup that the compute node is moved into, under the launcher's own cgroup parent.
+    const COMPUTE_CGROUP_NAME: &str = "dkn-compute";
+
+    /// Moves the process into a cgroup (v2) with the given memory limit.
+    ///
+    /// The cgroup is created as a sibling of the launcher's own cgroup, which requires that
+    /// cgroup to be delegated to the user (e.g. via systemd) or root privileges.
+    pub fn limit_memory(pid: u32, memory_mb: u64) -> io::Result<()> {
+        // for cgroup v2, the line is like `0::/user.slice/user-1000.slice/session-1.scope`
+        let own_cgroup = fs::read_to_string("/proc/self/cgroup")?
+            .lines()
+            .find_map(|line| line.strip_prefix("0::").map(String::from))
+            .ok_or_else(|| io::Error::other("cgroup v2 is not available"))?;
+
+        let own_path = PathBuf::from("/sys/fs/cgroup").join(own_cgroup.trim_start_matches('/'));
+        let parent = own_path
+            .parent()
+            .ok_or_else(|| io::Error::other("launcher is in the root cgroup"))?;
+        let cgroup = parent.join(COMPUTE_CGROUP_NAME);
+
+        fs::create_dir_all(&cgroup)?;
+        fs::write(
+            cgroup.join("memory.max"),
+            (memory_mb * 1024 * 1024).to_string(),
+        )?;
+        fs::write(cgroup.join("cgroup.procs"), pid.to_string())?;
+
+        log::info!(
+            "Limited compute node memory to {memory_mb} MB via {}",
+            cgroup.display()
+        );
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+mod windows {
+    use std::io;
+    use tokio::process::Child;
+    use windows_sys::Win32::Foundation::CloseHandle;
+    use windows_sys::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, JobObjectExtendedLimitInformation,
+        SetInformationJobObject, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+        JOB_OBJECT_LIMIT_PROCESS_MEMORY,
+    };
+    use windows_sys::Win32::System::Threading::{
+        SetPriorityClass, SetProcessAffinityMask, ABOVE_NORMAL_PRIORITY_CLASS,
+        BELOW_NORMAL_PRIORITY_CLASS, HIGH_PRIORITY_CLASS, IDLE_PRIORITY_CLASS,
+        NORMAL_PRIORITY_CLASS,
+    };
+
+    use super::ResourceLimits;
+
+    /// Applies the limits to the process, using its handle.
+    pub fn apply(limits: &ResourceLimits, child: &Child) -> io::Result<()> {
+        let handle = child
+            .raw_handle()
+            .ok_or_else(|| io::Error::other("process has exited"))?;
+
+        if let Some(niceness) = limits.niceness {
+            // map niceness to the closest priority class
+            let class = match niceness {
+                15.. => IDLE_PRIORITY_CLASS,
+                5..=14 => BELOW_NORMAL_PRIORITY_CLASS,
+                -4..=4 => NORMAL_PRIORITY_CLASS,
+                -14..=-5 => ABOVE_NORMAL_PRIORITY_CLASS,
+                _ => HIGH_PRIORITY_CLASS,
+            };
+            if unsafe { SetPriorityClass(handle, class) } == 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+
+        if let Some(cpus) = &limits.cpu_affinity {
+            let mask = cpus
+                .iter()
+                .filter(|cpu| **cpu < usize::BITS as usize)
+                .fold(0usize, |mask, cpu| mask | (1 << cpu));
+            if unsafe { SetProcessAffinityMask(handle, mask) } == 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+
+        if let Some(memory_mb) = limits.memory_mb {
+            unsafe {
+                let job = CreateJobObjectW(std::ptr::null(), std::ptr::null());
+                if job.is_null() {
+                    return Err(io::Error::last_os_error());
+                }
+
+                let mut info: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = std::mem::zeroed();
+                info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_PROCESS_MEMORY;
+                info.ProcessMemoryLimit = (memory_mb * 1024 * 1024) as usize;
+
+                let result = if SetInformationJobObject(
+                    job,
+                    JobObjectExtendedLimitInformation,
+                    &info as *const _ as *const std::ffi::c_void,
+                    std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+                ) == 0
+                    || AssignProcessToJobObject(job, handle) == 0
+                {
+                    Err(io::Error::last_os_error())
+                } else {
+                    Ok(())
+                };
+
+                // the job lives on as long as the process is assigned to it
+                CloseHandle(job);
+                result?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cpu_list() {
+        assert_eq!(
+            ResourceLimits::parse_cpu_list_within("0-3, 6,2", 8).unwrap(),
+            vec![0, 1, 2, 3, 6]
+        );
+        assert!(ResourceLimits::parse_cpu_list_within("3-1", 8).is_err());
+        assert!(ResourceLimits::parse_cpu_list_within("a", 8).is_err());
+        assert!(ResourceLimits::parse_cpu_list_within("", 8).is_err());
+
+        // CPUs that do not exist, or would overflow the affinity mask
+        assert!(ResourceLimits::parse_cpu_list_within("8", 8).is_err());
+        assert!(ResourceLimits::parse_cpu_list_within("0-99999999999", 8).is_err());
+        assert!(ResourceLimits::parse_cpu_list("0").is_ok());
+        assert!(ResourceLimits::parse_cpu_list(&MAX_AFFINITY_CPUS.to_string()).is_err());
+    }
+}
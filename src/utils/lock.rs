@@ -0,0 +1,221 @@
+use eyre::{Context, Result};
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Tolerance when comparing the start time of a process with the time of a lock, as process start
+/// times are only known in whole seconds.
+const START_TIME_TOLERANCE: Duration = Duration::from_secs(2);
+
+/// A lock that prevents starting the same node (i.e. same environment file) twice.
+///
+/// The lock file is created next to the environment file with a `.lock` suffix,
+/// e.g. `.env.myprofile.lock`, and contains the PID of the launcher that holds it.
+/// It is removed when the lock is dropped.
+#[derive(Debug)]
+pub struct NodeLock {
+    path: PathBuf,
+}
+
+impl NodeLock {
+    /// Acquires the lock for the given environment file, under the given directory.
+    ///
+    /// A lock left behind by a launcher that is no longer running is taken over automatically.
+    ///
+    /// ### Arguments
+    /// - `exe_dir`: directory to create the lock file in
+    /// - `env_path`: path to the environment file, its name is used to key the lock
+    /// - `take_over`: take over the lock even if its holder seems to be running
+    ///
+    /// ### Errors
+    /// - If another launcher is running for the same environment file, unless `take_over` is set
+    /// - If the lock file could not be created
+    pub fn acquire(exe_dir: &Path, env_path: &Path, take_over: bool) -> Result<Self> {
+        let path = Self::path(exe_dir, env_path);
+
+        if let Some(pid) = read_lock_pid(&path) {
+            if pid == std::process::id() {
+                // same process, e.g. after an in-place update; nothing to do
+            } else if !is_holder_running(&path, pid) {
+                log::warn!(
+                    "Taking over stale lock at {} (launcher PID {pid} is not running).",
+                    path.display()
+                );
+            } else if take_over {
+                log::warn!("Taking over lock at {} held by PID {pid}.", path.display());
+            } else {
+                eyre::bail!(
+                    "A node is already running for {} (launcher PID {pid}).\nStop it first, or if you are sure that it is not running, use `--take-over` to take over its lock at {}",
+                    env_path.display(),
+                    path.display()
+                );
+            }
+
+            fs::remove_file(&path).wrap_err("could not remove existing lock file")?;
+        }
+
+        let mut file = match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+        {
+            Ok(file) => file,
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+                eyre::bail!(
+                    "Another launcher has just started for {}.",
+                    env_path.display()
+                );
+            }
+            Err(err) => return Err(err).wrap_err("could not create lock file"),
+        };
+        writeln!(file, "{}", std::process::id()).wrap_err("could not write lock file")?;
+
+        log::debug!("Acquired node lock at {}", path.display());
+        Ok(Self { path })
+    }
+
+    /// Returns the PID of the launcher that holds the lock for the given environment file,
+    /// if that launcher is still running; a process that has reused its PID is not returned.
+    pub fn holder(exe_dir: &Path, env_path: &Path) -> Option<u32> {
+        let path = Self::path(exe_dir, env_path);
+        read_lock_pid(&path).filter(|pid| is_holder_running(&path, *pid))
+    }
+
+    /// Returns when the lock for the given environment file was acquired, i.e. when its node was
+    /// started, if that launcher is still running.
+    pub fn held_since(exe_dir: &Path, env_path: &Path) -> Option<SystemTime> {
+        Self::holder(exe_dir, env_path)?;
+        fs::metadata(Self::path(exe_dir, env_path))
+            .ok()?
+            .modified()
+            .ok()
+    }
+
+    /// Returns the path to the lock file for the given environment file.
+    fn path(exe_dir: &Path, env_path: &Path) -> PathBuf {
+        let env_name = env_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| ".env".to_string());
+        exe_dir.join(format!("{env_name}.lock"))
+    }
+}
+
+impl Drop for NodeLock {
+    fn drop(&mut self) {
+        // only remove the lock if it is still ours, it may have been taken over
+        if read_lock_pid(&self.path) == Some(std::process::id()) {
+            if let Err(err) = fs::remove_file(&self.path) {
+                log::warn!("Could not remove lock file {}: {err}", self.path.display());
+            }
+        }
+    }
+}
+
+/// Reads the PID within the lock file, if it exists and is valid.
+fn read_lock_pid(path: &Path) -> Option<u32> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// Returns `true` if the launcher that wrote the given lock file with its PID is still running.
+///
+/// The launcher must have started before the lock was written, otherwise the PID has been reused
+/// by another process after the launcher exited, which must not be signalled as the launcher.
+fn is_holder_running(path: &Path, pid: u32) -> bool {
+    if !is_process_running(pid) {
+        return false;
+    }
+
+    let locked_at = fs::metadata(path).and_then(|metadata| metadata.modified());
+    match (locked_at, process_start_time(pid)) {
+        (Ok(locked_at), Some(started_at)) => started_at <= locked_at + START_TIME_TOLERANCE,
+        // assume its the launcher if we can't tell
+        _ => true,
+    }
+}
+
+/// Returns the start time of the process with the given PID, if it can be found.
+fn process_start_time(pid: u32) -> Option<SystemTime> {
+    use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+
+    let pid = Pid::from_u32(pid);
+    let mut system = System::new();
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&[pid]),
+        true,
+        ProcessRefreshKind::nothing(),
+    );
+
+    let started_at = system.process(pid)?.start_time();
+    Some(SystemTime::UNIX_EPOCH + Duration::from_secs(started_at))
+}
+
+/// Returns `true` if a process with the given PID is running.
+#[cfg(unix)]
+pub fn is_process_running(pid: u32) -> bool {
+    // non-positive values would refer to process groups
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    if pid <= 0 {
+        return false;
+    }
+
+    // signal `0` only checks whether the process exists & can be signalled
+    let result = unsafe { libc::kill(pid, 0) };
+    result == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// Returns `true` if a process with the given PID is running.
+#[cfg(windows)]
+pub fn is_process_running(pid: u32) -> bool {
+    std::process::Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {pid}"), "/NH"])
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).contains(&pid.to_string()))
+        .unwrap_or(true) // assume its running if we can't tell
+}
+
+/// Returns `true` if a process with the given PID is running.
+#[cfg(not(any(unix, windows)))]
+pub fn is_process_running(_pid: u32) -> bool {
+    true // assume its running if we can't tell
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_node_lock() {
+        let dir = std::env::temp_dir().join(format!(
+            "dkn-test-node-lock-{}-{}",
+            std::process::id(),
+            line!()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        let env_path = dir.join(".env.test");
+        let lock_path = dir.join(".env.test.lock");
+
+        // a stale lock is taken over
+        fs::write(&lock_path, format!("{}\n", i32::MAX)).unwrap();
+        let lock = NodeLock::acquire(&dir, &env_path, false).unwrap();
+        assert_eq!(read_lock_pid(&lock_path), Some(std::process::id()));
+
+        // the holder is running, unless its PID was reused by a process started after the lock
+        assert_eq!(NodeLock::holder(&dir, &env_path), Some(std::process::id()));
+        fs::File::options()
+            .write(true)
+            .open(&lock_path)
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(1))
+            .unwrap();
+        assert_eq!(NodeLock::holder(&dir, &env_path), None);
+
+        drop(lock);
+        assert!(!lock_path.exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
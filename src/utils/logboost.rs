@@ -0,0 +1,108 @@
+use std::time::{Duration, Instant};
+
+/// Number of consecutive failures after which the compute node log-level is boosted.
+const LOG_BOOST_FAILURE_THRESHOLD: usize = 2;
+
+/// Duration without failures after which the boosted log-level is reverted.
+const LOG_BOOST_STABLE_PERIOD: Duration = Duration::from_secs(30 * 60);
+
+/// The `RUST_LOG` value given to the compute node while the boost is active.
+const LOG_BOOST_RUST_LOG: &str = "dkn_compute=debug,dkn_p2p=debug,dkn_executor=debug";
+
+/// Tracks repeated failures (crashes, failed update checks) within the supervision loop,
+/// and decides whether the compute node should be restarted with a more verbose log-level.
+///
+/// The boost is enabled after [`LOG_BOOST_FAILURE_THRESHOLD`] consecutive failures, and is reverted
+/// once there has been no failure for [`LOG_BOOST_STABLE_PERIOD`]. The compute node crashes among
+/// the failures are counted on their own, for the restart limit.
+#[derive(Debug, Default)]
+pub struct LogBoost {
+    /// Number of consecutive failures.
+    failures: usize,
+    /// Number of consecutive compute node crashes (including hangs), a subset of `failures`.
+    crashes: usize,
+    /// Time of the last recorded failure.
+    last_failure: Option<Instant>,
+}
+
+impl LogBoost {
+    /// Records a failure, returns `true` if this failure has just enabled the boost.
+    pub fn record_failure(&mut self) -> bool {
+        self.refresh();
+
+        self.failures += 1;
+        self.last_failure = Some(Instant::now());
+
+        self.failures == LOG_BOOST_FAILURE_THRESHOLD
+    }
+
+    /// Records a compute node crash as a failure, returns `true` if it has just enabled the boost.
+    pub fn record_crash(&mut self) -> bool {
+        let enabled = self.record_failure();
+        self.crashes += 1;
+
+        enabled
+    }
+
+    /// Returns the number of consecutive compute node crashes.
+    #[inline]
+    pub fn crashes(&self) -> usize {
+        self.crashes
+    }
+
+    /// Returns `true` if the log-level is currently boosted.
+    #[inline]
+    pub fn is_boosted(&self) -> bool {
+        self.failures >= LOG_BOOST_FAILURE_THRESHOLD
+    }
+
+    /// Resets the failures if the stable period has passed since the last failure.
+    pub fn refresh(&mut self) {
+        if self
+            .last_failure
+            .is_some_and(|t| t.elapsed() >= LOG_BOOST_STABLE_PERIOD)
+        {
+            if self.is_boosted() {
+                log::info!("Compute node has been stable, reverting the log-level boost.");
+            }
+
+            self.failures = 0;
+            self.crashes = 0;
+            self.last_failure = None;
+        }
+    }
+
+    /// Returns the `RUST_LOG` override for the next compute node spawn, if the boost is active.
+    pub fn rust_log(&mut self) -> Option<&'static str> {
+        self.refresh();
+
+        self.is_boosted().then_some(LOG_BOOST_RUST_LOG)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_boost() {
+        let mut boost = LogBoost::default();
+        assert!(boost.rust_log().is_none());
+
+        assert!(!boost.record_failure());
+        assert!(boost.rust_log().is_none());
+
+        assert!(boost.record_failure());
+        assert_eq!(boost.rust_log(), Some(LOG_BOOST_RUST_LOG));
+
+        // further failures keep the boost, but do not "enable" it again
+        assert!(!boost.record_crash());
+        assert!(boost.is_boosted());
+        assert_eq!((boost.failures, boost.crashes), (3, 1));
+
+        // revert after a stable period
+        boost.last_failure = Some(Instant::now() - LOG_BOOST_STABLE_PERIOD);
+        assert!(boost.rust_log().is_none());
+        assert_eq!((boost.failures, boost.crashes), (0, 0));
+    }
+}
@@ -0,0 +1,143 @@
+use std::fs::{File, OpenOptions};
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+
+use super::colors_enabled;
+use super::output::strip_ansi;
+
+/// The launcher log file is rotated to `<log file>.old` when it grows beyond this size.
+const LOG_FILE_MAX_BYTES: u64 = 5 * 1024 * 1024;
+
+/// Output format for the launcher logs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum LogFormat {
+    /// Human-readable log lines.
+    #[default]
+    Text,
+    /// Structured JSON lines, one object per log record.
+    Json,
+}
+
+/// Returns the path of the launcher log file for the given environment file, which is next to
+/// it so that each profile has its own logs, e.g. `.env.launcher.log`.
+pub fn launcher_log_path(env_path: &Path) -> PathBuf {
+    let file_name = env_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| ".env".to_string());
+
+    env_path.with_file_name(format!("{file_name}.launcher.log"))
+}
+
+/// Writes the logs to stderr, and to the log file (without colors) if there is one.
+struct LogWriter {
+    file: Option<File>,
+}
+
+impl Write for LogWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        std::io::stderr().write_all(buf)?;
+        if let Some(file) = &mut self.file {
+            // the log file is best-effort, it should never stop the logs
+            let _ = file.write_all(strip_ansi(&String::from_utf8_lossy(buf)).as_bytes());
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        std::io::stderr().flush()?;
+        if let Some(file) = &mut self.file {
+            let _ = file.flush();
+        }
+
+        Ok(())
+    }
+}
+
+/// Opens the log file for appending, rotating it first if it is too large.
+pub(super) fn open_log_file(log_path: &Path) -> Option<File> {
+    if std::fs::metadata(log_path).is_ok_and(|metadata| metadata.len() > LOG_FILE_MAX_BYTES) {
+        let mut old_path = log_path.as_os_str().to_owned();
+        old_path.push(".old");
+        let _ = std::fs::rename(log_path, old_path);
+    }
+
+    if let Some(dir) = log_path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        let _ = std::fs::create_dir_all(dir);
+    }
+
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_path)
+        .ok()
+}
+
+/// Initializes the logger for the launcher.
+///
+/// The launcher logs at `info` level by default, which can be overridden with `RUST_LOG`; in quiet
+/// mode only warnings & errors are logged, regardless of `RUST_LOG`.
+///
+/// ### Arguments
+/// - `format`: the output format of the logs
+/// - `profile`: the profile name, included within JSON logs
+/// - `log_path`: the file that the logs are appended to as well, see [`launcher_log_path`]
+/// - `quiet`: whether to log warnings & errors only
+pub fn init_logger(
+    format: LogFormat,
+    profile: Option<String>,
+    log_path: Option<&Path>,
+    quiet: bool,
+) {
+    let mut builder = env_logger::builder();
+    builder
+        .format_timestamp(Some(env_logger::TimestampPrecision::Seconds))
+        .filter(None, log::LevelFilter::Off)
+        .filter_module("dkn_compute_launcher", log::LevelFilter::Info)
+        // the logs are piped to the writer, so colors are enabled w.r.t the terminal here
+        .write_style(if std::io::stderr().is_terminal() && colors_enabled() {
+            env_logger::WriteStyle::Always
+        } else {
+            env_logger::WriteStyle::Never
+        })
+        .target(env_logger::Target::Pipe(Box::new(LogWriter {
+            file: log_path.and_then(open_log_file),
+        })))
+        .parse_default_env();
+
+    if format == LogFormat::Json {
+        builder.format(move |buf, record| {
+            let line = serde_json::json!({
+                "timestamp": buf.timestamp_seconds().to_string(),
+                "level": record.level().as_str(),
+                "module": record.module_path().unwrap_or_default(),
+                "message": record.args().to_string(),
+                "profile": profile,
+            });
+            writeln!(buf, "{line}")
+        });
+    }
+
+    builder.init();
+    if quiet {
+        log::set_max_level(log::LevelFilter::Warn.min(log::max_level()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_launcher_log_path() {
+        assert_eq!(
+            launcher_log_path(Path::new("/home/user/.dria/.env.work")),
+            Path::new("/home/user/.dria/.env.work.launcher.log")
+        );
+        assert_eq!(
+            launcher_log_path(Path::new(".env")),
+            Path::new(".env.launcher.log")
+        );
+    }
+}
@@ -0,0 +1,428 @@
+use eyre::{Context, Result};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_util::sync::CancellationToken;
+
+use super::{
+    cached, get_latest_release, get_points, DriaEnv, DriaRelease, DriaRepository, HealthState,
+    DKN_LAUNCHER_VERSION,
+};
+
+/// Maximum number of bytes read from an incoming request, requests have no bodies.
+const MAX_REQUEST_SIZE: usize = 8 * 1024;
+/// How long a connection has to send its request, so that idle connections are dropped.
+const REQUEST_READ_TIMEOUT: Duration = Duration::from_secs(10);
+/// Number of log lines returned by `GET /logs` by default.
+const DEFAULT_LOG_LINES: usize = 100;
+/// Maximum number of log lines returned by `GET /logs`.
+const MAX_LOG_LINES: usize = 1000;
+/// The single-page dashboard, which uses the API with the token entered by the user.
+const DASHBOARD_HTML: &str = include_str!("../assets/dashboard.html");
+/// Content security policy of the responses, the dashboard only talks to the API itself.
+const DASHBOARD_CSP: &str =
+    "default-src 'none'; script-src 'unsafe-inline'; style-src 'unsafe-inline'; connect-src 'self'";
+
+/// A request to the management API, only with the parts that are used.
+#[derive(Debug, PartialEq)]
+struct ManagementRequest<'a> {
+    method: &'a str,
+    path: &'a str,
+    query: Option<&'a str>,
+    /// Token of the `Authorization: Bearer <token>` header.
+    bearer: Option<&'a str>,
+}
+
+impl<'a> ManagementRequest<'a> {
+    /// Parses the request line & headers of a raw HTTP request.
+    fn parse(request: &'a str) -> Self {
+        let mut lines = request.lines();
+
+        // request line is like `GET /logs?lines=50 HTTP/1.1`
+        let mut request_line = lines.next().unwrap_or_default().split(' ');
+        let method = request_line.next().unwrap_or_default();
+        let target = request_line.next().unwrap_or_default();
+        let (path, query) = match target.split_once('?') {
+            Some((path, query)) => (path, Some(query)),
+            None => (target, None),
+        };
+
+        let bearer = lines
+            .take_while(|line| !line.is_empty())
+            .filter_map(|line| line.split_once(':'))
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case("authorization"))
+            .and_then(|(_, value)| value.trim().strip_prefix("Bearer "))
+            .map(str::trim);
+
+        Self {
+            method,
+            path,
+            query,
+            bearer,
+        }
+    }
+
+    /// Returns the value of the given query parameter.
+    fn query_param(&self, name: &str) -> Option<&'a str> {
+        self.query?
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value)
+    }
+
+    /// Returns `true` if the request has the given token, compared in constant time.
+    fn is_authorized(&self, token: &str) -> bool {
+        self.bearer.is_some_and(|bearer| {
+            bearer.len() == token.len()
+                && bearer
+                    .bytes()
+                    .zip(token.bytes())
+                    .fold(0, |diff, (a, b)| diff | (a ^ b))
+                    == 0
+        })
+    }
+}
+
+/// Shared state of the management API server.
+pub struct ManagementContext {
+    /// The token that requests are authenticated with.
+    pub token: String,
+    /// The health state shared with the supervision loop.
+    pub health: Arc<HealthState>,
+    /// The launcher log file, see [`launcher_log_path`](super::launcher_log_path).
+    pub log_path: PathBuf,
+    /// The directory of the compute node binaries, to check its version.
+    pub exe_dir: PathBuf,
+    /// When the node was started, for its uptime.
+    pub started: Instant,
+}
+
+/// A response of the management API.
+struct ManagementResponse {
+    status: &'static str,
+    content_type: &'static str,
+    body: String,
+}
+
+impl ManagementResponse {
+    fn json(status: &'static str, body: serde_json::Value) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: body.to_string(),
+        }
+    }
+
+    fn error(status: &'static str, error: impl std::fmt::Display) -> Self {
+        Self::json(status, serde_json::json!({ "error": error.to_string() }))
+    }
+}
+
+/// Serves the management API & the dashboard at the given port on localhost, until the
+/// cancellation token is cancelled.
+///
+/// The dashboard is served at `GET /` and holds no data itself; every other request must have the
+/// `Authorization: Bearer <token>` header.
+///
+/// - `GET /status` returns the versions, PID & uptime along with the health report.
+/// - `GET /logs?lines=N` returns the last `N` lines (100 by default) of the launcher logs.
+/// - `GET /points` returns the $DRIA points of the wallet.
+/// - `GET /models` returns the configured models.
+/// - `GET /updates` returns the current & latest versions of the launcher and the compute node.
+/// - `POST /restart` restarts the compute node.
+/// - `GET /settings` returns the environment, with its secrets masked.
+///
+/// ### Arguments
+/// - `port`: the port to listen on, only on `127.0.0.1`
+/// - `context`: the state shared by the requests
+/// - `cancellation`: stops the server when cancelled
+pub async fn serve_management(
+    port: u16,
+    context: ManagementContext,
+    cancellation: CancellationToken,
+) -> Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .await
+        .wrap_err_with(|| format!("could not bind management API to port {port}"))?;
+    log::info!("Serving management API & dashboard at http://127.0.0.1:{port}");
+
+    let context = Arc::new(context);
+    loop {
+        tokio::select! {
+            _ = cancellation.cancelled() => break,
+            accepted = listener.accept() => {
+                let (stream, _) = match accepted {
+                    Ok(accepted) => accepted,
+                    Err(err) => {
+                        log::warn!("Could not accept management API connection: {err}");
+                        continue;
+                    }
+                };
+
+                let context = context.clone();
+                tokio::spawn(async move {
+                    if let Err(err) = handle_management_request(stream, &context).await {
+                        log::debug!("Management API request failed: {err}");
+                    }
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn handle_management_request(
+    mut stream: TcpStream,
+    context: &ManagementContext,
+) -> Result<()> {
+    let buf = tokio::time::timeout(REQUEST_READ_TIMEOUT, read_request_head(&mut stream))
+        .await
+        .wrap_err("timed out reading the request")??;
+    let request = String::from_utf8_lossy(&buf);
+    let request = ManagementRequest::parse(&request);
+
+    let response = if request.method == "GET" && matches!(request.path, "/" | "/index.html") {
+        ManagementResponse {
+            status: "200 OK",
+            content_type: "text/html; charset=utf-8",
+            body: DASHBOARD_HTML.to_string(),
+        }
+    } else if !request.is_authorized(&context.token) {
+        log::warn!(
+            "Unauthorized management API request: {} {}",
+            request.method,
+            request.path
+        );
+        ManagementResponse::error("401 Unauthorized", "unauthorized")
+    } else {
+        route_management_request(&request, context).await
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nX-Frame-Options: DENY\r\nContent-Security-Policy: {DASHBOARD_CSP}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        response.content_type,
+        response.body.len(),
+        response.body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+
+    Ok(())
+}
+
+/// Reads the request line & headers, which may arrive in multiple segments, until the empty line
+/// that ends them or until the connection is closed.
+///
+/// ### Errors
+/// - If the request is larger than [`MAX_REQUEST_SIZE`] without its headers ending
+/// - If the connection fails
+async fn read_request_head(stream: &mut (impl AsyncRead + Unpin)) -> Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 1024];
+    while !buf.windows(4).any(|window| window == b"\r\n\r\n") {
+        if buf.len() >= MAX_REQUEST_SIZE {
+            eyre::bail!("request is larger than {MAX_REQUEST_SIZE} bytes");
+        }
+
+        let len = stream.read(&mut chunk).await?;
+        if len == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..len]);
+    }
+
+    Ok(buf)
+}
+
+/// Handles an authorized request.
+async fn route_management_request(
+    request: &ManagementRequest<'_>,
+    context: &ManagementContext,
+) -> ManagementResponse {
+    match (request.method, request.path) {
+        ("GET", "/status") => {
+            let (healthy, report) = context.health.check().await;
+            ManagementResponse::json(
+                "200 OK",
+                serde_json::json!({
+                    "launcher_version": DKN_LAUNCHER_VERSION,
+                    "compute_version": DriaRelease::get_compute_version(&context.exe_dir)
+                        .map(|version| version.trim().to_string()),
+                    "pid": std::process::id(),
+                    "uptime_secs": context.started.elapsed().as_secs(),
+                    "healthy": healthy,
+                    "health": report,
+                }),
+            )
+        }
+        ("GET", "/logs") => {
+            let lines = request
+                .query_param("lines")
+                .and_then(|lines| lines.parse().ok())
+                .unwrap_or(DEFAULT_LOG_LINES)
+                .min(MAX_LOG_LINES);
+            match tokio::fs::read(&context.log_path).await {
+                Ok(content) => ManagementResponse {
+                    status: "200 OK",
+                    content_type: "text/plain; charset=utf-8",
+                    body: tail_lines(&String::from_utf8_lossy(&content), lines),
+                },
+                Err(err) => ManagementResponse::error(
+                    "404 Not Found",
+                    format!("could not read logs: {err}"),
+                ),
+            }
+        }
+        ("GET", "/points") => {
+            let address = match DriaEnv::new_from_env().get_account() {
+                Ok((_, _, address)) => address,
+                Err(err) => return ManagementResponse::error("404 Not Found", err),
+            };
+            match get_points(&address).await {
+                Ok(points) => ManagementResponse::json(
+                    "200 OK",
+                    serde_json::json!({
+                        "address": format!("0x{address}"),
+                        "score": points.score,
+                        "percentile": points.percentile,
+                    }),
+                ),
+                Err(err) => ManagementResponse::error("502 Bad Gateway", format!("{err:#}")),
+            }
+        }
+        ("GET", "/models") => {
+            let mut models = DriaEnv::new_from_env()
+                .get_models()
+                .iter()
+                .map(|model| {
+                    serde_json::json!({
+                        "name": model.to_string(),
+                        "provider": model.provider().to_string(),
+                    })
+                })
+                .collect::<Vec<_>>();
+            models.sort_by_key(|model| model["name"].to_string());
+            ManagementResponse::json("200 OK", serde_json::json!({ "models": models }))
+        }
+        ("GET", "/updates") => {
+            let compute_version = DriaRelease::get_compute_version(&context.exe_dir)
+                .map(|version| version.trim().to_string());
+            let (launcher, compute) = tokio::join!(
+                update_status(
+                    DriaRepository::Launcher,
+                    Some(DKN_LAUNCHER_VERSION.to_string())
+                ),
+                update_status(DriaRepository::ComputeNode, compute_version),
+            );
+            ManagementResponse::json(
+                "200 OK",
+                serde_json::json!({ "launcher": launcher, "compute": compute }),
+            )
+        }
+        ("POST", "/restart") => {
+            log::info!("Compute node restart is requested with the management API.");
+            context.health.request_restart();
+            ManagementResponse::json("202 Accepted", serde_json::json!({ "restarting": true }))
+        }
+        ("GET", "/settings") => {
+            let dria_env = DriaEnv::new_from_env();
+            let masked = dria_env.masked();
+            let settings = DriaEnv::KEY_NAMES
+                .into_iter()
+                .filter_map(|key| Some((key.to_string(), masked.get(key)?.into())))
+                .collect::<serde_json::Map<_, _>>();
+            ManagementResponse::json("200 OK", serde_json::Value::Object(settings))
+        }
+        (
+            _,
+            "/status" | "/logs" | "/points" | "/models" | "/updates" | "/restart" | "/settings",
+        ) => ManagementResponse::error("405 Method Not Allowed", "method not allowed"),
+        _ => ManagementResponse::error("404 Not Found", "not found"),
+    }
+}
+
+/// Returns the current & latest versions of the given repository, the latest version is cached
+/// for a while so that the dashboard does not exhaust the GitHub rate limits.
+async fn update_status(repo: DriaRepository, current: Option<String>) -> serde_json::Value {
+    let latest = cached(&format!("github:latest/{repo}"), async {
+        get_latest_release(repo)
+            .await
+            .map(|release| release.version().to_string())
+    })
+    .await;
+
+    match latest {
+        Ok(latest) => serde_json::json!({
+            "current": current,
+            "available": current.as_deref() != Some(latest.as_str()),
+            "latest": latest,
+        }),
+        Err(err) => serde_json::json!({
+            "current": current,
+            "error": format!("{err:#}"),
+        }),
+    }
+}
+
+/// Returns the last `count` lines of the given content.
+fn tail_lines(content: &str, count: usize) -> String {
+    let lines = content.lines().collect::<Vec<_>>();
+    let mut tail = lines[lines.len().saturating_sub(count)..].join("\n");
+    tail.push('\n');
+    tail
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_management_request() {
+        let request = ManagementRequest::parse(
+            "GET /logs?lines=5&x=1 HTTP/1.1\r\nHost: 127.0.0.1\r\nauthorization: Bearer secret\r\n\r\n",
+        );
+        assert_eq!(request.method, "GET");
+        assert_eq!(request.path, "/logs");
+        assert_eq!(request.query_param("lines"), Some("5"));
+        assert_eq!(request.query_param("y"), None);
+        assert!(request.is_authorized("secret"));
+        assert!(!request.is_authorized("secret2"));
+        assert!(!request.is_authorized("other!"));
+
+        let request = ManagementRequest::parse("POST /restart HTTP/1.1\r\n\r\n");
+        assert_eq!(request.bearer, None);
+        assert!(!request.is_authorized(""));
+
+        assert_eq!(tail_lines("a\nb\nc\n", 2), "b\nc\n");
+        assert_eq!(tail_lines("a\nb\n", 5), "a\nb\n");
+    }
+
+    #[tokio::test]
+    async fn test_read_request_head() {
+        // the headers arrive in segments, and the body is not waited for
+        let (mut client, mut server) = tokio::io::duplex(64);
+        let reader = tokio::spawn(async move { read_request_head(&mut server).await });
+        client.write_all(b"GET /status HTTP/1.1\r\n").await.unwrap();
+        client
+            .write_all(b"Authorization: Bearer secret\r\n")
+            .await
+            .unwrap();
+        client.write_all(b"\r\n").await.unwrap();
+        let head = reader.await.unwrap().unwrap();
+        let head = String::from_utf8_lossy(&head);
+        assert!(ManagementRequest::parse(&head).is_authorized("secret"));
+
+        let (mut client, mut server) = tokio::io::duplex(64);
+        client.write_all(b"GET / HTTP/1.1\r\n").await.unwrap();
+        drop(client);
+        assert_eq!(
+            read_request_head(&mut server).await.unwrap(),
+            b"GET / HTTP/1.1\r\n"
+        );
+    }
+}
@@ -20,12 +20,165 @@ pub mod referrals;
 
 pub mod crypto;
 
+mod ledger;
+pub use ledger::LedgerSigner;
+
 mod signal;
 pub use signal::*;
 
 mod fdlimit;
 pub use fdlimit::configure_fdlimit;
 
+mod logboost;
+pub use logboost::LogBoost;
+
+mod health;
+pub use health::{serve_health, HealthState, ReadyCheck};
+
+mod management;
+pub use management::{serve_management, ManagementContext};
+
+mod timings;
+pub use timings::{enable_timing_summary, TimingSummary};
+
+mod httptrace;
+pub use httptrace::{enable_http_tracing, TracedSend};
+
+mod apiclient;
+pub use apiclient::{send_with_retries, ApiClientPolicy};
+
+mod apicache;
+pub use apicache::{cached, disable_api_cache, invalidate_cached};
+
+mod notify;
+pub use notify::notify_failure;
+
+mod exitcode;
+pub use exitcode::{exit_code, ExitReason};
+
+mod i18n;
+pub use i18n::{language, set_language, translate, translate_with, Language};
+
+mod prompt;
+pub use prompt::with_prompt_timeout;
+
+mod preflight;
+pub use preflight::PreflightChecks;
+
+mod transaction;
+pub use transaction::FsTransaction;
+
+mod logging;
+pub use logging::{init_logger, launcher_log_path, LogFormat};
+
+mod terminal;
+pub use terminal::{
+    colors_enabled, column_widths, init_terminal, is_quiet, new_progress_bar, progress_draw_target,
+    set_shared_progress,
+};
+
+mod output;
+pub use output::ComputeOutput;
+
+mod tps;
+use tps::parse_task_tps;
+pub use tps::{eval_tps, find_slow_models, get_running_models, TpsTracker, MINIMUM_EVAL_TPS};
+
+mod idle;
+pub use idle::run_idle_unloader;
+
+mod terminate;
+pub use terminate::{
+    force_kill, kill_process_tree, request_termination, signal_termination, terminate_gracefully,
+    terminate_pid_gracefully, track_process_tree, wait_or_kill,
+};
+
+mod shutdown;
+pub use shutdown::ShutdownProgress;
+
+mod lock;
+pub use lock::{is_process_running, NodeLock};
+
+mod limits;
+pub use limits::ResourceLimits;
+
+mod registry;
+pub use registry::{is_valid_keep_alive, parse_listen_addrs, parse_listen_port, EnvKey};
+
+mod network;
+pub use network::{
+    audit_network, get_network_env, get_network_urls_with, get_points_api_url, record_network,
+    resolve_referrals_api_base_url, KNOWN_NETWORKS,
+};
+
+mod legacy;
+pub use legacy::{
+    apply_legacy_env, get_current_key_name, offer_legacy_rewrite, OBSOLETE_KEY_NAMES,
+};
+
+mod portmap;
+pub use portmap::{get_local_ip, PortMapping, PortMappingTask, PORT_MAPPING_REMOVAL_TIMEOUT};
+
+#[cfg(windows)]
+mod quarantine;
+#[cfg(windows)]
+pub use quarantine::{
+    check_quarantine, is_quarantined, offer_quarantine_redownload, quarantine_hint,
+};
+
+mod ports;
+pub use ports::{
+    assign_profile_ports, offer_free_port, other_profile_env_paths, resolve_p2p_port_conflict,
+};
+
+mod benchmarks;
+pub use benchmarks::{benchmark_hardware, BenchmarkCache, BenchmarkResult};
+
+mod hardware;
+pub use hardware::{estimate_model_memory_mb, HardwareInfo};
+
+mod utilization;
+pub use utilization::{Utilization, UtilizationSampler};
+
+mod ollama_install;
+pub use ollama_install::{install_ollama, installed_ollama_path};
+
+mod ollama_pid;
+pub use ollama_pid::OllamaPidFile;
+
+mod banner;
+pub use banner::{format_startup_banner, is_valid_profile_name, profile_name};
+
+mod delta;
+pub use delta::{apply_patch, patch_asset_name};
+
+mod github;
+pub(crate) use github::{fetch_github_api, parse_github_release, with_github_auth};
+
+mod scheduler;
+pub use scheduler::{UpdateCheck, UpdateScheduler};
+
+mod watchdog;
+pub use watchdog::{Watchdog, WATCHDOG_CHECK_INTERVAL};
+
+mod reload;
+pub use reload::ReloadPlan;
+
+mod points;
+pub use points::get_points;
+
+mod wallets;
+pub use wallets::{AddressBook, SavedWallet};
+
+mod envwatch;
+pub use envwatch::{append_env_audit, audit_change, EnvWatcher, DKN_ENV_AUDIT_FILE};
+
+mod identity;
+pub use identity::NodeIdentity;
+
+mod datadir;
+pub use datadir::{follow_moved_data, shared_data_dir, DKN_MOVED_MARKER_FILE};
+
 /// The launcher version, taken from the `Cargo.toml` file of the running binary.
 pub const DKN_LAUNCHER_VERSION: &str = env!("CARGO_PKG_VERSION");
 
@@ -48,17 +201,3 @@ pub const PROGRESS_BAR_CHARS: &str = "=>-";
 /// `UserAgent` header value for the launcher, used for HTTP requests.
 pub const LAUNCHER_USER_AGENT: &str =
     concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
-
-/// Returns the network type based on the `DKN_NETWORK` environment variable.
-///
-/// This is usually not used at all by the user, but rather used in testing and development.
-#[inline(always)]
-pub fn get_network_env() -> String {
-    std::env::var("DKN_NETWORK")
-        .map(|s| match s.as_str() {
-            // only accept `testnet` as a valid network, otherwise default to `mainnet`
-            "testnet" => s,
-            _ => "mainnet".to_string(),
-        })
-        .unwrap_or_else(|_| "mainnet".to_string())
-}
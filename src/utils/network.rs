@@ -0,0 +1,208 @@
+use std::path::Path;
+use std::time::Duration;
+
+use super::{DriaEnv, TracedSend, LAUNCHER_USER_AGENT};
+
+/// The filename for the network tracker file, simply stores the network that was last used.
+pub const DKN_NETWORK_TRACKER_FILE: &str = ".dkn-network";
+
+/// Networks that are recognized by `DKN_NETWORK`.
+pub const KNOWN_NETWORKS: [&str; 2] = ["mainnet", "testnet"];
+
+/// Timeout for the reachability check of each URL.
+const URL_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Returns the network type based on the `DKN_NETWORK` environment variable.
+///
+/// This is usually not changed by the user, but rather used in testing and development;
+/// it can be set within the env file via the settings menu as well.
+#[inline(always)]
+pub fn get_network_env() -> String {
+    std::env::var(DriaEnv::DKN_NETWORK_KEY)
+        .map(|s| match s.as_str() {
+            // only accept `testnet` as a valid network, otherwise default to `mainnet`
+            "testnet" => s,
+            _ => "mainnet".to_string(),
+        })
+        .unwrap_or_else(|_| "mainnet".to_string())
+}
+
+/// Returns the base URL of the points API for the given network.
+#[inline]
+pub fn get_points_api_base_url(network: &str) -> String {
+    format!("https://{network}.dkn.dria.co/points/v0")
+}
+
+/// Returns the base URL of the referrals API for the given network.
+#[inline]
+pub fn get_referrals_api_base_url(network: &str) -> String {
+    format!("https://{network}.dkn.dria.co/referral/v0")
+}
+
+/// Returns the base URL of the points API, which is `DKN_POINTS_API_URL` if set,
+/// or derived from the network otherwise.
+pub fn resolve_points_api_base_url() -> String {
+    get_url_override(DriaEnv::DKN_POINTS_API_URL_KEY, process_env_lookup)
+        .unwrap_or_else(|| get_points_api_base_url(&get_network_env()))
+}
+
+/// Returns the base URL of the referrals API, which is `DKN_REFERRALS_API_URL` if set,
+/// or derived from the network otherwise.
+pub fn resolve_referrals_api_base_url() -> String {
+    get_url_override(DriaEnv::DKN_REFERRALS_API_URL_KEY, process_env_lookup)
+        .unwrap_or_else(|| get_referrals_api_base_url(&get_network_env()))
+}
+
+/// Returns the points API URL for the given address, w.r.t the network.
+#[inline]
+pub fn get_points_api_url(address: &str) -> String {
+    let base_url = resolve_points_api_base_url();
+    let address = address.trim_start_matches("0x");
+    format!("{base_url}/total/node/0x{address}")
+}
+
+/// Returns all URLs that are used with the given network along with their names,
+/// w.r.t the overrides within the process environment.
+#[inline]
+pub fn get_network_urls(network: &str) -> Vec<(&'static str, String)> {
+    get_network_urls_with(network, process_env_lookup)
+}
+
+/// Returns all URLs that are used with the given network along with their names, where the
+/// overrides of base URLs (e.g. `DKN_POINTS_API_URL`) are looked up with the given function.
+pub fn get_network_urls_with(
+    network: &str,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Vec<(&'static str, String)> {
+    vec![
+        (
+            "points",
+            get_url_override(DriaEnv::DKN_POINTS_API_URL_KEY, &lookup)
+                .unwrap_or_else(|| get_points_api_base_url(network)),
+        ),
+        (
+            "referrals",
+            get_url_override(DriaEnv::DKN_REFERRALS_API_URL_KEY, &lookup)
+                .unwrap_or_else(|| get_referrals_api_base_url(network)),
+        ),
+    ]
+}
+
+/// Returns the base URL override within the given key, without a trailing slash.
+fn get_url_override(key: &str, lookup: impl Fn(&str) -> Option<String>) -> Option<String> {
+    lookup(key)
+        .map(|url| url.trim().trim_end_matches('/').to_string())
+        .filter(|url| !url.is_empty())
+}
+
+#[inline]
+fn process_env_lookup(key: &str) -> Option<String> {
+    std::env::var(key).ok()
+}
+
+/// Records the current network within the given directory, and returns the previously
+/// recorded network if it is different than the current one.
+///
+/// Nothing is returned when the network is recorded for the first time.
+pub fn record_network(exe_dir: &Path) -> Option<String> {
+    let network = get_network_env();
+    let tracker_path = exe_dir.join(DKN_NETWORK_TRACKER_FILE);
+    let previous = std::fs::read_to_string(&tracker_path)
+        .ok()
+        .map(|previous| previous.trim().to_string());
+
+    if previous.as_deref() != Some(network.as_str()) {
+        if let Err(err) = std::fs::write(&tracker_path, &network) {
+            log::warn!("Could not record the network: {err}");
+        }
+    }
+
+    previous.filter(|previous| *previous != network)
+}
+
+/// Lists the URLs that are derived from the current network (and how they changed w.r.t the
+/// previous network, if given), and checks if each of them is reachable.
+///
+/// Returns `true` if all URLs are reachable.
+pub async fn audit_network(previous: Option<&str>) -> bool {
+    let network = get_network_env();
+    if let Ok(value) = std::env::var(DriaEnv::DKN_NETWORK_KEY) {
+        if !KNOWN_NETWORKS.contains(&value.as_str()) {
+            log::warn!("Unknown DKN_NETWORK \"{value}\", falling back to {network}.");
+        }
+    }
+
+    match previous {
+        Some(previous) => log::warn!("Network has changed from {previous} to {network}."),
+        None => log::info!("Using the {network} network."),
+    }
+
+    let previous_urls = previous.map(get_network_urls).unwrap_or_default();
+    let mut all_reachable = true;
+    for (name, url) in get_network_urls(&network) {
+        let reachable = is_url_reachable(&url).await;
+        all_reachable &= reachable;
+
+        let status = if reachable {
+            "reachable"
+        } else {
+            "unreachable"
+        };
+        match previous_urls
+            .iter()
+            .find(|(prev_name, _)| *prev_name == name)
+        {
+            Some((_, previous_url)) if *previous_url != url => {
+                log::info!("  {name}: {previous_url} -> {url} ({status})")
+            }
+            _ => log::info!("  {name}: {url} ({status})"),
+        }
+    }
+
+    if !all_reachable {
+        log::warn!("Some endpoints of the {network} network are unreachable.");
+    }
+
+    all_reachable
+}
+
+/// Returns `true` if the given URL responds at all, regardless of the status code.
+async fn is_url_reachable(url: &str) -> bool {
+    let Ok(client) = reqwest::Client::builder()
+        .user_agent(LAUNCHER_USER_AGENT)
+        .timeout(URL_CHECK_TIMEOUT)
+        .build()
+    else {
+        return false;
+    };
+
+    client.get(url).send_traced().await.is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_network_urls() {
+        let mainnet = get_network_urls("mainnet");
+        let testnet = get_network_urls("testnet");
+        assert_eq!(mainnet.len(), testnet.len());
+        for ((name, mainnet_url), (_, testnet_url)) in mainnet.iter().zip(testnet.iter()) {
+            assert!(mainnet_url.contains("mainnet"), "{name}");
+            assert!(testnet_url.contains("testnet"), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_network_url_overrides() {
+        let urls = get_network_urls_with("testnet", |key| {
+            (key == DriaEnv::DKN_POINTS_API_URL_KEY).then(|| "https://points.internal/v0/".into())
+        });
+        assert_eq!(
+            urls[0],
+            ("points", "https://points.internal/v0".to_string())
+        );
+        assert!(urls[1].1.contains("testnet"));
+    }
+}
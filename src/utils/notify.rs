@@ -0,0 +1,226 @@
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use super::{shared_data_dir, DriaEnv, ExitReason};
+
+/// How long a notification command may take, notifications must never hold the launcher back.
+const NOTIFY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Notifies the operator that the node has stopped unattended, i.e. the compute node has exited
+/// and the restart policy has given up, with a desktop notification (`DKN_NOTIFY_DESKTOP`) and an
+/// email (`DKN_NOTIFY_EMAIL`) if they are enabled.
+///
+/// Desktop notifications are shown with `notify-send` on Linux, `osascript` on macOS and
+/// PowerShell on Windows. Emails are sent through the SMTP server of `DKN_NOTIFY_SMTP_URL` with
+/// `curl` if it is set, see [`send_smtp_email`]; otherwise they are handed to the `sendmail`
+/// command of the system (e.g. from Postfix or msmtp), which relays them w.r.t its own configuration.
+///
+/// Notifying is best-effort: failures are only logged.
+///
+/// ### Arguments
+/// - `dria_env`: the environment with the notification settings
+/// - `reason`: why the node has stopped
+/// - `details`: further details, e.g. the last error of the compute node
+pub async fn notify_failure(dria_env: &DriaEnv, reason: ExitReason, details: Option<&str>) {
+    let title = match dria_env.get_node_name() {
+        Some(name) => format!("Dria node {name} has stopped"),
+        None => "Dria node has stopped".to_string(),
+    };
+    let body = match details {
+        Some(details) => format!("The launcher has given up: {reason}.\n{details}"),
+        None => format!("The launcher has given up: {reason}."),
+    };
+
+    if dria_env.is_desktop_notify_enabled() {
+        match desktop_command(&title, &body) {
+            Some(command) => run_notify_command(command, None, "desktop notification").await,
+            None => log::warn!("Desktop notifications are not supported on this platform."),
+        }
+    }
+
+    if let Some(to) = dria_env.get_notify_email() {
+        match dria_env.get_notify_smtp() {
+            Some((url, auth)) => send_smtp_email(url, auth, to, &title, &body).await,
+            None => {
+                let mut command = Command::new(sendmail_path());
+                command.arg("-t");
+                let message = format_email(None, to, &title, &body);
+                run_notify_command(command, Some(message), "notification email").await;
+            }
+        }
+    }
+}
+
+/// Sends the email through the given SMTP server with `curl`, which is required to use TLS.
+///
+/// The credentials are given within the config that `curl` reads from `stdin`, so that they do not
+/// show up within the process list; the message is given with a file within the data directory.
+///
+/// ### Arguments
+/// - `url`: the SMTP server, e.g. `smtps://smtp.example.com:465`
+/// - `auth`: the `username:password` credentials, where the username is the sender address; the
+///   recipient is the sender without credentials
+async fn send_smtp_email(url: &str, auth: Option<&str>, to: &str, title: &str, body: &str) {
+    let from = auth
+        .and_then(|auth| auth.split_once(':'))
+        .map_or(to, |(user, _)| user);
+    let Some(message_path) =
+        shared_data_dir().map(|dir| dir.join(format!("notify-email-{}.txt", std::process::id())))
+    else {
+        log::warn!("Could not send notification email: no home directory to write it to");
+        return;
+    };
+    if let Err(err) = message_path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::write(&message_path, format_email(Some(from), to, title, body)))
+    {
+        log::warn!("Could not send notification email: {err}");
+        return;
+    }
+
+    let mut command = Command::new("curl");
+    command
+        .args(["--silent", "--ssl-reqd", "--config", "-"])
+        .args(["--url", url, "--mail-from", from, "--mail-rcpt", to])
+        .arg("--upload-file")
+        .arg(&message_path);
+    let config = auth.map(curl_user_config).unwrap_or_default();
+    run_notify_command(command, Some(config), "notification email").await;
+
+    if let Err(err) = std::fs::remove_file(&message_path) {
+        log::debug!("Could not remove {}: {err}", message_path.display());
+    }
+}
+
+/// Returns the `curl` config that holds the given `username:password` credentials.
+fn curl_user_config(auth: &str) -> String {
+    format!(
+        "user = \"{}\"\n",
+        auth.replace('\\', "\\\\").replace('"', "\\\"")
+    )
+}
+
+/// Runs the given notification command, writing the input to its `stdin` if there is one.
+async fn run_notify_command(mut command: Command, input: Option<String>, what: &str) {
+    command
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true);
+
+    let run = async {
+        let mut child = command.spawn()?;
+        if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+            stdin.write_all(input.as_bytes()).await?;
+        }
+        child.wait().await
+    };
+    match tokio::time::timeout(NOTIFY_TIMEOUT, run).await {
+        Ok(Ok(status)) if status.success() => log::info!("Sent {what}."),
+        Ok(Ok(status)) => log::warn!("Could not send {what}: exited with {status}"),
+        Ok(Err(err)) => log::warn!("Could not send {what}: {err}"),
+        Err(_) => log::warn!("Could not send {what}: timed out"),
+    }
+}
+
+/// Returns the command that shows a desktop notification on this platform.
+fn desktop_command(title: &str, body: &str) -> Option<Command> {
+    if cfg!(target_os = "linux") {
+        let mut command = Command::new("notify-send");
+        command.args(["--urgency=critical", "--app-name=Dria", title, body]);
+        Some(command)
+    } else if cfg!(target_os = "macos") {
+        let mut command = Command::new("osascript");
+        command.arg("-e").arg(format!(
+            "display notification {} with title {}",
+            applescript_string(body),
+            applescript_string(title)
+        ));
+        Some(command)
+    } else if cfg!(windows) {
+        let mut command = Command::new("powershell");
+        command
+            .args(["-NoProfile", "-NonInteractive", "-Command"])
+            .arg(format!(
+                "Add-Type -AssemblyName System.Windows.Forms; \
+             $n = New-Object System.Windows.Forms.NotifyIcon; \
+             $n.Icon = [System.Drawing.SystemIcons]::Warning; $n.Visible = $true; \
+             $n.ShowBalloonTip(10000, {}, {}, 'Error'); Start-Sleep -Seconds 5; $n.Dispose()",
+                powershell_string(title),
+                powershell_string(body)
+            ));
+        Some(command)
+    } else {
+        None
+    }
+}
+
+/// Returns the path of the `sendmail` command, which is not always within `PATH` (e.g. `/usr/sbin`).
+fn sendmail_path() -> &'static str {
+    ["/usr/sbin/sendmail", "/usr/lib/sendmail"]
+        .into_iter()
+        .find(|path| std::path::Path::new(path).exists())
+        .unwrap_or("sendmail")
+}
+
+/// Returns the email message for `sendmail -t`, which reads the recipients from the headers; the
+/// sender is set by `sendmail` if it is not given.
+fn format_email(from: Option<&str>, to: &str, subject: &str, body: &str) -> String {
+    // headers must not contain line breaks, otherwise further headers could be injected
+    let header = |value: &str| value.replace(['\r', '\n'], " ");
+
+    let from = from.map_or_else(String::new, |from| format!("From: {}\n", header(from)));
+    format!(
+        "{from}To: {}\nSubject: {}\nContent-Type: text/plain; charset=utf-8\n\n{body}\n",
+        header(to),
+        header(subject)
+    )
+}
+
+/// Returns the given text as an AppleScript string literal.
+fn applescript_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Returns the given text as a PowerShell string literal, without any interpolation.
+fn powershell_string(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notification_formats() {
+        assert_eq!(
+            format_email(
+                None,
+                "ops@example.com\nBcc: x@example.com",
+                "Node stopped",
+                "details"
+            ),
+            "To: ops@example.com Bcc: x@example.com\nSubject: Node stopped\nContent-Type: text/plain; charset=utf-8\n\ndetails\n"
+        );
+        assert!(
+            format_email(Some("node@example.com"), "ops@example.com", "a", "b")
+                .starts_with("From: node@example.com\nTo: ops@example.com\n")
+        );
+        assert_eq!(
+            curl_user_config(r#"node@example.com:p"a\ss"#),
+            "user = \"node@example.com:p\\\"a\\\\ss\"\n"
+        );
+        assert_eq!(
+            applescript_string(r#"say "hi" \ bye"#),
+            r#""say \"hi\" \\ bye""#
+        );
+        assert_eq!(powershell_string("it's $env:PATH"), "'it''s $env:PATH'");
+    }
+}
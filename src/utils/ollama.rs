@@ -1,18 +1,36 @@
+use base64::Engine;
 use dkn_executor::ollama_rs::{error::OllamaError, Ollama};
 use eyre::{Context, Result};
 use futures::StreamExt;
-use indicatif::{ProgressBar, ProgressStyle};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
 use std::env;
+use std::io::SeekFrom;
+use std::path::PathBuf;
 use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncSeekExt, BufReader};
 use tokio::process::{Child, Command};
 use which::which;
 
 use crate::DriaEnv;
 
-use super::{PROGRESS_BAR_CHARS, PROGRESS_BAR_TEMPLATE};
+use super::{
+    installed_ollama_path, is_process_running, logging::open_log_file, progress_draw_target,
+    shared_data_dir, track_process_tree, OllamaPidFile, TracedSend, PROGRESS_BAR_CHARS,
+    PROGRESS_BAR_TEMPLATE,
+};
 
 const OLLAMA_RETRY_COUNT: usize = 10;
+/// Error message when the Ollama executable is not found, see [`is_ollama_installed`].
+pub const OLLAMA_NOT_FOUND_MESSAGE: &str = "could not find Ollama executable, please run `dkn-compute-launcher ollama install` or install it from https://ollama.com/download";
 const OLLAMA_RETRY_INTERVAL_MILLIS: u64 = 500;
+/// Delay before the first retry of a failed model pull, doubled for each retry.
+const PULL_RETRY_BASE_DELAY: Duration = Duration::from_secs(2);
+/// Maximum delay between retries of a failed model pull.
+const PULL_RETRY_MAX_DELAY: Duration = Duration::from_secs(60);
+/// Interval between the checks for new lines within the Ollama log, see [`stream_ollama_log`].
+const OLLAMA_LOG_STREAM_INTERVAL: Duration = Duration::from_millis(500);
 
 /// Spawns a local Ollama server process at the given host and port.
 ///
@@ -23,24 +41,45 @@ const OLLAMA_RETRY_INTERVAL_MILLIS: u64 = 500;
 /// A `Child` process handle to the spawned Ollama process.
 ///
 /// ### Errors
+/// - If Ollama is configured as a remote server, i.e. over HTTPS or with credentials.
 /// - If the Ollama executable is not found in the system.
 pub async fn spawn_ollama(dria_env: &DriaEnv) -> Result<Child> {
     let (host, port) = dria_env.get_ollama_config();
 
-    // find the path to binary
-    let exe_path = which("ollama").wrap_err(
-        "could not find Ollama executable, please install it from https://ollama.com/download",
-    )?;
+    // a remote server can not be spawned locally, so it must be unreachable
+    if is_remote_ollama(dria_env) {
+        eyre::bail!(
+            "could not reach Ollama at {host}:{port}, please check {}, {} and the credentials",
+            DriaEnv::OLLAMA_HOST_KEY,
+            DriaEnv::OLLAMA_PORT_KEY
+        );
+    }
+
+    // find the path to binary, falling back to the one installed by the launcher
+    let exe_path = which("ollama")
+        .ok()
+        .or_else(installed_ollama_path)
+        .ok_or_else(|| eyre::eyre!("{OLLAMA_NOT_FOUND_MESSAGE}"))?;
 
     // ollama requires the OLLAMA_HOST environment variable to be set before launching
     let old_var = env::var(DriaEnv::OLLAMA_HOST_KEY).ok();
     env::set_var(DriaEnv::OLLAMA_HOST_KEY, format!("{}:{}", host, port));
+    let (stdout, stderr) = ollama_output();
     let command = Command::new(exe_path)
         .arg("serve")
-        .stdout(Stdio::null()) // ignored
-        .stderr(Stdio::null()) // ignored
+        // keep-alive, context length & parallelism, if configured
+        .envs(dria_env.get_ollama_server_env())
+        .stdout(stdout)
+        .stderr(stderr)
         .spawn()
         .wrap_err("could not spawn Ollama")?;
+    track_process_tree(&command, "Ollama");
+    if let Some(pid) = command.id() {
+        OllamaPidFile::record(port, pid);
+        if dria_env.is_ollama_log_stream_enabled() {
+            tokio::spawn(stream_ollama_log(pid));
+        }
+    }
 
     // restore old variable
     if let Some(val) = old_var {
@@ -61,27 +100,310 @@ pub async fn spawn_ollama(dria_env: &DriaEnv) -> Result<Child> {
         .await;
     }
     if !check_ollama(dria_env).await {
-        eyre::bail!("Ollama failed to start after {OLLAMA_RETRY_COUNT} retries");
+        eyre::bail!(
+            "Ollama failed to start after {OLLAMA_RETRY_COUNT} retries{}",
+            ollama_log_path()
+                .map(|path| format!(", see its logs at {}", path.display()))
+                .unwrap_or_default()
+        );
     }
 
     Ok(command)
 }
 
+/// Returns the path of the log file of the Ollama spawned by the launcher, which is shared by
+/// all profiles like Ollama itself: `~/.dria/dkn-compute-launcher/ollama.log`; `None` without a
+/// home directory, in which case the logs are not kept.
+pub fn ollama_log_path() -> Option<PathBuf> {
+    shared_data_dir().map(|dir| dir.join("ollama.log"))
+}
+
+/// Returns the outputs for the spawned Ollama, which are appended to [`ollama_log_path`] so
+/// that GPU & driver errors can be diagnosed.
+///
+/// Ollama writes to the file by itself, so that its output is kept as is (even if it is not
+/// UTF-8), and it is not stopped by a broken pipe once the launcher exits, e.g. when it is
+/// adopted by the next launcher.
+fn ollama_output() -> (Stdio, Stdio) {
+    let Some(log_path) = ollama_log_path() else {
+        log::warn!("Could not find the home directory, Ollama logs will not be kept.");
+        return (Stdio::null(), Stdio::null());
+    };
+    let files = open_log_file(&log_path).and_then(|file| Some((file.try_clone().ok()?, file)));
+    match files {
+        Some((stdout, stderr)) => (Stdio::from(stdout), Stdio::from(stderr)),
+        None => {
+            log::warn!(
+                "Could not open {}, Ollama logs will not be kept.",
+                log_path.display()
+            );
+            (Stdio::null(), Stdio::null())
+        }
+    }
+}
+
+/// Follows the new lines of [`ollama_log_path`] and logs them at debug level, until the Ollama
+/// with the given PID exits; invalid UTF-8 is replaced instead of ending the stream.
+async fn stream_ollama_log(pid: u32) {
+    let Some(log_path) = ollama_log_path() else {
+        return;
+    };
+    let Ok(mut file) = tokio::fs::File::open(log_path).await else {
+        return;
+    };
+    if file.seek(SeekFrom::End(0)).await.is_err() {
+        return;
+    }
+
+    let mut reader = BufReader::new(file);
+    let mut line = Vec::new();
+    loop {
+        match reader.read_until(b'\n', &mut line).await {
+            // a partial line is kept until the rest of it is written
+            Ok(_) if line.ends_with(b"\n") => {
+                log::debug!("ollama: {}", String::from_utf8_lossy(&line).trim_end());
+                line.clear();
+            }
+            Ok(_) | Err(_) => {
+                if !is_process_running(pid) {
+                    return;
+                }
+                tokio::time::sleep(OLLAMA_LOG_STREAM_INTERVAL).await;
+            }
+        }
+    }
+}
+
 /// Checks if ollama is running at the configured host & port, returns `true` if it is.
 ///
 /// Ollama responds to a GET request at its root with "Ollama is running".
 pub async fn check_ollama(dria_env: &DriaEnv) -> bool {
     let (host, port) = dria_env.get_ollama_config();
+    let client = match ollama_http_client(dria_env) {
+        Ok(client) => client,
+        Err(err) => {
+            log::warn!("Could not create Ollama client: {err:?}");
+            return false;
+        }
+    };
 
-    match reqwest::get(&format!("{}:{}", host, port)).await {
+    match client.get(format!("{}:{}", host, port)).send_traced().await {
         Ok(response) => response.status().is_success(),
         Err(_) => false,
     }
 }
 
+/// Creates an Ollama client at the configured host & port, with the configured credentials & CA.
+pub fn connect_ollama(dria_env: &DriaEnv) -> Result<Ollama> {
+    let (host, port) = dria_env.get_ollama_config();
+    Ok(Ollama::new_with_client(
+        host,
+        port,
+        ollama_http_client(dria_env)?,
+    ))
+}
+
+/// Returns the names of the models that are available locally in Ollama.
+pub async fn list_local_model_names(dria_env: &DriaEnv) -> Result<Vec<String>> {
+    let local_models = connect_ollama(dria_env)?.list_local_models().await?;
+
+    Ok(local_models.into_iter().map(|m| m.name).collect())
+}
+
+/// Creates an HTTP client for Ollama requests.
+///
+/// - If `OLLAMA_AUTH_TOKEN` is set, it is sent as a bearer token.
+/// - Otherwise if `OLLAMA_BASIC_AUTH` is set as `username:password`, it is sent as basic auth.
+/// - If `OLLAMA_CA_CERT` is set, the PEM certificate(s) within are trusted as root certificates.
+///
+/// ### Errors
+/// - If the credentials are not valid header values.
+/// - If the CA certificate could not be read or parsed.
+pub fn ollama_http_client(dria_env: &DriaEnv) -> Result<reqwest::Client> {
+    let get_value = |key| dria_env.get(key).map(str::trim).filter(|v| !v.is_empty());
+
+    let mut headers = HeaderMap::new();
+    let authorization = if let Some(token) = get_value(DriaEnv::OLLAMA_AUTH_TOKEN_KEY) {
+        Some(format!("Bearer {token}"))
+    } else {
+        get_value(DriaEnv::OLLAMA_BASIC_AUTH_KEY).map(|credentials| {
+            let encoded = base64::engine::general_purpose::STANDARD.encode(credentials);
+            format!("Basic {encoded}")
+        })
+    };
+    if let Some(authorization) = authorization {
+        let mut value = HeaderValue::from_str(&authorization)
+            .wrap_err("Ollama credentials must be valid header values")?;
+        value.set_sensitive(true);
+        headers.insert(AUTHORIZATION, value);
+    }
+
+    let mut builder = reqwest::Client::builder().default_headers(headers);
+    if let Some(ca_path) = get_value(DriaEnv::OLLAMA_CA_CERT_KEY) {
+        let pem = std::fs::read(ca_path)
+            .wrap_err_with(|| format!("could not read Ollama CA certificate at {ca_path}"))?;
+        for cert in reqwest::Certificate::from_pem_bundle(&pem)
+            .wrap_err("could not parse Ollama CA certificate")?
+        {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+
+    builder.build().wrap_err("could not build Ollama client")
+}
+
+/// Returns the Ollama credential & certificate keys that are set; these are only used by the
+/// launcher, as the compute node connects to Ollama without them.
+pub fn ollama_launcher_only_keys(dria_env: &DriaEnv) -> Vec<&'static str> {
+    [
+        DriaEnv::OLLAMA_AUTH_TOKEN_KEY,
+        DriaEnv::OLLAMA_BASIC_AUTH_KEY,
+        DriaEnv::OLLAMA_CA_CERT_KEY,
+    ]
+    .into_iter()
+    .filter(|key| dria_env.get(key).is_some_and(|v| !v.trim().is_empty()))
+    .collect()
+}
+
+/// Returns `true` if Ollama is configured as a remote server, i.e. over HTTPS, with credentials
+/// or at a host other than this machine.
+pub fn is_remote_ollama(dria_env: &DriaEnv) -> bool {
+    let (host, _) = dria_env.get_ollama_config();
+    host.starts_with("https://")
+        || !is_local_host(host)
+        || [
+            DriaEnv::OLLAMA_AUTH_TOKEN_KEY,
+            DriaEnv::OLLAMA_BASIC_AUTH_KEY,
+        ]
+        .into_iter()
+        .any(|key| dria_env.get(key).is_some_and(|v| !v.trim().is_empty()))
+}
+
+/// Returns `true` if the given host (e.g. `http://127.0.0.1`) is a loopback or unspecified
+/// address, or `localhost`; i.e. this machine.
+fn is_local_host(host: &str) -> bool {
+    let url = reqwest::Url::parse(host).or_else(|_| reqwest::Url::parse(&format!("http://{host}")));
+    let Some(host) = url.as_ref().ok().and_then(|url| url.host_str()) else {
+        return false;
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    match host.parse::<std::net::IpAddr>() {
+        Ok(ip) => ip.is_loopback() || ip.is_unspecified(),
+        Err(_) => host.eq_ignore_ascii_case("localhost"),
+    }
+}
+
+/// Returns `true` if the Ollama executable is found, either on `PATH` or installed by the launcher.
+pub fn is_ollama_installed() -> bool {
+    which("ollama").is_ok() || installed_ollama_path().is_some()
+}
+
 /// Pulls a model from the Ollama server with progress indication.
-pub async fn pull_model_with_progress(ollama: &Ollama, model_name: String) -> Result<()> {
-    let mut pull_stream = ollama.pull_model_stream(model_name.clone(), false).await?;
+///
+/// Failed pulls are retried with an exponential backoff, up to `retries` times. Ollama keeps
+/// the partially downloaded layers, so a retried pull resumes from where it was left off.
+///
+/// ### Errors
+/// - If the model could not be found in the registry, which is not retried.
+/// - If the pull fails after all retries, with a hint if the failure was due to DNS.
+pub async fn pull_model_with_progress(
+    ollama: &Ollama,
+    model_name: String,
+    retries: u32,
+) -> Result<()> {
+    pull_model_with_retries(
+        ollama,
+        &model_name,
+        retries,
+        &MultiProgress::with_draw_target(progress_draw_target()),
+    )
+    .await
+}
+
+/// Pulls the given models concurrently with progress indication, at most `parallelism` at a time.
+///
+/// Each model is pulled with retries as in [`pull_model_with_progress`], and a failure does not
+/// stop the other pulls.
+///
+/// ### Errors
+/// - If any of the models could not be pulled, listing all failures.
+pub async fn pull_models_with_progress(
+    ollama: &Ollama,
+    model_names: Vec<String>,
+    retries: u32,
+    parallelism: usize,
+) -> Result<()> {
+    let progress = MultiProgress::with_draw_target(progress_draw_target());
+    let errors = futures::stream::iter(model_names)
+        .map(|model_name| {
+            let progress = &progress;
+            async move { pull_model_with_retries(ollama, &model_name, retries, progress).await }
+        })
+        .buffer_unordered(parallelism.max(1))
+        .filter_map(|result| async move { result.err() })
+        .collect::<Vec<_>>()
+        .await;
+
+    match errors.len() {
+        0 => Ok(()),
+        1 => Err(errors.into_iter().next().expect("has one error")),
+        _ => eyre::bail!(
+            "could not pull {} models:\n{}",
+            errors.len(),
+            errors
+                .iter()
+                .map(|err| format!("  - {err}"))
+                .collect::<Vec<_>>()
+                .join("\n")
+        ),
+    }
+}
+
+/// Pulls a model with retries, adding its progress bar to the given display.
+async fn pull_model_with_retries(
+    ollama: &Ollama,
+    model_name: &str,
+    retries: u32,
+    progress: &MultiProgress,
+) -> Result<()> {
+    let mut attempt = 0;
+    loop {
+        let Err(message) = pull_model_once(ollama, model_name, progress).await? else {
+            return Ok(());
+        };
+
+        let kind = PullErrorKind::classify(&message);
+        if kind == PullErrorKind::NotFound || attempt >= retries {
+            return Err(kind.into_report(model_name, &message));
+        }
+
+        attempt += 1;
+        let delay = PULL_RETRY_BASE_DELAY
+            .saturating_mul(1 << (attempt - 1).min(5))
+            .min(PULL_RETRY_MAX_DELAY);
+        log::warn!(
+            "Failed to pull model {model_name}: {message}\nRetrying in {}s ({attempt}/{retries})",
+            delay.as_secs()
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Pulls a model once, adding its progress bar to the given display.
+///
+/// Returns the error message within if the pull fails, so that the caller can decide to retry.
+async fn pull_model_once(
+    ollama: &Ollama,
+    model_name: &str,
+    progress: &MultiProgress,
+) -> Result<Result<(), String>> {
+    let mut pull_stream = match ollama
+        .pull_model_stream(model_name.to_string(), false)
+        .await
+    {
+        Ok(pull_stream) => pull_stream,
+        Err(err) => return Ok(Err(ollama_error_message(err))),
+    };
     let mut pull_error: Option<OllamaError> = None;
     let mut pull_bar: Option<ProgressBar> = None;
     while let Some(status) = pull_stream.next().await {
@@ -96,13 +418,15 @@ pub async fn pull_model_with_progress(ollama: &Ollama, model_name: String) -> Re
                 // otherwise try to create bar
                 if let Some(total) = status.total {
                     pull_bar = Some(
-                        ProgressBar::new(total)
-                            .with_message(format!("Pulling {}", model_name))
-                            .with_style(
-                                ProgressStyle::default_bar()
-                                    .template(PROGRESS_BAR_TEMPLATE)?
-                                    .progress_chars(PROGRESS_BAR_CHARS),
-                            ),
+                        progress.add(
+                            ProgressBar::new(total)
+                                .with_message(format!("Pulling {}", model_name))
+                                .with_style(
+                                    ProgressStyle::default_bar()
+                                        .template(PROGRESS_BAR_TEMPLATE)?
+                                        .progress_chars(PROGRESS_BAR_CHARS),
+                                ),
+                        ),
                     );
                 }
             }
@@ -114,19 +438,98 @@ pub async fn pull_model_with_progress(ollama: &Ollama, model_name: String) -> Re
     }
 
     if let Some(err) = pull_error {
-        log::error!("Failed to pull model {}: {:?}", model_name, err);
         // no need to care about `pull_bar` here, it will be dropped
+        return Ok(Err(ollama_error_message(err)));
     } else if let Some(pb) = pull_bar {
         pb.finish_with_message(format!("{} pull complete.", model_name));
     }
 
-    Ok(())
+    Ok(Ok(()))
+}
+
+/// Returns the most descriptive message of an Ollama error, i.e. including its source.
+fn ollama_error_message(err: OllamaError) -> String {
+    match err {
+        OllamaError::InternalError(err) => err.message,
+        OllamaError::ReqwestError(err) => format!("could not reach Ollama: {err}"),
+        err => err.to_string(),
+    }
+}
+
+/// Kind of a model pull failure, to tell the user what went wrong.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PullErrorKind {
+    /// The registry could not be resolved, e.g. due to broken DNS within WSL.
+    Dns,
+    /// The model does not exist in the registry.
+    NotFound,
+    /// Any other error, e.g. the connection was dropped or the registry returned an error.
+    Other,
+}
+
+impl PullErrorKind {
+    /// Classifies the error message returned by Ollama.
+    fn classify(message: &str) -> Self {
+        let message = message.to_lowercase();
+        if ["no such host", "name resolution", "server misbehaving"]
+            .iter()
+            .any(|pattern| message.contains(pattern))
+        {
+            Self::Dns
+        } else if ["file does not exist", "manifest unknown", "not found"]
+            .iter()
+            .any(|pattern| message.contains(pattern))
+        {
+            Self::NotFound
+        } else {
+            Self::Other
+        }
+    }
+
+    /// Creates an error report for the given model & message.
+    fn into_report(self, model_name: &str, message: &str) -> eyre::Report {
+        match self {
+            Self::Dns => eyre::eyre!(
+                "could not pull model {model_name}, the machine running Ollama could not resolve the registry address; please check its DNS settings (e.g. /etc/resolv.conf within WSL): {message}"
+            ),
+            Self::NotFound => eyre::eyre!(
+                "could not pull model {model_name}, it was not found in the Ollama registry: {message}"
+            ),
+            Self::Other => eyre::eyre!("could not pull model {model_name}: {message}"),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_pull_error_kind() {
+        assert_eq!(
+            PullErrorKind::classify("pull model manifest: Get \"https://registry.ollama.ai/v2/library/llama3.1/manifests/8b\": dial tcp: lookup registry.ollama.ai on 10.255.255.254:53: no such host"),
+            PullErrorKind::Dns
+        );
+        assert_eq!(
+            PullErrorKind::classify("pull model manifest: file does not exist"),
+            PullErrorKind::NotFound
+        );
+        assert_eq!(
+            PullErrorKind::classify("unexpected EOF"),
+            PullErrorKind::Other
+        );
+    }
+
+    #[test]
+    fn test_is_local_host() {
+        assert!(is_local_host("http://127.0.0.1"));
+        assert!(is_local_host("http://localhost"));
+        assert!(is_local_host("http://[::1]"));
+        assert!(is_local_host("0.0.0.0"));
+        assert!(!is_local_host("http://192.168.1.20"));
+        assert!(!is_local_host("https://ollama.example.com"));
+    }
+
     #[tokio::test]
     #[ignore = "requires Ollama"]
     async fn test_ollama_spawn_and_check() {
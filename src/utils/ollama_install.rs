@@ -0,0 +1,255 @@
+use eyre::{eyre, Context, Result};
+use inquire::Confirm;
+use self_update::update::ReleaseAsset;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::releases::{download_url, prepare_partial_path, sha256_file};
+use super::{
+    fetch_github_api, parse_github_release, shared_data_dir, with_github_auth, DriaEnv, TracedSend,
+    LAUNCHER_USER_AGENT,
+};
+use crate::tr;
+
+/// Name of the asset that holds the SHA-256 checksums of the Ollama release assets.
+const OLLAMA_CHECKSUMS_ASSET: &str = "sha256sum.txt";
+
+/// Returns the directory that Ollama is installed to by the launcher, which is shared by all
+/// profiles: `~/.dria/dkn-compute-launcher/ollama`; `None` without a home directory.
+pub fn ollama_install_dir() -> Option<PathBuf> {
+    shared_data_dir().map(|dir| dir.join("ollama"))
+}
+
+/// Returns the Ollama executable that is installed by the launcher, if any.
+pub fn installed_ollama_path() -> Option<PathBuf> {
+    let dir = ollama_install_dir()?;
+    [
+        dir.join("bin").join("ollama"),
+        dir.join("ollama"),
+        dir.join("ollama.exe"),
+    ]
+    .into_iter()
+    .find(|path| path.is_file())
+}
+
+/// Downloads the latest Ollama release for this OS & architecture from GitHub, verifies it
+/// against the checksums of the release, and installs it to [`ollama_install_dir`] where it is
+/// found by [`spawn_ollama`](super::spawn_ollama).
+///
+/// ### Arguments
+/// - `yes`: install without asking for confirmation
+///
+/// ### Returns
+/// The path of the installed executable, `None` if the user has declined.
+///
+/// ### Errors
+/// - If there is no release asset for this machine, or the release has no checksums
+/// - If the download fails, or its checksum does not match
+/// - If the archive could not be extracted
+pub async fn install_ollama(yes: bool) -> Result<Option<PathBuf>> {
+    let release =
+        parse_github_release(&fetch_github_api("repos/ollama/ollama/releases/latest").await?)?;
+    let asset = ollama_asset_names(std::env::consts::OS, std::env::consts::ARCH)
+        .into_iter()
+        .find_map(|name| release.assets.iter().find(|asset| asset.name == name))
+        .ok_or_else(|| {
+            eyre!(
+                "Ollama v{} has no release for {}-{}, please install it from https://ollama.com/download",
+                release.version,
+                std::env::consts::OS,
+                std::env::consts::ARCH
+            )
+        })?;
+    let install_dir = ollama_install_dir().ok_or_else(|| {
+        eyre!("could not find the home directory to install Ollama to, please install it from https://ollama.com/download")
+    })?;
+    if !yes
+        && !Confirm::new(&tr!(
+            "Download Ollama v{version} ({asset}) and install it to {dir}?",
+            version = release.version,
+            asset = asset.name,
+            dir = install_dir.display()
+        ))
+        .with_default(true)
+        .prompt()?
+    {
+        return Ok(None);
+    }
+
+    // the checksums are required, as the executable is run by the launcher
+    let checksums_asset = release
+        .assets
+        .iter()
+        .find(|asset| asset.name == OLLAMA_CHECKSUMS_ASSET)
+        .ok_or_else(|| {
+            eyre!(
+                "Ollama v{} has no {OLLAMA_CHECKSUMS_ASSET}",
+                release.version
+            )
+        })?;
+    let expected = parse_checksums(&fetch_text(checksums_asset).await?)
+        .remove(&asset.name)
+        .ok_or_else(|| {
+            eyre!(
+                "{OLLAMA_CHECKSUMS_ASSET} has no checksum for {}",
+                asset.name
+            )
+        })?;
+
+    log::info!("Downloading Ollama v{} ({})", release.version, asset.name);
+    fs::create_dir_all(&install_dir)?;
+    let archive_path = prepare_partial_path(&install_dir, &asset.name, &release.version);
+    download_url(
+        &asset.download_url,
+        &archive_path,
+        true,
+        DriaEnv::new_from_env().get_download_timeout(),
+    )
+    .await
+    .wrap_err_with(|| format!("could not download {}", asset.name))?;
+    let actual = sha256_file(&archive_path)?;
+    if actual != expected {
+        fs::remove_file(&archive_path)?;
+        eyre::bail!(
+            "checksum mismatch for {}: expected {expected}, got {actual}",
+            asset.name
+        );
+    }
+
+    log::info!("Extracting Ollama to {}", install_dir.display());
+    let extracted = extract_archive(&archive_path, &asset.name, &install_dir);
+    fs::remove_file(&archive_path)?;
+    extracted?;
+
+    let exe_path = installed_ollama_path()
+        .ok_or_else(|| eyre!("Ollama executable not found within {}", asset.name))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&exe_path, fs::Permissions::from_mode(0o755))?;
+    }
+    log::info!(
+        "Installed Ollama v{} at {}",
+        release.version,
+        exe_path.display()
+    );
+
+    Ok(Some(exe_path))
+}
+
+/// Returns the names of the Ollama release assets for the given OS & architecture, preferred
+/// first; newer releases use `.tar.zst` archives on Linux instead of `.tgz`.
+fn ollama_asset_names(os: &str, arch: &str) -> Vec<String> {
+    let arch = match arch {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        _ => return Vec::new(),
+    };
+
+    match os {
+        "linux" => vec![
+            format!("ollama-linux-{arch}.tar.zst"),
+            format!("ollama-linux-{arch}.tgz"),
+        ],
+        "macos" => vec!["ollama-darwin.tgz".to_string()],
+        "windows" => vec![format!("ollama-windows-{arch}.zip")],
+        _ => Vec::new(),
+    }
+}
+
+/// Parses the `sha256sum` lines like `<checksum>  ./<asset>` into a map of asset names to
+/// their checksums.
+fn parse_checksums(content: &str) -> HashMap<String, String> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let (checksum, name) = line.split_once(char::is_whitespace)?;
+            let name = name.trim().trim_start_matches('*').trim_start_matches("./");
+            Some((name.to_string(), checksum.to_lowercase()))
+        })
+        .collect()
+}
+
+/// Fetches the content of the given (small) release asset as text.
+async fn fetch_text(asset: &ReleaseAsset) -> Result<String> {
+    let client = reqwest::Client::builder()
+        .user_agent(LAUNCHER_USER_AGENT)
+        .timeout(DriaEnv::new_from_env().get_download_timeout())
+        .build()?;
+    let url = &asset.download_url;
+
+    with_github_auth(client.get(url), url)
+        .header(reqwest::header::ACCEPT, "application/octet-stream")
+        .send_traced()
+        .await
+        .and_then(|res| res.error_for_status())
+        .wrap_err_with(|| format!("could not fetch {}", asset.name))?
+        .text()
+        .await
+        .wrap_err_with(|| format!("could not read {}", asset.name))
+}
+
+/// Extracts the archive at the given path to the given directory, w.r.t the extension of its
+/// asset name; tarballs are extracted with the `tar` of the system.
+fn extract_archive(archive_path: &Path, name: &str, dest_dir: &Path) -> Result<()> {
+    if name.ends_with(".zip") {
+        return zip::ZipArchive::new(fs::File::open(archive_path)?)?
+            .extract(dest_dir)
+            .wrap_err("could not extract the archive");
+    }
+
+    let tar_path = if name.ends_with(".tar.zst") {
+        let tar_path = archive_path.with_extension("tar");
+        zstd::stream::copy_decode(fs::File::open(archive_path)?, fs::File::create(&tar_path)?)
+            .wrap_err("could not decompress the archive")?;
+        Some(tar_path)
+    } else {
+        None
+    };
+
+    let status = Command::new("tar")
+        .arg(if tar_path.is_some() { "-xf" } else { "-xzf" })
+        .arg(tar_path.as_deref().unwrap_or(archive_path))
+        .arg("-C")
+        .arg(dest_dir)
+        .status();
+    if let Some(tar_path) = &tar_path {
+        fs::remove_file(tar_path)?;
+    }
+    match status {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => eyre::bail!("could not extract the archive, tar exited with {status}"),
+        Err(err) => Err(err).wrap_err("could not run tar to extract the archive"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ollama_assets() {
+        assert_eq!(
+            ollama_asset_names("linux", "x86_64"),
+            ["ollama-linux-amd64.tar.zst", "ollama-linux-amd64.tgz"]
+        );
+        assert_eq!(
+            ollama_asset_names("macos", "aarch64"),
+            ["ollama-darwin.tgz"]
+        );
+        assert_eq!(
+            ollama_asset_names("windows", "x86_64"),
+            ["ollama-windows-amd64.zip"]
+        );
+        assert!(ollama_asset_names("linux", "riscv64").is_empty());
+
+        let checksums = parse_checksums(
+            "ABCDEF  ./ollama-linux-amd64.tgz\n012345 *ollama-darwin.tgz\ninvalid\n",
+        );
+        assert_eq!(checksums.len(), 2);
+        assert_eq!(checksums["ollama-linux-amd64.tgz"], "abcdef");
+        assert_eq!(checksums["ollama-darwin.tgz"], "012345");
+    }
+}
@@ -0,0 +1,234 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{is_process_running, shared_data_dir};
+
+/// Records the Ollama spawned by the launcher, so that its ownership survives a restart of the
+/// launcher (e.g. after a self-update): an Ollama that was started by the user is never stopped,
+/// and an Ollama left behind by an earlier launcher is adopted instead of being orphaned.
+///
+/// There is a file per Ollama port, shared by all profiles like Ollama itself; the spawned Ollama
+/// is always local, so its port tells it apart from the other Ollama servers on this machine. It
+/// contains the PIDs of Ollama and of the launcher that owns it, e.g. `1234 1200`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OllamaPidFile {
+    /// PID of the spawned Ollama.
+    pub ollama_pid: u32,
+    /// PID of the launcher that owns Ollama.
+    pub launcher_pid: u32,
+}
+
+impl OllamaPidFile {
+    /// Returns the path of the PID file of the Ollama at the given port, e.g.
+    /// `~/.dria/dkn-compute-launcher/ollama-11434.pid`; `None` without a home directory, in
+    /// which case the ownership is not recorded.
+    pub fn path(port: u16) -> Option<PathBuf> {
+        shared_data_dir().map(|dir| dir.join(format!("ollama-{port}.pid")))
+    }
+
+    /// Records the given Ollama at the given port as owned by this launcher; this is best-effort.
+    pub fn record(port: u16, ollama_pid: u32) {
+        let Some(path) = Self::path(port) else {
+            log::warn!("Could not find the home directory to record Ollama PID {ollama_pid}.");
+            return;
+        };
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+
+        let content = format!("{ollama_pid} {}\n", std::process::id());
+        if let Err(err) = fs::write(&path, content) {
+            log::warn!("Could not record Ollama PID to {}: {err}", path.display());
+        }
+    }
+
+    /// Removes the record of the given Ollama if it is still recorded, e.g. after it is stopped;
+    /// its port is not needed, as the PID is unique among the records.
+    pub fn forget(ollama_pid: u32) {
+        let Some(Ok(entries)) = shared_data_dir().map(fs::read_dir) else {
+            return;
+        };
+
+        for path in entries.flatten().map(|entry| entry.path()) {
+            let is_pid_file = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("ollama-") && name.ends_with(".pid"));
+            if is_pid_file
+                && Self::read(&path).is_some_and(|record| record.ollama_pid == ollama_pid)
+            {
+                let _ = fs::remove_file(&path);
+            }
+        }
+    }
+
+    /// Returns the PID of the recorded Ollama at the given port if it is still serving that port
+    /// but its launcher is not running, i.e. it was orphaned by an earlier launcher; a stale
+    /// record is removed.
+    pub fn find_orphan(port: u16) -> Option<u32> {
+        let path = Self::path(port)?;
+        let record = Self::read(&path)?;
+        if !is_process_running(record.ollama_pid)
+            || !is_ollama_process(record.ollama_pid)
+            || !is_listening_on(record.ollama_pid, port)
+        {
+            let _ = fs::remove_file(&path);
+            return None;
+        }
+
+        (record.launcher_pid != std::process::id() && !is_process_running(record.launcher_pid))
+            .then_some(record.ollama_pid)
+    }
+
+    /// Reads the PID file at the given path, if it exists and is valid.
+    fn read(path: &Path) -> Option<Self> {
+        Self::parse(&fs::read_to_string(path).ok()?)
+    }
+
+    /// Parses the content of the PID file, i.e. `<ollama PID> <launcher PID>`.
+    fn parse(content: &str) -> Option<Self> {
+        let mut pids = content.split_whitespace().map(|pid| pid.parse().ok());
+
+        Some(Self {
+            ollama_pid: pids.next()??,
+            launcher_pid: pids.next()??,
+        })
+    }
+}
+
+/// Returns `true` if the process with the given PID is Ollama, so that a reused PID is not
+/// mistaken for it.
+fn is_ollama_process(pid: u32) -> bool {
+    use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+
+    let pid = Pid::from_u32(pid);
+    let mut system = System::new();
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&[pid]),
+        true,
+        ProcessRefreshKind::nothing(),
+    );
+
+    system.process(pid).is_some_and(|process| {
+        process
+            .name()
+            .to_string_lossy()
+            .to_lowercase()
+            .contains("ollama")
+    })
+}
+
+/// Returns `true` if the process with the given PID listens on the given TCP port, so that an
+/// Ollama is only adopted (and stopped later) if it is the one serving the node; `false` if it
+/// does not, or if it can not be told.
+#[cfg(target_os = "linux")]
+fn is_listening_on(pid: u32, port: u16) -> bool {
+    let inodes = ["/proc/net/tcp", "/proc/net/tcp6"]
+        .into_iter()
+        .filter_map(|path| fs::read_to_string(path).ok())
+        .flat_map(|content| listening_socket_inodes(&content, port))
+        .map(|inode| format!("socket:[{inode}]"))
+        .collect::<Vec<_>>();
+    if inodes.is_empty() {
+        return false;
+    }
+
+    let Ok(fds) = fs::read_dir(format!("/proc/{pid}/fd")) else {
+        return false;
+    };
+    fds.flatten()
+        .filter_map(|fd| fs::read_link(fd.path()).ok())
+        .any(|link| {
+            inodes
+                .iter()
+                .any(|inode| link.as_os_str() == inode.as_str())
+        })
+}
+
+/// Returns the inodes of the sockets that listen on the given port, within the content of
+/// `/proc/net/tcp` (or `tcp6`), e.g. `0: 0100007F:2CA3 00000000:0000 0A ... 0 12345 ...`.
+#[cfg(target_os = "linux")]
+fn listening_socket_inodes(content: &str, port: u16) -> Vec<String> {
+    // the state of a listening socket is `0A`
+    const TCP_LISTEN: &str = "0A";
+
+    content
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            let (_, local_port) = fields.get(1)?.rsplit_once(':')?;
+            (u16::from_str_radix(local_port, 16).ok()? == port && *fields.get(3)? == TCP_LISTEN)
+                .then(|| fields.get(9).map(|inode| inode.to_string()))?
+        })
+        .collect()
+}
+
+/// See the Linux variant, this uses `lsof`.
+#[cfg(all(unix, not(target_os = "linux")))]
+fn is_listening_on(pid: u32, port: u16) -> bool {
+    std::process::Command::new("lsof")
+        .args(["-nP", "-a", "-t", "-sTCP:LISTEN"])
+        .arg(format!("-p{pid}"))
+        .arg(format!("-iTCP:{port}"))
+        .output()
+        .is_ok_and(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .any(|line| line.trim() == pid.to_string())
+        })
+}
+
+/// See the Linux variant, this uses `netstat`, whose lines are e.g.
+/// `TCP    127.0.0.1:11434    0.0.0.0:0    LISTENING    1234`.
+#[cfg(windows)]
+fn is_listening_on(pid: u32, port: u16) -> bool {
+    std::process::Command::new("netstat")
+        .args(["-ano", "-p", "TCP"])
+        .output()
+        .is_ok_and(|output| {
+            String::from_utf8_lossy(&output.stdout).lines().any(|line| {
+                let fields = line.split_whitespace().collect::<Vec<_>>();
+                matches!(fields.as_slice(), ["TCP", local, _, "LISTENING", owner]
+                    if local.ends_with(&format!(":{port}")) && *owner == pid.to_string())
+            })
+        })
+}
+
+/// See the Linux variant, it can not be told on other platforms.
+#[cfg(not(any(unix, windows)))]
+fn is_listening_on(_pid: u32, _port: u16) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ollama_pid_file() {
+        assert_eq!(
+            OllamaPidFile::parse("1234 1200\n"),
+            Some(OllamaPidFile {
+                ollama_pid: 1234,
+                launcher_pid: 1200
+            })
+        );
+        assert_eq!(OllamaPidFile::parse("1234"), None);
+        assert_eq!(OllamaPidFile::parse("abc 1200"), None);
+        assert_eq!(OllamaPidFile::parse(""), None);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_listening_socket_inodes() {
+        let content = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:2CA2 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 4242 1 0000000000000000 100 0 0 10 0
+   1: 0100007F:2CA2 0100007F:9C40 01 00000000:00000000 00:00000000 00000000  1000        0 4343 1 0000000000000000 20 4 30 10 -1
+   2: 00000000:0016 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 1111 1 0000000000000000 100 0 0 10 0
+";
+        assert_eq!(listening_socket_inodes(content, 11426), ["4242"]);
+        assert_eq!(listening_socket_inodes(content, 22), ["1111"]);
+        assert!(listening_socket_inodes(content, 11434).is_empty());
+    }
+}
@@ -0,0 +1,222 @@
+use std::io::{IsTerminal, Write};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::{Child, Command};
+
+use super::{colors_enabled, parse_task_tps, TpsTracker};
+
+/// Lowercase patterns that indicate a task has been completed by the compute node; only the
+/// completion line of a task matches, not its other lines such as the publishing of its result.
+const TASK_COMPLETED_PATTERNS: [&str; 2] = ["task completed", "completed task"];
+
+/// Lowercase patterns that precede the peer count within a compute node log line.
+const PEER_COUNT_PATTERNS: [&str; 3] = ["peer count", "peers:", "connected peers"];
+
+/// Statistics extracted from the compute node output.
+#[derive(Debug, Default, Clone)]
+pub struct ComputeStats {
+    /// Last reported number of peers.
+    pub peer_count: Option<usize>,
+    /// Number of completed tasks.
+    pub tasks_completed: u64,
+    /// Number of error lines.
+    pub errors: u64,
+    /// The last error line.
+    pub last_error: Option<String>,
+    /// Time of the last output line.
+    pub last_output: Option<Instant>,
+    /// Time of the last completed task.
+    pub last_task: Option<Instant>,
+    /// Moving averages of the TPS per model, w.r.t the completed tasks that report it.
+    pub tps: TpsTracker,
+}
+
+impl ComputeStats {
+    /// Parses a single output line of the compute node, updating the statistics.
+    pub fn observe(&mut self, line: &str) {
+        let line = strip_ansi(line);
+        let lowercase = line.to_lowercase();
+        let now = Instant::now();
+        self.last_output = Some(now);
+
+        // env_logger lines look like `[2025-01-01T00:00:00Z ERROR dkn_compute] message`
+        if line.contains(" ERROR ") || line.starts_with("ERROR") {
+            self.errors += 1;
+            self.last_error = Some(line.trim().to_string());
+        }
+
+        if TASK_COMPLETED_PATTERNS
+            .iter()
+            .any(|pattern| lowercase.contains(pattern))
+        {
+            self.tasks_completed += 1;
+            self.last_task = Some(now);
+            if let Some((model, tps)) = parse_task_tps(&lowercase) {
+                self.tps.update(&model, tps);
+            }
+        }
+
+        // the first number after a peer count pattern is the peer count
+        if let Some(idx) = PEER_COUNT_PATTERNS
+            .iter()
+            .find_map(|pattern| lowercase.find(pattern).map(|i| i + pattern.len()))
+        {
+            let count = lowercase[idx..]
+                .trim_start_matches(|c: char| !c.is_ascii_digit())
+                .split(|c: char| !c.is_ascii_digit())
+                .next()
+                .and_then(|digits| digits.parse().ok());
+            if count.is_some() {
+                self.peer_count = count;
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ComputeStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "peers: {}, tasks completed: {}, errors: {}",
+            self.peer_count
+                .map(|c| c.to_string())
+                .unwrap_or_else(|| "unknown".to_string()),
+            self.tasks_completed,
+            self.errors
+        )
+    }
+}
+
+/// A shared handle to the compute node output statistics.
+///
+/// Cloning this is cheap, and all clones refer to the same statistics; they persist
+/// across compute node restarts.
+#[derive(Debug, Clone, Default)]
+pub struct ComputeOutput(Arc<Mutex<ComputeStats>>);
+
+impl ComputeOutput {
+    /// Returns a snapshot of the current statistics.
+    pub fn stats(&self) -> ComputeStats {
+        self.0.lock().expect("poisoned lock").clone()
+    }
+
+    /// Configures the command so that its output is piped to the launcher.
+    ///
+    /// If the launcher is attached to a terminal with colors, the compute node is asked to keep
+    /// its colors; otherwise it is asked to drop them.
+    pub fn configure(command: &mut Command) {
+        command.env(
+            "RUST_LOG_STYLE",
+            if std::io::stderr().is_terminal() && colors_enabled() {
+                "always"
+            } else {
+                "never"
+            },
+        );
+
+        command
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped());
+    }
+
+    /// Takes the piped outputs of the child process, and forwards them to the launcher's own
+    /// `stdout` and `stderr` while parsing each line.
+    pub fn capture(&self, child: &mut Child) {
+        if let Some(stdout) = child.stdout.take() {
+            tokio::spawn(self.clone().forward(stdout, false));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            tokio::spawn(self.clone().forward(stderr, true));
+        }
+    }
+
+    /// Forwards the lines of the given output until it is closed or can not be read anymore;
+    /// invalid UTF-8 is replaced, and failed writes are skipped, as the compute node blocks once
+    /// its output is no longer drained.
+    async fn forward(self, reader: impl AsyncRead + Unpin, is_stderr: bool) {
+        let mut reader = BufReader::new(reader);
+        let mut buf = Vec::new();
+        loop {
+            buf.clear();
+            match reader.read_until(b'\n', &mut buf).await {
+                Ok(0) => break,
+                Ok(_) => {}
+                Err(err) => {
+                    log::warn!("Could not read compute node output: {err}");
+                    break;
+                }
+            }
+
+            let line = String::from_utf8_lossy(&buf);
+            let line = line.trim_end_matches(['\n', '\r']);
+            let _ = if is_stderr {
+                writeln!(std::io::stderr(), "{line}")
+            } else {
+                writeln!(std::io::stdout(), "{line}")
+            };
+
+            self.0.lock().expect("poisoned lock").observe(line);
+        }
+    }
+}
+
+/// Removes ANSI escape sequences (e.g. colors) from the given line.
+pub(super) fn strip_ansi(line: &str) -> String {
+    let mut result = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // skip until the final byte of the sequence, which is a letter
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            result.push(c);
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compute_stats() {
+        let mut stats = ComputeStats::default();
+
+        stats.observe("[2025-01-01T00:00:00Z INFO  dkn_compute] Connected peers: 12");
+        assert_eq!(stats.peer_count, Some(12));
+
+        stats.observe("[2025-01-01T00:00:00Z INFO  dkn_compute] Publishing result for task 1");
+        stats.observe("[2025-01-01T00:00:00Z INFO  dkn_compute] Task completed (model: gemma3:4b, 20 tokens/s)");
+        stats.observe("[2025-01-01T00:00:00Z \x1b[31mERROR\x1b[0m dkn_compute] Something failed");
+        assert_eq!(stats.tasks_completed, 1);
+        assert_eq!(stats.tps.emas()["gemma3:4b"], 20.0);
+        assert_eq!(stats.errors, 1);
+        assert_eq!(
+            stats.last_error.as_deref(),
+            Some("[2025-01-01T00:00:00Z ERROR dkn_compute] Something failed")
+        );
+
+        // unrelated lines do not change the counters
+        stats.observe("[2025-01-01T00:00:00Z INFO  dkn_compute] Peer is dialing");
+        assert_eq!(stats.peer_count, Some(12));
+        assert!(stats.last_output.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_forward_invalid_utf8() {
+        let output = ComputeOutput::default();
+        let reader: &[u8] =
+            b"bad \xff byte\n[2025-01-01T00:00:00Z INFO  dkn_compute] Connected peers: 3\r\n";
+        output.clone().forward(reader, true).await;
+
+        // lines after the invalid byte are still read
+        assert_eq!(output.stats().peer_count, Some(3));
+    }
+}
@@ -0,0 +1,48 @@
+use eyre::Context;
+
+use super::{cached, get_points_api_url, send_with_retries, DriaEnv};
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct PointsRes {
+    /// Indicates in which top percentile your points are.
+    pub percentile: usize,
+    /// The total number of points you have accumulated.
+    pub score: f64,
+}
+
+/// Fetches the $DRIA points of the given address from the points API of the configured network.
+///
+/// The request is retried w.r.t the timeouts & retries within the environment, see
+/// [`DriaEnv::get_api_client_policy`], and its response is cached for a while, see [`cached`].
+pub async fn get_points(address: &str) -> eyre::Result<PointsRes> {
+    let url = get_points_api_url(address);
+
+    cached(&url, async {
+        let policy = DriaEnv::new_from_env().get_api_client_policy();
+        let client = policy
+            .client()
+            .wrap_err("could not create reqwest client")?;
+
+        let res = send_with_retries(client.get(&url), policy.retries)
+            .await
+            .wrap_err("could not make request")?;
+
+        res.json::<PointsRes>()
+            .await
+            .wrap_err("could not parse body")
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_get_points() {
+        let address = "0x1234567890abcdef1234567890abcdef12345678";
+        let points = get_points(address).await.unwrap();
+        assert!(points.score >= 0.0);
+        assert!(points.percentile <= 100);
+    }
+}
@@ -0,0 +1,366 @@
+use eyre::{Context, Result};
+use igd_next::aio::{tokio::Tokio, Gateway};
+use igd_next::{PortMappingProtocol, SearchOptions};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+/// Lease duration of a port mapping, it is renewed at half of this while the node is running.
+const PORT_MAPPING_LEASE: Duration = Duration::from_secs(60 * 60);
+/// Timeout for the UPnP gateway search.
+const UPNP_SEARCH_TIMEOUT: Duration = Duration::from_secs(5);
+/// Description of the UPnP port mapping, shown in the router's interface.
+const UPNP_DESCRIPTION: &str = "dkn-compute-node";
+/// Port of the NAT-PMP service on the gateway.
+const NATPMP_PORT: u16 = 5351;
+/// Initial timeout of a NAT-PMP request, doubled on each retry as in RFC 6886.
+const NATPMP_INITIAL_TIMEOUT: Duration = Duration::from_millis(250);
+/// Number of attempts for a NAT-PMP request.
+const NATPMP_ATTEMPTS: u32 = 4;
+/// Time to wait for the port mapping to be removed, on shutdown or when the node fails to start.
+pub const PORT_MAPPING_REMOVAL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A TCP port mapping on the gateway (router), so that the node can receive inbound connections.
+pub enum PortMapping {
+    /// Mapped with UPnP (IGD).
+    Upnp { gateway: Gateway<Tokio>, port: u16 },
+    /// Mapped with NAT-PMP.
+    NatPmp { gateway: Ipv4Addr, port: u16 },
+}
+
+impl std::fmt::Display for PortMapping {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Upnp { .. } => write!(f, "UPnP"),
+            Self::NatPmp { .. } => write!(f, "NAT-PMP"),
+        }
+    }
+}
+
+impl PortMapping {
+    /// Maps the given TCP port of this machine to the same external port on the gateway, trying
+    /// UPnP first and NAT-PMP next.
+    ///
+    /// ### Returns
+    /// The mapping along with the external address.
+    ///
+    /// ### Errors
+    /// - If the local address could not be found
+    /// - If neither UPnP nor NAT-PMP could map the port
+    pub async fn create(port: u16) -> Result<(Self, SocketAddr)> {
+        let local_ip = get_local_ip().ok_or_else(|| eyre::eyre!("could not find local address"))?;
+
+        let upnp_err = match map_upnp(local_ip, port).await {
+            Ok((gateway, external_ip)) => {
+                return Ok((
+                    Self::Upnp { gateway, port },
+                    SocketAddr::new(external_ip, port),
+                ))
+            }
+            Err(err) => err,
+        };
+        log::debug!("UPnP port mapping failed: {upnp_err}");
+
+        if !local_ip.is_ipv4() {
+            eyre::bail!("UPnP failed ({upnp_err}), and NAT-PMP requires IPv4");
+        }
+        let Some(gateway) = default_gateway() else {
+            eyre::bail!("UPnP failed ({upnp_err}), and the gateway for NAT-PMP could not be found");
+        };
+        match map_natpmp(gateway, port, PORT_MAPPING_LEASE).await {
+            Ok(external_addr) => Ok((Self::NatPmp { gateway, port }, external_addr.into())),
+            Err(err) => eyre::bail!("UPnP failed ({upnp_err}), NAT-PMP failed ({err})"),
+        }
+    }
+
+    /// Renews the mapping in the background until the given token is cancelled, or the returned
+    /// task is removed; the mapping is removed afterwards.
+    pub fn spawn(self, cancellation: &CancellationToken) -> PortMappingTask {
+        let cancellation = cancellation.child_token();
+        PortMappingTask {
+            handle: tokio::spawn(self.run(cancellation.clone())),
+            cancellation,
+        }
+    }
+
+    /// Renews the mapping periodically until cancelled, and removes it afterwards.
+    async fn run(self, cancellation: CancellationToken) {
+        let mut interval = tokio::time::interval(PORT_MAPPING_LEASE / 2);
+        interval.tick().await; // the mapping is fresh
+
+        loop {
+            tokio::select! {
+                _ = cancellation.cancelled() => break,
+                _ = interval.tick() => {
+                    if let Err(err) = self.renew().await {
+                        log::warn!("Could not renew {self} port mapping: {err}");
+                    }
+                }
+            }
+        }
+
+        if let Err(err) = self.remove().await {
+            log::warn!("Could not remove {self} port mapping: {err}");
+        }
+    }
+
+    async fn renew(&self) -> Result<()> {
+        match self {
+            Self::Upnp { gateway, port } => {
+                let local_ip =
+                    get_local_ip().ok_or_else(|| eyre::eyre!("could not find local address"))?;
+                gateway
+                    .add_port(
+                        PortMappingProtocol::TCP,
+                        *port,
+                        SocketAddr::new(local_ip, *port),
+                        PORT_MAPPING_LEASE.as_secs() as u32,
+                        UPNP_DESCRIPTION,
+                    )
+                    .await?;
+            }
+            Self::NatPmp { gateway, port } => {
+                map_natpmp(*gateway, *port, PORT_MAPPING_LEASE).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn remove(&self) -> Result<()> {
+        match self {
+            Self::Upnp { gateway, port } => {
+                gateway.remove_port(PortMappingProtocol::TCP, *port).await?
+            }
+            // a mapping with zero lifetime is a removal request
+            Self::NatPmp { gateway, port } => {
+                map_natpmp(*gateway, *port, Duration::ZERO).await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A port mapping that is renewed in the background, see [`PortMapping::spawn`].
+pub struct PortMappingTask {
+    handle: JoinHandle<()>,
+    cancellation: CancellationToken,
+}
+
+impl PortMappingTask {
+    /// Stops renewing the mapping and waits up to the given timeout for it to be removed.
+    ///
+    /// ### Returns
+    /// `true` if the renewal has stopped in time, failures to remove are logged by the task.
+    pub async fn remove(self, timeout: Duration) -> bool {
+        self.cancellation.cancel();
+        tokio::time::timeout(timeout, self.handle)
+            .await
+            .is_ok_and(|joined| joined.is_ok())
+    }
+}
+
+/// Returns the local IP address of this machine within its network, if it can be found.
+///
+/// A UDP socket is "connected" to a public address to find the outgoing interface,
+/// no packets are actually sent.
+pub fn get_local_ip() -> Option<IpAddr> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("8.8.8.8:80").ok()?;
+    socket.local_addr().ok().map(|addr| addr.ip())
+}
+
+/// Maps the port with UPnP, returns the gateway and the external IP address.
+async fn map_upnp(local_ip: IpAddr, port: u16) -> Result<(Gateway<Tokio>, IpAddr)> {
+    let gateway = igd_next::aio::tokio::search_gateway(SearchOptions {
+        timeout: Some(UPNP_SEARCH_TIMEOUT),
+        ..Default::default()
+    })
+    .await
+    .wrap_err("no UPnP gateway found")?;
+
+    gateway
+        .add_port(
+            PortMappingProtocol::TCP,
+            port,
+            SocketAddr::new(local_ip, port),
+            PORT_MAPPING_LEASE.as_secs() as u32,
+            UPNP_DESCRIPTION,
+        )
+        .await?;
+    let external_ip = gateway.get_external_ip().await?;
+
+    Ok((gateway, external_ip))
+}
+
+/// Maps the port with NAT-PMP (RFC 6886) for the given lifetime, returns the external address.
+async fn map_natpmp(gateway: Ipv4Addr, port: u16, lifetime: Duration) -> Result<SocketAddrV4> {
+    let socket = tokio::net::UdpSocket::bind("0.0.0.0:0").await?;
+    socket.connect((gateway, NATPMP_PORT)).await?;
+
+    // external address request
+    let response = natpmp_request(&socket, &[0, 0]).await?;
+    let external_ip = parse_natpmp_external_address(&response)?;
+
+    // TCP mapping request, with the same internal & external port
+    let mut request = vec![0, 2, 0, 0];
+    request.extend_from_slice(&port.to_be_bytes());
+    request.extend_from_slice(&port.to_be_bytes());
+    request.extend_from_slice(&(lifetime.as_secs() as u32).to_be_bytes());
+    let response = natpmp_request(&socket, &request).await?;
+    let external_port = parse_natpmp_mapping(&response)?;
+
+    Ok(SocketAddrV4::new(external_ip, external_port))
+}
+
+/// Sends a NAT-PMP request, retrying with an increasing timeout; returns the response.
+async fn natpmp_request(socket: &tokio::net::UdpSocket, request: &[u8]) -> Result<Vec<u8>> {
+    let mut timeout = NATPMP_INITIAL_TIMEOUT;
+    let mut buf = [0u8; 16];
+    for _ in 0..NATPMP_ATTEMPTS {
+        socket.send(request).await?;
+        if let Ok(len) = tokio::time::timeout(timeout, socket.recv(&mut buf)).await {
+            return Ok(buf[..len?].to_vec());
+        }
+        timeout *= 2;
+    }
+
+    eyre::bail!("no NAT-PMP response from the gateway")
+}
+
+/// Parses a NAT-PMP external address response.
+fn parse_natpmp_external_address(response: &[u8]) -> Result<Ipv4Addr> {
+    check_natpmp_response(response, 128, 12)?;
+    Ok(Ipv4Addr::new(
+        response[8],
+        response[9],
+        response[10],
+        response[11],
+    ))
+}
+
+/// Parses a NAT-PMP TCP mapping response, returns the external port.
+fn parse_natpmp_mapping(response: &[u8]) -> Result<u16> {
+    check_natpmp_response(response, 130, 16)?;
+    Ok(u16::from_be_bytes([response[10], response[11]]))
+}
+
+/// Checks the length, opcode & result code of a NAT-PMP response.
+fn check_natpmp_response(response: &[u8], opcode: u8, len: usize) -> Result<()> {
+    if response.len() < len || response[1] != opcode {
+        eyre::bail!("invalid NAT-PMP response");
+    }
+
+    match u16::from_be_bytes([response[2], response[3]]) {
+        0 => Ok(()),
+        2 => eyre::bail!("NAT-PMP is disabled on the gateway"),
+        3 => eyre::bail!("gateway has no external address"),
+        code => eyre::bail!("NAT-PMP request failed with result code {code}"),
+    }
+}
+
+/// Returns the IPv4 default gateway from the routing table of this machine, if it can be found.
+fn default_gateway() -> Option<Ipv4Addr> {
+    let command_output = |program: &str, args: &[&str]| {
+        std::process::Command::new(program)
+            .args(args)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).into_owned())
+    };
+
+    if cfg!(target_os = "linux") {
+        parse_default_gateway(&std::fs::read_to_string("/proc/net/route").ok()?)
+    } else if cfg!(windows) {
+        parse_route_print(&command_output("route", &["print", "-4", "0.0.0.0"])?)
+    } else {
+        // macOS & BSDs
+        parse_route_get(&command_output("route", &["-n", "get", "default"])?)
+    }
+}
+
+/// Parses the default gateway from the contents of `/proc/net/route`, where addresses are
+/// little-endian hexadecimals.
+fn parse_default_gateway(routes: &str) -> Option<Ipv4Addr> {
+    routes.lines().skip(1).find_map(|line| {
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+        if fields.get(1) != Some(&"00000000") {
+            return None;
+        }
+
+        let gateway = u32::from_str_radix(fields.get(2)?, 16).ok()?;
+        (gateway != 0).then(|| Ipv4Addr::from(gateway.to_le_bytes()))
+    })
+}
+
+/// Parses the default gateway from the output of `route -n get default` on macOS & BSDs.
+fn parse_route_get(output: &str) -> Option<Ipv4Addr> {
+    output.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        (key.trim() == "gateway")
+            .then(|| value.trim().parse().ok())
+            .flatten()
+    })
+}
+
+/// Parses the default gateway from the output of `route print -4 0.0.0.0` on Windows, where
+/// the default route is given as `0.0.0.0 0.0.0.0 <gateway> <interface> <metric>`.
+fn parse_route_print(output: &str) -> Option<Ipv4Addr> {
+    output.lines().find_map(
+        |line| match line.split_whitespace().collect::<Vec<_>>()[..] {
+            ["0.0.0.0", "0.0.0.0", gateway, ..] => gateway.parse().ok(),
+            _ => None,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_default_gateway() {
+        let routes = "Iface\tDestination\tGateway\tFlags\tRefCnt\tUse\tMetric\tMask\n\
+            eth0\t0001A8C0\t00000000\t0001\t0\t0\t0\t00FFFFFF\n\
+            eth0\t00000000\t0101A8C0\t0003\t0\t0\t0\t00000000\n";
+        assert_eq!(
+            parse_default_gateway(routes),
+            Some(Ipv4Addr::new(192, 168, 1, 1))
+        );
+        assert_eq!(parse_default_gateway("Iface\tDestination\tGateway\n"), None);
+
+        let route_get =
+            "   route to: default\ndestination: default\n    gateway: 10.0.0.1\n  interface: en0\n";
+        assert_eq!(parse_route_get(route_get), Some(Ipv4Addr::new(10, 0, 0, 1)));
+
+        let route_print = "Network Destination        Netmask          Gateway       Interface  Metric\n          0.0.0.0          0.0.0.0      192.168.1.1    192.168.1.10     25\n";
+        assert_eq!(
+            parse_route_print(route_print),
+            Some(Ipv4Addr::new(192, 168, 1, 1))
+        );
+        assert_eq!(
+            parse_route_print("0.0.0.0 0.0.0.0 On-link 10.0.0.2 25"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_natpmp_responses() {
+        let external = [0, 128, 0, 0, 0, 0, 0, 1, 203, 0, 113, 7];
+        assert_eq!(
+            parse_natpmp_external_address(&external).unwrap(),
+            Ipv4Addr::new(203, 0, 113, 7)
+        );
+
+        let mapping = [
+            0, 130, 0, 0, 0, 0, 0, 1, 0x0f, 0xa1, 0x0f, 0xa2, 0, 0, 0x0e, 0x10,
+        ];
+        assert_eq!(parse_natpmp_mapping(&mapping).unwrap(), 4002);
+
+        let refused = [0, 130, 0, 2, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0];
+        assert!(parse_natpmp_mapping(&refused).is_err());
+    }
+}
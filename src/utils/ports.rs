@@ -0,0 +1,311 @@
+use inquire::Confirm;
+use std::collections::BTreeSet;
+use std::io::IsTerminal;
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+
+use super::{is_valid_profile_name, parse_listen_port, DriaEnv, EnvKey};
+use crate::tr;
+
+/// Returns the environment files of the other profiles, i.e. the files next to the given one that
+/// are named as `<env>` or `<env>.<profile>` w.r.t `--profile`, where `<env>` is the environment
+/// file without its profile, e.g. `.env` for `.env.work`.
+///
+/// Other files next to it such as logs, locks & the example are not profiles, e.g. `.env.lock`.
+pub fn other_profile_env_paths(env_path: &Path) -> Vec<PathBuf> {
+    let Some(dir) = env_path.parent().filter(|dir| !dir.as_os_str().is_empty()) else {
+        return Vec::new();
+    };
+    let Some(env_file_name) = env_path.file_name().and_then(|name| name.to_str()) else {
+        return Vec::new();
+    };
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let base_name = match env_file_name.strip_prefix(".env.") {
+        Some(profile) if is_valid_profile_name(profile) => ".env",
+        _ => env_file_name,
+    };
+    entries
+        .flatten()
+        .filter(|entry| entry.path().is_file())
+        .filter(|entry| {
+            let name = entry.file_name();
+            let Some(name) = name.to_str() else {
+                return false;
+            };
+            name != env_file_name
+                && !name.ends_with(".lock")
+                && !name.ends_with(".example")
+                && (name == base_name
+                    || name
+                        .strip_prefix(base_name)
+                        .and_then(|rest| rest.strip_prefix('.'))
+                        .is_some_and(is_valid_profile_name))
+        })
+        .map(|entry| entry.path())
+        .collect()
+}
+
+/// Ports configured by the other profiles, see [`other_profile_ports`].
+#[derive(Debug, Default)]
+struct ProfilePorts {
+    p2p: BTreeSet<u16>,
+    ollama: BTreeSet<u16>,
+    /// Ports of the management API, only of the profiles that have it enabled.
+    management: BTreeSet<u16>,
+}
+
+/// Returns the P2P, Ollama & management API ports configured by each of the other profiles.
+fn other_profile_ports(env_path: &Path) -> ProfilePorts {
+    let mut ports = ProfilePorts::default();
+    for path in other_profile_env_paths(env_path) {
+        let Ok(entries) = dotenvy::from_path_iter(&path) else {
+            continue;
+        };
+        let entries = entries
+            .flatten()
+            .filter(|(_, value)| !value.is_empty())
+            .collect::<Vec<_>>();
+        let get = |key: &str| {
+            entries
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, value)| value.as_str())
+                .or_else(|| EnvKey::find(key).and_then(|key| key.default))
+                .unwrap_or_default()
+        };
+
+        ports
+            .p2p
+            .extend(parse_listen_port(get(DriaEnv::DKN_P2P_LISTEN_ADDR_KEY)));
+        ports
+            .ollama
+            .extend(get(DriaEnv::OLLAMA_PORT_KEY).parse::<u16>().ok());
+        if get(DriaEnv::DKN_MANAGEMENT_API_KEY)
+            .trim()
+            .eq_ignore_ascii_case("true")
+        {
+            ports
+                .management
+                .extend(get(DriaEnv::DKN_MANAGEMENT_PORT_KEY).parse::<u16>().ok());
+        }
+    }
+
+    ports
+}
+
+/// Returns `true` if nothing is listening on the given TCP port.
+#[inline]
+pub fn is_port_free(port: u16) -> bool {
+    TcpListener::bind(("0.0.0.0", port)).is_ok()
+}
+
+/// Returns the first port starting from `port` that is not taken, w.r.t the given predicate.
+fn find_port(port: u16, is_taken: impl Fn(u16) -> bool) -> Option<u16> {
+    (port..=u16::MAX).find(|port| !is_taken(*port))
+}
+
+/// Replaces the TCP port within comma-separated listen addresses like `/ip4/0.0.0.0/tcp/4001`.
+fn with_listen_port(addrs: &str, port: u16) -> String {
+    addrs
+        .split(',')
+        .map(|addr| {
+            let mut is_port = false;
+            addr.split('/')
+                .map(|part| {
+                    let part = if is_port {
+                        port.to_string()
+                    } else {
+                        part.to_string()
+                    };
+                    is_port = part == "tcp";
+                    part
+                })
+                .collect::<Vec<_>>()
+                .join("/")
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Assigns non-conflicting P2P, Ollama & management API ports to this profile, so that multiple
+/// profiles can run on the same machine without manual port bookkeeping.
+///
+/// - The P2P port must not be configured by another profile, nor be in use by a live listener.
+/// - The Ollama port must not be configured by another profile; a live listener there is most likely
+///   an Ollama server that this profile can use, so it is not considered a conflict.
+/// - The management API port, if the API is enabled, must not be configured by another profile
+///   that has it enabled, nor be in use by a live listener.
+///
+/// Nothing is changed if there are no other profiles. The changes are logged, and only made
+/// within the given environment, the caller is responsible for saving it.
+pub fn assign_profile_ports(dria_env: &mut DriaEnv, env_path: &Path) {
+    let other_ports = other_profile_ports(env_path);
+    if other_ports.p2p.is_empty() && other_ports.ollama.is_empty() {
+        return;
+    }
+
+    let listen_addr = dria_env
+        .get_or_default(DriaEnv::DKN_P2P_LISTEN_ADDR_KEY)
+        .expect("has a default")
+        .to_string();
+    if let Some(p2p_port) = parse_listen_port(&listen_addr) {
+        let is_taken = |port| other_ports.p2p.contains(&port) || !is_port_free(port);
+        if is_taken(p2p_port) {
+            if let Some(new_port) = find_port(p2p_port, is_taken) {
+                log::warn!("P2P port {p2p_port} is used by another profile or process, using {new_port} instead.");
+                dria_env.set(
+                    DriaEnv::DKN_P2P_LISTEN_ADDR_KEY,
+                    with_listen_port(&listen_addr, new_port),
+                );
+            }
+        }
+    }
+
+    let (_, ollama_port) = dria_env.get_ollama_config();
+    if other_ports.ollama.contains(&ollama_port) {
+        if let Some(new_port) = find_port(ollama_port, |port| other_ports.ollama.contains(&port)) {
+            log::warn!(
+                "Ollama port {ollama_port} is used by another profile, using {new_port} instead."
+            );
+            dria_env.set(DriaEnv::OLLAMA_PORT_KEY, new_port);
+        }
+    }
+
+    if dria_env.get_bool(DriaEnv::DKN_MANAGEMENT_API_KEY) {
+        let management_port: u16 = dria_env
+            .get_parsed(DriaEnv::DKN_MANAGEMENT_PORT_KEY)
+            .expect("has a default");
+        let is_taken = |port| other_ports.management.contains(&port) || !is_port_free(port);
+        if is_taken(management_port) {
+            if let Some(new_port) = find_port(management_port, is_taken) {
+                log::warn!("Management API port {management_port} is used by another profile or process, using {new_port} instead.");
+                dria_env.set(DriaEnv::DKN_MANAGEMENT_PORT_KEY, new_port);
+            }
+        }
+    }
+}
+
+/// Checks whether the given P2P port is in use, and if so offers to use the next free port instead.
+///
+/// The free port is used without asking outside of an interactive terminal, as the compute node
+/// would fail to bind the port otherwise. A random port (`0`) is never in use.
+///
+/// ### Returns
+/// The port to use, which is the given one if it is free or the offer is declined.
+///
+/// ### Errors
+/// - If the prompt fails
+pub fn offer_free_port(port: u16) -> eyre::Result<u16> {
+    if port == 0 || is_port_free(port) {
+        return Ok(port);
+    }
+    let Some(free_port) = find_port(port, |port| !is_port_free(port)) else {
+        log::warn!("P2P port {port} is already in use, and no free port was found.");
+        return Ok(port);
+    };
+
+    if !std::io::stdin().is_terminal() {
+        log::warn!("P2P port {port} is already in use, using {free_port} instead.");
+        return Ok(free_port);
+    }
+
+    let answer = Confirm::new(&tr!(
+        "P2P port {port} is already in use, use {free_port} instead?",
+        port = port,
+        free_port = free_port
+    ))
+    .with_help_message(tr!(
+        "If your node is running with this port right now, answer no"
+    ))
+    .with_default(true)
+    .prompt()?;
+
+    Ok(if answer { free_port } else { port })
+}
+
+/// Checks whether the P2P port of the given environment is in use, and if so offers to use the next
+/// free port instead, see [`offer_free_port`]; the new port is set within the listen addresses.
+///
+/// ### Errors
+/// - If the listen addresses are invalid
+/// - If the prompt fails
+pub fn resolve_p2p_port_conflict(dria_env: &mut DriaEnv) -> eyre::Result<()> {
+    let port = dria_env.get_port()?;
+    let new_port = offer_free_port(port)?;
+    if new_port != port {
+        let listen_addr = dria_env
+            .get_or_default(DriaEnv::DKN_P2P_LISTEN_ADDR_KEY)
+            .expect("has a default")
+            .to_string();
+        let new_listen_addr = with_listen_port(&listen_addr, new_port);
+        log::info!("New listen address: {:?}", new_listen_addr);
+        dria_env.set(DriaEnv::DKN_P2P_LISTEN_ADDR_KEY, new_listen_addr);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_port() {
+        let taken = BTreeSet::from([4001, 4002, 4004]);
+        assert_eq!(find_port(4001, |port| taken.contains(&port)), Some(4003));
+        assert_eq!(find_port(4000, |port| taken.contains(&port)), Some(4000));
+    }
+
+    #[test]
+    fn test_other_profile_env_paths() {
+        let dir = std::env::temp_dir().join(format!(
+            "dkn-test-profiles-{}-{}",
+            std::process::id(),
+            line!()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in [
+            ".env",
+            ".env.work",
+            ".env.launcher.log",
+            ".env.launcher.log.old",
+            ".env.lock",
+            ".env.work.lock",
+            ".env.example",
+        ] {
+            std::fs::write(dir.join(name), "").unwrap();
+        }
+
+        let names = |env_path: &Path| {
+            let mut names = other_profile_env_paths(env_path)
+                .into_iter()
+                .map(|path| path.file_name().unwrap().to_string_lossy().to_string())
+                .collect::<Vec<_>>();
+            names.sort();
+            names
+        };
+        assert_eq!(names(&dir.join(".env")), [".env.work"]);
+        assert_eq!(names(&dir.join(".env.work")), [".env"]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_with_listen_port() {
+        assert_eq!(
+            with_listen_port("/ip4/0.0.0.0/tcp/4001", 4002),
+            "/ip4/0.0.0.0/tcp/4002"
+        );
+        assert_eq!(
+            with_listen_port("/ip6/::/tcp/4001/ws", 4005),
+            "/ip6/::/tcp/4005/ws"
+        );
+        assert_eq!(
+            with_listen_port("/ip4/0.0.0.0/tcp/4001,/ip6/::/tcp/4001", 4002),
+            "/ip4/0.0.0.0/tcp/4002,/ip6/::/tcp/4002"
+        );
+    }
+}
@@ -0,0 +1,76 @@
+use colored::Colorize;
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::{progress_draw_target, set_shared_progress, TimingSummary};
+
+/// Template of the spinner of each check.
+const PREFLIGHT_SPINNER_TEMPLATE: &str = "{spinner} {msg} ({elapsed})";
+/// Interval between spinner ticks.
+const PREFLIGHT_TICK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Runs preflight checks concurrently, with a combined display of one spinner per check.
+///
+/// Checks are awaited together by the caller (e.g. with `tokio::join!`), each wrapped with [`PreflightChecks::run`].
+/// Progress bars of the checks themselves (e.g. a download) are drawn along with the spinners
+/// until [`PreflightChecks::finish`], see [`set_shared_progress`].
+#[derive(Debug)]
+pub struct PreflightChecks {
+    progress: MultiProgress,
+    durations: Mutex<Vec<(String, Duration)>>,
+}
+
+impl Default for PreflightChecks {
+    fn default() -> Self {
+        Self {
+            progress: MultiProgress::with_draw_target(progress_draw_target()),
+            durations: Mutex::default(),
+        }
+    }
+}
+
+impl PreflightChecks {
+    pub fn new() -> Self {
+        let checks = Self::default();
+        set_shared_progress(Some(checks.progress.clone()));
+
+        checks
+    }
+
+    /// Awaits the given check while displaying a spinner for it, and records how long it took.
+    pub async fn run<T>(&self, name: &str, check: impl Future<Output = T>) -> T {
+        let spinner = self.progress.add(
+            ProgressBar::new_spinner()
+                .with_style(
+                    ProgressStyle::default_spinner()
+                        .template(PREFLIGHT_SPINNER_TEMPLATE)
+                        .expect("template is valid"),
+                )
+                .with_message(name.to_string()),
+        );
+        spinner.enable_steady_tick(PREFLIGHT_TICK_INTERVAL);
+
+        let started = Instant::now();
+        let result = check.await;
+        let elapsed = started.elapsed();
+
+        spinner.finish_with_message(format!("{} {name}", "✓".green()));
+        self.durations
+            .lock()
+            .expect("lock is not poisoned")
+            .push((format!("preflight: {name}"), elapsed));
+
+        result
+    }
+
+    /// Clears the spinners, and records the duration of each check within the given summary.
+    pub fn finish(self, timings: &mut TimingSummary) {
+        set_shared_progress(None);
+        let _ = self.progress.clear();
+        for (step, duration) in self.durations.into_inner().expect("lock is not poisoned") {
+            timings.record_duration(step, duration);
+        }
+    }
+}
@@ -1,24 +1,117 @@
+use dkn_executor::{Model, ModelProvider};
 use eyre::{Context, Result};
 use self_update::self_replace;
-use std::path::PathBuf;
+use std::collections::HashSet;
+use std::env;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::process::{Child, Command};
+use tokio::task::JoinHandle;
 use tokio::time::interval;
 use tokio_util::sync::CancellationToken;
 
-use crate::utils::{DriaRelease, DKN_LATEST_COMPUTE_FILE};
+use crate::tr;
+use crate::utils::{
+    DriaRelease, ExitReason, FsTransaction, DKN_LATEST_COMPUTE_FILE, DKN_LAUNCHER_VERSION,
+    DKN_VERSION_TRACKER_FILE,
+};
 
-use super::{check_for_compute_node_update, check_for_launcher_update};
+use super::{
+    append_env_audit, audit_change, check_for_compute_node_update, check_for_launcher_update,
+    check_launcher_replaceable, check_ollama, connect_ollama, ensure_launcher_supports,
+    find_slow_models, kill_process_tree, list_local_model_names, notify_failure,
+    pull_models_with_progress, set_language, signal_termination, spawn_force_quit, spawn_ollama,
+    terminate_gracefully, terminate_pid_gracefully, track_process_tree, wait_or_kill,
+    ComputeOutput, DriaEnv, EnvChange, EnvWatcher, HealthState, Language, LogBoost, NodeLock,
+    OllamaPidFile, PortMappingTask, ReloadPlan, ReloadSignal, ResourceLimits, ShutdownProgress,
+    UpdateCheck, UpdateScheduler, Watchdog, MINIMUM_EVAL_TPS, PORT_MAPPING_REMOVAL_TIMEOUT,
+    WATCHDOG_CHECK_INTERVAL,
+};
+#[cfg(windows)]
+use super::{is_quarantined, quarantine_hint};
 
-/// Number of seconds between refreshing for compute node updates.
-const COMPUTE_NODE_UPDATE_CHECK_INTERVAL_SECS: Duration = Duration::from_secs(60 * 60); // every few hours
-/// Number of seconds between refreshing for launcher updates.
-const LAUNCHER_UPDATE_CHECK_INTERVAL_SECS: Duration = Duration::from_secs(3 * 60 * 60); // every few hours
+/// An env key that compute node checks to get the path to the environment file.
+/// This is set by the launcher when it spawns the compute node.
+const DKN_COMPUTE_ENV_KEY: &str = "DKN_COMPUTE_ENV";
+
+/// The file that keeps the last exit status of the compute node, see [`read_last_compute_exit`].
+const DKN_COMPUTE_EXIT_FILE: &str = ".dkn-compute-exit";
+
+/// Number of times a compute node that crashes repeatedly is restarted, before giving up.
+const COMPUTE_MAX_RESTARTS: usize = 5;
+/// Time to wait before restarting a crashed compute node.
+const COMPUTE_RESTART_DELAY: Duration = Duration::from_secs(5);
+/// Number of attempts to respawn Ollama after it exits unexpectedly, before giving up.
+const OLLAMA_MAX_RESPAWNS: u32 = 5;
+/// Time to wait before the first Ollama respawn attempt, doubled after each failed attempt.
+const OLLAMA_RESPAWN_DELAY: Duration = Duration::from_secs(5);
+/// Interval between summary logs of the compute node output.
+const COMPUTE_SUMMARY_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// Spawns the compute node executable at `exe_path` with the given environment file.
+///
+/// ### Arguments
+/// - `exe_path`: path to the compute node binary
+/// - `env_path`: path to the environment file, passed to the compute node
+/// - `models`: models to run, which take precedence over `DKN_MODELS` within the file
+/// - `rust_log`: optional `RUST_LOG` override for the compute node
+/// - `output`: the output handle that captures & parses the compute node output
+///
+/// ### Errors
+/// - If the compute node process could not be spawned
+pub fn spawn_compute_process(
+    exe_path: &Path,
+    env_path: &Path,
+    models: &HashSet<Model>,
+    rust_log: Option<&str>,
+    output: &ComputeOutput,
+    limits: &ResourceLimits,
+) -> Result<Child> {
+    let exec_platform = env::var("DKN_EXEC_PLATFORM")
+        .unwrap_or_else(|_| format!("launcher/v{DKN_LAUNCHER_VERSION}")); // default to launcher value if not set
+
+    let mut command = Command::new(exe_path);
+    command
+        // add env variable for the path, respecting the `--profile` option
+        .env(DKN_COMPUTE_ENV_KEY, env_path)
+        // let compute node know that it is started by the launcher
+        // see: https://github.com/firstbatchxyz/dkn-compute-node/blob/master/compute/src/config.rs#L126
+        .env("DKN_EXEC_PLATFORM", exec_platform);
+
+    // compute node does not override existing env vars when it reads the env file,
+    // so these take precedence over the values within the file
+    let mut models = models.iter().map(|m| m.to_string()).collect::<Vec<_>>();
+    models.sort();
+    command.env(DriaEnv::DKN_MODELS_KEY, models.join(","));
+    if let Some(rust_log) = rust_log {
+        command.env("RUST_LOG", rust_log);
+    }
+
+    ComputeOutput::configure(&mut command);
+    limits.configure(&mut command);
+    let mut child = command.spawn().wrap_err("failed to spawn compute node")?;
+    limits.apply(&child);
+    track_process_tree(&child, "compute node");
+    output.capture(&mut child);
+
+    Ok(child)
+}
 
 /// A launched compute node.
 pub struct ComputeInstance {
     /// Executed compute node's directory.
     pub compute_dir: PathBuf,
+    /// Executed compute node's path.
+    pub compute_path: PathBuf,
+    /// Environment file used by the compute node.
+    pub env_path: PathBuf,
+    /// The environment file as it was last applied, its changes are found w.r.t this on reload.
+    pub env_snapshot: DriaEnv,
+    /// Models that the compute node runs, i.e. the models within the environment file except
+    /// the ones that are skipped by the checks on start.
+    pub models: HashSet<Model>,
     /// The compute process handle.
     pub compute_process: Child,
     /// Executed launcher version.
@@ -28,75 +121,297 @@ pub struct ComputeInstance {
     /// This is only used when the compute node is started with Ollama models
     /// and an Ollama instance is NOT running at that time.
     pub ollama_process: Option<Child>,
+    /// PID of an Ollama that was launched by an earlier launcher and adopted at startup (e.g.
+    /// after a restart), see [`OllamaPidFile`]; it is stopped along with the compute node like
+    /// `ollama_process`, but it is not respawned if it exits.
+    pub adopted_ollama_pid: Option<u32>,
     /// Whether to check for updates or not.
     ///
     /// This is `true` unless you are running a specific version for a particular reason.
     pub check_updates: bool,
+    /// Whether to skip the Ollama models that are too slow on this machine, also when the
+    /// models are changed on reload; `false` with `--skip-perf-check`.
+    pub perf_check: bool,
+    /// A reload whose models are being prepared, applied once they are ready.
+    pub pending_reload: Option<PendingReload>,
     /// [`CancellationToken`] for the main loop.
     pub cancellation: CancellationToken,
+    /// Failure tracker, used to boost the compute node log-level on repeated failures.
+    pub log_boost: LogBoost,
+    /// Number of times Ollama has exited unexpectedly, tracked apart from the compute node
+    /// failures as it is respawned on its own, see [`ComputeInstance::handle_ollama_exit`].
+    pub ollama_exits: usize,
+    /// Health state, shared with the health-check server.
+    pub health: Arc<HealthState>,
+    /// Captured compute node output & its statistics.
+    pub output: ComputeOutput,
+    /// Time to wait for child processes to exit on shutdown, before killing them.
+    pub shutdown_timeout: Duration,
+    /// Resource limits applied to the compute node process.
+    pub limits: ResourceLimits,
+    /// Schedule of the compute node & launcher update checks.
+    pub update_scheduler: UpdateScheduler,
+    /// Liveness check of the compute node, restarts it when it hangs.
+    pub watchdog: Watchdog,
+    /// Watcher of the environment file, `None` if both applying & auditing its changes are disabled.
+    pub env_watcher: Option<EnvWatcher>,
+    /// Renewal of the P2P port mapping on the router, if any; awaited on shutdown so that the
+    /// mapping is removed before the launcher exits.
+    pub port_mapping: Option<PortMappingTask>,
+    /// Lock that prevents another launcher from starting the same node, released on drop.
+    pub _lock: NodeLock,
 }
 
 impl ComputeInstance {
     /// The main loop of compute process. It handles the following:
     ///
-    /// - Monitors compute node process, exits on error.
-    /// - Keeps a handle on Ollama process as well if needed, to shut it down when compute node is stopped.
-    /// - Handles signals to gracefully shut down the compute node.
-    /// - Every [`COMPUTE_NODE_UPDATE_CHECK_INTERVAL_SECS`] checks for the latest compute node release, and restarts it if there is an update.
-    /// - EVery [`LAUNCHER_UPDATE_CHECK_INTERVAL_SECS`] checks for the latest launcher release, and replaces the binary "in-place" if there is an update.
-    pub async fn monitor_process(&mut self) {
-        let mut compute_node_update_interval = interval(COMPUTE_NODE_UPDATE_CHECK_INTERVAL_SECS);
-        let mut launcher_update_interval = interval(LAUNCHER_UPDATE_CHECK_INTERVAL_SECS);
+    /// - Monitors compute node process, restarts it on a crash up to [`COMPUTE_MAX_RESTARTS`] consecutive times.
+    /// - Boosts the compute node log-level for the next restart on repeated failures, see [`LogBoost`].
+    /// - Every [`COMPUTE_SUMMARY_INTERVAL`] logs a summary of the compute node output, see [`ComputeOutput`].
+    /// - Every [`WATCHDOG_CHECK_INTERVAL`] checks if the compute node is hung, and restarts it if so, see [`Watchdog`].
+    /// - Keeps a handle on Ollama process as well if needed, to shut it down when compute node is stopped,
+    ///   and respawns it if it exits unexpectedly, see [`ComputeInstance::handle_ollama_exit`].
+    /// - Handles signals to gracefully shut down the compute node, see [`ComputeInstance::shutdown`].
+    /// - Reloads the environment file on `SIGHUP` or when the file changes (see [`EnvWatcher`]),
+    ///   see [`ComputeInstance::handle_reload`]; newly selected models are prepared in the background,
+    ///   and the reload is applied once they are ready.
+    /// - As scheduled by [`UpdateScheduler`], checks for the latest compute node release and restarts it if there is an update,
+    ///   or checks for the latest launcher release and replaces the binary "in-place" if there is an update.
+    ///
+    /// ### Errors
+    /// - [`ExitReason::ComputeCrash`] if the compute node has crashed (or hung) and was not restarted
+    /// - [`ExitReason::OllamaUnavailable`] if Ollama has exited and could not be brought back
+    pub async fn monitor_process(&mut self) -> Result<()> {
+        let mut outcome = Ok(());
+        let mut summary_interval = interval(COMPUTE_SUMMARY_INTERVAL);
+        let mut watchdog_interval = interval(WATCHDOG_CHECK_INTERVAL);
+        let mut reload_signal = ReloadSignal::listen();
 
         // move one tick
-        launcher_update_interval.tick().await;
-        compute_node_update_interval.tick().await;
+        summary_interval.tick().await;
+        watchdog_interval.tick().await;
 
         loop {
             tokio::select! {
               // additional check in case the process is closed unexpectedly
-              _ = self.compute_process.wait() => {
-                log::info!("Compute node was closed, terminating.");
+              status = self.compute_process.wait() => {
+                  self.health.set_compute_alive(false);
+                  record_compute_exit(&self.compute_dir, &status);
+                  match status {
+                      Ok(status) if status.success() => log::info!("{}", tr!("Compute node was closed, terminating.")),
+                      Ok(status) => {
+                          log::error!("{}", tr!("Compute node crashed ({status}).", status = status));
+                          if self.handle_compute_crash().await {
+                              continue;
+                          }
+                          if !self.cancellation.is_cancelled() {
+                              outcome = Err(ExitReason::ComputeCrash);
+                          }
+                      }
+                      Err(err) => {
+                          log::error!("Could not wait for compute node: {err}");
+                          outcome = Err(ExitReason::ComputeCrash);
+                      }
+                  }
 
                   // now that compute is closed, we should kill Ollama if it was launched by us
-                  self.close_ollama().await.unwrap_or_else(|e| log::warn!("Failed to close Ollama: {}", e));
+                  self.close_ollama().await;
                   break;
               },
+              // Ollama exits unexpectedly, only if it was launched by us
+              status = wait_optional(&mut self.ollama_process) => {
+                  self.ollama_process = None;
+                  self.ollama_exits += 1;
+                  match status {
+                      Ok(status) => log::error!("Ollama exited unexpectedly ({status}), {} times since start.", self.ollama_exits),
+                      Err(err) => log::error!("Could not wait for Ollama: {err}"),
+                  }
+
+                  if !self.handle_ollama_exit().await {
+                      if !self.cancellation.is_cancelled() {
+                          log::error!("{}", tr!("Could not bring Ollama back, shutting down."));
+                          outcome = Err(ExitReason::OllamaUnavailable);
+                      }
+                      if self.shutdown().await && outcome.is_ok() {
+                          outcome = Err(ExitReason::ForceQuit);
+                      }
+                      break;
+                  }
+              },
               // cancellation signal, indicates that a signal has been received to shut down
               _ = self.cancellation.cancelled() => {
-                  log::info!("Received cancellation signal, shutting down launcher.");
-
-                  // close ollama if it was launched by us
-                  self.close_ollama().await.unwrap_or_else(|e| log::warn!("Failed to close Ollama: {}", e));
-
-                  // kill the compute process, note that the compute process may handle the signal as well on its own,
-                  // but we need to make sure that it is killed in case it doesn't (TODO: may be OS related?)
-                  if let Err(e) = self.compute_process.kill().await {
-                    log::warn!("Failed to kill compute process: {}", e);
+                  log::info!("{}", tr!("Received cancellation signal, shutting down launcher."));
+                  if self.shutdown().await {
+                      outcome = Err(ExitReason::ForceQuit);
                   }
 
                   break;
               }
-              // compute node update checks
-               _ = compute_node_update_interval.tick() => {
+              // compute node & launcher update checks
+              check = self.update_scheduler.next() => {
                   if !self.check_updates { continue; }
 
-                  if let Err(err) = self.handle_compute_update().await {
-                    log::error!("Error updating compute node: {err}");
+                  let (component, result) = match check {
+                      UpdateCheck::Compute => ("compute", self.handle_compute_update().await),
+                      UpdateCheck::Launcher => ("launcher", self.handle_launcher_update().await),
+                  };
+                  if let Err(err) = result {
+                    log::error!("Error updating {component}: {err}");
+                    self.record_failure();
                   }
               },
-              // launcher self-update checks
-               _ = launcher_update_interval.tick() => {
-                  if !self.check_updates { continue; }
+              // reload of the environment file
+              _ = reload_signal.recv() => {
+                  if let Err(err) = self.handle_reload().await {
+                      log::error!("Could not reload {}: {err:#}", self.env_path.display());
+                  }
+              },
+              // the models of a reload are ready (or could not be prepared)
+              prepared = reload_prepared(&mut self.pending_reload) => {
+                  let Some(pending) = self.pending_reload.take() else { continue };
+                  if let Err(err) = match prepared {
+                      Ok(prepared) => self.apply_reload(pending.file_env, pending.changes, Some(prepared)).await,
+                      Err(err) => Err(err),
+                  } {
+                      log::error!("Could not reload {}: {err:#}", self.env_path.display());
+                      log::warn!("The running node is out of sync with {}.", self.env_path.display());
+                  }
+              },
+              // restart of the compute node, e.g. requested with the management API
+              _ = self.health.restart_requested() => {
+                  log::info!("Restarting compute node on request.");
+                  if let Err(err) = self.restart_compute().await {
+                      log::error!("Could not restart compute node: {err:#}");
+                  }
+              },
+              // the environment file is changed, e.g. models are selected with `settings`
+              _ = env_changed(&mut self.env_watcher) => {
+                  self.handle_env_change().await;
+              },
+              // liveness check of the compute node
+              _ = watchdog_interval.tick() => {
+                  let Some(silence) = self.watchdog.stalled_for(self.output.stats().last_output) else {
+                      continue;
+                  };
 
-                  if let Err(err) = self.handle_launcher_update().await {
-                    log::error!("Error updating launcher: {err}");
+                  log::error!(
+                      "Compute node has not written any output for {} minutes, it seems to be hung; restarting it.",
+                      silence.as_secs() / 60
+                  );
+                  terminate_gracefully(&mut self.compute_process, "compute node", self.shutdown_timeout).await;
+                  if !self.handle_compute_crash().await {
+                      self.close_ollama().await;
+                      if !self.cancellation.is_cancelled() {
+                          outcome = Err(ExitReason::ComputeCrash);
+                      }
+                      break;
+                  }
+              },
+              // periodic summary of the compute node output
+              _ = summary_interval.tick() => {
+                  let stats = self.output.stats();
+                  log::info!("Compute node summary: {stats}");
+                  if !stats.tps.emas().is_empty() {
+                      log::info!("TPS of the completed tasks (moving average): {}", stats.tps);
                   }
               },
             }
         }
 
-        log::warn!("Quitting launcher!");
+        // an unattended node should not stop silently, a force-quit is requested by the user
+        if let Some(reason) = outcome
+            .err()
+            .filter(|reason| *reason != ExitReason::ForceQuit)
+        {
+            let last_error = self.output.stats().last_error;
+            notify_failure(&DriaEnv::new_from_env(), reason, last_error.as_deref()).await;
+        }
+
+        log::warn!("{}", tr!("Quitting launcher!"));
+        outcome.map_err(Into::into)
+    }
+
+    /// Shuts down the compute node, and Ollama if it was launched by us, in stages while
+    /// displaying the progress of each stage.
+    ///
+    /// Another termination signal during the shutdown force-quits the launcher, killing the
+    /// processes immediately; the remaining stages do not wait then.
+    ///
+    /// ### Returns
+    /// `true` if the launcher was force-quit.
+    async fn shutdown(&mut self) -> bool {
+        self.cancel_pending_reload().await;
+        let forced = spawn_force_quit(
+            std::iter::once(&self.compute_process)
+                .chain(self.ollama_process.as_ref())
+                .filter_map(|process| process.id())
+                .chain(self.adopted_ollama_pid)
+                .collect(),
+        );
+        let mut progress = ShutdownProgress::begin();
+
+        // ask the compute process to terminate, and kill it if it does not exit in time
+        let mut signaled = Ok(false);
+        progress
+            .stage("signaling compute node", async {
+                signaled = signal_termination(&mut self.compute_process);
+                signaled.is_ok()
+            })
+            .await;
+        match signaled {
+            Ok(true) => {
+                let timeout = self.shutdown_timeout;
+                progress
+                    .stage(
+                        &format!("waiting for compute node (up to {}s)", timeout.as_secs()),
+                        wait_or_kill(&mut self.compute_process, "compute node", timeout),
+                    )
+                    .await;
+            }
+            Ok(false) => progress.skip("waiting for compute node", "already exited"),
+            Err(err) => {
+                log::warn!("Could not ask compute node to terminate: {err}");
+                progress
+                    .stage("killing compute node", async {
+                        kill_process_tree(&mut self.compute_process, "compute node")
+                            .await
+                            .is_ok()
+                    })
+                    .await;
+            }
+        }
+
+        // close ollama if it was launched by us
+        if self.ollama_process.is_some() || self.adopted_ollama_pid.is_some() {
+            progress.stage("stopping Ollama", self.close_ollama()).await;
+        } else {
+            progress.skip("stopping Ollama", "not started by the launcher");
+        }
+
+        match self.port_mapping.take() {
+            Some(port_mapping) => {
+                progress
+                    .stage("removing port mapping", async {
+                        tokio::select! {
+                            removed = port_mapping.remove(PORT_MAPPING_REMOVAL_TIMEOUT) => removed,
+                            _ = forced.cancelled() => false,
+                        }
+                    })
+                    .await
+            }
+            None => progress.skip("removing port mapping", "not mapped"),
+        }
+
+        progress
+            .stage("flushing logs", async {
+                log::info!("Compute node summary: {}", self.output.stats());
+                log::logger().flush();
+                std::io::stdout().flush().is_ok() && std::io::stderr().flush().is_ok()
+            })
+            .await;
+
+        progress.finish();
+        forced.is_cancelled()
     }
 
     /// Checks for the latest compute node release and updates if needed.
@@ -108,48 +423,420 @@ impl ComputeInstance {
             check_for_compute_node_update(&self.compute_dir).await?;
 
         if requires_update {
-            // kill existing compute node
+            // the running compute node is kept if this launcher can not run the latest one
+            if let Err(err) = ensure_launcher_supports(&latest_release) {
+                log::warn!("{err:#}");
+                return Ok(());
+            }
+
+            // stop existing compute node
             //
-            // its safe to do this here even though `monitor_process` waits for a kill
-            // signal, because that thread is used within this function at this moment
-            self.compute_process.kill().await?;
+            // its safe to do this here even though `monitor_process` waits for the process
+            // to exit, because that thread is used within this function at this moment
+            terminate_gracefully(
+                &mut self.compute_process,
+                "compute node",
+                self.shutdown_timeout,
+            )
+            .await;
 
             log::info!(
-                "Updating compute node to version from to {}",
-                latest_release.version()
+                "Updating compute node to version {}, see the release notes at {}",
+                latest_release.version(),
+                latest_release.release_url()
             );
 
+            // the binary and its version tracker are replaced together, restored on failure
+            let mut transaction = FsTransaction::begin(&self.compute_dir)?;
+            transaction.modify(&self.compute_dir.join(DKN_LATEST_COMPUTE_FILE))?;
+            transaction.modify(&self.compute_dir.join(DKN_VERSION_TRACKER_FILE))?;
+
             let latest_path = latest_release
                 .download_release(&self.compute_dir, DKN_LATEST_COMPUTE_FILE, true)
                 .await?;
 
+            // update version tracker
+            DriaRelease::set_compute_version(&self.compute_dir, latest_release.version())?;
+            transaction.commit()?;
+
             // restart the compute node
             //
             // we dont set file-descriptors here again, because the process already
             // has that setting on the first launch
-            self.compute_process = Command::new(latest_path).spawn()?;
+            self.compute_process = spawn_compute_process(
+                &latest_path,
+                &self.env_path,
+                &self.models,
+                self.log_boost.rust_log(),
+                &self.output,
+                &self.limits,
+            )?;
+            self.compute_path = latest_path;
+            self.watchdog.reset();
+        }
 
-            // update version tracker
-            DriaRelease::set_compute_version(&self.compute_dir, latest_release.version())?;
+        Ok(())
+    }
+
+    /// Reloads the environment file, and applies its changes to the running node:
+    ///
+    /// - Launcher settings (e.g. update intervals, watchdog) are applied right away.
+    /// - Other settings (e.g. models, API keys) are applied by restarting the compute node.
+    /// - Settings that are only read on startup (e.g. health port) are warned about, as they
+    ///   require a full `restart`.
+    ///
+    /// Changes are found w.r.t the file as it was last applied (see [`ComputeInstance::env_snapshot`]),
+    /// and the process environment is updated with them, as it takes precedence over the env file
+    /// for the compute node. Keys that are set by the environment of the launcher itself (e.g. with
+    /// `docker run -e`) keep their values, and keys that are removed from the file keep their
+    /// previous values.
+    ///
+    /// If the models are changed, they are prepared in the background before anything is applied,
+    /// see [`prepare_models`]; a reload that is still preparing is replaced by this one.
+    ///
+    /// ### Errors
+    /// - If the environment file could not be read
+    /// - If the compute node could not be restarted
+    pub async fn handle_reload(&mut self) -> Result<()> {
+        log::info!("Reloading {}", self.env_path.display());
+        let (file_env, _) = DriaEnv::new_from_file(&self.env_path)?;
+        self.cancel_pending_reload().await;
+
+        let changes = file_env.changes_from(&self.env_snapshot);
+        if changes.is_empty() {
+            log::info!("No changes found.");
+            return Ok(());
         }
 
+        // a value that differs from the file it was loaded from is set by the launcher's environment
+        let (changes, overridden): (Vec<_>, Vec<_>) = changes.into_iter().partition(|change| {
+            env::var(change.key).ok().filter(|value| !value.is_empty()) == change.old
+        });
+        if !overridden.is_empty() {
+            log::warn!(
+                "{} are set by the environment of the launcher, so their changes within {} are not applied.",
+                overridden
+                    .iter()
+                    .map(|change| change.key)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                self.env_path.display()
+            );
+        }
+
+        if !changes
+            .iter()
+            .any(|change| change.key == DriaEnv::DKN_MODELS_KEY)
+        {
+            return self.apply_reload(file_env, changes, None).await;
+        }
+
+        let mut dria_env = DriaEnv::new_from_env();
+        dria_env.apply(&changes);
+        let cancellation = self.cancellation.child_token();
+        let task = tokio::spawn(prepare_models(
+            dria_env,
+            self.ollama_process.is_none(),
+            self.perf_check,
+            cancellation.clone(),
+        ));
+        self.pending_reload = Some(PendingReload {
+            file_env,
+            changes,
+            cancellation,
+            task,
+        });
+
         Ok(())
     }
 
-    async fn close_ollama(&mut self) -> Result<()> {
-        if let Some(ollama_process) = &mut self.ollama_process {
-            if let Err(e) = ollama_process.kill().await {
-                log::warn!("Failed to kill Ollama process: {}", e);
+    /// Applies the given changes of the environment file, with the models that are prepared for
+    /// them if the models are changed; see [`ComputeInstance::handle_reload`].
+    ///
+    /// ### Errors
+    /// - If the compute node could not be restarted
+    async fn apply_reload(
+        &mut self,
+        file_env: DriaEnv,
+        changes: Vec<EnvChange>,
+        prepared: Option<PreparedModels>,
+    ) -> Result<()> {
+        if let Some(prepared) = prepared {
+            if let Some(ollama_process) = prepared.ollama_process {
+                self.ollama_process = Some(ollama_process);
+                self.adopted_ollama_pid = None;
+            }
+            self.models = prepared.models;
+        }
+        for change in &changes {
+            log::info!("Changed {change}");
+            match &change.new {
+                Some(value) => env::set_var(change.key, value),
+                None => env::remove_var(change.key),
             }
         }
+        self.env_snapshot = file_env;
+
+        let dria_env = DriaEnv::new_from_env();
+        let plan = ReloadPlan::new(&changes);
+        self.shutdown_timeout = dria_env.get_shutdown_timeout();
+        let (compute_interval, launcher_interval) = dria_env.get_update_intervals();
+        self.update_scheduler = UpdateScheduler::new(compute_interval, launcher_interval);
+        self.watchdog = Watchdog::new(dria_env.get_watchdog_timeout());
+        self.limits = dria_env.get_resource_limits();
+        set_language(Language::from_env(&dria_env));
+        let is_watched = dria_env.is_env_watch_enabled() || dria_env.is_env_audit_enabled();
+        if is_watched != self.env_watcher.is_some() {
+            self.env_watcher = is_watched.then(|| EnvWatcher::new(&self.env_path));
+        }
+        if !plan.startup.is_empty() {
+            log::warn!(
+                "{} only take effect after a restart, e.g. with `dkn-compute-launcher restart`.",
+                plan.startup.join(", ")
+            );
+        }
+
+        if plan.requires_compute_restart() {
+            log::info!(
+                "Restarting compute node to apply {}.",
+                plan.compute.join(", ")
+            );
+            self.restart_compute().await?;
+        }
+        log::info!("Reloaded {}", self.env_path.display());
 
         Ok(())
     }
 
+    /// Cancels the reload whose models are being prepared, if any; the Ollama that it has
+    /// spawned is stopped.
+    async fn cancel_pending_reload(&mut self) {
+        let Some(pending) = self.pending_reload.take() else {
+            return;
+        };
+
+        log::info!("Cancelling the previous reload, its models were not ready.");
+        pending.cancellation.cancel();
+        // the models may have been ready right before, along with a spawned Ollama
+        if let Ok(Ok(PreparedModels {
+            ollama_process: Some(mut ollama_process),
+            ..
+        })) = pending.task.await
+        {
+            stop_spawned_ollama(&mut ollama_process, self.shutdown_timeout).await;
+        }
+    }
+
+    /// Stops the compute node gracefully and spawns it again, e.g. to apply new settings; this
+    /// does not count as a crash.
+    ///
+    /// ### Errors
+    /// - If the compute node could not be spawned again
+    async fn restart_compute(&mut self) -> Result<()> {
+        terminate_gracefully(
+            &mut self.compute_process,
+            "compute node",
+            self.shutdown_timeout,
+        )
+        .await;
+        self.compute_process = spawn_compute_process(
+            &self.compute_path,
+            &self.env_path,
+            &self.models,
+            self.log_boost.rust_log(),
+            &self.output,
+            &self.limits,
+        )?;
+        self.health.set_compute_alive(true);
+        self.watchdog.reset();
+
+        Ok(())
+    }
+
+    /// Handles an external edit of the environment file, as detected by [`EnvWatcher`].
+    ///
+    /// The changes are recorded to the audit log if `DKN_ENV_AUDIT` is enabled, and applied with
+    /// [`ComputeInstance::handle_reload`] if `DKN_WATCH_ENV` is enabled. Otherwise, or if they
+    /// could not be applied, a warning is logged as the running node is out of sync with the file.
+    async fn handle_env_change(&mut self) {
+        let env_path = self.env_path.display().to_string();
+        let file_env = match DriaEnv::new_from_file(&self.env_path) {
+            Ok((dria_env, _)) => dria_env,
+            Err(err) => {
+                log::warn!("{env_path} has changed, but could not be read: {err:#}");
+                return;
+            }
+        };
+
+        // the watch & audit settings of the file itself take effect right away; each edit is
+        // audited once, even if the running node stays out of sync with the file
+        let edits = match &mut self.env_watcher {
+            Some(env_watcher) => env_watcher.changes_since_seen(&file_env),
+            None => Vec::new(),
+        };
+        if file_env.is_env_audit_enabled() && !edits.is_empty() {
+            for change in &edits {
+                log::info!("{env_path} changed {}", audit_change(change));
+            }
+            append_env_audit(&self.compute_dir, &self.env_path, &edits);
+        }
+
+        let changes = file_env.changes_from(&self.env_snapshot);
+        if changes.is_empty() {
+            log::debug!("{env_path} has changed, but none of its settings.");
+            return;
+        }
+
+        let keys = changes
+            .iter()
+            .map(|change| change.key)
+            .collect::<Vec<_>>()
+            .join(", ");
+        if !file_env.is_env_watch_enabled() {
+            log::warn!(
+                "The running node is out of sync with {env_path} ({keys}), apply the changes with `dkn-compute-launcher reload`."
+            );
+            return;
+        }
+
+        log::info!("{env_path} has changed.");
+        if let Err(err) = self.handle_reload().await {
+            log::error!("Could not reload {env_path}: {err:#}");
+            log::warn!("The running node is out of sync with {env_path} ({keys}).");
+        }
+    }
+
+    /// Handles a crashed compute node by restarting it, unless the restart limit is reached.
+    ///
+    /// A compute node that is hung (see [`Watchdog`]) is handled the same way once it is stopped.
+    ///
+    /// Returns `true` if the compute node was restarted, `false` if it was not restarted or the
+    /// launcher is cancelled meanwhile.
+    async fn handle_compute_crash(&mut self) -> bool {
+        if self.log_boost.record_crash() {
+            log::warn!(
+                "Repeated failures detected, compute node will be restarted with debug logs."
+            );
+        }
+        let crashes = self.log_boost.crashes();
+        if crashes > COMPUTE_MAX_RESTARTS {
+            log::error!("Compute node crashed {crashes} times in a row, giving up.");
+            return false;
+        }
+
+        log::info!(
+            "Restarting compute node in {} seconds.",
+            COMPUTE_RESTART_DELAY.as_secs()
+        );
+        tokio::select! {
+            _ = tokio::time::sleep(COMPUTE_RESTART_DELAY) => {},
+            _ = self.cancellation.cancelled() => return false,
+        }
+
+        match spawn_compute_process(
+            &self.compute_path,
+            &self.env_path,
+            &self.models,
+            self.log_boost.rust_log(),
+            &self.output,
+            &self.limits,
+        ) {
+            Ok(process) => {
+                self.compute_process = process;
+                self.health.set_compute_alive(true);
+                self.watchdog.reset();
+                true
+            }
+            Err(err) => {
+                log::error!("Could not restart compute node: {err}");
+                #[cfg(windows)]
+                if is_quarantined(&self.compute_path, err.downcast_ref()) {
+                    log::error!("{}", quarantine_hint(&self.compute_path));
+                }
+                false
+            }
+        }
+    }
+
+    /// Records a failure within the supervision loop, see [`LogBoost`].
+    fn record_failure(&mut self) {
+        if self.log_boost.record_failure() {
+            log::warn!(
+                "Repeated failures detected, compute node will be restarted with debug logs."
+            );
+        }
+    }
+
+    /// Respawns Ollama after it exited unexpectedly, retrying up to [`OLLAMA_MAX_RESPAWNS`] times
+    /// with an exponential backoff; if Ollama is reachable again (e.g. restarted by the user), it
+    /// is used as is.
+    ///
+    /// Returns `true` if Ollama is running again, `false` if it could not be respawned or the
+    /// launcher is cancelled meanwhile.
+    async fn handle_ollama_exit(&mut self) -> bool {
+        let dria_env = DriaEnv::new_from_env();
+        let mut delay = OLLAMA_RESPAWN_DELAY;
+
+        for attempt in 1..=OLLAMA_MAX_RESPAWNS {
+            log::info!(
+                "Respawning Ollama in {} seconds ({attempt}/{OLLAMA_MAX_RESPAWNS}).",
+                delay.as_secs()
+            );
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {},
+                _ = self.cancellation.cancelled() => return false,
+            }
+            delay *= 2;
+
+            if check_ollama(&dria_env).await {
+                log::info!("Ollama is reachable again, resuming.");
+                return true;
+            }
+            match spawn_ollama(&dria_env).await {
+                Ok(process) => {
+                    log::info!("Ollama is respawned, resuming.");
+                    self.ollama_process = Some(process);
+                    return true;
+                }
+                Err(err) => log::error!("Could not respawn Ollama: {err:#}"),
+            }
+        }
+
+        false
+    }
+
+    /// Stops Ollama if it was launched by us, or adopted from an earlier launcher.
+    ///
+    /// Returns `true` if Ollama exited by itself (or there was none), `false` if it was killed.
+    async fn close_ollama(&mut self) -> bool {
+        if let Some(ollama_process) = &mut self.ollama_process {
+            let pid = ollama_process.id();
+            let exited =
+                terminate_gracefully(ollama_process, "Ollama", self.shutdown_timeout).await;
+            if let Some(pid) = pid {
+                OllamaPidFile::forget(pid);
+            }
+            exited
+        } else if let Some(pid) = self.adopted_ollama_pid.take() {
+            let exited = terminate_pid_gracefully(pid, "Ollama", self.shutdown_timeout).await;
+            OllamaPidFile::forget(pid);
+            exited
+        } else {
+            true
+        }
+    }
+
     /// Checks for the latest launcher release and updates if needed.
     ///
     /// This replaces the existing launcher binary.
     pub async fn handle_launcher_update(&mut self) -> Result<()> {
+        // the launcher can not update itself from a read-only location, the user
+        // is warned about this at startup so we skip silently here
+        if let Err(err) = check_launcher_replaceable() {
+            log::debug!("Skipping launcher update: {err}");
+            return Ok(());
+        }
+
         // check version
         let (latest_release, requires_update) =
             check_for_launcher_update(&self.launcher_version).await?;
@@ -177,3 +864,196 @@ impl ComputeInstance {
         Ok(())
     }
 }
+
+/// Waits for the given process to exit, or forever if there is none.
+async fn wait_optional(process: &mut Option<Child>) -> std::io::Result<std::process::ExitStatus> {
+    match process {
+        Some(process) => process.wait().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// A reload of the environment file whose models are being prepared, see [`prepare_models`].
+pub struct PendingReload {
+    /// The reloaded environment file.
+    file_env: DriaEnv,
+    /// Changes to apply once the models are ready.
+    changes: Vec<EnvChange>,
+    /// Cancels the preparation, e.g. when the launcher shuts down.
+    cancellation: CancellationToken,
+    task: JoinHandle<Result<PreparedModels>>,
+}
+
+/// Models that are prepared for a reload, see [`prepare_models`].
+pub struct PreparedModels {
+    /// Models that the compute node runs, without the ones that are too slow.
+    models: HashSet<Model>,
+    /// Ollama, if it was not running and is spawned for the models.
+    ollama_process: Option<Child>,
+}
+
+/// Waits for the models of the pending reload to be prepared, or forever if there is none.
+async fn reload_prepared(pending: &mut Option<PendingReload>) -> Result<PreparedModels> {
+    match pending {
+        Some(pending) => (&mut pending.task)
+            .await
+            .unwrap_or_else(|err| Err(eyre::eyre!("could not prepare the models: {err}"))),
+        None => std::future::pending().await,
+    }
+}
+
+/// Prepares the models selected within the given environment before the compute node is
+/// restarted with them, as on start: Ollama is spawned if `spawn` is set & it is not running,
+/// the Ollama models that are not available locally are pulled, and the ones that are too
+/// slow on this machine are skipped if `perf_check` is set.
+///
+/// The Ollama that is spawned here is stopped if the preparation fails or is cancelled.
+///
+/// ### Errors
+/// - If no models are selected, or none of them are fast enough
+/// - If Ollama could not be spawned, or the models could not be pulled
+/// - If the preparation is cancelled
+async fn prepare_models(
+    dria_env: DriaEnv,
+    spawn: bool,
+    perf_check: bool,
+    cancellation: CancellationToken,
+) -> Result<PreparedModels> {
+    let mut ollama_process = None;
+    let models = tokio::select! {
+        models = prepare_selected_models(&dria_env, spawn, perf_check, &mut ollama_process) => models,
+        _ = cancellation.cancelled() => Err(eyre::eyre!("cancelled")),
+    };
+
+    match models {
+        Ok(models) => Ok(PreparedModels {
+            models,
+            ollama_process,
+        }),
+        Err(err) => {
+            if let Some(ollama_process) = &mut ollama_process {
+                stop_spawned_ollama(ollama_process, dria_env.get_shutdown_timeout()).await;
+            }
+            Err(err)
+        }
+    }
+}
+
+/// See [`prepare_models`], the spawned Ollama is given back within `ollama_process`.
+async fn prepare_selected_models(
+    dria_env: &DriaEnv,
+    spawn: bool,
+    perf_check: bool,
+    ollama_process: &mut Option<Child>,
+) -> Result<HashSet<Model>> {
+    let mut models = dria_env.get_models();
+    if models.is_empty() {
+        eyre::bail!("no models are selected");
+    }
+    log::info!(
+        "Switching to models: {}",
+        models
+            .iter()
+            .map(|m| m.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+
+    let ollama_models = models
+        .iter()
+        .filter(|m| m.provider() == ModelProvider::Ollama)
+        .cloned()
+        .collect::<Vec<_>>();
+    if ollama_models.is_empty() {
+        return Ok(models);
+    }
+
+    if spawn && !check_ollama(dria_env).await {
+        log::info!("Ollama is not running, spawning it for the selected models.");
+        *ollama_process = Some(spawn_ollama(dria_env).await?);
+    }
+
+    let local_model_names = list_local_model_names(dria_env).await?;
+    let models_to_be_pulled = ollama_models
+        .iter()
+        .map(|m| m.to_string())
+        .filter(|model| !local_model_names.contains(model))
+        .collect::<Vec<_>>();
+    if !models_to_be_pulled.is_empty() {
+        log::info!(
+            "Pulling models from Ollama: {}",
+            models_to_be_pulled.join(", ")
+        );
+        pull_models_with_progress(
+            &connect_ollama(dria_env)?,
+            models_to_be_pulled,
+            dria_env.get_pull_retries(),
+            dria_env.get_pull_parallelism(),
+        )
+        .await?;
+    }
+
+    if perf_check {
+        let slow_models = find_slow_models(dria_env, &ollama_models).await?;
+        if !slow_models.is_empty() {
+            log::warn!(
+                "Skipping models that are below the minimum of {MINIMUM_EVAL_TPS} TPS on this machine: {}",
+                slow_models
+                    .iter()
+                    .map(|(model, tps)| format!("{model} ({tps:.2} TPS)"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+            models.retain(|m| !slow_models.iter().any(|(slow, _)| slow == m));
+            if models.is_empty() {
+                eyre::bail!("none of the selected models are fast enough on this machine");
+            }
+        }
+    }
+
+    Ok(models)
+}
+
+/// Stops an Ollama that was spawned for a reload, along with its record.
+async fn stop_spawned_ollama(ollama_process: &mut Child, timeout: Duration) {
+    let pid = ollama_process.id();
+    terminate_gracefully(ollama_process, "Ollama", timeout).await;
+    if let Some(pid) = pid {
+        OllamaPidFile::forget(pid);
+    }
+}
+
+/// Waits for the environment file to change, or forever if it is not watched.
+async fn env_changed(watcher: &mut Option<EnvWatcher>) {
+    match watcher {
+        Some(watcher) => watcher.changed().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Records the exit status of the compute node along with the time, so that it can be
+/// included within `report`; this is best-effort.
+fn record_compute_exit(compute_dir: &Path, status: &std::io::Result<std::process::ExitStatus>) {
+    let status = match status {
+        Ok(status) => status.to_string(),
+        Err(err) => format!("unknown ({err})"),
+    };
+    let time = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or_default();
+    if let Err(err) = std::fs::write(
+        compute_dir.join(DKN_COMPUTE_EXIT_FILE),
+        format!("{time} {status}\n"),
+    ) {
+        log::debug!("Could not record compute node exit: {err}");
+    }
+}
+
+/// Returns the last recorded exit of the compute node within the given directory, as
+/// `<unix-seconds> <status>`.
+pub fn read_last_compute_exit(compute_dir: &Path) -> Option<String> {
+    std::fs::read_to_string(compute_dir.join(DKN_COMPUTE_EXIT_FILE))
+        .ok()
+        .map(|content| content.trim().to_string())
+}
@@ -0,0 +1,129 @@
+use eyre::Result;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use super::ExitReason;
+
+/// Runs interactive prompts with a timeout, so that an unattended launcher (e.g. restarted by
+/// systemd) does not block forever on a prompt.
+///
+/// The prompts run on a separate thread; if they are not answered in time, the terminal is
+/// restored to its state at the start, as a prompt leaves it in raw mode, and that thread is left
+/// blocked on the prompt until the launcher exits.
+///
+/// ### Arguments
+/// - `timeout`: time to wait for the prompts, they are run in place if not given
+/// - `reason`: what the prompts are for, used in the error
+/// - `prompts`: the prompts to run
+///
+/// ### Errors
+/// - If the prompts fail
+/// - With [`ExitReason::PromptTimeout`] if the prompts are not answered within the timeout
+pub fn with_prompt_timeout<T: Send + 'static>(
+    timeout: Option<Duration>,
+    reason: &str,
+    prompts: impl FnOnce() -> Result<T> + Send + 'static,
+) -> Result<T> {
+    let Some(timeout) = timeout else {
+        return prompts();
+    };
+
+    let (result_tx, result_rx) = mpsc::channel();
+    let terminal = TerminalMode::save();
+    std::thread::spawn(move || {
+        // the receiver is gone if the prompts have timed out
+        let _ = result_tx.send(prompts());
+    });
+
+    match result_rx.recv_timeout(timeout) {
+        Ok(result) => result,
+        Err(mpsc::RecvTimeoutError::Timeout) => {
+            terminal.restore();
+            Err(eyre::eyre!(
+                "No answer to the prompt for {reason} within {}s.",
+                timeout.as_secs()
+            )
+            .wrap_err(ExitReason::PromptTimeout))
+        }
+        Err(mpsc::RecvTimeoutError::Disconnected) => {
+            eyre::bail!("The prompt for {reason} has panicked.")
+        }
+    }
+}
+
+/// Mode of the terminal attached to the standard input, if any.
+struct TerminalMode {
+    #[cfg(unix)]
+    termios: Option<libc::termios>,
+    #[cfg(windows)]
+    console_mode: Option<u32>,
+}
+
+impl TerminalMode {
+    /// Saves the current mode of the terminal.
+    fn save() -> Self {
+        #[cfg(unix)]
+        {
+            let mut termios = std::mem::MaybeUninit::<libc::termios>::uninit();
+            let result = unsafe { libc::tcgetattr(libc::STDIN_FILENO, termios.as_mut_ptr()) };
+            Self {
+                termios: (result == 0).then(|| unsafe { termios.assume_init() }),
+            }
+        }
+        #[cfg(windows)]
+        {
+            use windows_sys::Win32::System::Console::{
+                GetConsoleMode, GetStdHandle, STD_INPUT_HANDLE,
+            };
+
+            let mut mode = 0;
+            let result = unsafe { GetConsoleMode(GetStdHandle(STD_INPUT_HANDLE), &mut mode) };
+            Self {
+                console_mode: (result != 0).then_some(mode),
+            }
+        }
+        #[cfg(not(any(unix, windows)))]
+        Self {}
+    }
+
+    /// Restores the saved mode of the terminal, and shows the cursor that a prompt may hide.
+    fn restore(&self) {
+        #[cfg(unix)]
+        if let Some(termios) = &self.termios {
+            unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, termios) };
+        }
+        #[cfg(windows)]
+        if let Some(mode) = self.console_mode {
+            use windows_sys::Win32::System::Console::{
+                GetStdHandle, SetConsoleMode, STD_INPUT_HANDLE,
+            };
+
+            unsafe { SetConsoleMode(GetStdHandle(STD_INPUT_HANDLE), mode) };
+        }
+
+        // the prompt line is left as is, so the error starts on a new line
+        eprintln!("\x1b[?25h");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::exit_code;
+
+    #[test]
+    fn test_prompt_timeout() {
+        // answered in time, or without a timeout
+        let answer = with_prompt_timeout(Some(Duration::from_secs(10)), "test", || Ok(42));
+        assert_eq!(answer.unwrap(), 42);
+        assert_eq!(with_prompt_timeout(None, "test", || Ok(42)).unwrap(), 42);
+
+        // not answered in time
+        let err = with_prompt_timeout(Some(Duration::from_millis(10)), "test", || {
+            std::thread::sleep(Duration::from_secs(1));
+            Ok(42)
+        })
+        .unwrap_err();
+        assert_eq!(exit_code(&err), ExitReason::PromptTimeout.code());
+    }
+}
@@ -0,0 +1,145 @@
+use eyre::Result;
+use inquire::Confirm;
+use std::io::{self, IsTerminal};
+use std::path::Path;
+use std::time::Duration;
+
+use super::{
+    get_latest_release, get_releases, DriaRelease, DriaRepository, DKN_LATEST_COMPUTE_FILE,
+};
+use crate::tr;
+
+/// Windows error code for a file that could not be opened because it contains a virus.
+const ERROR_VIRUS_INFECTED: i32 = 225;
+/// Windows error code for a file that could not be opened because a virus was removed from it.
+const ERROR_VIRUS_DELETED: i32 = 226;
+
+/// Time to wait after a download before checking the binary, as antivirus scans run right after
+/// the file is written.
+const QUARANTINE_CHECK_DELAY: Duration = Duration::from_secs(3);
+
+/// Returns `true` if the given error is what an antivirus causes when it blocks or removes a file.
+pub fn is_quarantine_error(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::NotFound | io::ErrorKind::PermissionDenied
+    ) || matches!(
+        err.raw_os_error(),
+        Some(ERROR_VIRUS_INFECTED | ERROR_VIRUS_DELETED)
+    )
+}
+
+/// Returns `true` if the binary at the given path seems to be quarantined, i.e. it is gone or
+/// the given error (e.g. from spawning it) is what an antivirus causes.
+pub fn is_quarantined(path: &Path, err: Option<&io::Error>) -> bool {
+    !path.exists() || err.is_some_and(is_quarantine_error)
+}
+
+/// Returns an explanation of a quarantined binary, along with the exclusion path to add.
+pub fn quarantine_hint(path: &Path) -> String {
+    let dir = path
+        .parent()
+        .and_then(|dir| dir.canonicalize().ok())
+        .unwrap_or_else(|| path.parent().unwrap_or(path).to_path_buf());
+
+    format!(
+        "The binary at {path} was removed or blocked right after it was written, most likely by your antivirus flagging it as a false positive.\n\
+        Add an exclusion for this folder within your antivirus: {dir}\n\
+        For Windows Defender, run this in PowerShell as Administrator:\n  Add-MpPreference -ExclusionPath \"{dir}\"",
+        path = path.display(),
+        dir = dir.display(),
+    )
+}
+
+/// Checks that a freshly downloaded binary is still there and readable, after giving the
+/// antivirus a moment to scan it.
+///
+/// ### Errors
+/// - If the binary is gone or blocked, with an explanation of the cause
+pub async fn check_quarantine(path: &Path) -> Result<()> {
+    tokio::time::sleep(QUARANTINE_CHECK_DELAY).await;
+
+    match std::fs::File::open(path) {
+        Err(err) if is_quarantine_error(&err) => {
+            eyre::bail!("{err}\n{}", quarantine_hint(path))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Explains the cause of a quarantined compute node binary, and offers to download it again
+/// (e.g. after an exclusion is added) within an interactive terminal.
+///
+/// ### Returns
+/// `true` if the binary was downloaded again.
+///
+/// ### Errors
+/// - If the prompt fails
+/// - If the binary could not be downloaded again
+pub async fn offer_quarantine_redownload(exe_path: &Path) -> Result<bool> {
+    log::error!("{}", quarantine_hint(exe_path));
+    if !std::io::stdin().is_terminal() {
+        return Ok(false);
+    }
+
+    let answer = Confirm::new(tr!("Download the compute node again?"))
+        .with_help_message(tr!(
+            "Add the exclusion above first, otherwise the antivirus may remove it again"
+        ))
+        .with_default(true)
+        .prompt()?;
+    if !answer {
+        return Ok(false);
+    }
+
+    redownload_compute(exe_path).await?;
+    Ok(true)
+}
+
+/// Downloads the compute node binary at the given path again, either the latest release
+/// or the specific release that the filename belongs to.
+async fn redownload_compute(exe_path: &Path) -> Result<()> {
+    let exe_dir = exe_path.parent().expect("must be a file");
+    let filename = exe_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| eyre::eyre!("invalid compute node path: {}", exe_path.display()))?;
+
+    let is_latest = filename == DKN_LATEST_COMPUTE_FILE;
+    let release = if is_latest {
+        get_latest_release(DriaRepository::ComputeNode).await?
+    } else {
+        get_releases(DriaRepository::ComputeNode)
+            .await?
+            .into_iter()
+            .find(|release| release.to_filename().is_ok_and(|name| name == filename))
+            .ok_or_else(|| eyre::eyre!("no release found for {filename}"))?
+    };
+
+    release.download_release(exe_dir, filename, true).await?;
+    if is_latest {
+        DriaRelease::set_compute_version(exe_dir, release.version())?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_quarantined() {
+        let exe_path = std::env::current_exe().unwrap();
+        assert!(!is_quarantined(&exe_path, None));
+        assert!(is_quarantined(&exe_path.with_extension("missing"), None));
+        assert!(is_quarantined(
+            &exe_path,
+            Some(&io::Error::from_raw_os_error(ERROR_VIRUS_INFECTED))
+        ));
+        assert!(!is_quarantined(
+            &exe_path,
+            Some(&io::Error::from(io::ErrorKind::OutOfMemory))
+        ));
+    }
+}
@@ -1,34 +1,35 @@
-use crate::utils::{crypto::eip191_hash, get_network_env};
+use crate::utils::{crypto::Signer, resolve_referrals_api_base_url};
 use eyre::{Context, Result};
-use libsecp256k1::SecretKey;
 
-use super::LAUNCHER_USER_AGENT;
-
-#[inline]
-fn get_referrals_api_base_url() -> String {
-    let network = get_network_env();
-    format!("https://{network}.dkn.dria.co/referral/v0")
-}
+use super::{cached, invalidate_cached, send_with_retries, DriaEnv, TracedSend};
 
 pub struct ReferralsClient {
     base_url: String,
     client: reqwest::Client,
+    /// How many times a failed `GET` request is retried.
+    retries: u32,
 }
 
 impl Default for ReferralsClient {
     fn default() -> Self {
-        Self::new(get_referrals_api_base_url())
+        Self::new(resolve_referrals_api_base_url())
     }
 }
 
 impl ReferralsClient {
+    /// Creates a client with the given base URL, use [`ReferralsClient::default`] to respect
+    /// `DKN_REFERRALS_API_URL` and the network.
+    ///
+    /// The timeouts & retries are taken from the environment, see [`DriaEnv::get_api_client_policy`].
     pub fn new(base_url: String) -> Self {
-        let client = reqwest::Client::builder()
-            .user_agent(LAUNCHER_USER_AGENT)
-            .build()
-            .expect("could not create reqwest client");
+        let policy = DriaEnv::new_from_env().get_api_client_policy();
+        let client = policy.client().expect("could not create reqwest client");
 
-        Self { base_url, client }
+        Self {
+            base_url,
+            client,
+            retries: policy.retries,
+        }
     }
 
     /// Returns a list of addresses of the users referred by the given `address`.
@@ -43,17 +44,18 @@ impl ReferralsClient {
     /// }
     /// ```
     pub async fn get_referrals(&self, address: &str) -> Result<Option<Vec<String>>> {
-        let res = self
-            .client
-            .get(format!("{}/get_referrals/{}", self.base_url, address))
-            .send()
-            .await?;
-
-        if res.status().is_client_error() {
-            Ok(None)
-        } else {
-            Ok(res.json().await.map(Some)?)
-        }
+        let url = format!("{}/get_referrals/{}", self.base_url, address);
+        cached(&url, async {
+            let res = send_with_retries(self.client.get(&url), self.retries).await?;
+
+            // only a missing address is cached as none, other client errors are not cached
+            if res.status() == reqwest::StatusCode::NOT_FOUND {
+                Ok(None)
+            } else {
+                Ok(res.error_for_status()?.json().await.map(Some)?)
+            }
+        })
+        .await
     }
 
     /// Returns the user that referred the given `address`.
@@ -68,41 +70,48 @@ impl ReferralsClient {
     /// }
     /// ```
     pub async fn get_referred_by(&self, address: &str) -> Result<Option<String>, reqwest::Error> {
-        let res = self
-            .client
-            .get(format!("{}/get_referred_by/{}", self.base_url, address))
-            .send()
-            .await?;
-
         #[derive(serde::Deserialize)]
         #[serde(rename_all = "camelCase")]
         struct Res {
             referrer_address: String,
         }
 
-        if res.status().is_client_error() {
-            Ok(None)
-        } else {
-            res.json::<Res>().await.map(|r| Some(r.referrer_address))
-        }
+        let url = format!("{}/get_referred_by/{}", self.base_url, address);
+        cached(&url, async {
+            let res = send_with_retries(self.client.get(&url), self.retries).await?;
+
+            if res.status() == reqwest::StatusCode::NOT_FOUND {
+                Ok(None)
+            } else {
+                res.error_for_status()?
+                    .json::<Res>()
+                    .await
+                    .map(|r| Some(r.referrer_address))
+            }
+        })
+        .await
     }
 
     /// Returns the number of referral code uses for this address.
     pub async fn get_max_uses(&self, address: &str) -> Result<usize> {
-        let res = self
-            .client
-            .get(format!("{}/get_max_uses/{}", self.base_url, address))
-            .send()
-            .await?
-            .error_for_status()?;
-
-        res.text()
-            .await?
-            .parse()
-            .wrap_err("could not parse returned value")
+        let url = format!("{}/get_max_uses/{}", self.base_url, address);
+        cached(&url, async {
+            let res = send_with_retries(self.client.get(&url), self.retries)
+                .await?
+                .error_for_status()?;
+
+            res.text()
+                .await?
+                .parse()
+                .wrap_err("could not parse returned value")
+        })
+        .await
     }
-    /// Requests a challenge from the referral API, and completes it to get a referral code.
-    pub async fn get_referral_code(&self, secret_key: &SecretKey, address: &str) -> Result<String> {
+
+    /// Requests a challenge from the referral API for the address of the signer, and completes
+    /// it to get a referral code.
+    pub async fn get_referral_code(&self, signer: &Signer) -> Result<String> {
+        let address = signer.address();
         let res = self
             .client
             .post(format!("{}/get_challenge", self.base_url))
@@ -113,7 +122,7 @@ impl ReferralsClient {
                 })
                 .to_string(),
             )
-            .send()
+            .send_traced()
             .await?;
         let challenge = if res.status().is_success() {
             res.text().await?
@@ -122,8 +131,7 @@ impl ReferralsClient {
         };
 
         // alice signs the challenge and calls `get_code`
-        let digest = eip191_hash(&challenge);
-        let (sig, rec_id) = libsecp256k1::sign(&digest, secret_key);
+        let (sig, rec_id) = signer.sign_message(&challenge).await?;
         let res = self
             .client
             .post(format!("{}/get_code", self.base_url))
@@ -138,7 +146,7 @@ impl ReferralsClient {
                 })
                 .to_string(),
             )
-            .send()
+            .send_traced()
             .await?;
         let code = if res.status().is_success() {
             res.text().await?
@@ -149,10 +157,9 @@ impl ReferralsClient {
         Ok(code)
     }
 
-    /// Signs a code with the user's wallet secret key and sends it to the referral API.
-    pub async fn enter_referral_code(&self, secret_key: &SecretKey, code: &str) -> Result<()> {
-        let digest = eip191_hash(code);
-        let (sig, rec_id) = libsecp256k1::sign(&digest, secret_key);
+    /// Signs a code with the given signer (e.g. the user's wallet) and sends it to the referral API.
+    pub async fn enter_referral_code(&self, signer: &Signer, code: &str) -> Result<()> {
+        let (sig, rec_id) = signer.sign_message(code).await?;
 
         let res = self
             .client
@@ -168,9 +175,11 @@ impl ReferralsClient {
                 })
                 .to_string(),
             )
-            .send()
+            .send_traced()
             .await?;
         if res.status().is_success() {
+            // the referrer is cached as none before the code is entered
+            invalidate_cached(&format!("{}/get_referred_by/", self.base_url));
             log::info!("Successfully entered referral code");
         } else {
             eyre::bail!("Failed to enter referral code: {}", res.text().await?);
@@ -0,0 +1,469 @@
+use dkn_executor::Model;
+use multiaddr::{Multiaddr, Protocol};
+
+use super::{
+    crypto::secret_key_to_account, expand_mirror_url, DriaEnv, Language, ReadyCheck,
+    ResourceLimits, KNOWN_NETWORKS,
+};
+
+/// Kind of value that a key expects, used for validation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueKind {
+    /// Any string.
+    Text,
+    /// `true` or `false`, case-insensitive.
+    Bool,
+    /// A 16-bit unsigned integer.
+    Port,
+    /// A non-negative integer.
+    Uint,
+    /// A positive integer.
+    PositiveUint,
+    /// An integer from `-20` to `19`.
+    Niceness,
+    /// A URL, e.g. `http://127.0.0.1`.
+    Url,
+    /// A libp2p listen address, e.g. `/ip4/0.0.0.0/tcp/4001`.
+    ListenAddr,
+    /// Comma-separated model names.
+    Models,
+    /// A secp256k1 secret key in hexadecimal.
+    SecretKey,
+    /// Comma-separated `key=value` labels.
+    Labels,
+    /// Comma-separated CPU cores & ranges.
+    CpuList,
+    /// Comma-separated readiness checks.
+    ReadyChecks,
+    /// An Ollama keep-alive duration.
+    KeepAlive,
+    /// Basic authentication credentials as `username:password`.
+    Credentials,
+    /// A path to an existing file.
+    File,
+    /// One of the known networks, see [`KNOWN_NETWORKS`].
+    Network,
+    /// Comma-separated URL templates of download mirrors, see [`expand_mirror_url`].
+    UrlTemplates,
+    /// One of the supported languages, see [`Language`].
+    Language,
+}
+
+impl ValueKind {
+    /// Validates the given (non-empty) value, returns an error message if it is invalid.
+    pub fn validate(&self, value: &str) -> Result<(), String> {
+        let value = value.trim();
+        let is_valid = match self {
+            Self::Text => true,
+            Self::Bool => value.eq_ignore_ascii_case("true") || value.eq_ignore_ascii_case("false"),
+            Self::Port => value.parse::<u16>().is_ok(),
+            Self::Uint => value.parse::<u64>().is_ok(),
+            Self::PositiveUint => value.parse::<u64>().is_ok_and(|v| v > 0),
+            Self::Niceness => value.parse::<i32>().is_ok_and(|v| (-20..=19).contains(&v)),
+            Self::Url => reqwest::Url::parse(value).is_ok(),
+            Self::ListenAddr => return parse_listen_addrs(value).map(|_| ()),
+            Self::Models => {
+                if let Some(invalid) = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|model| !model.is_empty())
+                    .find(|model| Model::try_from(model.to_string()).is_err())
+                {
+                    return Err(format!("unknown model \"{invalid}\""));
+                }
+                true
+            }
+            Self::SecretKey => {
+                return secret_key_to_account(value)
+                    .map(|_| ())
+                    .map_err(|err| err.to_string())
+            }
+            Self::Labels => {
+                return DriaEnv::parse_labels(value)
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            }
+            Self::CpuList => {
+                return ResourceLimits::parse_cpu_list(value)
+                    .map(|_| ())
+                    .map_err(|err| err.to_string())
+            }
+            Self::ReadyChecks => {
+                return ReadyCheck::parse_list(value)
+                    .map(|_| ())
+                    .map_err(|err| err.to_string())
+            }
+            Self::KeepAlive => is_valid_keep_alive(value),
+            Self::Credentials => value
+                .split_once(':')
+                .is_some_and(|(username, _)| !username.is_empty()),
+            Self::File => std::path::Path::new(value).is_file(),
+            Self::Network => KNOWN_NETWORKS.contains(&value),
+            Self::Language => Language::from_code(value).is_some(),
+            Self::UrlTemplates => value
+                .split(',')
+                .map(str::trim)
+                .filter(|template| !template.is_empty())
+                .all(|template| {
+                    template.contains("{asset}")
+                        && reqwest::Url::parse(&expand_mirror_url(
+                            template,
+                            "dkn-compute-node",
+                            "0.0.0",
+                            "asset",
+                        ))
+                        .is_ok()
+                }),
+        };
+
+        if is_valid {
+            Ok(())
+        } else {
+            Err(format!("expected {self}"))
+        }
+    }
+}
+
+impl std::fmt::Display for ValueKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Text => write!(f, "any text"),
+            Self::Bool => write!(f, "true or false"),
+            Self::Port => write!(f, "a port number (0-65535)"),
+            Self::Uint => write!(f, "a non-negative integer"),
+            Self::PositiveUint => write!(f, "a positive integer"),
+            Self::Niceness => write!(f, "an integer from -20 to 19"),
+            Self::Url => write!(f, "a URL like http://127.0.0.1"),
+            Self::ListenAddr => write!(
+                f,
+                "comma-separated addresses like /ip4/0.0.0.0/tcp/4001 or /ip6/::/tcp/4001"
+            ),
+            Self::Models => write!(f, "comma-separated model names"),
+            Self::SecretKey => write!(f, "a 32-byte secret key in hexadecimal"),
+            Self::Labels => write!(f, "comma-separated key=value pairs"),
+            Self::CpuList => write!(f, "comma-separated CPU cores & ranges like 0-3,6"),
+            Self::ReadyChecks => write!(f, "comma-separated checks"),
+            Self::KeepAlive => write!(f, "a duration like 30m, 24h or -1"),
+            Self::Credentials => write!(f, "credentials like username:password"),
+            Self::File => write!(f, "a path to an existing file"),
+            Self::Network => write!(f, "one of {}", KNOWN_NETWORKS.join(", ")),
+            Self::Language => write!(
+                f,
+                "one of {}",
+                enum_iterator::all::<Language>()
+                    .map(|language| language.code())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Self::UrlTemplates => write!(
+                f,
+                "comma-separated URLs with {{asset}}, like https://example.com/{{repo}}/v{{version}}/{{asset}}"
+            ),
+        }
+    }
+}
+
+/// An environment key along with its default value and the kind of value it expects.
+#[derive(Debug, Clone, Copy)]
+pub struct EnvKey {
+    /// Name of the key.
+    pub name: &'static str,
+    /// Default value of the key, if any.
+    pub default: Option<&'static str>,
+    /// Kind of value the key expects.
+    pub kind: ValueKind,
+}
+
+impl EnvKey {
+    const fn new(name: &'static str, default: Option<&'static str>, kind: ValueKind) -> Self {
+        Self {
+            name,
+            default,
+            kind,
+        }
+    }
+
+    /// Returns the registry entry for the given key, if it exists.
+    #[inline]
+    pub fn find(name: &str) -> Option<&'static EnvKey> {
+        ENV_REGISTRY.iter().find(|key| key.name == name)
+    }
+}
+
+/// The single source of defaults & value kinds for all keys in [`DriaEnv::KEY_NAMES`].
+///
+/// The values in `.env.example` must match the defaults here, which is checked by a test.
+pub const ENV_REGISTRY: [EnvKey; DriaEnv::KEY_NAMES.len()] = [
+    EnvKey::new(DriaEnv::LOG_LEVEL_KEY, Some("none"), ValueKind::Text),
+    EnvKey::new(DriaEnv::DKN_WALLET_KEY, None, ValueKind::SecretKey),
+    EnvKey::new(DriaEnv::DKN_MODELS_KEY, None, ValueKind::Models),
+    EnvKey::new(
+        DriaEnv::DKN_P2P_LISTEN_ADDR_KEY,
+        Some("/ip4/0.0.0.0/tcp/4001"),
+        ValueKind::ListenAddr,
+    ),
+    EnvKey::new(DriaEnv::DKN_BATCH_SIZE_KEY, None, ValueKind::PositiveUint),
+    EnvKey::new(DriaEnv::DKN_HEALTH_PORT_KEY, None, ValueKind::Port),
+    EnvKey::new(DriaEnv::DKN_NODE_NAME_KEY, None, ValueKind::Text),
+    EnvKey::new(DriaEnv::DKN_LABELS_KEY, None, ValueKind::Labels),
+    EnvKey::new(
+        DriaEnv::DKN_SHUTDOWN_TIMEOUT_KEY,
+        Some("10"),
+        ValueKind::Uint,
+    ),
+    EnvKey::new(
+        DriaEnv::DKN_READY_CHECKS_KEY,
+        Some("compute,ollama"),
+        ValueKind::ReadyChecks,
+    ),
+    EnvKey::new(DriaEnv::DKN_READY_MIN_PEERS_KEY, Some("1"), ValueKind::Uint),
+    EnvKey::new(
+        DriaEnv::DKN_MANAGEMENT_API_KEY,
+        Some("false"),
+        ValueKind::Bool,
+    ),
+    EnvKey::new(
+        DriaEnv::DKN_MANAGEMENT_PORT_KEY,
+        Some("8788"),
+        ValueKind::Port,
+    ),
+    EnvKey::new(DriaEnv::DKN_MANAGEMENT_TOKEN_KEY, None, ValueKind::Text),
+    EnvKey::new(DriaEnv::DKN_CPU_AFFINITY_KEY, None, ValueKind::CpuList),
+    EnvKey::new(DriaEnv::DKN_NICENESS_KEY, None, ValueKind::Niceness),
+    EnvKey::new(DriaEnv::DKN_MEMORY_LIMIT_KEY, None, ValueKind::PositiveUint),
+    EnvKey::new(
+        DriaEnv::DKN_NETWORK_KEY,
+        Some("mainnet"),
+        ValueKind::Network,
+    ),
+    EnvKey::new(DriaEnv::DKN_POINTS_API_URL_KEY, None, ValueKind::Url),
+    EnvKey::new(DriaEnv::DKN_REFERRALS_API_URL_KEY, None, ValueKind::Url),
+    EnvKey::new(
+        DriaEnv::DKN_API_CONNECT_TIMEOUT_KEY,
+        Some("10"),
+        ValueKind::PositiveUint,
+    ),
+    EnvKey::new(
+        DriaEnv::DKN_API_TIMEOUT_KEY,
+        Some("30"),
+        ValueKind::PositiveUint,
+    ),
+    EnvKey::new(DriaEnv::DKN_API_RETRIES_KEY, Some("3"), ValueKind::Uint),
+    EnvKey::new(
+        DriaEnv::DKN_PROMPT_TIMEOUT_KEY,
+        None,
+        ValueKind::PositiveUint,
+    ),
+    EnvKey::new(
+        DriaEnv::DKN_AUTO_PORT_FORWARD_KEY,
+        Some("false"),
+        ValueKind::Bool,
+    ),
+    EnvKey::new(
+        DriaEnv::DKN_DOWNLOAD_MIRRORS_KEY,
+        None,
+        ValueKind::UrlTemplates,
+    ),
+    EnvKey::new(
+        DriaEnv::DKN_DOWNLOAD_TIMEOUT_KEY,
+        Some("30"),
+        ValueKind::PositiveUint,
+    ),
+    EnvKey::new(DriaEnv::DKN_GITHUB_TOKEN_KEY, None, ValueKind::Text),
+    EnvKey::new(
+        DriaEnv::DKN_COMPUTE_UPDATE_INTERVAL_KEY,
+        Some("60"),
+        ValueKind::PositiveUint,
+    ),
+    EnvKey::new(
+        DriaEnv::DKN_LAUNCHER_UPDATE_INTERVAL_KEY,
+        Some("180"),
+        ValueKind::PositiveUint,
+    ),
+    EnvKey::new(DriaEnv::DKN_WATCHDOG_KEY, Some("30"), ValueKind::Uint),
+    EnvKey::new(DriaEnv::DKN_WATCH_ENV_KEY, Some("true"), ValueKind::Bool),
+    EnvKey::new(DriaEnv::DKN_ENV_AUDIT_KEY, Some("false"), ValueKind::Bool),
+    EnvKey::new(DriaEnv::DKN_LANG_KEY, None, ValueKind::Language),
+    EnvKey::new(
+        DriaEnv::DKN_NOTIFY_DESKTOP_KEY,
+        Some("false"),
+        ValueKind::Bool,
+    ),
+    EnvKey::new(DriaEnv::DKN_NOTIFY_EMAIL_KEY, None, ValueKind::Text),
+    EnvKey::new(DriaEnv::DKN_NOTIFY_SMTP_URL_KEY, None, ValueKind::Url),
+    EnvKey::new(
+        DriaEnv::DKN_NOTIFY_SMTP_AUTH_KEY,
+        None,
+        ValueKind::Credentials,
+    ),
+    EnvKey::new(DriaEnv::OPENAI_APIKEY_KEY, None, ValueKind::Text),
+    EnvKey::new(DriaEnv::GEMINI_APIKEY_KEY, None, ValueKind::Text),
+    EnvKey::new(DriaEnv::OPENROUTER_APIKEY_KEY, None, ValueKind::Text),
+    EnvKey::new(DriaEnv::SERPER_APIKEY_KEY, None, ValueKind::Text),
+    EnvKey::new(DriaEnv::JINA_APIKEY_KEY, None, ValueKind::Text),
+    EnvKey::new(
+        DriaEnv::OLLAMA_HOST_KEY,
+        Some("http://127.0.0.1"),
+        ValueKind::Url,
+    ),
+    EnvKey::new(DriaEnv::OLLAMA_PORT_KEY, Some("11434"), ValueKind::Port),
+    EnvKey::new(DriaEnv::OLLAMA_AUTO_PULL_KEY, Some("true"), ValueKind::Bool),
+    EnvKey::new(DriaEnv::OLLAMA_KEEP_ALIVE_KEY, None, ValueKind::KeepAlive),
+    EnvKey::new(
+        DriaEnv::OLLAMA_CONTEXT_LENGTH_KEY,
+        None,
+        ValueKind::PositiveUint,
+    ),
+    EnvKey::new(
+        DriaEnv::OLLAMA_NUM_PARALLEL_KEY,
+        None,
+        ValueKind::PositiveUint,
+    ),
+    EnvKey::new(DriaEnv::OLLAMA_AUTH_TOKEN_KEY, None, ValueKind::Text),
+    EnvKey::new(DriaEnv::OLLAMA_BASIC_AUTH_KEY, None, ValueKind::Credentials),
+    EnvKey::new(DriaEnv::OLLAMA_CA_CERT_KEY, None, ValueKind::File),
+    EnvKey::new(DriaEnv::OLLAMA_PULL_RETRIES_KEY, Some("3"), ValueKind::Uint),
+    EnvKey::new(
+        DriaEnv::OLLAMA_PULL_PARALLELISM_KEY,
+        Some("2"),
+        ValueKind::PositiveUint,
+    ),
+    EnvKey::new(
+        DriaEnv::OLLAMA_IDLE_UNLOAD_KEY,
+        None,
+        ValueKind::PositiveUint,
+    ),
+    EnvKey::new(
+        DriaEnv::OLLAMA_LOG_STREAM_KEY,
+        Some("false"),
+        ValueKind::Bool,
+    ),
+];
+
+/// Parses comma-separated listen addresses, each must be a multiaddr that starts with an IP address
+/// and a TCP port, like `/ip4/0.0.0.0/tcp/4001` or `/ip6/::1/tcp/4001/ws`.
+///
+/// ### Errors
+/// Returns an error message for the first invalid address.
+pub fn parse_listen_addrs(value: &str) -> Result<Vec<Multiaddr>, String> {
+    let addrs = value
+        .split(',')
+        .map(str::trim)
+        .filter(|addr| !addr.is_empty())
+        .map(|addr| {
+            let multiaddr = addr
+                .parse::<Multiaddr>()
+                .map_err(|err| format!("invalid address \"{addr}\": {err}"))?;
+
+            let mut protocols = multiaddr.iter();
+            if !matches!(protocols.next(), Some(Protocol::Ip4(_) | Protocol::Ip6(_))) {
+                return Err(format!("address \"{addr}\" must start with /ip4 or /ip6"));
+            }
+            if !matches!(protocols.next(), Some(Protocol::Tcp(_))) {
+                return Err(format!(
+                    "address \"{addr}\" must have a TCP port after the IP"
+                ));
+            }
+
+            Ok(multiaddr)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if addrs.is_empty() {
+        return Err("expected at least one address".into());
+    }
+
+    Ok(addrs)
+}
+
+/// Returns the TCP port of the first address within comma-separated listen addresses,
+/// like `/ip4/0.0.0.0/tcp/4001`.
+pub fn parse_listen_port(value: &str) -> Option<u16> {
+    parse_listen_addrs(value)
+        .ok()?
+        .first()?
+        .iter()
+        .find_map(|protocol| match protocol {
+            Protocol::Tcp(port) => Some(port),
+            _ => None,
+        })
+}
+
+/// Returns `true` if the given value is a valid Ollama keep-alive.
+///
+/// Ollama accepts a number of seconds, or a duration with a unit like `10m` or `24h`;
+/// negative values keep the model loaded forever.
+pub fn is_valid_keep_alive(keep_alive: &str) -> bool {
+    let digits = keep_alive.strip_prefix('-').unwrap_or(keep_alive);
+    let unit_start = digits
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(digits.len());
+    let (number, unit) = digits.split_at(unit_start);
+
+    !number.is_empty() && ["", "ms", "s", "m", "h"].contains(&unit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_matches_keys() {
+        for (key, name) in ENV_REGISTRY.iter().zip(DriaEnv::KEY_NAMES) {
+            assert_eq!(key.name, name);
+            if let Some(default) = key.default {
+                assert!(key.kind.validate(default).is_ok(), "{name}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_registry_matches_env_example() {
+        const ENV_EXAMPLE: &str = include_str!("../../.env.example");
+
+        for key in ENV_REGISTRY {
+            let line = ENV_EXAMPLE
+                .lines()
+                .find(|line| line.starts_with(&format!("{}=", key.name)))
+                .unwrap_or_else(|| panic!("{} is missing in .env.example", key.name));
+            let value = line.split_once('=').unwrap().1;
+            if !value.is_empty() {
+                assert_eq!(Some(value), key.default, "{}", key.name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_value_kinds() {
+        assert!(ValueKind::UrlTemplates
+            .validate("https://cdn.example.com/{repo}/v{version}/{asset}, https://mirror.example.com/{asset}")
+            .is_ok());
+        assert!(ValueKind::UrlTemplates
+            .validate("https://cdn.example.com/latest")
+            .is_err());
+        assert!(ValueKind::ListenAddr
+            .validate("/ip4/0.0.0.0/tcp/4001")
+            .is_ok());
+        assert!(ValueKind::ListenAddr
+            .validate("/ip4/0.0.0.0/udp/4001")
+            .is_err());
+        assert!(ValueKind::ListenAddr
+            .validate("/ip4/192.168.1.10/tcp/4001, /ip6/::/tcp/4001/ws")
+            .is_ok());
+        assert!(ValueKind::ListenAddr.validate("/ip4/0.0.0.0").is_err());
+        assert!(ValueKind::ListenAddr
+            .validate("/ip4/0.0.0.0/tcp/4001,/ip6/not-an-ip/tcp/4001")
+            .is_err());
+        assert_eq!(
+            parse_listen_port("/ip6/::/tcp/4002,/ip4/0.0.0.0/tcp/4001"),
+            Some(4002)
+        );
+        assert!(ValueKind::KeepAlive.validate("-1").is_ok());
+        assert!(ValueKind::KeepAlive.validate("24h").is_ok());
+        assert!(ValueKind::KeepAlive.validate("24d").is_err());
+        assert!(ValueKind::Bool.validate("TRUE").is_ok());
+        assert!(ValueKind::Niceness.validate("20").is_err());
+        assert!(ValueKind::Credentials.validate("user:pass").is_ok());
+        assert!(ValueKind::Credentials.validate(":pass").is_err());
+    }
+}
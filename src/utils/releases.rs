@@ -1,11 +1,24 @@
-use eyre::{eyre, Context, Result};
-use self_update::backends::github;
+use eyre::{eyre, Context, OptionExt, Result};
+use indicatif::{HumanBytes, ProgressBar, ProgressStyle};
 use self_update::update::{Release, ReleaseAsset};
+use sha2::{Digest, Sha256};
 use std::env::consts::{ARCH, FAMILY, OS};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
 
-use super::{DKN_VERSION_TRACKER_FILE, PROGRESS_BAR_CHARS, PROGRESS_BAR_TEMPLATE};
+#[cfg(windows)]
+use super::check_quarantine;
+use super::new_progress_bar;
+use super::{
+    apply_patch, fetch_github_api, parse_github_release, patch_asset_name, with_github_auth,
+    DriaEnv, TracedSend, DKN_LATEST_COMPUTE_FILE, DKN_LAUNCHER_VERSION, DKN_VERSION_TRACKER_FILE,
+    LAUNCHER_USER_AGENT, PROGRESS_BAR_CHARS, PROGRESS_BAR_TEMPLATE,
+};
+
+/// Number of times a download is resumed from the same source, as long as it makes progress.
+const DOWNLOAD_RESUME_ATTEMPTS: usize = 3;
 
 /// A Dria repostiry enum, to differentiate between compute and launcher.
 /// Can maybe add oracle here as well some day!
@@ -48,6 +61,56 @@ impl DriaRelease {
         &self.0.version
     }
 
+    /// Returns the `major.minor.patch` numbers of the version, if it is well-formed.
+    #[inline]
+    pub fn version_numbers(&self) -> Option<(u64, u64, u64)> {
+        parse_version(self.version())
+    }
+
+    /// Returns the minimum launcher version required by this release, if its notes declare one
+    /// with a line like `Minimum launcher version: v0.1.10`.
+    pub fn min_launcher_version(&self) -> Option<String> {
+        self.0.body.as_deref().and_then(parse_min_launcher_version)
+    }
+
+    /// Returns the release notes (i.e. the changelog) of this release, if it has any.
+    pub fn release_notes(&self) -> Option<&str> {
+        self.0
+            .body
+            .as_deref()
+            .map(str::trim)
+            .filter(|body| !body.is_empty())
+    }
+
+    /// Returns the URL of this release on GitHub.
+    pub fn release_url(&self) -> String {
+        format!(
+            "https://github.com/firstbatchxyz/{}/releases/tag/v{}",
+            self.1,
+            self.version()
+        )
+    }
+
+    /// Returns `true` if the release is marked as yanked by the maintainers, i.e. its name
+    /// starts with `[yanked]` or its release notes have a line like `Yanked: <reason>`.
+    pub fn is_yanked(&self) -> bool {
+        self.name().to_lowercase().starts_with("[yanked]")
+            || self
+                .0
+                .body
+                .as_deref()
+                .is_some_and(|body| body.lines().any(is_yanked_line))
+    }
+
+    /// Returns the reason why this release can not be used on this machine, if any.
+    pub fn unusable_reason(&self) -> Option<String> {
+        if self.is_yanked() {
+            return Some("it is marked as yanked".to_string());
+        }
+
+        self.asset().err().map(|err| err.to_string())
+    }
+
     /// Returns the filename for the current machine for this release.
     pub fn to_filename(&self) -> Result<String> {
         if let Some((_, _, ext)) = Self::get_labels() {
@@ -143,8 +206,212 @@ impl DriaRelease {
             .cloned()
     }
 
+    /// Downloads the given asset of this release to the given path, trying the mirrors within
+    /// `DKN_DOWNLOAD_MIRRORS` in order, and GitHub itself at last.
+    ///
+    /// The download goes through a partial file that is specific to the asset & version, which
+    /// is kept on failure so that the next download resumes it. If the release publishes a
+    /// `<asset>.sha256` checksum, the download is verified against it; otherwise the mirrors are
+    /// not used, as there is nothing to verify their executables against.
+    ///
+    /// ### Errors
+    /// - If the asset could not be downloaded from any of the sources
+    /// - If the checksum of the download does not match
+    async fn download_asset(
+        &self,
+        asset: &ReleaseAsset,
+        dest_path: &Path,
+        show_progress: bool,
+    ) -> Result<()> {
+        let dria_env = DriaEnv::new_from_env();
+        let timeout = dria_env.get_download_timeout();
+        let repo = self.1.to_string();
+        let checksum = self.fetch_checksum(asset, timeout).await;
+        let mirrors = dria_env.get_download_mirrors();
+        if checksum.is_none() && !mirrors.is_empty() {
+            log::warn!(
+                "{} has no checksum to verify the mirrors against, downloading from GitHub.",
+                asset.name
+            );
+        }
+        let sources = mirrors
+            .iter()
+            .filter(|_| checksum.is_some())
+            .map(|template| expand_mirror_url(template, &repo, self.version(), &asset.name))
+            .chain(std::iter::once(asset.download_url.clone()))
+            .collect::<Vec<_>>();
+
+        let dest_dir = dest_path.parent().unwrap_or(Path::new(""));
+        let partial_path = prepare_partial_path(dest_dir, &asset.name, self.version());
+        let mut last_err = None;
+        for url in &sources {
+            match download_url(url, &partial_path, show_progress, timeout).await {
+                Ok(()) => {
+                    last_err = None;
+                    break;
+                }
+                Err(err) => {
+                    log::warn!("Could not download {} from {url}: {err:#}", asset.name);
+                    last_err = Some(err);
+                }
+            }
+        }
+        if let Some(err) = last_err {
+            return Err(err.wrap_err(format!(
+                "could not download {} from any of {} sources",
+                asset.name,
+                sources.len()
+            )));
+        }
+
+        // verify the download, a corrupt file is discarded so that it is not resumed again
+        if let Some(expected) = checksum {
+            let actual = sha256_file(&partial_path)?;
+            if actual != expected {
+                fs::remove_file(&partial_path)?;
+                eyre::bail!(
+                    "checksum mismatch for {}: expected {expected}, got {actual}",
+                    asset.name
+                );
+            }
+        }
+
+        // rename from the partial file to dest_path
+        fs::rename(&partial_path, dest_path)?;
+
+        // set to read, write, execute in Unix
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(dest_path, fs::Permissions::from_mode(0o777))?;
+        }
+
+        Ok(())
+    }
+
+    /// Fetches the SHA-256 checksum of the given asset, if the release publishes one as a
+    /// `<asset>.sha256` asset; failures are logged and ignored.
+    async fn fetch_checksum(&self, asset: &ReleaseAsset, timeout: Duration) -> Option<String> {
+        let checksum_name = format!("{}.sha256", asset.name);
+        let checksum_asset = self.0.assets.iter().find(|a| a.name == checksum_name)?;
+
+        let client = reqwest::Client::builder()
+            .user_agent(LAUNCHER_USER_AGENT)
+            .timeout(timeout)
+            .build()
+            .ok()?;
+        let url = &checksum_asset.download_url;
+        let content = with_github_auth(client.get(url), url)
+            .header(reqwest::header::ACCEPT, "application/octet-stream")
+            .send_traced()
+            .await
+            .and_then(|res| res.error_for_status());
+        let content = match content {
+            Ok(response) => response.text().await.ok(),
+            Err(err) => {
+                log::warn!("Could not fetch {checksum_name}, skipping verification: {err}");
+                return None;
+            }
+        };
+
+        let checksum = content.as_deref().and_then(parse_sha256_file);
+        if checksum.is_none() {
+            log::warn!("Invalid checksum within {checksum_name}, skipping verification.");
+        }
+        checksum
+    }
+
+    /// Returns the binary that a patch can be applied to for the given destination, along with
+    /// its version: the existing `latest` compute node, or the running launcher.
+    fn patch_base(&self, dest_path: &Path) -> Option<(PathBuf, String)> {
+        let (base_path, base_version) = match self.1 {
+            DriaRepository::ComputeNode => {
+                let is_latest = dest_path
+                    .file_name()
+                    .is_some_and(|name| name == DKN_LATEST_COMPUTE_FILE);
+                if !is_latest || !dest_path.is_file() {
+                    return None;
+                }
+
+                let version = Self::get_compute_version(dest_path.parent()?)?;
+                (dest_path.to_path_buf(), version.trim().to_string())
+            }
+            DriaRepository::Launcher => (
+                std::env::current_exe().ok()?,
+                DKN_LAUNCHER_VERSION.to_string(),
+            ),
+        };
+
+        (base_version != self.version()).then_some((base_path, base_version))
+    }
+
+    /// Downloads the patch asset from the given base version, and applies it to the base binary
+    /// at the given destination.
+    ///
+    /// The patched binary is verified against the checksum of the full asset, so a patch is only
+    /// applied if the release publishes a `<asset>.sha256` checksum.
+    ///
+    /// ### Errors
+    /// - If the release has no checksum for the full asset
+    /// - If the patch could not be downloaded or applied
+    /// - If the checksum of the patched binary does not match
+    async fn download_patch(
+        &self,
+        asset: &ReleaseAsset,
+        patch: ReleaseAsset,
+        base_path: &Path,
+        dest_path: &Path,
+        show_progress: bool,
+    ) -> Result<()> {
+        let timeout = DriaEnv::new_from_env().get_download_timeout();
+        let Some(expected) = self.fetch_checksum(asset, timeout).await else {
+            eyre::bail!(
+                "{} has no checksum to verify the patched binary against",
+                asset.name
+            );
+        };
+
+        let patch_path = dest_path.with_file_name(&patch.name);
+        self.download_asset(&patch, &patch_path, show_progress)
+            .await?;
+
+        let base = fs::read(base_path).wrap_err("could not read the binary to patch")?;
+        let patch_bytes = fs::read(&patch_path).wrap_err("could not read the patch")?;
+        fs::remove_file(&patch_path)?;
+        let patched = tokio::task::spawn_blocking(move || apply_patch(&base, &patch_bytes))
+            .await?
+            .wrap_err("could not apply the patch")?;
+        let actual = hex::encode(Sha256::digest(&patched));
+        if actual != expected {
+            eyre::bail!(
+                "checksum mismatch for the patched {}: expected {expected}, got {actual}",
+                asset.name
+            );
+        }
+
+        // write to a tempfile first, so that the destination is intact on failure
+        let tmp_path = dest_path.with_file_name(format!(
+            "tmp_{}",
+            dest_path.file_name().unwrap_or_default().to_string_lossy()
+        ));
+        fs::write(&tmp_path, patched)?;
+        fs::rename(&tmp_path, dest_path)?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(dest_path, fs::Permissions::from_mode(0o777))?;
+        }
+
+        Ok(())
+    }
+
     /// Downloads this release under the given directory at the given `dest_name`.
     ///
+    /// If the release has a patch asset from the version that is being replaced (see
+    /// [`patch_asset_name`]) along with the checksum of the full asset, only the patch is
+    /// downloaded and applied; otherwise, or if the patch fails, the full asset is downloaded.
+    ///
     /// ### Arguments
     /// - `dest_dir`: The directory where the release will be downloaded.
     /// - `dest_name`: The name of the downloaded release.
@@ -157,6 +424,7 @@ impl DriaRelease {
     /// - If the destination directory does not exist or is not a directory.
     /// - If the asset could not be found for the current OS and ARCH.
     /// - If the asset could not be downloaded.
+    /// - If the downloaded file is removed or blocked right away, e.g. by an antivirus.
     pub async fn download_release(
         &self,
         dest_dir: &Path,
@@ -178,81 +446,294 @@ impl DriaRelease {
             self.version(),
             dest_path.display()
         );
-        download_asset_via_url(asset.download_url, &dest_path, show_progress).await?;
+
+        // try a differential update first, as the binaries are large
+        let patch = self
+            .patch_base(&dest_path)
+            .and_then(|(base_path, base_version)| {
+                let patch_name = patch_asset_name(&asset.name, &base_version);
+                self.0
+                    .assets
+                    .iter()
+                    .find(|asset| asset.name == patch_name)
+                    .cloned()
+                    .map(|patch| (patch, base_path))
+            });
+        let is_patched = match patch {
+            Some((patch, base_path)) => {
+                log::info!("Applying {} instead of the full download", patch.name);
+                match self
+                    .download_patch(&asset, patch, &base_path, &dest_path, show_progress)
+                    .await
+                {
+                    Ok(()) => true,
+                    Err(err) => {
+                        log::warn!("Could not apply patch ({err:#}), downloading the full binary.");
+                        false
+                    }
+                }
+            }
+            None => false,
+        };
+
+        if !is_patched {
+            self.download_asset(&asset, &dest_path, show_progress)
+                .await?;
+        }
+        // antivirus software may quarantine the binary right after it is downloaded
+        #[cfg(windows)]
+        check_quarantine(&dest_path).await?;
 
         Ok(dest_path)
     }
 }
 
-/// Downloads the asset from the given URL to the given path.
+/// Parses a version like `0.1.10` (or `v0.1.10`) into its `major.minor.patch` numbers.
+pub fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let mut parts = version
+        .trim()
+        .trim_start_matches('v')
+        .split('.')
+        .map(|part| part.parse::<u64>().ok());
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(Some(major)), Some(Some(minor)), Some(Some(patch)), None) => {
+            Some((major, minor, patch))
+        }
+        _ => None,
+    }
+}
+
+/// Returns `true` if the given line of release notes marks the release as yanked, e.g.
+/// `Yanked: crashes on startup`; a mention of "yanked" elsewhere does not.
+fn is_yanked_line(line: &str) -> bool {
+    line.split_once(':').is_some_and(|(key, _)| {
+        key.trim_matches(|c: char| !c.is_alphanumeric())
+            .eq_ignore_ascii_case("yanked")
+    })
+}
+
+/// Finds the minimum launcher version declared within release notes, with a line like
+/// `Minimum launcher version: v0.1.10` or `min-launcher-version = 0.1.10`.
+fn parse_min_launcher_version(notes: &str) -> Option<String> {
+    notes.lines().find_map(|line| {
+        let line = line.to_lowercase().replace(['-', '_'], " ");
+        let (key, value) = line.split_once([':', '='])?;
+        let key = key.trim_matches(|c: char| !c.is_alphanumeric());
+        if key != "minimum launcher version" && key != "min launcher version" {
+            return None;
+        }
+
+        let version = value
+            .trim_matches(|c: char| !c.is_ascii_digit())
+            .to_string();
+        parse_version(&version).map(|_| version)
+    })
+}
+
+/// Downloads the file at the given URL into the given partial file, resuming from its current
+/// size with an HTTP `Range` request if it exists; the partial file is kept on failure so that
+/// the next attempt resumes from where it stopped.
+///
+/// A download that fails midway is resumed up to [`DOWNLOAD_RESUME_ATTEMPTS`] times as long as
+/// it makes progress. The timeout applies to connecting and to each read, so that a stalled
+/// source is abandoned while a slow but progressing download is not.
 ///
-/// The downloaded file will be first written to a temporary file,
-/// and when the download is finished it will be renamed to actualy destination.
-/// This prevents corrupt files when the download is interrupted.
-async fn download_asset_via_url(
-    download_url: String,
-    dest_path: &PathBuf,
+/// ### Errors
+/// - If the download fails, or the downloaded size does not match the expected size
+pub(crate) async fn download_url(
+    url: &str,
+    partial_path: &Path,
     show_progress: bool,
+    timeout: Duration,
 ) -> Result<()> {
-    // TODO: use tempfile here
-    // download asset to a tempfile
-    let tmp_file = dest_path.with_file_name(format!(
-        "tmp_{}",
-        dest_path.file_name().unwrap_or_default().to_string_lossy()
-    ));
-    let tmp_dest = fs::File::create(&tmp_file)?;
-    tokio::task::spawn_blocking(move || {
-        self_update::Download::from_url(download_url.as_ref())
-            .set_progress_style(PROGRESS_BAR_TEMPLATE.into(), PROGRESS_BAR_CHARS.into())
-            .set_header(
-                reqwest::header::ACCEPT,
-                // this is unlikely to panic
-                "application/octet-stream".parse().unwrap(),
-            )
-            .show_progress(show_progress)
-            .download_to(tmp_dest)
-            .expect("could not download asset")
-    })
-    .await
-    .wrap_err("could not download asset")?;
+    let client = reqwest::Client::builder()
+        .user_agent(LAUNCHER_USER_AGENT)
+        .connect_timeout(timeout)
+        .read_timeout(timeout)
+        .build()?;
+
+    let mut attempts = 0;
+    loop {
+        let offset = partial_len(partial_path);
+        match download_range(&client, url, partial_path, show_progress).await {
+            Ok(()) => return Ok(()),
+            Err(err) => {
+                attempts += 1;
+                let progressed = partial_len(partial_path) > offset;
+                if attempts >= DOWNLOAD_RESUME_ATTEMPTS || !progressed {
+                    return Err(err);
+                }
+                log::warn!(
+                    "Download was interrupted at {} ({err:#}), resuming.",
+                    HumanBytes(partial_len(partial_path))
+                );
+            }
+        }
+    }
+}
 
-    // rename from tempfile to dest_path
-    fs::rename(tmp_file, dest_path)?;
+/// Returns the partial file to download the given asset & version to within the given directory,
+/// see [`download_url`]; the partial files of the other versions of the asset are removed, as
+/// they are not resumed anymore.
+pub(crate) fn prepare_partial_path(dir: &Path, asset_name: &str, version: &str) -> PathBuf {
+    let stale_prefix = format!(".partial_{asset_name}_v");
+    let file_name = format!("{stale_prefix}{version}");
+    for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with(&stale_prefix) && name != file_name {
+            match fs::remove_file(entry.path()) {
+                Ok(()) => log::debug!("Removed the stale partial download {name}"),
+                Err(err) => log::warn!("Could not remove the stale partial download {name}: {err}"),
+            }
+        }
+    }
 
-    // set to read, write, execute in Unix
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        fs::set_permissions(dest_path, fs::Permissions::from_mode(0o777))?;
+    dir.join(file_name)
+}
+
+/// Returns the size of the partial file, `0` if it does not exist.
+fn partial_len(partial_path: &Path) -> u64 {
+    fs::metadata(partial_path)
+        .map(|metadata| metadata.len())
+        .unwrap_or_default()
+}
+
+/// Downloads the rest of the file at the given URL into the partial file, see [`download_url`].
+async fn download_range(
+    client: &reqwest::Client,
+    url: &str,
+    partial_path: &Path,
+    show_progress: bool,
+) -> Result<()> {
+    let (mut response, offset) = loop {
+        let offset = partial_len(partial_path);
+        let mut request = with_github_auth(client.get(url), url)
+            .header(reqwest::header::ACCEPT, "application/octet-stream");
+        if offset > 0 {
+            request = request.header(reqwest::header::RANGE, format!("bytes={offset}-"));
+        }
+
+        let response = request.send_traced().await?;
+        match response.status() {
+            reqwest::StatusCode::PARTIAL_CONTENT => break (response, offset),
+            // the partial file does not belong to this file (e.g. it is larger), start over
+            reqwest::StatusCode::RANGE_NOT_SATISFIABLE if offset > 0 => {
+                log::warn!("Discarding the partial download, as it does not match the file.");
+                fs::remove_file(partial_path)?;
+            }
+            // the server does not support ranges, start over
+            _ => break (response.error_for_status()?, 0),
+        }
+    };
+
+    let total = match offset {
+        0 => response.content_length(),
+        _ => response
+            .headers()
+            .get(reqwest::header::CONTENT_RANGE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_content_range_total)
+            .or_else(|| response.content_length().map(|len| offset + len)),
+    };
+
+    let progress_bar = if show_progress {
+        let progress_bar = new_progress_bar(Some(total.unwrap_or_default()));
+        progress_bar.set_style(
+            ProgressStyle::with_template(PROGRESS_BAR_TEMPLATE)?.progress_chars(PROGRESS_BAR_CHARS),
+        );
+        progress_bar.set_position(offset);
+        progress_bar
+    } else {
+        ProgressBar::hidden()
+    };
+
+    let mut partial = tokio::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(offset > 0)
+        .truncate(offset == 0)
+        .open(partial_path)
+        .await?;
+    while let Some(chunk) = response.chunk().await? {
+        partial.write_all(&chunk).await?;
+        progress_bar.inc(chunk.len() as u64);
+    }
+    partial.flush().await?;
+    drop(partial);
+    progress_bar.finish_and_clear();
+
+    let len = partial_len(partial_path);
+    if let Some(total) = total.filter(|total| *total != len) {
+        eyre::bail!("incomplete download, got {len} of {total} bytes");
     }
 
     Ok(())
 }
 
+/// Parses the total size within a `Content-Range` header like `bytes 100-999/1000`.
+fn parse_content_range_total(content_range: &str) -> Option<u64> {
+    content_range
+        .trim()
+        .strip_prefix("bytes ")?
+        .split_once('/')?
+        .1
+        .parse()
+        .ok()
+}
+
+/// Returns the SHA-256 checksum within a checksum file, which is either the hexadecimal
+/// checksum itself or a `sha256sum` line like `<checksum>  <filename>`.
+fn parse_sha256_file(content: &str) -> Option<String> {
+    let checksum = content.split_whitespace().next()?.to_lowercase();
+    (checksum.len() == 64 && checksum.chars().all(|c| c.is_ascii_hexdigit())).then_some(checksum)
+}
+
+/// Returns the SHA-256 checksum of the file at the given path, in hexadecimal.
+pub(crate) fn sha256_file(path: &Path) -> Result<String> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Returns the download URL of an asset within the given mirror URL template, where `{repo}`,
+/// `{version}` and `{asset}` are replaced with the repository, version & asset names.
+pub fn expand_mirror_url(template: &str, repo: &str, version: &str, asset: &str) -> String {
+    template
+        .trim()
+        .replace("{repo}", repo)
+        .replace("{version}", version)
+        .replace("{asset}", asset)
+}
+
 /// Returns the entire list of releases for the given repository, owned by `firstbatchxyz`.
 ///
-/// Due to an [issue](https://github.com/jaemk/self_update/issues/44) of `self_update` not
-/// working within async contexts, we do a blocking task spawn here.
+/// The list is cached on disk and revalidated with GitHub, see [`fetch_github_api`]. Releases
+/// that could not be parsed are skipped with a warning.
 ///
 /// While the returned list is sorted, the latest may not be the first element.
 /// Use [`get_latest_release`] to get the latest release instead.
 pub(crate) async fn get_releases(repo: DriaRepository) -> Result<Vec<DriaRelease>> {
-    let releases = tokio::task::spawn_blocking(move || {
-        let mut rel_builder = github::ReleaseList::configure();
-
-        rel_builder
-            .repo_owner("firstbatchxyz")
-            .repo_name(&repo.to_string())
-            .build()
-            .expect("could not build ReleaseList")
-            .fetch()
-            .expect("could not fetch releases")
-            .into_iter()
-            .map(|r| DriaRelease(r, repo))
-            .collect::<Vec<_>>()
-    })
-    .await
-    .wrap_err("could not get releases")?;
+    let releases = fetch_github_api(&format!("repos/firstbatchxyz/{repo}/releases"))
+        .await
+        .wrap_err("could not get releases")?
+        .as_array()
+        .ok_or_eyre("could not get releases: not a list")?
+        .iter()
+        // a malformed release is skipped, so that it does not hide the others
+        .filter_map(|release| match parse_github_release(release) {
+            Ok(release) => Some(DriaRelease(release, repo)),
+            Err(err) => {
+                log::warn!(
+                    "Skipping invalid release {} of {repo}: {err:#}",
+                    release["tag_name"].as_str().unwrap_or("without a tag")
+                );
+                None
+            }
+        })
+        .collect::<Vec<_>>();
 
     // filter out the launcher releases that are not at least 0.1.0
     if let DriaRepository::Launcher = repo {
@@ -267,26 +748,15 @@ pub(crate) async fn get_releases(repo: DriaRepository) -> Result<Vec<DriaRelease
 
 /// Returns the latest release for the given repository.
 ///
-/// Due to an [issue](https://github.com/jaemk/self_update/issues/44) of `self_update` not
-/// working within async contexts, we do a blocking task spawn here.
+/// The release is cached on disk and revalidated with GitHub, see [`fetch_github_api`].
 ///
 /// This respects the `latest` tag, so even if the version tag is lower than the actual latest,
 /// it will return the tagged-as-latest release.
 pub(crate) async fn get_latest_release(repo: DriaRepository) -> Result<DriaRelease> {
-    let result = tokio::task::spawn_blocking(move || {
-        github::Update::configure()
-            .repo_owner("firstbatchxyz")
-            .repo_name(&repo.to_string())
-            .bin_name(Default::default()) // ignored within `get_latest_release`
-            .current_version(Default::default()) // ignored within `get_latest_release`
-            .build()
-            .expect("could not build ReleaseUpdate")
-            .get_latest_release()
-            .map(|r| DriaRelease(r, repo))
-            .unwrap()
-    })
-    .await
-    .wrap_err("could not get latest release")?;
+    let release = fetch_github_api(&format!("repos/firstbatchxyz/{repo}/releases/latest"))
+        .await
+        .wrap_err("could not get latest release")?;
+    let result = DriaRelease(parse_github_release(&release)?, repo);
 
     // check if the launcher version is at least 0.1.0
     if let DriaRepository::Launcher = repo {
@@ -295,14 +765,114 @@ pub(crate) async fn get_latest_release(repo: DriaRepository) -> Result<DriaRelea
         }
     }
 
+    // fall back to an older release if the latest one can not be used on this machine
+    if let Some(reason) = result.unusable_reason() {
+        let releases = get_releases(repo).await?;
+        let fallback = find_fallback_release(&result, releases).ok_or_else(|| {
+            eyre!(
+                "latest {repo} release v{} can not be used ({reason}), and no older release has an asset for this machine",
+                result.version()
+            )
+        })?;
+
+        log::warn!(
+            "Latest {repo} release v{} can not be used ({reason}), falling back to v{}.",
+            result.version(),
+            fallback.version()
+        );
+        return Ok(fallback);
+    }
+
     Ok(result)
 }
 
+/// Returns the most recent usable release that is older than the given (unusable) latest release.
+fn find_fallback_release(latest: &DriaRelease, releases: Vec<DriaRelease>) -> Option<DriaRelease> {
+    let latest_version = latest.version_numbers();
+
+    releases
+        .into_iter()
+        .filter(|release| {
+            release.version_numbers().is_some_and(|version| {
+                latest_version.map_or(true, |latest_version| version < latest_version)
+            })
+        })
+        .filter(|release| release.unusable_reason().is_none())
+        .max_by_key(|release| release.version_numbers())
+}
+
 #[cfg(test)]
 mod tests {
     use std::{path::PathBuf, str::FromStr};
 
-    use super::DriaRepository;
+    use self_update::update::{Release, ReleaseAsset};
+
+    use super::{
+        expand_mirror_url, parse_content_range_total, parse_sha256_file, prepare_partial_path,
+        DriaRelease, DriaRepository,
+    };
+
+    /// Creates a compute node release, with an asset for this machine if `has_asset` is set.
+    fn make_release(version: &str, has_asset: bool, body: Option<&str>) -> DriaRelease {
+        let (os, arch, ext) = DriaRelease::get_labels().unwrap();
+        let assets = if has_asset {
+            vec![ReleaseAsset {
+                name: format!("dkn-compute-binary-{}-{}{}", os, arch, ext),
+                download_url: String::new(),
+            }]
+        } else {
+            vec![]
+        };
+
+        DriaRelease(
+            Release {
+                name: format!("v{version}"),
+                version: version.to_string(),
+                date: String::new(),
+                body: body.map(String::from),
+                assets,
+            },
+            DriaRepository::ComputeNode,
+        )
+    }
+
+    #[test]
+    fn test_min_launcher_version() {
+        let release = make_release(
+            "0.6.0",
+            true,
+            Some("## Changes\n- stuff\n\n**Minimum launcher version:** `v0.1.10`\n"),
+        );
+        assert_eq!(release.min_launcher_version().as_deref(), Some("0.1.10"));
+
+        let release = make_release("0.6.0", true, Some("min_launcher_version = 0.2.0"));
+        assert_eq!(release.min_launcher_version().as_deref(), Some("0.2.0"));
+
+        let release = make_release("0.6.0", true, Some("No requirements here."));
+        assert_eq!(release.min_launcher_version(), None);
+    }
+
+    #[test]
+    fn test_fallback_release() {
+        let latest = make_release("0.5.0", false, None);
+        assert!(latest.unusable_reason().is_some());
+
+        let releases = vec![
+            make_release("0.5.0", false, None),
+            make_release("0.6.0-rc1", true, None),
+            make_release("0.4.2", true, Some("## Changes\n**Yanked**: due to a bug")),
+            make_release("0.4.1", true, None),
+            make_release("0.3.9", true, None),
+        ];
+        let fallback = super::find_fallback_release(&latest, releases).unwrap();
+        assert_eq!(fallback.version(), "0.4.1");
+
+        // only an explicit marker yanks a release
+        assert!(!make_release("0.4.3", true, Some("Fixes the yanked v0.4.2")).is_yanked());
+        let mut release = make_release("0.4.2", true, None);
+        release.0.name = "[Yanked] v0.4.2".to_string();
+        assert!(release.is_yanked());
+    }
 
     #[tokio::test]
     async fn test_download_latest_compute_release() {
@@ -339,4 +909,58 @@ mod tests {
 
         assert!(path.exists());
     }
+
+    #[test]
+    fn test_expand_mirror_url() {
+        assert_eq!(
+            expand_mirror_url(
+                " https://cdn.example.com/{repo}/v{version}/{asset} ",
+                "dkn-compute-node",
+                "0.6.5",
+                "dkn-compute-binary-linux-amd64"
+            ),
+            "https://cdn.example.com/dkn-compute-node/v0.6.5/dkn-compute-binary-linux-amd64"
+        );
+    }
+
+    #[test]
+    fn test_parse_download_headers() {
+        assert_eq!(parse_content_range_total("bytes 100-999/1000"), Some(1000));
+        assert_eq!(parse_content_range_total("bytes 100-999/*"), None);
+
+        let checksum = "a".repeat(64);
+        assert_eq!(
+            parse_sha256_file(&format!(
+                "{}  dkn-compute-binary-linux-amd64\n",
+                checksum.to_uppercase()
+            )),
+            Some(checksum)
+        );
+        assert_eq!(parse_sha256_file("not a checksum"), None);
+    }
+
+    #[test]
+    fn test_prepare_partial_path() {
+        let dir = std::env::temp_dir().join(format!(
+            "dkn-test-partials-{}-{}",
+            std::process::id(),
+            line!()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in [
+            ".partial_asset_v0.1.0",
+            ".partial_asset_v0.2.0",
+            ".partial_other_v0.1.0",
+        ] {
+            std::fs::write(dir.join(name), "partial").unwrap();
+        }
+
+        let partial_path = prepare_partial_path(&dir, "asset", "0.2.0");
+        assert_eq!(partial_path, dir.join(".partial_asset_v0.2.0"));
+        assert!(partial_path.exists());
+        assert!(!dir.join(".partial_asset_v0.1.0").exists());
+        assert!(dir.join(".partial_other_v0.1.0").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
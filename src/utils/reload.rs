@@ -0,0 +1,109 @@
+use super::{DriaEnv, EnvChange};
+
+/// Keys that are applied by the launcher itself, without restarting the compute node.
+const LAUNCHER_KEYS: [&str; 20] = [
+    DriaEnv::DKN_SHUTDOWN_TIMEOUT_KEY,
+    DriaEnv::DKN_PROMPT_TIMEOUT_KEY,
+    DriaEnv::DKN_POINTS_API_URL_KEY,
+    DriaEnv::DKN_REFERRALS_API_URL_KEY,
+    DriaEnv::DKN_API_CONNECT_TIMEOUT_KEY,
+    DriaEnv::DKN_API_TIMEOUT_KEY,
+    DriaEnv::DKN_API_RETRIES_KEY,
+    DriaEnv::DKN_DOWNLOAD_MIRRORS_KEY,
+    DriaEnv::DKN_DOWNLOAD_TIMEOUT_KEY,
+    DriaEnv::DKN_GITHUB_TOKEN_KEY,
+    DriaEnv::DKN_COMPUTE_UPDATE_INTERVAL_KEY,
+    DriaEnv::DKN_LAUNCHER_UPDATE_INTERVAL_KEY,
+    DriaEnv::DKN_WATCHDOG_KEY,
+    DriaEnv::DKN_WATCH_ENV_KEY,
+    DriaEnv::DKN_ENV_AUDIT_KEY,
+    DriaEnv::DKN_LANG_KEY,
+    DriaEnv::DKN_NOTIFY_DESKTOP_KEY,
+    DriaEnv::DKN_NOTIFY_EMAIL_KEY,
+    DriaEnv::DKN_NOTIFY_SMTP_URL_KEY,
+    DriaEnv::DKN_NOTIFY_SMTP_AUTH_KEY,
+];
+
+/// Keys that are only applied when the node is started, e.g. as they configure the health server
+/// or the Ollama server; these require a full `restart`.
+const STARTUP_KEYS: [&str; 13] = [
+    DriaEnv::DKN_HEALTH_PORT_KEY,
+    DriaEnv::DKN_READY_CHECKS_KEY,
+    DriaEnv::DKN_READY_MIN_PEERS_KEY,
+    DriaEnv::DKN_MANAGEMENT_API_KEY,
+    DriaEnv::DKN_MANAGEMENT_PORT_KEY,
+    DriaEnv::DKN_MANAGEMENT_TOKEN_KEY,
+    DriaEnv::DKN_AUTO_PORT_FORWARD_KEY,
+    DriaEnv::OLLAMA_KEEP_ALIVE_KEY,
+    DriaEnv::OLLAMA_CONTEXT_LENGTH_KEY,
+    DriaEnv::OLLAMA_NUM_PARALLEL_KEY,
+    DriaEnv::OLLAMA_AUTO_PULL_KEY,
+    DriaEnv::OLLAMA_IDLE_UNLOAD_KEY,
+    DriaEnv::OLLAMA_LOG_STREAM_KEY,
+];
+
+/// How the changes of a reloaded environment file are applied to a running node.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ReloadPlan {
+    /// Keys that the launcher applies by itself.
+    pub launcher: Vec<&'static str>,
+    /// Keys that require the compute node to be restarted.
+    pub compute: Vec<&'static str>,
+    /// Keys that are only applied with a full `restart` of the node.
+    pub startup: Vec<&'static str>,
+}
+
+impl ReloadPlan {
+    /// Classifies the given changes of the environment.
+    pub fn new(changes: &[EnvChange]) -> Self {
+        let mut plan = Self::default();
+        for change in changes {
+            if LAUNCHER_KEYS.contains(&change.key) {
+                plan.launcher.push(change.key);
+            } else if STARTUP_KEYS.contains(&change.key) {
+                plan.startup.push(change.key);
+            } else {
+                plan.compute.push(change.key);
+            }
+        }
+
+        plan
+    }
+
+    /// Returns `true` if the compute node must be restarted for the changes.
+    #[inline]
+    pub fn requires_compute_restart(&self) -> bool {
+        !self.compute.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reload_plan() {
+        let change = |key| EnvChange {
+            key,
+            old: None,
+            new: Some("1".to_string()),
+        };
+
+        let plan = ReloadPlan::new(&[
+            change(DriaEnv::DKN_WATCHDOG_KEY),
+            change(DriaEnv::DKN_MODELS_KEY),
+            change(DriaEnv::DKN_HEALTH_PORT_KEY),
+            change(DriaEnv::OPENAI_APIKEY_KEY),
+        ]);
+        assert_eq!(plan.launcher, [DriaEnv::DKN_WATCHDOG_KEY]);
+        assert_eq!(
+            plan.compute,
+            [DriaEnv::DKN_MODELS_KEY, DriaEnv::OPENAI_APIKEY_KEY]
+        );
+        assert_eq!(plan.startup, [DriaEnv::DKN_HEALTH_PORT_KEY]);
+        assert!(plan.requires_compute_restart());
+
+        let plan = ReloadPlan::new(&[change(DriaEnv::DKN_NOTIFY_DESKTOP_KEY)]);
+        assert!(!plan.requires_compute_restart());
+    }
+}
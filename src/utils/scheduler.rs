@@ -0,0 +1,140 @@
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Maximum jitter of each update check, as a fraction of its interval; so that the nodes that
+/// are started at once do not check GitHub at once.
+const UPDATE_CHECK_JITTER: f64 = 0.1;
+
+/// Minimum time between any two update checks, shared by the compute node & launcher checks so
+/// that they do not hit GitHub back to back.
+const UPDATE_CHECK_MIN_GAP: Duration = Duration::from_secs(5 * 60);
+
+/// Maximum interval of each update check, so that very large configured intervals can not
+/// overflow the schedule.
+const UPDATE_CHECK_MAX_INTERVAL: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+
+/// An update check that is due, see [`UpdateScheduler`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateCheck {
+    Compute,
+    Launcher,
+}
+
+/// Schedules the compute node & launcher update checks of a running node.
+///
+/// Each check runs every its interval with a random jitter, and the checks are kept at least
+/// [`UPDATE_CHECK_MIN_GAP`] apart from each other. The first checks happen after their first
+/// interval, as the binaries are already checked at startup.
+#[derive(Debug)]
+pub struct UpdateScheduler {
+    compute_interval: Duration,
+    launcher_interval: Duration,
+    next_compute: Instant,
+    next_launcher: Instant,
+    last_check: Option<Instant>,
+}
+
+impl UpdateScheduler {
+    /// Creates a scheduler with the given intervals of the compute node & launcher checks, each
+    /// capped at [`UPDATE_CHECK_MAX_INTERVAL`].
+    pub fn new(compute_interval: Duration, launcher_interval: Duration) -> Self {
+        let compute_interval = compute_interval.min(UPDATE_CHECK_MAX_INTERVAL);
+        let launcher_interval = launcher_interval.min(UPDATE_CHECK_MAX_INTERVAL);
+        let now = Instant::now();
+        Self {
+            compute_interval,
+            launcher_interval,
+            next_compute: now + jittered(compute_interval, random_unit()),
+            next_launcher: now + jittered(launcher_interval, random_unit()),
+            last_check: None,
+        }
+    }
+
+    /// Waits until the next update check is due, and schedules the one after it.
+    ///
+    /// This is cancel-safe, the schedule only changes once the wait is over.
+    pub async fn next(&mut self) -> UpdateCheck {
+        let (check, due) = self.next_due();
+        tokio::time::sleep_until(due).await;
+
+        let now = Instant::now();
+        self.last_check = Some(now);
+        match check {
+            UpdateCheck::Compute => {
+                self.next_compute = now + jittered(self.compute_interval, random_unit())
+            }
+            UpdateCheck::Launcher => {
+                self.next_launcher = now + jittered(self.launcher_interval, random_unit())
+            }
+        }
+
+        check
+    }
+
+    /// Returns the check that is due first along with when it is due, respecting the minimum
+    /// gap after the last check.
+    fn next_due(&self) -> (UpdateCheck, Instant) {
+        let (check, due) = if self.next_compute <= self.next_launcher {
+            (UpdateCheck::Compute, self.next_compute)
+        } else {
+            (UpdateCheck::Launcher, self.next_launcher)
+        };
+
+        match self.last_check {
+            Some(last_check) => (check, due.max(last_check + UPDATE_CHECK_MIN_GAP)),
+            None => (check, due),
+        }
+    }
+}
+
+/// Returns the interval shifted by `unit * UPDATE_CHECK_JITTER` of itself, where `unit` is
+/// within `[-1, 1]`.
+fn jittered(interval: Duration, unit: f64) -> Duration {
+    interval.mul_f64(1.0 + unit.clamp(-1.0, 1.0) * UPDATE_CHECK_JITTER)
+}
+
+/// Returns a random number within `[-1, 1]`, `0` if randomness is not available.
+fn random_unit() -> f64 {
+    let mut bytes = [0u8; 4];
+    match getrandom::getrandom(&mut bytes) {
+        Ok(()) => u32::from_le_bytes(bytes) as f64 / u32::MAX as f64 * 2.0 - 1.0,
+        Err(_) => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_scheduler() {
+        let hour = Duration::from_secs(60 * 60);
+        assert_eq!(jittered(hour, 0.0), hour);
+        assert_eq!(jittered(hour, 1.0), Duration::from_secs(66 * 60));
+        assert_eq!(jittered(hour, -5.0), Duration::from_secs(54 * 60));
+        assert!((-1.0..=1.0).contains(&random_unit()));
+
+        let now = Instant::now();
+        let mut scheduler = UpdateScheduler {
+            compute_interval: hour,
+            launcher_interval: 3 * hour,
+            next_compute: now + hour,
+            next_launcher: now + hour + Duration::from_secs(10),
+            last_check: None,
+        };
+        assert_eq!(scheduler.next_due(), (UpdateCheck::Compute, now + hour));
+
+        // the launcher check is pushed back to keep the gap after the compute check
+        scheduler.last_check = Some(now + hour);
+        scheduler.next_compute = now + 2 * hour;
+        assert_eq!(
+            scheduler.next_due(),
+            (UpdateCheck::Launcher, now + hour + UPDATE_CHECK_MIN_GAP)
+        );
+
+        // very large intervals are capped instead of overflowing
+        let scheduler = UpdateScheduler::new(Duration::MAX, hour);
+        assert_eq!(scheduler.compute_interval, UPDATE_CHECK_MAX_INTERVAL);
+        assert_eq!(scheduler.launcher_interval, hour);
+    }
+}
@@ -0,0 +1,98 @@
+use colored::Colorize;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use super::{is_quiet, progress_draw_target};
+
+/// Number of stages of the shutdown, including the final one.
+const SHUTDOWN_STAGES: usize = 5;
+/// Template of the spinner of each stage.
+const SHUTDOWN_SPINNER_TEMPLATE: &str = "{spinner} {msg} ({elapsed})";
+/// Interval between spinner ticks.
+const SHUTDOWN_TICK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Displays the progress of a shutdown, one stage after another with their timings,
+/// so that a slow shutdown does not look like the launcher has hung; nothing is displayed in
+/// quiet mode.
+#[derive(Debug)]
+pub struct ShutdownProgress {
+    started: Instant,
+    stage: usize,
+}
+
+impl ShutdownProgress {
+    /// Starts displaying the shutdown, along with how to force-quit.
+    pub fn begin() -> Self {
+        if !is_quiet() {
+            eprintln!(
+                "{}",
+                "Shutting down, press Ctrl-C again to force quit.".bold()
+            );
+        }
+
+        Self {
+            started: Instant::now(),
+            stage: 0,
+        }
+    }
+
+    /// Awaits the given stage while displaying a spinner for it, and marks it with its timing
+    /// when it is done.
+    ///
+    /// The stage returns `false` if it did not go as expected (e.g. a process had to be killed).
+    pub async fn stage(&mut self, name: &str, stage: impl Future<Output = bool>) {
+        let label = self.next_label(name);
+        let spinner = ProgressBar::with_draw_target(None, progress_draw_target())
+            .with_style(
+                ProgressStyle::default_spinner()
+                    .template(SHUTDOWN_SPINNER_TEMPLATE)
+                    .expect("template is valid"),
+            )
+            .with_message(label.clone());
+        spinner.enable_steady_tick(SHUTDOWN_TICK_INTERVAL);
+
+        let started = Instant::now();
+        let mark = if stage.await {
+            "✓".green()
+        } else {
+            "✗".yellow()
+        };
+        spinner.finish_and_clear();
+        if !is_quiet() {
+            eprintln!(
+                "{mark} {label} {}",
+                format!("({:.1}s)", started.elapsed().as_secs_f64()).dimmed()
+            );
+        }
+    }
+
+    /// Marks the stage as skipped, with the given reason.
+    pub fn skip(&mut self, name: &str, reason: &str) {
+        let label = self.next_label(name);
+        if !is_quiet() {
+            eprintln!(
+                "{} {label} {}",
+                "-".dimmed(),
+                format!("({reason})").dimmed()
+            );
+        }
+    }
+
+    /// Marks the shutdown as done, with the total time it took.
+    pub fn finish(mut self) {
+        let label = self.next_label("done");
+        if !is_quiet() {
+            eprintln!(
+                "{} {label} {}",
+                "✓".green(),
+                format!("(in {:.1}s)", self.started.elapsed().as_secs_f64()).dimmed()
+            );
+        }
+    }
+
+    fn next_label(&mut self, name: &str) -> String {
+        self.stage += 1;
+        format!("[{}/{SHUTDOWN_STAGES}] {name}", self.stage)
+    }
+}
@@ -1,9 +1,107 @@
 use tokio_util::sync::CancellationToken;
 
+use super::force_kill;
+
 /// Waits for various termination signals, and cancels the given token when the signal is received.
 ///
 /// Handles Unix and Windows [target families](https://doc.rust-lang.org/reference/conditional-compilation.html#target_family).
 pub async fn wait_for_termination(cancellation: CancellationToken) -> std::io::Result<()> {
+    tokio::select! {
+        result = recv_termination_signal() => result?,
+        _ = cancellation.cancelled() => {
+            // no need to wait if cancelled anyways
+            // although this is not likely to happen
+            return Ok(());
+        }
+    };
+
+    cancellation.cancel();
+    Ok(())
+}
+
+/// Listens for another termination signal during shutdown, and force-quits the launcher when
+/// it is received: the given processes (e.g. compute node & Ollama) are killed immediately, and
+/// the returned token is cancelled so that the rest of the shutdown does not wait for anything.
+pub fn spawn_force_quit(pids: Vec<u32>) -> CancellationToken {
+    let forced = CancellationToken::new();
+    let forced_clone = forced.clone();
+    tokio::spawn(async move {
+        if let Err(err) = recv_termination_signal().await {
+            log::warn!("Could not listen for force quit: {err}");
+            return;
+        }
+
+        log::warn!("Force quitting, killing processes immediately.");
+        for pid in pids {
+            if let Err(err) = force_kill(pid) {
+                log::warn!("Could not kill process {pid}: {err}");
+            }
+        }
+        forced_clone.cancel();
+    });
+
+    forced
+}
+
+/// Listens for requests to reload the environment file, i.e. `SIGHUP` on Unix; it never fires on
+/// other platforms.
+pub struct ReloadSignal {
+    #[cfg(unix)]
+    signal: Option<tokio::signal::unix::Signal>,
+}
+
+impl ReloadSignal {
+    /// Starts listening for reload requests; failures are logged, and the listener never fires then.
+    pub fn listen() -> Self {
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{signal, SignalKind};
+            let signal = signal(SignalKind::hangup())
+                .inspect_err(|err| log::warn!("Could not listen for SIGHUP: {err}"))
+                .ok();
+            Self { signal }
+        }
+
+        #[cfg(not(unix))]
+        Self {}
+    }
+
+    /// Waits until a reload is requested.
+    pub async fn recv(&mut self) {
+        #[cfg(unix)]
+        if let Some(signal) = &mut self.signal {
+            signal.recv().await;
+            log::info!("Received SIGHUP");
+            return;
+        }
+
+        std::future::pending::<()>().await;
+    }
+}
+
+/// Asks the launcher with the given PID to reload its environment file, see [`ReloadSignal`].
+#[cfg(unix)]
+pub fn request_reload(pid: u32) -> std::io::Result<()> {
+    // safe because `kill` only sends a signal, it does not touch any memory
+    let result = unsafe { libc::kill(pid as libc::pid_t, libc::SIGHUP) };
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error())
+    }
+}
+
+/// Asks the launcher with the given PID to reload its environment file, see [`ReloadSignal`].
+#[cfg(not(unix))]
+pub fn request_reload(_pid: u32) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "reloading a running node is not supported on this platform, use `restart` instead",
+    ))
+}
+
+/// Waits until one of the termination signals is received.
+async fn recv_termination_signal() -> std::io::Result<()> {
     #[cfg(unix)]
     {
         use tokio::signal::unix::{signal, SignalKind};
@@ -12,14 +110,7 @@ pub async fn wait_for_termination(cancellation: CancellationToken) -> std::io::R
         tokio::select! {
             _ = sigterm.recv() => log::warn!("Recieved SIGTERM"),
             _ = sigint.recv() => log::warn!("Recieved SIGINT"),
-            _ = cancellation.cancelled() => {
-                // no need to wait if cancelled anyways
-                // although this is not likely to happen
-                return Ok(());
-            }
         };
-
-        cancellation.cancel();
     }
 
     #[cfg(windows)]
@@ -37,20 +128,16 @@ pub async fn wait_for_termination(cancellation: CancellationToken) -> std::io::R
             _ = signal_break.recv() => log::warn!("Received CTRL_BREAK"),
             _ = signal_close.recv() => log::warn!("Received CTRL_CLOSE"),
             _ = signal_shutdown.recv() => log::warn!("Received CTRL_SHUTDOWN"),
-            _ = cancellation.cancelled() => {
-                // no need to wait if cancelled anyways
-                // although this is not likely to happen
-                return Ok(());
-            }
         };
-
-        cancellation.cancel();
     }
 
     #[cfg(not(any(unix, windows)))]
     {
-        log::error!("No signal handling for this platform: {}", env::consts::OS);
-        cancellation.cancel();
+        log::error!(
+            "No signal handling for this platform: {}",
+            std::env::consts::OS
+        );
+        std::future::pending::<()>().await;
     }
 
     Ok(())
@@ -0,0 +1,127 @@
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget};
+use std::ffi::OsStr;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/// Whether the output is colored, see [`init_terminal`].
+static COLORS_ENABLED: AtomicBool = AtomicBool::new(true);
+/// Whether the launcher is quiet, see [`init_terminal`].
+static QUIET: AtomicBool = AtomicBool::new(false);
+/// The progress bars that are currently drawn together, see [`set_shared_progress`].
+static SHARED_PROGRESS: Mutex<Option<MultiProgress>> = Mutex::new(None);
+
+/// Decides whether the output of the launcher is colored & whether progress bars are drawn, so
+/// that piping the output (e.g. of `points` or `models measure`) to a file does not embed ANSI
+/// escape codes everywhere.
+///
+/// Colors are disabled with `--no-color`, a non-empty `NO_COLOR` (see <https://no-color.org>), or
+/// when `stdout` is not a terminal; progress bars are hidden when `stdout` is not a terminal. The
+/// decision is applied to `colored`, the prompts and the logs of the launcher & compute node.
+///
+/// In quiet mode, progress bars & spinners are hidden along with the status lines such as the
+/// startup summary, e.g. for cron or systemd where redraws pollute the journal.
+///
+/// ### Arguments
+/// - `no_color`: whether `--no-color` is given
+/// - `quiet`: whether `--quiet` is given
+pub fn init_terminal(no_color: bool, quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+
+    let enabled = should_colorize(
+        no_color,
+        std::env::var_os("NO_COLOR").as_deref(),
+        std::io::stdout().is_terminal(),
+    );
+    COLORS_ENABLED.store(enabled, Ordering::Relaxed);
+
+    if !enabled {
+        colored::control::set_override(false);
+        inquire::set_global_render_config(inquire::ui::RenderConfig::empty());
+    }
+}
+
+/// Returns `true` if the output of the launcher is colored, see [`init_terminal`].
+pub fn colors_enabled() -> bool {
+    COLORS_ENABLED.load(Ordering::Relaxed)
+}
+
+/// Returns `true` if the launcher is quiet, see [`init_terminal`].
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// Returns the draw target of the progress bars & spinners, which is hidden when `stdout` is
+/// not a terminal or the launcher is quiet.
+pub fn progress_draw_target() -> ProgressDrawTarget {
+    if std::io::stdout().is_terminal() && !is_quiet() {
+        ProgressDrawTarget::stderr()
+    } else {
+        ProgressDrawTarget::hidden()
+    }
+}
+
+/// Sets the progress bars that new progress bars are drawn along with, e.g. the spinners of the
+/// concurrent preflight checks; otherwise they would overwrite each other. `None` unsets it.
+pub fn set_shared_progress(progress: Option<MultiProgress>) {
+    *SHARED_PROGRESS.lock().expect("lock is not poisoned") = progress;
+}
+
+/// Returns a new progress bar of the given length (or a spinner), drawn along with the shared
+/// progress bars if there are any, see [`set_shared_progress`].
+pub fn new_progress_bar(len: Option<u64>) -> ProgressBar {
+    let progress_bar = ProgressBar::with_draw_target(len, progress_draw_target());
+    match SHARED_PROGRESS
+        .lock()
+        .expect("lock is not poisoned")
+        .as_ref()
+    {
+        Some(progress) => progress.add(progress_bar),
+        None => progress_bar,
+    }
+}
+
+/// Returns `true` if the output should be colored w.r.t `--no-color`, the `NO_COLOR` variable
+/// and whether `stdout` is a terminal.
+fn should_colorize(no_color: bool, no_color_var: Option<&OsStr>, is_terminal: bool) -> bool {
+    let no_color_var = no_color_var.is_some_and(|value| !value.is_empty());
+
+    !no_color && !no_color_var && is_terminal
+}
+
+/// Returns the width of each column of a table, w.r.t the longest cell.
+pub fn column_widths<'a, const N: usize>(
+    rows: impl Iterator<Item = &'a [String; N]>,
+) -> [usize; N] {
+    rows.fold([0; N], |mut widths, row| {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+        widths
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_should_colorize() {
+        assert!(should_colorize(false, None, true));
+        assert!(!should_colorize(true, None, true));
+        assert!(!should_colorize(false, Some(OsStr::new("1")), true));
+        assert!(!should_colorize(false, None, false));
+
+        // an empty `NO_COLOR` is ignored, as per the convention
+        assert!(should_colorize(false, Some(OsStr::new("")), true));
+    }
+
+    #[test]
+    fn test_column_widths() {
+        let rows = [
+            ["a".to_string(), "bcd".to_string()],
+            ["ef".to_string(), "g".to_string()],
+        ];
+        assert_eq!(column_widths(rows.iter()), [2, 3]);
+    }
+}
@@ -0,0 +1,281 @@
+use std::time::Duration;
+use tokio::process::Child;
+
+use super::is_process_running;
+
+/// Terminates the given child process gracefully.
+///
+/// The process is first asked to terminate (`SIGTERM` on Unix, `taskkill` on Windows), and
+/// is killed forcefully only if it does not exit within the given `timeout`.
+///
+/// ### Arguments
+/// - `child`: the process to terminate
+/// - `name`: name of the process, used in logs
+/// - `timeout`: time to wait before killing the process
+///
+/// ### Returns
+/// `true` if the process exited by itself (or had already exited), `false` if it was killed.
+pub async fn terminate_gracefully(child: &mut Child, name: &str, timeout: Duration) -> bool {
+    match signal_termination(child) {
+        Ok(false) => true,
+        Ok(true) => {
+            log::info!("Waiting up to {}s for {name} to exit.", timeout.as_secs());
+            wait_or_kill(child, name, timeout).await
+        }
+        Err(err) => {
+            log::warn!("Could not ask {name} to terminate: {err}");
+            if let Err(err) = kill_process_tree(child, name).await {
+                log::warn!("Failed to kill {name}: {err}");
+            }
+            false
+        }
+    }
+}
+
+/// Terminates the process with the given PID gracefully, for a process that is not a child of the
+/// launcher (e.g. one that is adopted after a restart), see [`terminate_gracefully`].
+///
+/// ### Returns
+/// `true` if the process exited by itself (or had already exited), `false` if it was killed.
+pub async fn terminate_pid_gracefully(pid: u32, name: &str, timeout: Duration) -> bool {
+    if !is_process_running(pid) {
+        return true;
+    }
+    if let Err(err) = request_termination(pid) {
+        log::warn!("Could not ask {name} to terminate: {err}");
+    } else {
+        log::info!("Waiting up to {}s for {name} to exit.", timeout.as_secs());
+        let deadline = tokio::time::Instant::now() + timeout;
+        while tokio::time::Instant::now() < deadline {
+            if !is_process_running(pid) {
+                return true;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        log::warn!("{name} did not exit in time, killing it.");
+    }
+
+    if let Err(err) = force_kill(pid) {
+        log::warn!("Failed to kill {name}: {err}");
+    }
+    false
+}
+
+/// Keeps track of the process tree of the given child, so that killing it with
+/// [`kill_process_tree`] kills its descendants as well (e.g. the model runners of Ollama).
+///
+/// On Windows, killing a process does not kill its children; so the child is assigned to a Job
+/// Object, which is terminated instead. The job also kills the entire tree when the launcher
+/// exits, and when another process is tracked with the same `name` (e.g. after a restart) so that
+/// the leftovers of a crashed process do not linger. On other platforms, this does nothing.
+///
+/// Failures are logged as warnings, as the process can still be killed by itself.
+pub fn track_process_tree(child: &Child, name: &'static str) {
+    #[cfg(windows)]
+    if let Err(err) = job::track(child, name) {
+        log::warn!("Could not track the process tree of {name}: {err}");
+    }
+
+    #[cfg(not(windows))]
+    let _ = (child, name);
+}
+
+/// Kills the given child process immediately, along with its descendants if its process tree is
+/// tracked, see [`track_process_tree`].
+pub async fn kill_process_tree(child: &mut Child, name: &str) -> std::io::Result<()> {
+    #[cfg(windows)]
+    job::terminate(name);
+    #[cfg(not(windows))]
+    let _ = name;
+
+    child.kill().await
+}
+
+/// Asks the given child process to terminate, without waiting for it.
+///
+/// Returns `false` if the process has already exited.
+pub fn signal_termination(child: &mut Child) -> std::io::Result<bool> {
+    // process has already exited
+    if let Ok(Some(_)) = child.try_wait() {
+        return Ok(false);
+    }
+    let Some(pid) = child.id() else {
+        return Ok(false);
+    };
+
+    request_termination(pid).map(|_| true)
+}
+
+/// Waits for the given child process to exit, and kills it if it does not exit within the `timeout`.
+///
+/// Returns `true` if the process exited by itself.
+pub async fn wait_or_kill(child: &mut Child, name: &str, timeout: Duration) -> bool {
+    match tokio::time::timeout(timeout, child.wait()).await {
+        Ok(Ok(status)) => {
+            log::info!("{name} exited ({status}).");
+            return true;
+        }
+        Ok(Err(err)) => log::warn!("Could not wait for {name}: {err}"),
+        Err(_) => log::warn!("{name} did not exit in time, killing it."),
+    }
+
+    if let Err(err) = kill_process_tree(child, name).await {
+        log::warn!("Failed to kill {name}: {err}");
+    }
+
+    false
+}
+
+/// Asks the process with the given PID to terminate.
+#[cfg(unix)]
+pub fn request_termination(pid: u32) -> std::io::Result<()> {
+    // safe because `kill` only sends a signal, it does not touch any memory
+    let result = unsafe { libc::kill(pid as libc::pid_t, libc::SIGTERM) };
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error())
+    }
+}
+
+/// Asks the process with the given PID to terminate.
+#[cfg(windows)]
+pub fn request_termination(pid: u32) -> std::io::Result<()> {
+    // without `/F`, `taskkill` asks the process (and its children with `/T`) to close instead of killing it
+    let status = std::process::Command::new("taskkill")
+        .args(["/T", "/PID", &pid.to_string()])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(std::io::Error::other(format!(
+            "taskkill exited with {status}"
+        )))
+    }
+}
+
+/// Asks the process with the given PID to terminate.
+#[cfg(not(any(unix, windows)))]
+pub fn request_termination(_pid: u32) -> std::io::Result<()> {
+    Err(std::io::Error::other(
+        "graceful termination is not supported",
+    ))
+}
+
+/// Kills the process with the given PID immediately, along with its children on Windows.
+#[cfg(unix)]
+pub fn force_kill(pid: u32) -> std::io::Result<()> {
+    // safe because `kill` only sends a signal, it does not touch any memory
+    let result = unsafe { libc::kill(pid as libc::pid_t, libc::SIGKILL) };
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error())
+    }
+}
+
+/// Kills the process with the given PID immediately, along with its children on Windows.
+#[cfg(windows)]
+pub fn force_kill(pid: u32) -> std::io::Result<()> {
+    let status = std::process::Command::new("taskkill")
+        .args(["/F", "/T", "/PID", &pid.to_string()])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(std::io::Error::other(format!(
+            "taskkill exited with {status}"
+        )))
+    }
+}
+
+/// Kills the process with the given PID immediately, along with its children on Windows.
+#[cfg(not(any(unix, windows)))]
+pub fn force_kill(_pid: u32) -> std::io::Result<()> {
+    Err(std::io::Error::other("force kill is not supported"))
+}
+
+#[cfg(windows)]
+mod job {
+    use std::io;
+    use std::sync::Mutex;
+    use tokio::process::Child;
+    use windows_sys::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows_sys::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, JobObjectExtendedLimitInformation,
+        SetInformationJobObject, TerminateJobObject, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+        JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+    };
+
+    /// Exit code of the processes that are killed along with their job.
+    const JOB_KILL_EXIT_CODE: u32 = 1;
+
+    /// A Job Object that kills its processes when its handle is closed.
+    struct Job(HANDLE);
+
+    // the handle is only used through the Win32 API, which is thread-safe
+    unsafe impl Send for Job {}
+
+    impl Drop for Job {
+        fn drop(&mut self) {
+            unsafe { CloseHandle(self.0) };
+        }
+    }
+
+    /// The jobs of the tracked process trees, by their names.
+    static JOBS: Mutex<Vec<(&'static str, Job)>> = Mutex::new(Vec::new());
+
+    /// Assigns the process to a new job, replacing (and so killing) the job of the same name.
+    pub fn track(child: &Child, name: &'static str) -> io::Result<()> {
+        let handle = child
+            .raw_handle()
+            .ok_or_else(|| io::Error::other("process has exited"))?;
+
+        let job = unsafe {
+            let job = CreateJobObjectW(std::ptr::null(), std::ptr::null());
+            if job.is_null() {
+                return Err(io::Error::last_os_error());
+            }
+            let job = Job(job);
+
+            let mut info: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = std::mem::zeroed();
+            info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+            if SetInformationJobObject(
+                job.0,
+                JobObjectExtendedLimitInformation,
+                &info as *const _ as *const std::ffi::c_void,
+                std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+            ) == 0
+                || AssignProcessToJobObject(job.0, handle) == 0
+            {
+                return Err(io::Error::last_os_error());
+            }
+
+            job
+        };
+
+        let mut jobs = JOBS.lock().expect("poisoned lock");
+        jobs.retain(|(job_name, _)| *job_name != name);
+        jobs.push((name, job));
+
+        Ok(())
+    }
+
+    /// Terminates the job of the given name along with all of its processes, if it is tracked.
+    pub fn terminate(name: &str) {
+        let mut jobs = JOBS.lock().expect("poisoned lock");
+        if let Some(idx) = jobs.iter().position(|(job_name, _)| *job_name == name) {
+            let (_, job) = jobs.remove(idx);
+            if unsafe { TerminateJobObject(job.0, JOB_KILL_EXIT_CODE) } == 0 {
+                log::warn!(
+                    "Could not terminate the process tree of {name}: {}",
+                    io::Error::last_os_error()
+                );
+            }
+        }
+    }
+}
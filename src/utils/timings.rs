@@ -0,0 +1,103 @@
+use colored::Colorize;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// Whether timing summaries are enabled, set once by the `--summary` flag.
+static TIMING_SUMMARY_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Enables timing summaries for the rest of the process.
+#[inline]
+pub fn enable_timing_summary() {
+    TIMING_SUMMARY_ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Records how long each step of a command takes, and prints a breakdown at the end.
+///
+/// Nothing is recorded nor printed unless the `--summary` flag is given, see [`enable_timing_summary`].
+/// The timings are only printed locally, they are never sent anywhere.
+#[derive(Debug)]
+pub struct TimingSummary {
+    title: &'static str,
+    enabled: bool,
+    started: Instant,
+    steps: Vec<(String, Duration)>,
+}
+
+impl TimingSummary {
+    /// Creates a new summary with the given title, e.g. the command name.
+    pub fn new(title: &'static str) -> Self {
+        Self {
+            title,
+            enabled: TIMING_SUMMARY_ENABLED.load(Ordering::Relaxed),
+            started: Instant::now(),
+            steps: Vec::new(),
+        }
+    }
+
+    /// Records a step that has started at the given instant and ended now.
+    pub fn record(&mut self, step: impl Into<String>, started: Instant) {
+        self.record_duration(step, started.elapsed());
+    }
+
+    /// Records a step that took the given duration.
+    pub fn record_duration(&mut self, step: impl Into<String>, duration: Duration) {
+        if self.enabled {
+            self.steps.push((step.into(), duration));
+        }
+    }
+
+    /// Appends the steps of another summary, e.g. one that was recorded concurrently.
+    pub fn extend(&mut self, other: TimingSummary) {
+        self.steps.extend(other.steps);
+    }
+
+    /// Awaits the given future and records the time it took as a step.
+    pub async fn time<T>(&mut self, step: impl Into<String>, fut: impl Future<Output = T>) -> T {
+        let started = Instant::now();
+        let result = fut.await;
+        self.record(step, started);
+        result
+    }
+
+    /// Prints the summary to `stderr`, if enabled.
+    pub fn print(&self) {
+        if self.enabled {
+            eprintln!("{self}");
+        }
+    }
+}
+
+impl std::fmt::Display for TimingSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", format!("Timing summary ({}):", self.title).bold())?;
+        for (step, duration) in &self.steps {
+            writeln!(f, "  {:<32} {:>9.2}s", step, duration.as_secs_f64())?;
+        }
+        write!(
+            f,
+            "  {:<32} {:>9.2}s",
+            "total".bold(),
+            self.started.elapsed().as_secs_f64()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_timing_summary() {
+        let mut summary = TimingSummary::new("test");
+        summary.enabled = true;
+
+        let value = summary.time("step one", async { 42 }).await;
+        assert_eq!(value, 42);
+        summary.record("step two", Instant::now());
+
+        assert_eq!(summary.steps.len(), 2);
+        assert_eq!(summary.steps[0].0, "step one");
+        assert!(summary.to_string().contains("step two"));
+    }
+}
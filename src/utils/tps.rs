@@ -0,0 +1,262 @@
+use dkn_executor::ollama_rs::{
+    generation::completion::{request::GenerationRequest, GenerationResponse},
+    models::ModelOptions,
+    Ollama,
+};
+use dkn_executor::Model;
+use eyre::{Context, Result};
+use std::collections::{BTreeMap, HashMap};
+
+use super::{
+    benchmark_hardware, connect_ollama, ollama_http_client, BenchmarkCache, BenchmarkResult,
+    DriaEnv, TracedSend,
+};
+use crate::tr;
+
+/// Smoothing factor of the moving average, higher values favor recent samples.
+const TPS_EMA_ALPHA: f64 = 0.3;
+/// Number of tokens to generate for a measurement of [`measure_eval_tps`].
+const TPS_MEASURE_TOKENS: i32 = 128;
+/// Lowercase patterns that follow the TPS within a compute node log line, e.g. `23.5 tokens/s`.
+const TPS_SUFFIX_PATTERNS: [&str; 3] = ["tokens/s", "tok/s", "tps"];
+
+/// Minimum evaluation TPS that a model must have to serve the tasks of the network in time.
+pub const MINIMUM_EVAL_TPS: f64 = 15.0;
+
+/// Tracks an exponential moving average (EMA) of the generation throughput (TPS) per model,
+/// w.r.t the tasks that the compute node has completed while running, see [`parse_task_tps`].
+#[derive(Debug, Clone, Default)]
+pub struct TpsTracker(BTreeMap<String, f64>);
+
+impl TpsTracker {
+    /// Adds a new sample for the given model.
+    pub fn update(&mut self, model: &str, tps: f64) {
+        self.0
+            .entry(model.to_string())
+            .and_modify(|ema| *ema = TPS_EMA_ALPHA * tps + (1.0 - TPS_EMA_ALPHA) * *ema)
+            .or_insert(tps);
+    }
+
+    /// Returns the current moving averages per model.
+    #[inline]
+    pub fn emas(&self) -> &BTreeMap<String, f64> {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for TpsTracker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, "no samples");
+        }
+
+        let emas = self
+            .0
+            .iter()
+            .map(|(model, tps)| format!("{model}: {tps:.2}"))
+            .collect::<Vec<_>>();
+        write!(f, "{}", emas.join(", "))
+    }
+}
+
+/// Parses the model & its TPS from a (lowercase) task completion line of the compute node, e.g.
+/// `completed task 1 (model: gemma3:4b, 23.5 tokens/s)`; returns `None` if the
+/// line does not have both.
+pub(super) fn parse_task_tps(line: &str) -> Option<(String, f64)> {
+    let model = line
+        .split_once("model")?
+        .1
+        .trim_start_matches([':', '=', ' ', '"', '\''])
+        .split([' ', ',', ')', '"', '\''])
+        .next()
+        .filter(|model| !model.is_empty())?;
+
+    let tps = TPS_SUFFIX_PATTERNS.iter().find_map(|pattern| {
+        let (before, after) = line.split_once(pattern)?;
+        // either `23.5 tokens/s` or `tps: 23.5`
+        let number = match before.trim_end().rsplit(' ').next() {
+            Some(number) if number.parse::<f64>().is_ok() => number,
+            _ => after
+                .trim_start_matches([':', '=', ' '])
+                .split(|c: char| !c.is_ascii_digit() && c != '.')
+                .next()?,
+        };
+        number
+            .parse::<f64>()
+            .ok()
+            .filter(|tps| tps.is_finite() && *tps > 0.0)
+    })?;
+
+    Some((model.to_string(), tps))
+}
+
+/// Returns the models that are currently loaded by Ollama along with their expiry, via `/api/ps`;
+/// Ollama refreshes the expiry of a model with each request.
+pub async fn get_running_models(dria_env: &DriaEnv) -> Result<HashMap<String, String>> {
+    #[derive(serde::Deserialize)]
+    struct RunningModel {
+        name: String,
+        #[serde(default)]
+        expires_at: String,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct PsResponse {
+        models: Vec<RunningModel>,
+    }
+
+    let (host, port) = dria_env.get_ollama_config();
+    let res = ollama_http_client(dria_env)?
+        .get(format!("{host}:{port}/api/ps"))
+        .send_traced()
+        .await?
+        .error_for_status()?
+        .json::<PsResponse>()
+        .await
+        .wrap_err("could not parse running models")?;
+
+    Ok(res
+        .models
+        .into_iter()
+        .map(|m| (m.name, m.expires_at))
+        .collect())
+}
+
+/// Measures the TPS of the given Ollama models one by one, returns the ones that are below
+/// [`MINIMUM_EVAL_TPS`] along with their TPS.
+///
+/// Models that are measured before with their current version on this hardware (see the
+/// `measure` option within the models settings) are not measured again, and new measurements
+/// are kept within the [`BenchmarkCache`]. Models that could not be measured are kept with a
+/// warning, as the compute node reports its own errors for them.
+///
+/// ### Errors
+/// - If Ollama could not be reached to list its models
+pub async fn find_slow_models<'a>(
+    dria_env: &DriaEnv,
+    ollama_models: impl IntoIterator<Item = &'a Model>,
+) -> Result<Vec<(Model, f64)>> {
+    let ollama = connect_ollama(dria_env)?;
+    let model_versions = ollama
+        .list_local_models()
+        .await?
+        .into_iter()
+        .map(|m| (m.name, m.modified_at))
+        .collect::<HashMap<_, _>>();
+    let hardware = benchmark_hardware(dria_env);
+    let mut cache = BenchmarkCache::load();
+    let mut is_cache_changed = false;
+
+    let mut slow_models = Vec::new();
+    for model in ollama_models {
+        let name = model.to_string();
+        let version = model_versions.get(&name).cloned().unwrap_or_default();
+        let tps = match cache.get(&name, &version, &hardware) {
+            Some(result) => {
+                log::info!(
+                    "{model} runs at {:.2} TPS (measured {}).",
+                    result.eval_tps,
+                    result.age()
+                );
+                result.eval_tps
+            }
+            None => {
+                log::info!(
+                    "{}",
+                    tr!("Measuring the performance of {model}...", model = model)
+                );
+                match measure_eval(&ollama, &name).await {
+                    Ok(res) => {
+                        let result = BenchmarkResult::new(&res, name, version, hardware.clone());
+                        log::info!("{model} runs at {:.2} TPS.", result.eval_tps);
+                        let tps = result.eval_tps;
+                        cache.insert(result);
+                        is_cache_changed = true;
+                        tps
+                    }
+                    Err(err) => {
+                        log::warn!("Could not measure the performance of {model}: {err}");
+                        continue;
+                    }
+                }
+            }
+        };
+        if tps < MINIMUM_EVAL_TPS {
+            slow_models.push((*model, tps));
+        }
+    }
+    if is_cache_changed {
+        if let Err(err) = cache.save() {
+            log::warn!("Could not save the measurements: {err}");
+        }
+    }
+
+    Ok(slow_models)
+}
+
+/// Measures the given model quickly, after loading it into memory with a warm-up generation
+/// so that the loading time is not measured; returns the measured generation.
+async fn measure_eval(ollama: &Ollama, model: &str) -> Result<GenerationResponse> {
+    ollama
+        .generate(
+            GenerationRequest::new(model.to_string(), "Say hello.")
+                .options(ModelOptions::default().num_predict(1)),
+        )
+        .await
+        .wrap_err("warm-up generation failed")?;
+
+    let res = ollama
+        .generate(
+            GenerationRequest::new(model.to_string(), "Say hello.")
+                .options(ModelOptions::default().num_predict(TPS_MEASURE_TOKENS)),
+        )
+        .await?;
+    eval_tps(&res)?;
+
+    Ok(res)
+}
+
+/// Returns the evaluation TPS of the given generation, w.r.t the evaluation duration reported
+/// by Ollama; i.e. without the prompt evaluation and the time it waits within the queue.
+pub fn eval_tps(res: &GenerationResponse) -> Result<f64> {
+    let (Some(count), Some(duration)) = (res.eval_count, res.eval_duration) else {
+        eyre::bail!("response has no evaluation metadata");
+    };
+    if duration == 0 {
+        eyre::bail!("evaluation duration is zero");
+    }
+
+    Ok(count as f64 / duration as f64 * 1e9)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tps_ema() {
+        let mut tracker = TpsTracker::default();
+        tracker.update("llama3.1:8b", 10.0);
+        assert_eq!(tracker.emas()["llama3.1:8b"], 10.0);
+
+        tracker.update("llama3.1:8b", 20.0);
+        let ema = tracker.emas()["llama3.1:8b"];
+        assert!((ema - 13.0).abs() < 1e-9);
+
+        assert_eq!(tracker.to_string(), "llama3.1:8b: 13.00");
+    }
+
+    #[test]
+    fn test_parse_task_tps() {
+        assert_eq!(
+            parse_task_tps("completed task 1 (model: gemma3:4b, 23.5 tokens/s)"),
+            Some(("gemma3:4b".to_string(), 23.5))
+        );
+        assert_eq!(
+            parse_task_tps("task completed model=llama3.1:8b tps=12"),
+            Some(("llama3.1:8b".to_string(), 12.0))
+        );
+        assert_eq!(parse_task_tps("completed task 1"), None);
+        assert_eq!(parse_task_tps("task completed (model: gemma3:4b)"), None);
+    }
+}
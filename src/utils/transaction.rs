@@ -0,0 +1,273 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Name of the staging directory for a transaction, created within the transaction directory.
+const STAGING_DIR: &str = ".dkn-transaction";
+/// Suffix of the staged files, which are kept next to their targets so that they are renamed
+/// within the same filesystem.
+const STAGED_SUFFIX: &str = ".dkn-staged";
+/// Name of the journal file within the staging directory.
+const JOURNAL_FILE: &str = "journal";
+
+/// A journaled operation, each one knows how to undo itself.
+#[derive(Debug, Clone, PartialEq)]
+enum Operation {
+    /// File was moved to the staging path, undone by moving it back.
+    Removed { path: PathBuf, staged: PathBuf },
+    /// File was copied to the staging path before being modified, undone by moving it back.
+    Modified { path: PathBuf, staged: PathBuf },
+    /// File did not exist before, undone by removing it.
+    Created { path: PathBuf },
+}
+
+impl Operation {
+    fn to_line(&self) -> String {
+        match self {
+            Self::Removed { path, staged } => {
+                format!("removed\t{}\t{}", path.display(), staged.display())
+            }
+            Self::Modified { path, staged } => {
+                format!("modified\t{}\t{}", path.display(), staged.display())
+            }
+            Self::Created { path } => format!("created\t{}", path.display()),
+        }
+    }
+
+    fn from_line(line: &str) -> Option<Self> {
+        let mut parts = line.split('\t');
+        match (parts.next()?, parts.next()?, parts.next()) {
+            ("removed", path, Some(staged)) => Some(Self::Removed {
+                path: path.into(),
+                staged: staged.into(),
+            }),
+            ("modified", path, Some(staged)) => Some(Self::Modified {
+                path: path.into(),
+                staged: staged.into(),
+            }),
+            ("created", path, None) => Some(Self::Created { path: path.into() }),
+            _ => None,
+        }
+    }
+
+    fn undo(&self) -> io::Result<()> {
+        match self {
+            Self::Removed { path, staged } => fs::rename(staged, path),
+            Self::Modified { path, staged } => fs::rename(staged, path),
+            Self::Created { path } => match fs::remove_file(path) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+                _ => Ok(()),
+            },
+        }
+    }
+}
+
+/// A transaction-like helper for multi-file mutations, such as `uninstall` and `update`.
+///
+/// Every mutation is first recorded to a journal within a staging directory, so that the
+/// files can be restored if something fails halfway; the previous files are staged next to
+/// their targets, as a rename across filesystems fails.
+///
+/// - [`FsTransaction::commit`] makes the changes permanent by removing the staged files.
+/// - [`FsTransaction::rollback`] restores the files in reverse order.
+/// - Dropping a transaction without committing rolls it back.
+///
+/// If the launcher is killed in the middle of a transaction, the journal is left behind and
+/// the next [`FsTransaction::begin`] within the same directory rolls it back first.
+#[derive(Debug)]
+pub struct FsTransaction {
+    staging_dir: PathBuf,
+    operations: Vec<Operation>,
+    finished: bool,
+}
+
+impl FsTransaction {
+    /// Begins a new transaction within the given directory, recovering an interrupted one if needed.
+    pub fn begin(dir: &Path) -> io::Result<Self> {
+        let staging_dir = dir.join(STAGING_DIR);
+
+        // roll back a transaction that was left behind
+        let journal_path = staging_dir.join(JOURNAL_FILE);
+        if journal_path.exists() {
+            log::warn!("Found an interrupted operation, restoring the previous files.");
+            let operations = fs::read_to_string(&journal_path)?
+                .lines()
+                .filter_map(Operation::from_line)
+                .collect();
+            Self {
+                staging_dir: staging_dir.clone(),
+                operations,
+                finished: false,
+            }
+            .rollback()?;
+        }
+
+        fs::create_dir_all(&staging_dir)?;
+        Ok(Self {
+            staging_dir,
+            operations: Vec::new(),
+            finished: false,
+        })
+    }
+
+    /// Removes the file at the given path, it is kept within the staging directory until commit.
+    pub fn remove(&mut self, path: &Path) -> io::Result<()> {
+        let staged = self.next_staged_path(path);
+        self.journal(Operation::Removed {
+            path: path.into(),
+            staged: staged.clone(),
+        })?;
+
+        fs::rename(path, staged)
+    }
+
+    /// Records the file at the given path before it is modified (or created) by the caller.
+    pub fn modify(&mut self, path: &Path) -> io::Result<()> {
+        if path.exists() {
+            let staged = self.next_staged_path(path);
+            fs::copy(path, &staged)?;
+            self.journal(Operation::Modified {
+                path: path.into(),
+                staged,
+            })
+        } else {
+            self.journal(Operation::Created { path: path.into() })
+        }
+    }
+
+    /// Copies a file from `from` to `to`, recording the destination beforehand.
+    pub fn copy(&mut self, from: &Path, to: &Path) -> io::Result<()> {
+        self.modify(to)?;
+        fs::copy(from, to).map(|_| ())
+    }
+
+    /// Makes the changes permanent, removing the staged files & the journal.
+    pub fn commit(mut self) -> io::Result<()> {
+        self.finished = true;
+        for operation in &self.operations {
+            if let Operation::Removed { staged, .. } | Operation::Modified { staged, .. } =
+                operation
+            {
+                if let Err(err) = fs::remove_file(staged) {
+                    log::warn!("Could not remove {}: {err}", staged.display());
+                }
+            }
+        }
+
+        fs::remove_dir_all(&self.staging_dir)
+    }
+
+    /// Restores all files in reverse order, and removes the staging directory.
+    pub fn rollback(mut self) -> io::Result<()> {
+        self.finished = true;
+        self.undo_all()
+    }
+
+    fn undo_all(&mut self) -> io::Result<()> {
+        for operation in self.operations.iter().rev() {
+            if let Err(err) = operation.undo() {
+                log::error!("Could not undo {}: {err}", operation.to_line());
+            }
+        }
+        self.operations.clear();
+
+        if self.staging_dir.exists() {
+            fs::remove_dir_all(&self.staging_dir)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the path to stage the given file at, within the same directory.
+    fn next_staged_path(&self, path: &Path) -> PathBuf {
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        path.with_file_name(format!(
+            ".{file_name}{STAGED_SUFFIX}_{}",
+            self.operations.len()
+        ))
+    }
+
+    /// Appends the operation to the journal, before it is executed.
+    fn journal(&mut self, operation: Operation) -> io::Result<()> {
+        let mut journal = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.staging_dir.join(JOURNAL_FILE))?;
+        writeln!(journal, "{}", operation.to_line())?;
+        journal.sync_all()?;
+
+        self.operations.push(operation);
+        Ok(())
+    }
+}
+
+impl Drop for FsTransaction {
+    fn drop(&mut self) {
+        if !self.finished {
+            log::warn!("Operation did not complete, restoring the previous files.");
+            if let Err(err) = self.undo_all() {
+                log::error!("Could not restore files: {err}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transaction_rollback_and_commit() {
+        let dir = std::env::temp_dir().join(format!(
+            "dkn-test-transaction-{}-{}",
+            std::process::id(),
+            line!()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let removed = dir.join("removed.txt");
+        let modified = dir.join("modified.txt");
+        let created = dir.join("created.txt");
+        fs::write(&removed, "removed").unwrap();
+        fs::write(&modified, "old").unwrap();
+
+        // roll back on drop
+        {
+            let mut tx = FsTransaction::begin(&dir).unwrap();
+            tx.remove(&removed).unwrap();
+            tx.modify(&modified).unwrap();
+            fs::write(&modified, "new").unwrap();
+            tx.copy(&modified, &created).unwrap();
+            assert!(!removed.exists());
+        }
+        assert_eq!(fs::read_to_string(&removed).unwrap(), "removed");
+        assert_eq!(fs::read_to_string(&modified).unwrap(), "old");
+        assert!(!created.exists());
+        assert!(!dir.join(STAGING_DIR).exists());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+
+        // commit
+        let mut tx = FsTransaction::begin(&dir).unwrap();
+        tx.remove(&removed).unwrap();
+        tx.commit().unwrap();
+        assert!(!removed.exists());
+        assert!(!dir.join(STAGING_DIR).exists());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_journal_lines() {
+        let operation = Operation::Removed {
+            path: "/a/b".into(),
+            staged: "/a/.b.dkn-staged_0".into(),
+        };
+        assert_eq!(
+            Operation::from_line(&operation.to_line()),
+            Some(operation.clone())
+        );
+        assert_eq!(Operation::from_line("unknown\t/a"), None);
+    }
+}
@@ -1,8 +1,21 @@
+use std::io::IsTerminal;
 use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use eyre::Result;
+use colored::Colorize;
+use eyre::{Context, Result};
+use inquire::Confirm;
 
-use super::{get_latest_release, DriaRelease, DriaRepository, DKN_LATEST_COMPUTE_FILE};
+use super::{
+    get_latest_release, parse_version, DriaRelease, DriaRepository, DKN_LATEST_COMPUTE_FILE,
+    DKN_LAUNCHER_VERSION,
+};
+use crate::tr;
+
+/// Number of release notes lines shown before an update, the rest is on GitHub.
+const RELEASE_NOTES_MAX_LINES: usize = 30;
+/// The file that records when the compute node updates were last checked, in unix seconds.
+const DKN_UPDATE_CHECK_FILE: &str = ".dkn-update-check";
 
 /// Check if there is an update required for the compute node.
 ///
@@ -22,6 +35,7 @@ pub async fn check_for_compute_node_update(exe_dir: &Path) -> Result<(DriaReleas
     // get the latest release version from repo
     let latest_release = get_latest_release(DriaRepository::ComputeNode).await?;
     let latest_version = latest_release.version();
+    record_update_check(exe_dir);
 
     // checks if compute path exists
     let compute_exists = exe_dir.join(DKN_LATEST_COMPUTE_FILE).exists();
@@ -35,6 +49,50 @@ pub async fn check_for_compute_node_update(exe_dir: &Path) -> Result<(DriaReleas
     Ok((latest_release, requires_update))
 }
 
+/// Checks that the given compute node release can be run by this launcher, as an old launcher
+/// may not be able to spawn & configure a newer compute node correctly.
+///
+/// ### Errors
+/// - if the release requires a newer launcher than this one.
+pub fn ensure_launcher_supports(release: &DriaRelease) -> Result<()> {
+    if let Some(min_version) = release.min_launcher_version() {
+        if parse_version(DKN_LAUNCHER_VERSION) < parse_version(&min_version) {
+            eyre::bail!(
+                "compute node v{} requires launcher v{} or newer, but this is v{}; please update the launcher with `dkn-compute-launcher update` and restart it",
+                release.version(),
+                min_version,
+                DKN_LAUNCHER_VERSION
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Records the time of a successful update check under the given directory, which is shown by
+/// `info --verbose`; this is best-effort.
+fn record_update_check(exe_dir: &Path) {
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or_default();
+    if let Err(err) = std::fs::write(exe_dir.join(DKN_UPDATE_CHECK_FILE), format!("{time}\n")) {
+        log::debug!("Could not record update check: {err}");
+    }
+}
+
+/// Returns when the compute node updates were last checked successfully under the given
+/// directory, if ever.
+pub fn read_last_update_check(exe_dir: &Path) -> Option<SystemTime> {
+    let secs = std::fs::read_to_string(exe_dir.join(DKN_UPDATE_CHECK_FILE))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+
+    Some(UNIX_EPOCH + Duration::from_secs(secs))
+}
+
 /// Check if there is an update required for the launcher.
 ///
 /// ### Arguments
@@ -55,3 +113,119 @@ pub async fn check_for_launcher_update(current_version: &str) -> Result<(DriaRel
 
     Ok((latest_release, requires_update))
 }
+
+/// Shows the release notes of the given release, and asks for a confirmation to update to it
+/// unless `auto_approve` is set.
+///
+/// Outside an interactive terminal, the update is skipped unless `auto_approve` is set.
+///
+/// ### Returns
+/// `true` if the update should be applied.
+///
+/// ### Errors
+/// - if the prompt fails.
+pub fn confirm_update(
+    release: &DriaRelease,
+    current_version: Option<&str>,
+    auto_approve: bool,
+) -> Result<bool> {
+    eprintln!(
+        "{} {} → v{}",
+        release.name().bold(),
+        current_version
+            .map(|version| format!("v{}", version.trim()))
+            .unwrap_or_else(|| "not installed".to_string()),
+        release.version()
+    );
+    match release.release_notes() {
+        Some(notes) => eprintln!("{}", format_release_notes(notes, RELEASE_NOTES_MAX_LINES)),
+        None => eprintln!("{}", "No release notes.".dimmed()),
+    }
+    eprintln!("{}", release.release_url().dimmed());
+
+    if auto_approve {
+        return Ok(true);
+    }
+    if !std::io::stdin().is_terminal() {
+        log::warn!("Skipping the update to v{} in a non-interactive terminal, use --auto-approve to update unattended.", release.version());
+        return Ok(false);
+    }
+
+    Ok(
+        Confirm::new(&tr!("Update to v{version}?", version = release.version()))
+            .with_default(true)
+            .prompt()?,
+    )
+}
+
+/// Returns the release notes indented, truncated to the given number of lines.
+fn format_release_notes(notes: &str, max_lines: usize) -> String {
+    let lines = notes.lines().collect::<Vec<_>>();
+    let mut formatted = lines
+        .iter()
+        .take(max_lines)
+        .map(|line| format!("  {}", line.trim_end()))
+        .collect::<Vec<_>>();
+    if lines.len() > max_lines {
+        formatted.push(format!("  ... ({} more lines)", lines.len() - max_lines));
+    }
+
+    formatted.join("\n")
+}
+
+/// Checks whether the launcher binary can replace itself, i.e. its location is writable.
+///
+/// The launcher may reside in a read-only location, such as a mounted image, a system directory
+/// without elevated privileges or the Nix store; in which case self-updates are not possible.
+/// Compute node updates are not affected by this, as they are stored within the data directory.
+///
+/// ### Errors
+/// - if the launcher location is not writable, with guidance for the user.
+pub fn check_launcher_replaceable() -> Result<()> {
+    let exe_path = std::env::current_exe().wrap_err("could not get launcher path")?;
+    let guidance = |reason: &str| {
+        eyre::eyre!(
+            "Launcher at {} can not update itself ({reason}).\nUpdate it the same way you have installed it (e.g. via your package manager), or move it to a writable directory. Compute node updates are not affected.",
+            exe_path.display()
+        )
+    };
+
+    // Nix store is always read-only, and is managed by Nix itself
+    if exe_path.starts_with("/nix/store") {
+        return Err(guidance("managed by Nix"));
+    }
+
+    let is_readonly = std::fs::metadata(&exe_path)
+        .map(|metadata| metadata.permissions().readonly())
+        .unwrap_or(false);
+    if is_readonly {
+        return Err(guidance("binary is read-only"));
+    }
+
+    // `self_replace` writes a temporary file next to the binary, so we probe for that
+    let exe_dir = exe_path
+        .parent()
+        .ok_or_else(|| guidance("no parent directory"))?;
+    let probe_path = exe_dir.join(format!(".dkn-write-probe-{}", std::process::id()));
+    match std::fs::File::create(&probe_path) {
+        Ok(_) => {
+            let _ = std::fs::remove_file(&probe_path);
+            Ok(())
+        }
+        Err(err) => Err(guidance(&format!("directory is not writable: {err}"))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_release_notes() {
+        assert_eq!(
+            format_release_notes("## Changes\n- fix a bug  \n- add a feature", 2),
+            "  ## Changes\n  - fix a bug\n  ... (1 more lines)"
+        );
+        assert_eq!(format_release_notes("- fix", 2), "  - fix");
+    }
+}
@@ -0,0 +1,167 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use sysinfo::System;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+/// Interval between the samples of a [`UtilizationSampler`], CPU usage needs at least
+/// [`sysinfo::MINIMUM_CPU_UPDATE_INTERVAL`] between two samples.
+const UTILIZATION_SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Hardware utilization of this machine during a measurement.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Utilization {
+    /// Peak GPU memory used over all GPUs in MB, `None` if `nvidia-smi` is not available.
+    pub peak_gpu_memory_mb: Option<u64>,
+    /// Average utilization of the busiest GPU in percent, `None` if `nvidia-smi` is not available.
+    pub gpu_percent: Option<f64>,
+    /// Average CPU usage over all cores in percent.
+    pub cpu_percent: f64,
+}
+
+/// Samples the utilization of the CPU & NVIDIA GPUs in the background, e.g. while a model is
+/// generating, to see whether it is bound by the hardware.
+pub struct UtilizationSampler {
+    cancellation: CancellationToken,
+    handle: JoinHandle<Utilization>,
+}
+
+impl UtilizationSampler {
+    /// Starts sampling until [`UtilizationSampler::stop`] is called.
+    pub fn start() -> Self {
+        let cancellation = CancellationToken::new();
+        let handle = tokio::spawn(sample_utilization(cancellation.clone()));
+
+        Self {
+            cancellation,
+            handle,
+        }
+    }
+
+    /// Stops sampling and returns the utilization over the samples, `None` if sampling failed.
+    pub async fn stop(self) -> Option<Utilization> {
+        self.cancellation.cancel();
+        self.handle.await.ok()
+    }
+}
+
+/// Samples the utilization periodically until cancelled.
+async fn sample_utilization(cancellation: CancellationToken) -> Utilization {
+    let mut system = System::new();
+    system.refresh_cpu_usage();
+
+    let mut samples = UtilizationSamples::default();
+    loop {
+        tokio::select! {
+            _ = cancellation.cancelled() => break,
+            _ = tokio::time::sleep(UTILIZATION_SAMPLE_INTERVAL) => {
+                system.refresh_cpu_usage();
+                samples.cpu_percents.push(system.global_cpu_usage() as f64);
+                if let Some(gpus) = query_gpu_usage().await {
+                    samples.gpus.push(gpus);
+                }
+            }
+        }
+    }
+
+    samples.summarize()
+}
+
+/// Returns the `(memory used in MB, utilization in percent)` of each GPU reported by
+/// `nvidia-smi`, `None` if it is not available.
+async fn query_gpu_usage() -> Option<Vec<(u64, f64)>> {
+    let output = tokio::process::Command::new("nvidia-smi")
+        .args([
+            "--query-gpu=memory.used,utilization.gpu",
+            "--format=csv,noheader,nounits",
+        ])
+        .output()
+        .await
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let gpus = parse_nvidia_smi_usage(&String::from_utf8_lossy(&output.stdout));
+    (!gpus.is_empty()).then_some(gpus)
+}
+
+/// Parses the `memory, utilization` lines of `nvidia-smi`, where memory is in MB.
+fn parse_nvidia_smi_usage(output: &str) -> Vec<(u64, f64)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (memory, utilization) = line.split_once(',')?;
+            Some((
+                memory.trim().parse().ok()?,
+                utilization.trim().parse().ok()?,
+            ))
+        })
+        .collect()
+}
+
+/// Samples collected by a [`UtilizationSampler`].
+#[derive(Debug, Default)]
+struct UtilizationSamples {
+    cpu_percents: Vec<f64>,
+    /// Usage of each GPU per sample, see [`query_gpu_usage`].
+    gpus: Vec<Vec<(u64, f64)>>,
+}
+
+impl UtilizationSamples {
+    fn summarize(&self) -> Utilization {
+        let average = |values: &[f64]| values.iter().sum::<f64>() / values.len().max(1) as f64;
+        let gpu_percents = self
+            .gpus
+            .iter()
+            .map(|gpus| gpus.iter().map(|(_, percent)| *percent).fold(0.0, f64::max))
+            .collect::<Vec<_>>();
+
+        Utilization {
+            peak_gpu_memory_mb: self
+                .gpus
+                .iter()
+                .map(|gpus| gpus.iter().map(|(memory_mb, _)| memory_mb).sum())
+                .max(),
+            gpu_percent: (!gpu_percents.is_empty()).then(|| average(&gpu_percents)),
+            cpu_percent: average(&self.cpu_percents),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_utilization_samples() {
+        assert_eq!(
+            parse_nvidia_smi_usage("1024, 50\ninvalid\n2048, 100\n"),
+            [(1024, 50.0), (2048, 100.0)]
+        );
+
+        let samples = UtilizationSamples {
+            cpu_percents: vec![20.0, 40.0],
+            gpus: vec![
+                vec![(1000, 30.0), (500, 10.0)],
+                vec![(4000, 90.0), (500, 10.0)],
+            ],
+        };
+        assert_eq!(
+            samples.summarize(),
+            Utilization {
+                peak_gpu_memory_mb: Some(4500),
+                gpu_percent: Some(60.0),
+                cpu_percent: 30.0,
+            }
+        );
+
+        // no GPUs
+        let samples = UtilizationSamples {
+            cpu_percents: vec![50.0],
+            gpus: vec![],
+        };
+        assert_eq!(samples.summarize().gpu_percent, None);
+        assert_eq!(samples.summarize().peak_gpu_memory_mb, None);
+    }
+}
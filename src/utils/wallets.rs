@@ -0,0 +1,264 @@
+use eyre::{eyre, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use super::crypto::secret_key_to_account;
+use super::shared_data_dir;
+
+/// Where the secret key of a saved wallet is kept.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum WalletSource {
+    /// The secret key itself, in hexadecimal.
+    Key { secret_key: String },
+    /// An encrypted Ethereum keystore (V3) file, which is decrypted with its password on use.
+    Keystore { path: PathBuf },
+}
+
+/// A wallet within the [`AddressBook`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedWallet {
+    /// A human-friendly label, unique within the address book.
+    pub label: String,
+    /// The address of the wallet, in lowercase hexadecimal without `0x`.
+    pub address: String,
+    pub source: WalletSource,
+}
+
+impl SavedWallet {
+    /// Creates a wallet from its secret key.
+    ///
+    /// ### Errors
+    /// - If the secret key is invalid
+    pub fn from_secret_key(label: &str, secret_key: &str) -> Result<Self> {
+        let (_, _, address) = secret_key_to_account(secret_key)?;
+        Ok(Self {
+            label: label.trim().to_string(),
+            address,
+            source: WalletSource::Key {
+                secret_key: secret_key.trim().to_string(),
+            },
+        })
+    }
+
+    /// Creates a wallet from a keystore file, which is decrypted once to find its address.
+    ///
+    /// ### Errors
+    /// - If the keystore could not be decrypted with the given password
+    pub fn from_keystore(label: &str, path: &Path, password: &str) -> Result<Self> {
+        let secret_key = decrypt_keystore(path, password)?;
+        let (_, _, address) = secret_key_to_account(&secret_key)?;
+        Ok(Self {
+            label: label.trim().to_string(),
+            address,
+            source: WalletSource::Keystore {
+                path: path.to_path_buf(),
+            },
+        })
+    }
+
+    /// Returns the secret key of this wallet, decrypting its keystore with the given password
+    /// if needed.
+    ///
+    /// ### Errors
+    /// - If the keystore could not be decrypted, or it belongs to another address
+    pub fn secret_key(&self, password: Option<&str>) -> Result<String> {
+        match &self.source {
+            WalletSource::Key { secret_key } => Ok(secret_key.clone()),
+            WalletSource::Keystore { path } => {
+                let password = password.ok_or_else(|| eyre!("keystore requires a password"))?;
+                let secret_key = decrypt_keystore(path, password)?;
+                let (_, _, address) = secret_key_to_account(&secret_key)?;
+                if address != self.address {
+                    eyre::bail!(
+                        "keystore at {} belongs to 0x{address} instead of 0x{}",
+                        path.display(),
+                        self.address
+                    );
+                }
+
+                Ok(secret_key)
+            }
+        }
+    }
+
+    /// Returns `true` if the wallet is kept within an encrypted keystore.
+    #[inline]
+    pub fn is_keystore(&self) -> bool {
+        matches!(self.source, WalletSource::Keystore { .. })
+    }
+}
+
+impl std::fmt::Display for SavedWallet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (0x{})", self.label, self.address)?;
+        if self.is_keystore() {
+            write!(f, " [keystore]")?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the given content to a new file at the given path, which is created readable only by
+/// the owner on Unix; a leftover file at the path is replaced.
+fn write_private(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let _ = std::fs::remove_file(path);
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    file.write_all(content)?;
+    file.sync_all()
+}
+
+/// Wallets that are saved to be switched between, shared by all profiles.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AddressBook {
+    pub wallets: Vec<SavedWallet>,
+}
+
+impl AddressBook {
+    /// Returns the path of the address book, `~/.dria/dkn-compute-launcher/wallets.json`.
+    ///
+    /// ### Errors
+    /// - If the home directory could not be found, as secret keys must not be left elsewhere
+    fn path() -> Result<PathBuf> {
+        shared_data_dir()
+            .map(|dir| dir.join("wallets.json"))
+            .ok_or_else(|| eyre!("could not find the home directory to keep the address book"))
+    }
+
+    /// Loads the address book, which is empty if it does not exist yet.
+    ///
+    /// ### Errors
+    /// - If the home directory could not be found
+    /// - If the address book exists but could not be read or parsed
+    pub fn load() -> Result<Self> {
+        let path = Self::path()?;
+        match std::fs::read(&path) {
+            Ok(content) => serde_json::from_slice(&content)
+                .wrap_err_with(|| format!("could not parse {}", path.display())),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err).wrap_err_with(|| format!("could not read {}", path.display())),
+        }
+    }
+
+    /// Saves the address book, readable only by the owner on Unix as it may hold secret keys.
+    ///
+    /// It is written to a temporary file next to it that is renamed over it, so that an
+    /// interrupted save never leaves a truncated address book behind.
+    ///
+    /// ### Errors
+    /// - If the home directory could not be found
+    /// - If the address book could not be written
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path()?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let tmp_path = path.with_extension(format!("json.tmp.{}", std::process::id()));
+        write_private(&tmp_path, &serde_json::to_vec_pretty(self)?)
+            .and_then(|_| std::fs::rename(&tmp_path, &path))
+            .inspect_err(|_| {
+                let _ = std::fs::remove_file(&tmp_path);
+            })
+            .wrap_err_with(|| format!("could not write {}", path.display()))
+    }
+
+    /// Adds the given wallet.
+    ///
+    /// ### Errors
+    /// - If the label is empty, or another wallet has the same label or address
+    pub fn add(&mut self, wallet: SavedWallet) -> Result<()> {
+        if wallet.label.is_empty() {
+            eyre::bail!("label must not be empty");
+        }
+        if let Some(existing) = self
+            .wallets
+            .iter()
+            .find(|w| w.label == wallet.label || w.address == wallet.address)
+        {
+            eyre::bail!("wallet is already saved as {existing}");
+        }
+
+        self.wallets.push(wallet);
+        Ok(())
+    }
+
+    /// Removes the wallet with the given label, returns it if it existed.
+    pub fn remove(&mut self, label: &str) -> Option<SavedWallet> {
+        let index = self.wallets.iter().position(|w| w.label == label)?;
+        Some(self.wallets.remove(index))
+    }
+
+    /// Returns the wallet with the given address, with or without `0x`.
+    pub fn find_by_address(&self, address: &str) -> Option<&SavedWallet> {
+        let address = address.trim_start_matches("0x").to_lowercase();
+        self.wallets.iter().find(|w| w.address == address)
+    }
+}
+
+/// Decrypts an Ethereum keystore (V3) file, returns its secret key in hexadecimal with `0x`.
+///
+/// ### Errors
+/// - If the file could not be read, or the password is wrong
+pub fn decrypt_keystore(path: &Path, password: &str) -> Result<String> {
+    let secret_key = eth_keystore::decrypt_key(path, password)
+        .map_err(|err| eyre!("could not decrypt keystore at {}: {err}", path.display()))?;
+
+    Ok(format!("0x{}", hex::encode(secret_key)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_address_book() {
+        let key_a = "0x6472696164726961647269616472696164726961647269616472696164726961";
+        let key_b = "0x0000000000000000000000000000000000000000000000000000000000000001";
+        let mut book = AddressBook::default();
+
+        let wallet_a = SavedWallet::from_secret_key("main", key_a).unwrap();
+        book.add(wallet_a.clone()).unwrap();
+        assert!(book.add(wallet_a.clone()).is_err());
+        assert!(book
+            .add(SavedWallet::from_secret_key("main", key_b).unwrap())
+            .is_err());
+        assert!(book
+            .add(SavedWallet::from_secret_key(" ", key_b).unwrap())
+            .is_err());
+        book.add(SavedWallet::from_secret_key("backup", key_b).unwrap())
+            .unwrap();
+
+        assert_eq!(
+            book.find_by_address(&format!("0x{}", wallet_a.address.to_uppercase())),
+            Some(&wallet_a)
+        );
+        assert_eq!(wallet_a.secret_key(None).unwrap(), key_a);
+        assert_eq!(book.remove("main"), Some(wallet_a));
+        assert_eq!(book.remove("main"), None);
+        assert_eq!(book.wallets.len(), 1);
+
+        let keystore = SavedWallet {
+            label: "cold".to_string(),
+            address: "00".repeat(20),
+            source: WalletSource::Keystore {
+                path: PathBuf::from("/nonexistent/keystore.json"),
+            },
+        };
+        assert!(keystore.secret_key(None).is_err());
+        assert!(keystore.secret_key(Some("password")).is_err());
+        assert_eq!(
+            keystore.to_string(),
+            format!("cold (0x{}) [keystore]", "00".repeat(20))
+        );
+    }
+}
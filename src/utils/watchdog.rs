@@ -0,0 +1,85 @@
+use std::time::{Duration, Instant};
+
+/// Interval between the liveness checks of the compute node.
+pub const WATCHDOG_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Detects a hung compute node, i.e. one that is alive but has not written any output for a while.
+///
+/// The compute node logs its peer count & tasks periodically, so a long silence means that it is
+/// stuck. The watchdog is reset whenever the compute node is (re)started, so that a restarted node
+/// has the entire timeout to write its first output.
+#[derive(Debug, Clone)]
+pub struct Watchdog {
+    /// How long the compute node can stay silent, `None` if the watchdog is disabled.
+    stall_timeout: Option<Duration>,
+    /// When the compute node was (re)started.
+    armed_at: Instant,
+}
+
+impl Watchdog {
+    /// Creates a watchdog with the given stall timeout, `None` to disable it.
+    pub fn new(stall_timeout: Option<Duration>) -> Self {
+        Self {
+            stall_timeout,
+            armed_at: Instant::now(),
+        }
+    }
+
+    /// Resets the watchdog, to be called when the compute node is (re)started.
+    pub fn reset(&mut self) {
+        self.armed_at = Instant::now();
+    }
+
+    /// Returns how long the compute node has been silent if it is considered hung, w.r.t the
+    /// time of its last output.
+    pub fn stalled_for(&self, last_output: Option<Instant>) -> Option<Duration> {
+        self.stalled_for_at(last_output, Instant::now())
+    }
+
+    fn stalled_for_at(&self, last_output: Option<Instant>, now: Instant) -> Option<Duration> {
+        let stall_timeout = self.stall_timeout?;
+        let since = last_output.map_or(self.armed_at, |last| last.max(self.armed_at));
+        let silence = now.saturating_duration_since(since);
+
+        (silence >= stall_timeout).then_some(silence)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watchdog() {
+        let minute = Duration::from_secs(60);
+        let watchdog = Watchdog::new(Some(10 * minute));
+        let start = watchdog.armed_at;
+
+        assert_eq!(watchdog.stalled_for_at(None, start + 5 * minute), None);
+        assert_eq!(
+            watchdog.stalled_for_at(None, start + 10 * minute),
+            Some(10 * minute)
+        );
+        assert_eq!(
+            watchdog.stalled_for_at(Some(start + 5 * minute), start + 12 * minute),
+            None
+        );
+        assert_eq!(
+            watchdog.stalled_for_at(Some(start + 5 * minute), start + 16 * minute),
+            Some(11 * minute)
+        );
+
+        // output before a restart does not count against the restarted node
+        let restarted = Watchdog {
+            armed_at: start + 20 * minute,
+            ..watchdog.clone()
+        };
+        assert_eq!(
+            restarted.stalled_for_at(Some(start), start + 25 * minute),
+            None
+        );
+
+        let disabled = Watchdog::new(None);
+        assert_eq!(disabled.stalled_for_at(None, start + 60 * minute), None);
+    }
+}